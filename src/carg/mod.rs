@@ -1,8 +1,9 @@
 use std::{fs, io};
 
 use app_test::EAppTestCommands;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use container::ENodeContainer;
+use crate::device::backend::{print_devices, EAudioDeviceBackend};
 
 pub mod app_test;
 pub mod container;
@@ -11,6 +12,8 @@ pub mod v2;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct CommandArgs {
+    #[command(subcommand)]
+    command: Option<ECommands>,
     /// Application test option.
     #[arg(long, value_enum)]
     app_test: Option<EAppTestCommands>,
@@ -51,9 +54,29 @@ impl CommandArgs {
     }
 }
 
+// 処理ファイルを読み込む以外のコマンド。
+// 各バリアントとフィールドの`///`はclapのヘルプの説明としてそのまま出るので、ヘルプに合わせて英語で書く。
+// このコメントはヘルプに出さないので、普通のコメントにする。
+#[derive(Subcommand, Debug)]
+enum ECommands {
+    /// List playback and capture devices with their supported formats.
+    Devices {
+        /// Backend to enumerate devices. Uses the default backend order when not specified.
+        #[arg(long, value_enum)]
+        backend: Option<EAudioDeviceBackend>,
+    },
+}
+
 /// @brief コマンド引数をパーシングする。
 pub async fn parse_command_arguments() -> anyhow::Result<ENodeContainer> {
     let cli = CommandArgs::parse();
+    if let Some(command) = &cli.command {
+        match command {
+            ECommands::Devices { backend } => print_devices(*backend)?,
+        }
+        return Ok(ENodeContainer::None);
+    }
+
     let parsed_info = cli.try_parse_info().await?;
 
     // チェック。
@@ -7,7 +7,7 @@ use crate::carg::v2::node::common::{EProcessState, ProcessControlItemSetting};
 use crate::carg::v2::{ENode, ProcessItemCreateSetting, SItemSPtr, TProcessItem, TProcessItemPtr};
 use crate::{
     carg::v2::{ProcessControlItem, ProcessOutputBuffer, ProcessProcessorInput, TProcess},
-    wave::sample::UniformedSample,
+    wave::{envelope::AdsrEnvelope, sample::UniformedSample},
 };
 
 #[derive(Debug)]
@@ -15,15 +15,9 @@ pub struct AdapterEnvelopeAdsrProcessData {
     common: ProcessControlItem,
     /// 処理後に出力情報が保存されるところ。
     output: Option<ProcessOutputBuffer>,
-    attack_time: f64,
-    decay_time: f64,
+    /// エンベロープの形状。Sustainの長さは`sustain_time`で別に持つ。
+    envelope: AdsrEnvelope,
     sustain_time: f64,
-    release_time: f64,
-    attack_curve: f64,
-    decay_curve: f64,
-    release_curve: f64,
-    /// sustainで維持する振幅`[0, 1]`の値。
-    sustain_value: f64,
 }
 
 impl TPinCategory for AdapterEnvelopeAdsrProcessData {
@@ -86,14 +80,16 @@ impl TProcessItem for AdapterEnvelopeAdsrProcessData {
                         systems: &system_setting,
                     }),
                     output: None,
-                    attack_time: *attack_time,
-                    decay_time: *decay_time,
-                    attack_curve: *attack_curve,
-                    decay_curve: *decay_curve,
+                    envelope: AdsrEnvelope {
+                        attack_time: *attack_time,
+                        decay_time: *decay_time,
+                        release_time: *release_time,
+                        attack_curve: *attack_curve,
+                        decay_curve: *decay_curve,
+                        release_curve: *release_curve,
+                        sustain_value: *sustain_value,
+                    },
                     sustain_time: *sustain_time,
-                    release_time: *release_time,
-                    release_curve: *release_curve,
-                    sustain_value: *sustain_value,
                 };
                 Ok(SItemSPtr::new(item))
             }
@@ -130,10 +126,8 @@ impl AdapterEnvelopeAdsrProcessData {
         // inputのSettingのsample_rateから各バッファのサンプルの発生時間を計算する。
         let sample_rate = input.sample_rate as f64;
 
-        let decay_start_time = self.attack_time;
-        let sustain_start_time = decay_start_time + self.decay_time;
-        let release_start_time = sustain_start_time + self.sustain_time;
-        let stop_time = release_start_time + self.release_time;
+        let envelope = &self.envelope;
+        let release_start_time = envelope.attack_time + envelope.decay_time + self.sustain_time;
 
         let mut applied_buffer = vec![];
         applied_buffer.reserve(input.buffer.len());
@@ -141,34 +135,18 @@ impl AdapterEnvelopeAdsrProcessData {
         for (sample_i, sample) in input.buffer.iter().enumerate() {
             let sample_time = sample_i as f64 / sample_rate;
 
-            if sample_time >= stop_time {
-                // Envelopeが完全にとまったので。
-                applied_buffer.push(UniformedSample::MIN);
-            } else if sample_time >= release_start_time {
-                // Release中。
-                // curve < 1.0ならLog式、curve > 1.0なら指数関数式。
-                let rate = (sample_time - release_start_time) / self.release_time;
-                let input_rate = 1.0 - rate;
-                // y = input_rate^(curve)。
-                let value = self.sustain_value * input_rate.powf(self.release_curve);
-                applied_buffer.push(value * *sample);
-            } else if sample_time >= sustain_start_time {
-                // Sustain中。
-                applied_buffer.push(self.sustain_value * *sample);
-            } else if sample_time >= decay_start_time {
-                // Decay中。
-                // curve < 1.0ならLog式、curve > 1.0なら指数関数式。
-                let rate = (sample_time - decay_start_time) / self.decay_time;
-                let input_rate = 1.0 - rate;
-                // y = input_rate^(curve)。
-                let value = (1.0 - self.sustain_value) * input_rate.powf(self.decay_curve) + self.sustain_value;
-                applied_buffer.push(value * *sample);
+            if sample_time >= release_start_time {
+                // Release中。Envelopeが完全にとまったら0になる。
+                let release_time = sample_time - release_start_time;
+                if envelope.is_release_finished(release_time) {
+                    applied_buffer.push(UniformedSample::MIN);
+                } else {
+                    let value = envelope.release_gain(envelope.sustain_value, release_time);
+                    applied_buffer.push(value * *sample);
+                }
             } else {
-                // Attack中。
-                // curve < 1.0ならLog式、curve > 1.0なら指数関数式。
-                let rate = sample_time / self.attack_time;
-                // y = input_rate^(curve)。
-                let value = rate.powf(self.attack_curve);
+                // Attack・Decay・Sustain中。
+                let value = envelope.note_on_gain(sample_time);
                 applied_buffer.push(value * *sample);
             }
         }
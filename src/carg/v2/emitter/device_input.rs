@@ -0,0 +1,218 @@
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{system_category, ESystemCategoryFlag, InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessOutputBufferStereo, ProcessProcessorInput,
+    SItemSPtr, TProcess, TProcessItem, TProcessItemPtr,
+};
+use crate::nz_define_time_tick_for;
+use crate::wave::sample::UniformedSample;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaDeviceInputInfo {
+    /// 入力を受け取る長さ（秒）。指定しなければ止まるまでずっと受け取る。
+    #[serde(default)]
+    pub length: Option<f64>,
+}
+
+/// オーディオデバイスの入力（マイク・ライン入力）をバッファにして流すエミッター。
+///
+/// 使うには[`AudioDeviceSetting`](crate::device::AudioDeviceSetting)の`capture_channels`を指定すること。
+#[derive(Debug)]
+pub struct EmitterDeviceInputProcessData {
+    common: ProcessControlItem,
+    info: MetaDeviceInputInfo,
+    internal: InternalInfo,
+}
+
+#[derive(Default, Debug)]
+struct InternalInfo {
+    /// 今まで出力したフレームの数
+    emitted_frame_count: usize,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+const OUTPUT_OUT_STEREO: &'static str = "out_stereo";
+
+impl TPinCategory for EmitterDeviceInputProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT, OUTPUT_OUT_STEREO]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            OUTPUT_OUT_STEREO => Some(pin_category::BUFFER_STEREO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterDeviceInputProcessData {
+    fn get_dependent_system_categories() -> ESystemCategoryFlag {
+        system_category::AUDIO_DEVICE
+    }
+}
+nz_define_time_tick_for!(EmitterDeviceInputProcessData, false, true);
+
+impl TProcess for EmitterDeviceInputProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。またInputピンのリソース更新はしなくてもいい。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+
+        // もしデバイスが死んだら処理してはいけないし、処理中断する。
+        let device = self.common.systems.audio_device.as_ref().unwrap().upgrade();
+        if device.is_none() {
+            self.common.state = EProcessState::Finished;
+            return;
+        }
+
+        // critical section
+        let (samples, channels, sample_rate) = {
+            let device = device.as_ref().unwrap();
+            let proxy = device.lock().unwrap();
+            (
+                proxy.receive_captured_samples(),
+                proxy.get_capture_channels(),
+                proxy.get_sample_rate(),
+            )
+        };
+        // 作る時に確認しているので、ここに来るのはデバイスが解放された時だけ。
+        if channels == 0 {
+            self.common.state = EProcessState::Finished;
+            return;
+        }
+
+        // 指定した長さを超える分は捨てる。
+        let mut frame_count = samples.len() / channels;
+        let mut is_finished = false;
+        if let Some(length) = self.info.length {
+            let max_frame_count = ((sample_rate as f64) * length.max(0.0)).ceil() as usize;
+            let remained_frame_count = max_frame_count.saturating_sub(self.internal.emitted_frame_count);
+            if frame_count >= remained_frame_count {
+                frame_count = remained_frame_count;
+                is_finished = true;
+            }
+        }
+        self.internal.emitted_frame_count += frame_count;
+
+        // モノラルには最初のチャンネルだけを使う。
+        // ステレオの場合、入力がモノラルなら両方のチャンネルに同じサンプルを入れて、3チャンネル以上なら最初の2つだけを使う。
+        let mut left = Vec::with_capacity(frame_count);
+        let mut right = Vec::with_capacity(frame_count);
+        for frame in samples.chunks_exact(channels).take(frame_count) {
+            let l_sample = UniformedSample::from_f64(frame[0] as f64);
+            let r_sample = if channels >= 2 {
+                UniformedSample::from_f64(frame[1] as f64)
+            } else {
+                l_sample
+            };
+            left.push(l_sample);
+            right.push(r_sample);
+        }
+
+        // デバイスのコールバックがまだ来てなくてサンプルがなくても、
+        // 後ろのノードが処理できるように空のバッファを流す。
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(left.clone(), sample_rate)),
+            )
+            .unwrap();
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT_STEREO,
+                EProcessOutput::BufferStereo(ProcessOutputBufferStereo {
+                    ch_left: left,
+                    ch_right: right,
+                    sample_rate,
+                }),
+            )
+            .unwrap();
+
+        // 状態確認
+        if is_finished {
+            self.common.state = EProcessState::Finished;
+        } else {
+            self.common.state = EProcessState::Playing;
+        }
+    }
+}
+
+impl TProcessItem for EmitterDeviceInputProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterDeviceInput(v) = setting.node {
+            let capture_channels = system_setting
+                .audio_device
+                .as_ref()
+                .and_then(|v| v.upgrade())
+                .map_or(0, |v| v.lock().unwrap().get_capture_channels());
+            if capture_channels == 0 {
+                return Err(anyhow::anyhow!(
+                    "Audio device must be set `capture_channels` to use emitter-device-input."
+                ));
+            }
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterDeviceInput,
+                    systems: &system_setting,
+                }),
+                info: v.clone(),
+                internal: InternalInfo::default(),
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,169 @@
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{system_category, ESystemCategoryFlag, InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, ProcessItemCreateSetting, ProcessOutputText, ProcessProcessorInput, SItemSPtr, TProcess,
+    TProcessItem, TProcessItemPtr,
+};
+use crate::nz_define_time_tick_for;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaDeviceMetricsInfo {
+    /// 測定値を出力する間隔（秒）。指定しなければ1秒ごとに出力する。
+    #[serde(default)]
+    pub interval: Option<f64>,
+}
+
+/// オーディオデバイスのリアルタイム再生の測定値（xrunの数、グラフの処理時間、リングバッファの使用量、推定レイテンシー）を
+/// テキストで流すエミッター。`output-log`につないで使う。
+#[derive(Debug)]
+pub struct EmitterDeviceMetricsProcessData {
+    common: ProcessControlItem,
+    info: MetaDeviceMetricsInfo,
+    internal: InternalInfo,
+}
+
+#[derive(Default, Debug)]
+struct InternalInfo {
+    /// 処理を始めてから経った時間（秒）
+    elapsed_time: f64,
+    /// 最後に測定値を出力した時間（秒）
+    last_emitted_time: Option<f64>,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+
+impl TPinCategory for EmitterDeviceMetricsProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            OUTPUT_OUT => Some(pin_category::TEXT),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterDeviceMetricsProcessData {
+    fn get_dependent_system_categories() -> ESystemCategoryFlag {
+        system_category::AUDIO_DEVICE
+    }
+}
+nz_define_time_tick_for!(EmitterDeviceMetricsProcessData, false, true);
+
+impl TProcess for EmitterDeviceMetricsProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。またInputピンのリソース更新はしなくてもいい。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+
+        // もしデバイスが死んだら処理してはいけないし、処理中断する。
+        let device = self.common.systems.audio_device.as_ref().unwrap().upgrade();
+        if device.is_none() {
+            self.common.state = EProcessState::Finished;
+            return;
+        }
+        self.common.state = EProcessState::Playing;
+
+        // 間隔が経ってなければ何もしない。
+        self.internal.elapsed_time += input.common.frame_time;
+        let interval = self.info.interval.unwrap_or(1.0).max(0.0);
+        if let Some(last_emitted_time) = self.internal.last_emitted_time {
+            if (self.internal.elapsed_time - last_emitted_time) < interval {
+                return;
+            }
+        }
+
+        // critical section
+        let metrics = {
+            let device = device.as_ref().unwrap();
+            let proxy = device.lock().unwrap();
+            proxy.get_metrics()
+        };
+        let metrics = match metrics {
+            None => {
+                self.common.state = EProcessState::Finished;
+                return;
+            }
+            Some(v) => v,
+        };
+
+        self.internal.last_emitted_time = Some(self.internal.elapsed_time);
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::Text(ProcessOutputText {
+                    text: format!("{:.3}s, {}", self.internal.elapsed_time, metrics),
+                }),
+            )
+            .unwrap();
+    }
+}
+
+impl TProcessItem for EmitterDeviceMetricsProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterDeviceMetrics(v) = setting.node {
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterDeviceMetrics,
+                    systems: &system_setting,
+                }),
+                info: v.clone(),
+                internal: InternalInfo::default(),
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
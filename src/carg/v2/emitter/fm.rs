@@ -0,0 +1,195 @@
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, EmitterRange, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessProcessorInput, SItemSPtr,
+    TProcess, TProcessItem, TProcessItemPtr,
+};
+use crate::math::frequency::EFrequency;
+use crate::nz_define_time_tick_for;
+use crate::wave::sample::UniformedSample;
+use crate::wave::sine::fm::{EFmAlgorithm, FmOperatorSetting, FmRouting, FmUnitSampleEmitter};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaFmInfo {
+    /// 基本周波数。各オペレーターの周波数はこれに`ratio`をかけたもの。
+    pub frequency: EFrequency,
+    /// 全体の振幅`[0, 1]`
+    pub intensity: f64,
+    /// 2個から6個までのオペレーター
+    pub operators: Vec<FmOperatorSetting>,
+    pub algorithm: EFmAlgorithm,
+    /// エンベロープのリリースを始める時間（秒）。指定しなければ`range`の最後までSustainする。
+    #[serde(default)]
+    pub gate_length: Option<f64>,
+    pub range: EmitterRange,
+    pub sample_rate: usize,
+}
+
+/// 複数のオペレーターをアルゴリズム通りに繋いでFM合成するエミッター。
+#[derive(Debug)]
+pub struct EmitterFmProcessData {
+    common: ProcessControlItem,
+    info: MetaFmInfo,
+    routing: FmRouting,
+    sample_elapsed_time: f64,
+    emitter: Option<FmUnitSampleEmitter>,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+
+impl TPinCategory for EmitterFmProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterFmProcessData {}
+nz_define_time_tick_for!(EmitterFmProcessData, true, true);
+
+impl TProcess for EmitterFmProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。またInputピンのリソース更新はしなくてもいい。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+        if self.common.state == EProcessState::Stopped {
+            // 初期化する。
+            self.initialize();
+            assert!(self.emitter.is_some());
+        }
+
+        let buffer = self.next_samples(input);
+        if buffer.is_empty() {
+            return;
+        }
+
+        let sample_rate = self.info.sample_rate;
+        let elapsed_time = buffer.len() as f64 / sample_rate as f64;
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(buffer, sample_rate)),
+            )
+            .unwrap();
+
+        // 状態確認
+        self.sample_elapsed_time += elapsed_time;
+        if self.sample_elapsed_time < self.info.range.length {
+            self.common.state = EProcessState::Playing;
+        } else {
+            self.common.state = EProcessState::Finished;
+        }
+    }
+}
+
+impl TProcessItem for EmitterFmProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterFm(v) = setting.node {
+            if v.sample_rate == 0 {
+                return Err(anyhow::anyhow!("`sample_rate` must be bigger than 0."));
+            }
+            let routing = v.algorithm.resolve(v.operators.len())?;
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterFm,
+                    systems: &system_setting,
+                }),
+                info: v.clone(),
+                routing,
+                sample_elapsed_time: 0.0,
+                emitter: None,
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+impl EmitterFmProcessData {
+    fn initialize(&mut self) {
+        self.emitter = Some(FmUnitSampleEmitter::new(
+            self.info.frequency.to_frequency(),
+            self.info.intensity,
+            self.info.operators.clone(),
+            self.routing.clone(),
+            self.info.gate_length,
+            self.info.sample_rate,
+        ));
+    }
+
+    /// 設定のサンプル数ずつ吐き出す。ただし`range`の長さを超える分は0に埋める。
+    fn next_samples(&mut self, input: &ProcessProcessorInput) -> Vec<UniformedSample> {
+        let sample_rate = self.info.sample_rate;
+        let required_sample_count = input.get_realtime_required_samples(sample_rate);
+        if required_sample_count == 0 {
+            return vec![];
+        }
+
+        let remained_time = (self.info.range.length - self.common.elapsed_time).max(0.0);
+        let end_sample_index = ((remained_time * sample_rate as f64).ceil() as usize).min(required_sample_count);
+
+        let mut samples = self.emitter.as_mut().unwrap().next_samples(required_sample_count);
+        samples
+            .iter_mut()
+            .skip(end_sample_index)
+            .for_each(|v| *v = UniformedSample::MIN);
+        samples
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,315 @@
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, EmitterRange, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessOutputBufferStereo,
+    ProcessProcessorInput, SItemSPtr, TProcess, TProcessItem, TProcessItemPtr,
+};
+use crate::math::window::EWindowFunction;
+use crate::nz_define_time_tick_for;
+use crate::wave::container::WaveContainer;
+use crate::wave::granular::{GranularOutput, GranularSetting, GranularSynthesizer};
+use crate::wave::sample::UniformedSample;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::BufReader;
+
+/// グレインを切り出すソースの指定
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum EGranularSource {
+    /// WAVファイルを最初に全部読み込む。複数チャンネルなら平均してモノラルにする。
+    #[serde(rename = "file")]
+    File { path: String },
+    /// `in_source`ピンに入ってくるバッファを、最新の`buffer_length`秒分まで保持してソースにする。
+    #[serde(rename = "input")]
+    Input { buffer_length: f64 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaGranularInfo {
+    pub source: EGranularSource,
+    /// 1つのグレインの長さ（秒）
+    pub grain_length: f64,
+    /// 1秒に発生させるグレインの数
+    pub density: f64,
+    /// グレインを切り出すソース上の位置`[0, 1]`
+    #[serde(default)]
+    pub position: f64,
+    /// 切り出す位置をランダムにずらす最大の幅（秒）
+    #[serde(default)]
+    pub position_jitter: f64,
+    /// グレインの再生速度の比。`2`なら1オクターブ上がる。指定しなければ`1`。
+    #[serde(default)]
+    pub pitch_rate: Option<f64>,
+    /// グレインにかける窓関数。指定しなければ`hann`。
+    #[serde(default)]
+    pub window_function: Option<EWindowFunction>,
+    /// グレインごとにランダムに振るパンの幅`[0, 1]`。`out_stereo`だけに反映する。
+    #[serde(default)]
+    pub pan_spread: f64,
+    /// 全体の振幅。グレインの重なりでは正規化しない。
+    pub intensity: f64,
+    /// 位置とパンの揺らぎの乱数のシード。指定すると毎回同じ出力になる。
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub range: EmitterRange,
+    pub sample_rate: usize,
+}
+
+/// WAVファイルか入力のバッファからグレインを切り出して重ねるエミッター。
+#[derive(Debug)]
+pub struct EmitterGranularProcessData {
+    common: ProcessControlItem,
+    info: MetaGranularInfo,
+    sample_elapsed_time: f64,
+    synthesizer: Option<GranularSynthesizer>,
+    /// ファイルをソースにする時に、ノードを作る時に読み込んだモノラルのサンプルとサンプルレート
+    file_source: Option<(Vec<UniformedSample>, usize)>,
+}
+
+const INPUT_IN: &'static str = "in";
+const INPUT_SOURCE: &'static str = "in_source";
+const OUTPUT_OUT: &'static str = "out";
+const OUTPUT_OUT_STEREO: &'static str = "out_stereo";
+
+impl TPinCategory for EmitterGranularProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN, INPUT_SOURCE]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT, OUTPUT_OUT_STEREO]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            INPUT_SOURCE => Some(pin_category::BUFFER_MONO),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            OUTPUT_OUT_STEREO => Some(pin_category::BUFFER_STEREO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            INPUT_SOURCE => Some(input::container_category::BUFFER_MONO_DYNAMIC),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterGranularProcessData {}
+nz_define_time_tick_for!(EmitterGranularProcessData, true, true);
+
+impl TProcess for EmitterGranularProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。入力をソースにする時は`in_source`のバッファも更新する。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+        if self.common.state == EProcessState::Stopped {
+            // 初期化する。
+            self.initialize();
+            assert!(self.synthesizer.is_some());
+        }
+        if let EGranularSource::Input { .. } = self.info.source {
+            self.update_source();
+        }
+
+        let output = self.next_frames(input);
+        if output.mono.is_empty() {
+            return;
+        }
+
+        let sample_rate = self.info.sample_rate;
+        let elapsed_time = output.mono.len() as f64 / sample_rate as f64;
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(output.mono, sample_rate)),
+            )
+            .unwrap();
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT_STEREO,
+                EProcessOutput::BufferStereo(ProcessOutputBufferStereo {
+                    ch_left: output.left,
+                    ch_right: output.right,
+                    sample_rate,
+                }),
+            )
+            .unwrap();
+
+        // 状態確認
+        self.sample_elapsed_time += elapsed_time;
+        if self.sample_elapsed_time < self.info.range.length {
+            self.common.state = EProcessState::Playing;
+        } else {
+            self.common.state = EProcessState::Finished;
+        }
+    }
+}
+
+impl TProcessItem for EmitterGranularProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterGranular(v) = setting.node {
+            if v.sample_rate == 0 {
+                return Err(anyhow::anyhow!("`sample_rate` must be bigger than 0."));
+            }
+            if !(v.grain_length > 0.0) {
+                return Err(anyhow::anyhow!("`grain_length` must be bigger than 0."));
+            }
+            if !(v.density > 0.0) {
+                return Err(anyhow::anyhow!("`density` must be bigger than 0."));
+            }
+            if !v.pitch_rate.map_or(true, |v| v > 0.0) {
+                return Err(anyhow::anyhow!("`pitch_rate` must be bigger than 0."));
+            }
+            // ファイルは処理を始める前に全部読み込んで、読めなければエラーにする。
+            let file_source = match &v.source {
+                EGranularSource::File { path } => Some(Self::load_source(path)?),
+                EGranularSource::Input { buffer_length } => {
+                    if !(*buffer_length > 0.0) {
+                        return Err(anyhow::anyhow!("`buffer_length` must be bigger than 0."));
+                    }
+                    None
+                }
+            };
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterGranular,
+                    systems: &system_setting,
+                }),
+                info: v.clone(),
+                sample_elapsed_time: 0.0,
+                synthesizer: None,
+                file_source,
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+impl EmitterGranularProcessData {
+    fn initialize(&mut self) {
+        let setting = GranularSetting {
+            grain_length: self.info.grain_length,
+            density: self.info.density,
+            position: self.info.position,
+            position_jitter: self.info.position_jitter,
+            pitch_rate: self.info.pitch_rate.unwrap_or(1.0),
+            window_function: self.info.window_function.unwrap_or(EWindowFunction::Hann),
+            pan_spread: self.info.pan_spread,
+            intensity: self.info.intensity,
+            seed: self.info.seed,
+        };
+        let sample_rate = self.info.sample_rate;
+
+        let synthesizer = match &self.info.source {
+            EGranularSource::File { .. } => {
+                let (samples, source_sample_rate) = self.file_source.take().expect("Source file must be loaded.");
+                GranularSynthesizer::new(setting, &samples, source_sample_rate, sample_rate)
+            }
+            EGranularSource::Input { buffer_length } => {
+                // 入力のサンプルレートが分かるまでは出力と同じとみなす。
+                let max_source_length = ((buffer_length * sample_rate as f64).ceil() as usize).max(1);
+                GranularSynthesizer::with_source_capacity(setting, max_source_length, sample_rate, sample_rate)
+            }
+        };
+        self.synthesizer = Some(synthesizer);
+    }
+
+    /// WAVファイルを全部読み込んで、モノラルのサンプルとサンプルレートを返す。
+    fn load_source(path: &str) -> anyhow::Result<(Vec<UniformedSample>, usize)> {
+        let container = {
+            let file =
+                fs::File::open(path).map_err(|e| anyhow::anyhow!("Could not open sound file `{}`: {}", path, e))?;
+            let mut reader = BufReader::new(file);
+            WaveContainer::from_bufread(&mut reader)
+                .ok_or_else(|| anyhow::anyhow!("`{}` is not a supported WAV file.", path))?
+        };
+        if container.samples_per_second() == 0 {
+            return Err(anyhow::anyhow!("`{}` has no valid sample rate.", path));
+        }
+
+        let channel = container.channel().max(1) as usize;
+        let samples = container
+            .uniformed_sample_buffer()
+            .chunks(channel)
+            .map(|frame| UniformedSample::from_f64(frame.iter().map(|v| v.to_f64()).sum::<f64>() / channel as f64))
+            .collect_vec();
+        Ok((samples, container.samples_per_second() as usize))
+    }
+
+    /// `in_source`に溜まったバッファを全部ソースに移す。
+    fn update_source(&mut self) {
+        let mut input_internal = self.common.get_input_internal_mut(INPUT_SOURCE).unwrap();
+        let Some(input) = input_internal.buffer_mono_dynamic_mut() else {
+            return;
+        };
+        if !input.can_process() || input.buffer.is_empty() {
+            return;
+        }
+
+        let samples = input.buffer.drain(..).collect_vec();
+        self.synthesizer.as_mut().unwrap().push_source(&samples, input.sample_rate);
+    }
+
+    /// 設定のサンプル数ずつ吐き出す。ただし`range`の長さを超える分は0に埋める。
+    fn next_frames(&mut self, input: &ProcessProcessorInput) -> GranularOutput {
+        let sample_rate = self.info.sample_rate;
+        let required_sample_count = input.get_realtime_required_samples(sample_rate);
+        if required_sample_count == 0 {
+            return GranularOutput::default();
+        }
+
+        let remained_time = (self.info.range.length - self.common.elapsed_time).max(0.0);
+        let end_sample_index = ((remained_time * sample_rate as f64).ceil() as usize).min(required_sample_count);
+
+        let mut output = self.synthesizer.as_mut().unwrap().next_frames(required_sample_count);
+        for buffer in [&mut output.mono, &mut output.left, &mut output.right] {
+            buffer.iter_mut().skip(end_sample_index).for_each(|v| *v = UniformedSample::MIN);
+        }
+        output
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,218 @@
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessOutputBufferStereo, ProcessProcessorInput,
+    SItemSPtr, TProcess, TProcessItem, TProcessItemPtr,
+};
+use crate::midi::file::MidiFile;
+use crate::midi::synth::{EMidiVoiceWaveform, MidiSequencePlayer, MidiSynthSetting};
+use crate::nz_define_time_tick_for;
+use crate::wave::envelope::AdsrEnvelope;
+use crate::wave::sine::emitter::EOscillatorAntialias;
+use serde::{Deserialize, Serialize};
+
+/// 同時に鳴らせるボイスの数を指定しなかった時の値
+pub(super) const DEFAULT_POLYPHONY: usize = 16;
+/// 矩形波のデューティー比を指定しなかった時の値
+const DEFAULT_DUTY_RATE: f64 = 0.5;
+/// ベロシティの感度を指定しなかった時の値
+const DEFAULT_VELOCITY_SENSITIVITY: f64 = 1.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaMidiFileInfo {
+    /// Standard MIDI File（フォーマット0か1）のパス
+    pub path: String,
+    #[serde(flatten)]
+    pub synth: MetaMidiSynthInfo,
+}
+
+/// MIDIのノートでボイスを鳴らすエミッターで共通のシンセサイザーの設定
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaMidiSynthInfo {
+    /// ボイスに使うオシレーター。指定しなければサイン波。
+    #[serde(default)]
+    pub waveform: EMidiVoiceWaveform,
+    /// 矩形波のデューティー比`[0, 1]`。指定しなければ`0.5`。
+    #[serde(default)]
+    pub duty_rate: Option<f64>,
+    /// ノコギリ波・矩形波・三角波のエイリアシングの抑え方。指定しなければ何もしない。
+    #[serde(default)]
+    pub antialias: EOscillatorAntialias,
+    /// 全体の振幅`[0, 1]`
+    pub intensity: f64,
+    /// 各ボイスにかけるADSRエンベロープ
+    pub envelope: AdsrEnvelope,
+    /// 同時に鳴らせるボイスの最大数。超えたら古いボイスから止める。指定しなければ`16`。
+    #[serde(default)]
+    pub polyphony: Option<usize>,
+    /// ベロシティを振幅に反映する割合`[0, 1]`。指定しなければ`1`。
+    #[serde(default)]
+    pub velocity_sensitivity: Option<f64>,
+    pub sample_rate: usize,
+}
+
+/// MIDIファイルのノートでオシレーターのボイスを鳴らすエミッター。
+///
+/// `out`にはモノラル、`out_stereo`にはチャンネルのパン（CC#10）を反映したステレオのバッファを流す。
+#[derive(Debug)]
+pub struct EmitterMidiFileProcessData {
+    common: ProcessControlItem,
+    player: MidiSequencePlayer,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+const OUTPUT_OUT_STEREO: &'static str = "out_stereo";
+
+impl TPinCategory for EmitterMidiFileProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT, OUTPUT_OUT_STEREO]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            OUTPUT_OUT_STEREO => Some(pin_category::BUFFER_STEREO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterMidiFileProcessData {}
+nz_define_time_tick_for!(EmitterMidiFileProcessData, true, true);
+
+impl TProcess for EmitterMidiFileProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。またInputピンのリソース更新はしなくてもいい。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+
+        let sample_rate = self.player.sample_rate();
+        let frame_count = input.get_realtime_required_samples(sample_rate);
+        if frame_count == 0 {
+            return;
+        }
+
+        let output = self.player.next_frames(frame_count);
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(output.mono, sample_rate)),
+            )
+            .unwrap();
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT_STEREO,
+                EProcessOutput::BufferStereo(ProcessOutputBufferStereo {
+                    ch_left: output.left,
+                    ch_right: output.right,
+                    sample_rate,
+                }),
+            )
+            .unwrap();
+
+        // 状態確認
+        if self.player.is_finished() {
+            self.common.state = EProcessState::Finished;
+        } else {
+            self.common.state = EProcessState::Playing;
+        }
+    }
+}
+
+impl TProcessItem for EmitterMidiFileProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterMidiFile(v) = setting.node {
+            // MIDIファイルは小さいので、最初に全部読み込んでおく。
+            let midi_file = MidiFile::from_path(&v.path)?;
+            let player =
+                MidiSequencePlayer::new(v.synth.to_synth_setting()?, midi_file.timed_messages(), midi_file.duration());
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterMidiFile,
+                    systems: &system_setting,
+                }),
+                player,
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+impl MetaMidiSynthInfo {
+    /// 設定を確認して、シンセサイザーの設定を作る。
+    pub(super) fn to_synth_setting(&self) -> anyhow::Result<MidiSynthSetting> {
+        if self.sample_rate == 0 {
+            return Err(anyhow::anyhow!("`sample_rate` must be bigger than 0."));
+        }
+        if self.envelope.attack_curve <= 0.0 || self.envelope.decay_curve <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "`attack_curve` and `decay_curve` of `envelope` must be bigger than 0."
+            ));
+        }
+        if self.polyphony == Some(0) {
+            return Err(anyhow::anyhow!("`polyphony` must be bigger than 0."));
+        }
+
+        Ok(MidiSynthSetting {
+            waveform: self.waveform,
+            duty_rate: self.duty_rate.unwrap_or(DEFAULT_DUTY_RATE),
+            antialias: self.antialias,
+            intensity: self.intensity,
+            envelope: self.envelope,
+            polyphony: self.polyphony.unwrap_or(DEFAULT_POLYPHONY),
+            velocity_sensitivity: self.velocity_sensitivity.unwrap_or(DEFAULT_VELOCITY_SENSITIVITY),
+            sample_rate: self.sample_rate,
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,210 @@
+use super::midi_file::MetaMidiSynthInfo;
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{system_category, ESystemCategoryFlag, InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessOutputBufferStereo, ProcessProcessorInput,
+    SItemSPtr, TProcess, TProcessItem, TProcessItemPtr,
+};
+use crate::midi::synth::MidiSynthesizer;
+use crate::midi::EMidiMessage;
+use crate::nz_define_time_tick_for;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaMidiInputInfo {
+    /// 受け取るMIDIチャンネル`[0, 15]`。指定しなければ全部のチャンネルを受け取る。
+    #[serde(default)]
+    pub channel: Option<u8>,
+    #[serde(flatten)]
+    pub synth: MetaMidiSynthInfo,
+    /// 演奏を受け取る長さ（秒）。指定しなければ止まるまでずっと受け取る。
+    #[serde(default)]
+    pub length: Option<f64>,
+}
+
+/// ライブのMIDI入力のノートオンでボイスを鳴らして、ノートオフでエンベロープをリリースするエミッター。
+///
+/// メッセージは各処理の最初にまとめて反映するので、タイミングは処理の間隔単位になる。
+#[derive(Debug)]
+pub struct EmitterMidiInputProcessData {
+    common: ProcessControlItem,
+    info: MetaMidiInputInfo,
+    internal: InternalInfo,
+}
+
+#[derive(Debug)]
+struct InternalInfo {
+    synthesizer: MidiSynthesizer,
+    /// [`MidiInput`](crate::midi::input::MidiInput)から届いたメッセージを受け取る。
+    receiver: mpsc::Receiver<EMidiMessage>,
+    /// 今まで出力したフレームの数
+    emitted_frame_count: usize,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+const OUTPUT_OUT_STEREO: &'static str = "out_stereo";
+
+impl TPinCategory for EmitterMidiInputProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT, OUTPUT_OUT_STEREO]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            OUTPUT_OUT_STEREO => Some(pin_category::BUFFER_STEREO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterMidiInputProcessData {
+    fn get_dependent_system_categories() -> ESystemCategoryFlag {
+        system_category::REALTIME_TRIGGER_SYSTEM
+    }
+}
+nz_define_time_tick_for!(EmitterMidiInputProcessData, false, true);
+
+impl TProcess for EmitterMidiInputProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。またInputピンのリソース更新はしなくてもいい。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+
+        // 前の処理から今までに届いたメッセージを全部反映する。
+        for message in self.internal.receiver.try_iter() {
+            if self.info.channel.map_or(true, |v| v == message.channel()) {
+                self.internal.synthesizer.handle_message(&message);
+            }
+        }
+
+        let sample_rate = self.info.synth.sample_rate;
+        let mut frame_count = input.get_realtime_required_samples(sample_rate);
+
+        // 指定した長さを超える分は出力しない。
+        let mut is_finished = false;
+        if let Some(length) = self.info.length {
+            let max_frame_count = ((sample_rate as f64) * length.max(0.0)).ceil() as usize;
+            let remained_frame_count = max_frame_count.saturating_sub(self.internal.emitted_frame_count);
+            if frame_count >= remained_frame_count {
+                frame_count = remained_frame_count;
+                is_finished = true;
+            }
+        }
+        if frame_count == 0 && !is_finished {
+            return;
+        }
+        self.internal.emitted_frame_count += frame_count;
+
+        let output = self.internal.synthesizer.next_frames(frame_count);
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(output.mono, sample_rate)),
+            )
+            .unwrap();
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT_STEREO,
+                EProcessOutput::BufferStereo(ProcessOutputBufferStereo {
+                    ch_left: output.left,
+                    ch_right: output.right,
+                    sample_rate,
+                }),
+            )
+            .unwrap();
+
+        // 状態確認
+        if is_finished {
+            self.common.state = EProcessState::Finished;
+        } else {
+            self.common.state = EProcessState::Playing;
+        }
+    }
+}
+
+impl TProcessItem for EmitterMidiInputProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterMidiInput(v) = setting.node {
+            if v.channel.map_or(false, |v| v >= 16) {
+                return Err(anyhow::anyhow!("`channel` must be in [0, 15]."));
+            }
+            let synth_setting = v.synth.to_synth_setting()?;
+
+            // 作った時から届いたメッセージを受け取る。
+            let receiver = system_setting
+                .midi_input
+                .as_ref()
+                .and_then(|v| v.upgrade())
+                .ok_or_else(|| anyhow::anyhow!("MidiInput system must be initialized."))?
+                .lock()
+                .unwrap()
+                .subscribe();
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterMidiInput,
+                    systems: &system_setting,
+                }),
+                info: v.clone(),
+                internal: InternalInfo {
+                    synthesizer: MidiSynthesizer::new(synth_setting),
+                    receiver,
+                    emitted_frame_count: 0,
+                },
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -4,6 +4,16 @@ pub mod ifft;
 pub mod wav_mono;
 pub mod sine_sweep;
 pub mod wav_stereo;
+pub mod device_input;
+pub mod device_metrics;
+pub mod midi_file;
+pub mod midi_input;
+pub mod fm;
+pub mod wavetable;
+pub mod additive;
+pub mod pluck;
+pub mod granular;
+pub mod test_signal;
 
 // ----------------------------------------------------------------------------
 // EOF
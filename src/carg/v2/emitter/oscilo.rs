@@ -11,17 +11,63 @@ use crate::carg::v2::{
 use crate::nz_define_time_tick_for;
 use crate::{
     math::frequency::EFrequency,
-    wave::{sample::UniformedSample, sine::emitter::SineUnitSampleEmitter},
+    wave::{
+        sample::UniformedSample,
+        sine::emitter::{
+            ENoiseColor, ENoiseDistribution, EOscillatorAntialias, SineUnitSampleEmitter, DEFAULT_VELVET_DENSITY,
+        },
+    },
 };
 use serde::{Deserialize, Serialize};
 
+/// `in_freq`ピンの値が`1`の時に上げる音程（半音）の既定値
+const DEFAULT_PITCH_RANGE: f64 = 12.0;
+
 /// ノイズなタイプの設定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetaSineNoiseInfo {
     /// `[0, 1]`まで
     intensity: f64,
     range: EmitterRange,
-    sample_rate: usize,
+    pub(crate) sample_rate: usize,
+    /// 乱数のシード。指定すると毎回同じノイズを出力する。
+    #[serde(default)]
+    seed: Option<u64>,
+    /// 元にする乱数の分布。ベルベットノイズでは使わない。
+    #[serde(default)]
+    distribution: ENoiseDistribution,
+    /// ベルベットノイズの1秒あたりのインパルスの数`(0, sample_rate]`。他のノイズでは使わない。指定しなければ`2000`。
+    #[serde(default)]
+    density: Option<f64>,
+}
+
+/// ノイズではないタイプの、`in_freq`と`in_amp`ピンで変調する時の設定
+///
+/// `in_freq`ピンの値`v`で周波数を`frequency * 2^(v * pitch_range / 12)`に、
+/// `in_amp`ピンの値`v`で振幅を`intensity * v`にサンプルごとに変える。
+/// 変調のバッファは1サンプルずつ読むので、出力と同じサンプルレートで入れること。
+/// バッファが足りない分は最後の値のままにする。
+/// 変調で振幅が1を超えた分は、出力する時に`[-1, 1]`にクランプされる。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetaOscillatorModulationInfo {
+    /// `in_freq`ピンの値が`1`の時に上げる音程（半音）。指定しなければ`12`（1オクターブ）。
+    #[serde(default)]
+    pitch_range: Option<f64>,
+    /// `in_freq`ピンで周波数が変わった時に、新しい周波数まで滑らかに変える時間（秒）。`0`ならすぐに変わる。
+    #[serde(default)]
+    glide: f64,
+}
+
+impl MetaOscillatorModulationInfo {
+    fn validate(&self) -> anyhow::Result<()> {
+        if !(self.glide >= 0.0) {
+            return Err(anyhow::anyhow!("`glide` must be 0 or bigger."));
+        }
+        if !self.pitch_range.map_or(true, f64::is_finite) {
+            return Err(anyhow::anyhow!("`pitch_range` must be a finite value."));
+        }
+        Ok(())
+    }
 }
 
 /// ノイズではないタイプの設定
@@ -31,7 +77,12 @@ pub struct MetaSineEmitterInfo {
     /// `[0, 1]`まで
     intensity: f64,
     range: EmitterRange,
-    sample_rate: usize,
+    pub(crate) sample_rate: usize,
+    /// ノコギリ波と三角波のエイリアシングの抑え方。サイン波では使わない。
+    #[serde(default)]
+    antialias: EOscillatorAntialias,
+    #[serde(flatten)]
+    modulation: MetaOscillatorModulationInfo,
 }
 
 /// 矩形波の設定
@@ -43,13 +94,17 @@ pub struct MetaSineSquareInfo {
     /// `[0, 1]`まで
     intensity: f64,
     range: EmitterRange,
-    sample_rate: usize,
+    pub(crate) sample_rate: usize,
+    /// エイリアシングの抑え方
+    #[serde(default)]
+    antialias: EOscillatorAntialias,
+    #[serde(flatten)]
+    modulation: MetaOscillatorModulationInfo,
 }
 
 #[derive(Debug, Clone)]
 pub enum ESineWaveEmitterType {
-    PinkNoise(MetaSineNoiseInfo),
-    WhiteNoise(MetaSineNoiseInfo),
+    Noise(ENoiseColor, MetaSineNoiseInfo),
     Sine(MetaSineEmitterInfo),
     Saw(MetaSineEmitterInfo),
     Triangle(MetaSineEmitterInfo),
@@ -59,8 +114,7 @@ pub enum ESineWaveEmitterType {
 impl ESineWaveEmitterType {
     pub fn range_length(&self) -> f64 {
         match self {
-            ESineWaveEmitterType::PinkNoise(v) => v.range.length,
-            ESineWaveEmitterType::WhiteNoise(v) => v.range.length,
+            ESineWaveEmitterType::Noise(_, v) => v.range.length,
             ESineWaveEmitterType::Sine(v) => v.range.length,
             ESineWaveEmitterType::Saw(v) => v.range.length,
             ESineWaveEmitterType::Triangle(v) => v.range.length,
@@ -68,10 +122,51 @@ impl ESineWaveEmitterType {
         }
     }
 
+    /// ノイズとサイン波なら[`EOscillatorAntialias::None`]を返す。
+    pub fn antialias(&self) -> EOscillatorAntialias {
+        match self {
+            ESineWaveEmitterType::Saw(v) => v.antialias,
+            ESineWaveEmitterType::Triangle(v) => v.antialias,
+            ESineWaveEmitterType::Square(v) => v.antialias,
+            _ => EOscillatorAntialias::None,
+        }
+    }
+
+    /// ノイズなら`None`を返す。
+    fn modulation(&self) -> Option<&MetaOscillatorModulationInfo> {
+        match self {
+            ESineWaveEmitterType::Noise(..) => None,
+            ESineWaveEmitterType::Sine(v) => Some(&v.modulation),
+            ESineWaveEmitterType::Saw(v) => Some(&v.modulation),
+            ESineWaveEmitterType::Triangle(v) => Some(&v.modulation),
+            ESineWaveEmitterType::Square(v) => Some(&v.modulation),
+        }
+    }
+
+    /// ノイズなら`None`を返す。
+    fn frequency(&self) -> Option<f64> {
+        match self {
+            ESineWaveEmitterType::Noise(..) => None,
+            ESineWaveEmitterType::Sine(v) => Some(v.frequency.to_frequency()),
+            ESineWaveEmitterType::Saw(v) => Some(v.frequency.to_frequency()),
+            ESineWaveEmitterType::Triangle(v) => Some(v.frequency.to_frequency()),
+            ESineWaveEmitterType::Square(v) => Some(v.frequency.to_frequency()),
+        }
+    }
+
+    fn intensity(&self) -> f64 {
+        match self {
+            ESineWaveEmitterType::Noise(_, v) => v.intensity,
+            ESineWaveEmitterType::Sine(v) => v.intensity,
+            ESineWaveEmitterType::Saw(v) => v.intensity,
+            ESineWaveEmitterType::Triangle(v) => v.intensity,
+            ESineWaveEmitterType::Square(v) => v.intensity,
+        }
+    }
+
     pub fn sample_rate(&self) -> usize {
         match self {
-            ESineWaveEmitterType::PinkNoise(v) => v.sample_rate,
-            ESineWaveEmitterType::WhiteNoise(v) => v.sample_rate,
+            ESineWaveEmitterType::Noise(_, v) => v.sample_rate,
             ESineWaveEmitterType::Sine(v) => v.sample_rate,
             ESineWaveEmitterType::Saw(v) => v.sample_rate,
             ESineWaveEmitterType::Triangle(v) => v.sample_rate,
@@ -89,9 +184,17 @@ pub struct SineWaveEmitterProcessData {
     sample_elapsed_time: f64,
     /// 波形を出力するEmitter。
     emitter: Option<SineUnitSampleEmitter>,
+    /// `in_freq`ピンから最後に読んだ値
+    last_pitch_modulation: f64,
+    /// `in_amp`ピンから最後に読んだ値
+    last_amplitude_modulation: f64,
+    /// `in_freq`ピンで最後に変えた周波数
+    modulated_frequency: f64,
 }
 
 const INPUT_IN: &'static str = "in";
+const INPUT_FREQ: &'static str = "in_freq";
+const INPUT_AMP: &'static str = "in_amp";
 const OUTPUT_OUT: &'static str = "out";
 
 impl TProcessItem for SineWaveEmitterProcessData {
@@ -112,19 +215,48 @@ impl TProcessItem for SineWaveEmitterProcessData {
                 let item = SineWaveEmitterProcessData::new_white(v, setting.setting.clone(), &system_setting);
                 SItemSPtr::new(item)
             }
+            ENode::EmitterBrownNoise(v) => {
+                let item = SineWaveEmitterProcessData::new_brown(v, setting.setting.clone(), &system_setting);
+                SItemSPtr::new(item)
+            }
+            ENode::EmitterBlueNoise(v) => {
+                let item = SineWaveEmitterProcessData::new_blue(v, setting.setting.clone(), &system_setting);
+                SItemSPtr::new(item)
+            }
+            ENode::EmitterVioletNoise(v) => {
+                let item = SineWaveEmitterProcessData::new_violet(v, setting.setting.clone(), &system_setting);
+                SItemSPtr::new(item)
+            }
+            ENode::EmitterVelvetNoise(v) => {
+                // 1サンプルに1つより多くはインパルスを置けない。
+                let density = v.density.unwrap_or(DEFAULT_VELVET_DENSITY);
+                if !(density > 0.0 && density <= v.sample_rate as f64) {
+                    return Err(anyhow::anyhow!(
+                        "`density` must be in (0, {}] for sample rate {}Hz.",
+                        v.sample_rate,
+                        v.sample_rate
+                    ));
+                }
+                let item = SineWaveEmitterProcessData::new_velvet(v, setting.setting.clone(), &system_setting);
+                SItemSPtr::new(item)
+            }
             ENode::EmitterSineWave(v) => {
+                v.modulation.validate()?;
                 let item = SineWaveEmitterProcessData::new_sine(v, setting.setting.clone(), &system_setting);
                 SItemSPtr::new(item)
             }
             ENode::EmitterSawtooth(v) => {
+                v.modulation.validate()?;
                 let item = SineWaveEmitterProcessData::new_saw(v, setting.setting.clone(), &system_setting);
                 SItemSPtr::new(item)
             }
             ENode::EmitterTriangle(v) => {
+                v.modulation.validate()?;
                 let item = SineWaveEmitterProcessData::new_triangle(v, setting.setting.clone(), &system_setting);
                 SItemSPtr::new(item)
             }
             ENode::EmitterSquare(v) => {
+                v.modulation.validate()?;
                 let item = SineWaveEmitterProcessData::new_square(v, setting.setting.clone(), &system_setting);
                 SItemSPtr::new(item)
             }
@@ -136,29 +268,89 @@ impl TProcessItem for SineWaveEmitterProcessData {
 impl SineWaveEmitterProcessData {
     /// ピンクノイズの生成
     fn new_pink(info: &MetaSineNoiseInfo, setting: Setting, system_setting: &InitializeSystemAccessor) -> Self {
-        Self {
-            common: ProcessControlItem::new(ProcessControlItemSetting {
-                specifier: ENodeSpecifier::EmitterPinkNoise,
-                systems: &system_setting,
-            }),
-            emitter_type: ESineWaveEmitterType::PinkNoise(info.clone()),
-            sample_elapsed_time: 0.0,
+        Self::new_noise(
+            ENoiseColor::Pink,
+            ENodeSpecifier::EmitterPinkNoise,
+            info,
             setting,
-            emitter: None,
-        }
+            system_setting,
+        )
     }
 
     /// ホワイトノイズの生成
     fn new_white(info: &MetaSineNoiseInfo, setting: Setting, system_accessor: &InitializeSystemAccessor) -> Self {
+        Self::new_noise(
+            ENoiseColor::White,
+            ENodeSpecifier::EmitterWhiteNoise,
+            info,
+            setting,
+            system_accessor,
+        )
+    }
+
+    /// ブラウンノイズの生成
+    fn new_brown(info: &MetaSineNoiseInfo, setting: Setting, system_accessor: &InitializeSystemAccessor) -> Self {
+        Self::new_noise(
+            ENoiseColor::Brown,
+            ENodeSpecifier::EmitterBrownNoise,
+            info,
+            setting,
+            system_accessor,
+        )
+    }
+
+    /// ブルーノイズの生成
+    fn new_blue(info: &MetaSineNoiseInfo, setting: Setting, system_accessor: &InitializeSystemAccessor) -> Self {
+        Self::new_noise(
+            ENoiseColor::Blue,
+            ENodeSpecifier::EmitterBlueNoise,
+            info,
+            setting,
+            system_accessor,
+        )
+    }
+
+    /// バイオレットノイズの生成
+    fn new_violet(info: &MetaSineNoiseInfo, setting: Setting, system_accessor: &InitializeSystemAccessor) -> Self {
+        Self::new_noise(
+            ENoiseColor::Violet,
+            ENodeSpecifier::EmitterVioletNoise,
+            info,
+            setting,
+            system_accessor,
+        )
+    }
+
+    /// ベルベットノイズの生成
+    fn new_velvet(info: &MetaSineNoiseInfo, setting: Setting, system_accessor: &InitializeSystemAccessor) -> Self {
+        Self::new_noise(
+            ENoiseColor::Velvet,
+            ENodeSpecifier::EmitterVelvetNoise,
+            info,
+            setting,
+            system_accessor,
+        )
+    }
+
+    fn new_noise(
+        color: ENoiseColor,
+        specifier: ENodeSpecifier,
+        info: &MetaSineNoiseInfo,
+        setting: Setting,
+        system_accessor: &InitializeSystemAccessor,
+    ) -> Self {
         Self {
             common: ProcessControlItem::new(ProcessControlItemSetting {
-                specifier: ENodeSpecifier::EmitterWhiteNoise,
+                specifier,
                 systems: &system_accessor,
             }),
-            emitter_type: ESineWaveEmitterType::WhiteNoise(info.clone()),
+            emitter_type: ESineWaveEmitterType::Noise(color, info.clone()),
             sample_elapsed_time: 0.0,
             setting,
             emitter: None,
+            last_pitch_modulation: 0.0,
+            last_amplitude_modulation: 1.0,
+            modulated_frequency: 0.0,
         }
     }
 
@@ -173,6 +365,9 @@ impl SineWaveEmitterProcessData {
             sample_elapsed_time: 0.0,
             setting,
             emitter: None,
+            last_pitch_modulation: 0.0,
+            last_amplitude_modulation: 1.0,
+            modulated_frequency: 0.0,
         }
     }
 
@@ -187,6 +382,9 @@ impl SineWaveEmitterProcessData {
             sample_elapsed_time: 0.0,
             setting,
             emitter: None,
+            last_pitch_modulation: 0.0,
+            last_amplitude_modulation: 1.0,
+            modulated_frequency: 0.0,
         }
     }
 
@@ -201,6 +399,9 @@ impl SineWaveEmitterProcessData {
             sample_elapsed_time: 0.0,
             setting,
             emitter: None,
+            last_pitch_modulation: 0.0,
+            last_amplitude_modulation: 1.0,
+            modulated_frequency: 0.0,
         }
     }
 
@@ -215,6 +416,9 @@ impl SineWaveEmitterProcessData {
             setting,
             emitter: None,
             sample_elapsed_time: 0.0,
+            last_pitch_modulation: 0.0,
+            last_amplitude_modulation: 1.0,
+            modulated_frequency: 0.0,
         }
     }
 }
@@ -222,7 +426,7 @@ impl SineWaveEmitterProcessData {
 impl TPinCategory for SineWaveEmitterProcessData {
     /// 処理ノード（[`ProcessControlItem`]）に必要な、ノードの入力側のピンの名前を返す。
     fn get_input_pin_names() -> Vec<&'static str> {
-        vec![INPUT_IN]
+        vec![INPUT_IN, INPUT_FREQ, INPUT_AMP]
     }
 
     /// 処理ノード（[`ProcessControlItem`]）に必要な、ノードの出力側のピンの名前を返す。
@@ -234,6 +438,8 @@ impl TPinCategory for SineWaveEmitterProcessData {
     fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
         match pin_name {
             INPUT_IN => Some(pin_category::START),
+            INPUT_FREQ => Some(pin_category::BUFFER_MONO),
+            INPUT_AMP => Some(pin_category::BUFFER_MONO),
             OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
             _ => None,
         }
@@ -243,6 +449,8 @@ impl TPinCategory for SineWaveEmitterProcessData {
     fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
         match pin_name {
             INPUT_IN => Some(input::container_category::EMPTY),
+            INPUT_FREQ => Some(input::container_category::BUFFER_MONO_DYNAMIC),
+            INPUT_AMP => Some(input::container_category::BUFFER_MONO_DYNAMIC),
             _ => None,
         }
     }
@@ -251,9 +459,22 @@ impl TPinCategory for SineWaveEmitterProcessData {
 impl SineWaveEmitterProcessData {
     /// 初期化する
     fn initialize(&mut self) {
-        let emitter = match &self.emitter_type {
-            ESineWaveEmitterType::PinkNoise(v) => SineUnitSampleEmitter::new_pinknoise(v.intensity),
-            ESineWaveEmitterType::WhiteNoise(v) => SineUnitSampleEmitter::new_whitenoise(v.intensity),
+        let mut emitter = match &self.emitter_type {
+            ESineWaveEmitterType::Noise(color, v) => {
+                let mut emitter = match color {
+                    ENoiseColor::Velvet => SineUnitSampleEmitter::new_velvetnoise(
+                        v.density.unwrap_or(DEFAULT_VELVET_DENSITY),
+                        v.intensity,
+                        v.sample_rate,
+                    ),
+                    _ => SineUnitSampleEmitter::new_noise(*color, v.intensity, v.sample_rate),
+                };
+                emitter.set_noise_distribution(v.distribution);
+                if let Some(seed) = v.seed {
+                    emitter.set_seed(seed);
+                }
+                emitter
+            }
             ESineWaveEmitterType::Sine(v) => {
                 SineUnitSampleEmitter::new_sine(v.frequency.to_frequency(), 0.0, v.intensity, v.sample_rate)
             }
@@ -271,9 +492,76 @@ impl SineWaveEmitterProcessData {
                 v.sample_rate,
             ),
         };
+        emitter.set_antialias(self.emitter_type.antialias());
+        if let Some(modulation) = self.emitter_type.modulation() {
+            emitter.set_glide_time(modulation.glide);
+        }
+        self.modulated_frequency = self.emitter_type.frequency().unwrap_or(0.0);
         self.emitter = Some(emitter);
     }
 
+    /// `pin_name`の変調ピンから`count`サンプル分の値を取り出す。ピンが繋がっていなければ`None`を返す。
+    /// バッファが足りない分は`last_value`か、取り出した最後の値で埋める。
+    fn drain_modulation(&mut self, pin_name: &str, count: usize, last_value: f64) -> Option<Vec<f64>> {
+        if !self.common.is_input_pin_connected(pin_name) {
+            return None;
+        }
+
+        let mut values = vec![];
+        let mut input_internal = self.common.get_input_internal_mut(pin_name).unwrap();
+        if let Some(input) = input_internal.buffer_mono_dynamic_mut() {
+            let drain_count = count.min(input.buffer.len());
+            values.extend(input.buffer.drain(..drain_count).map(|v| v.to_f64()));
+        }
+        let last_value = values.last().copied().unwrap_or(last_value);
+        values.resize(count, last_value);
+        Some(values)
+    }
+
+    /// 変調ピンの値をサンプルごとに反映しながら、`count`サンプルを生成する。
+    /// ノイズか、変調ピンが繋がっていなければ設定のままで生成する。
+    fn next_emitter_samples(&mut self, count: usize) -> Vec<UniformedSample> {
+        let Some(pitch_range) = self
+            .emitter_type
+            .modulation()
+            .map(|v| v.pitch_range.unwrap_or(DEFAULT_PITCH_RANGE))
+        else {
+            return self.emitter.as_mut().unwrap().next_samples(count);
+        };
+        let pitches = self.drain_modulation(INPUT_FREQ, count, self.last_pitch_modulation);
+        let amplitudes = self.drain_modulation(INPUT_AMP, count, self.last_amplitude_modulation);
+        if pitches.is_none() && amplitudes.is_none() {
+            return self.emitter.as_mut().unwrap().next_samples(count);
+        }
+
+        let base_frequency = self.emitter_type.frequency().unwrap();
+        let intensity = self.emitter_type.intensity();
+        let emitter = self.emitter.as_mut().unwrap();
+        let mut samples = Vec::with_capacity(count);
+        for i in 0..count {
+            if let Some(pitches) = &pitches {
+                // グライド中に同じ値で変え直さないように、値が変わった時だけ周波数を変える。
+                let frequency = base_frequency * 2f64.powf(pitches[i] * pitch_range / 12.0);
+                if frequency != self.modulated_frequency {
+                    emitter.set_frequency(frequency);
+                    self.modulated_frequency = frequency;
+                }
+            }
+            if let Some(amplitudes) = &amplitudes {
+                emitter.set_intensity(intensity * amplitudes[i]);
+            }
+            samples.push(emitter.next_sample());
+        }
+
+        if let Some(v) = pitches.and_then(|v| v.last().copied()) {
+            self.last_pitch_modulation = v;
+        }
+        if let Some(v) = amplitudes.and_then(|v| v.last().copied()) {
+            self.last_amplitude_modulation = v;
+        }
+        samples
+    }
+
     /// 初期化した情報から設定分のOutputを更新する。
     fn next_samples(&mut self, input: &ProcessProcessorInput) -> Vec<UniformedSample> {
         assert!(self.emitter.is_some());
@@ -295,14 +583,13 @@ impl SineWaveEmitterProcessData {
                 add_time = range_length - self.common.elapsed_time;
             }
 
+            // 時間からサンプル数に戻すと誤差で1つ多くなることがあるので、要求した分までにする。
             let sample_rate = self.emitter_type.sample_rate();
             let samples = (add_time * sample_rate as f64).ceil() as usize;
-
-            debug_assert!(samples <= required_sample_count);
-            samples
+            samples.min(required_sample_count)
         };
 
-        let mut samples = self.emitter.as_mut().unwrap().next_samples(required_sample_count);
+        let mut samples = self.next_emitter_samples(required_sample_count);
         if end_sample_index < samples.len() {
             // [end_sample_index, len())までに0に埋める。
             samples
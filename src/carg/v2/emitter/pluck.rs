@@ -0,0 +1,290 @@
+use super::midi_file::DEFAULT_POLYPHONY;
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessProcessorInput, SItemSPtr, TProcess,
+    TProcessItem, TProcessItemPtr,
+};
+use crate::math::frequency::EFrequency;
+use crate::midi::file::{MidiFile, MidiTimedMessage};
+use crate::midi::{key_to_frequency, EMidiMessage};
+use crate::nz_define_time_tick_for;
+use crate::wave::sine::pluck::{EPluckExcitation, PluckNote, PluckSequencePlayer, PluckSetting, PluckString};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// ダンピングフィルターの係数を指定しなかった時の値
+const DEFAULT_DAMPING: f64 = 0.5;
+
+/// 弦を弾くタイミングの指定
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum EPluckTrigger {
+    /// 指定した時間にノートを弾く。
+    #[serde(rename = "notes")]
+    Notes { notes: Vec<MetaPluckNote> },
+    /// Standard MIDI Fileのノートオンで弾いて、ノートオフで止める。ベロシティは振幅に反映する。
+    /// サンプルレートの4分の1より高いノートは弾けないので飛ばす。
+    #[serde(rename = "midi_file")]
+    MidiFile {
+        path: String,
+        /// 受け取るMIDIチャンネル`[0, 15]`。指定しなければ全部のチャンネルを受け取る。
+        #[serde(default)]
+        channel: Option<u8>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaPluckNote {
+    /// 弾く時間（秒）
+    pub time: f64,
+    /// 弦の周波数。サンプルレートの4分の1より低くすること。
+    pub frequency: EFrequency,
+    /// 振幅`[0, 1]`
+    pub intensity: f64,
+    /// ノートオフまでの時間（秒）。指定しなければ自然に減衰するまで鳴らす。
+    #[serde(default)]
+    pub length: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaPluckInfo {
+    pub trigger: EPluckTrigger,
+    /// 最初に弦に入れるノイズ。指定しなければホワイトノイズ。
+    #[serde(default)]
+    pub excitation: EPluckExcitation,
+    /// ループのダンピングフィルターの係数`(0, 1)`。指定しなければ`0.5`。
+    #[serde(default)]
+    pub damping: Option<f64>,
+    /// 基本周波数の振幅が-60dBまで減衰する時間（秒）。指定しなければダンピングフィルターだけで減衰する。
+    #[serde(default)]
+    pub decay_time: Option<f64>,
+    /// 全体の振幅`[0, 1]`
+    pub intensity: f64,
+    /// 同時に鳴らせる弦の最大数。超えたら古い弦から止める。指定しなければ`16`。
+    #[serde(default)]
+    pub polyphony: Option<usize>,
+    /// 弦に入れるノイズの乱数のシード。指定すると毎回同じ音を出力する。
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub sample_rate: usize,
+}
+
+/// 拡張Karplus-Strong法で弦を弾いた音を出すエミッター。
+#[derive(Debug)]
+pub struct EmitterPluckProcessData {
+    common: ProcessControlItem,
+    player: PluckSequencePlayer,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+
+impl TPinCategory for EmitterPluckProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterPluckProcessData {}
+nz_define_time_tick_for!(EmitterPluckProcessData, true, true);
+
+impl TProcess for EmitterPluckProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。またInputピンのリソース更新はしなくてもいい。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+
+        let sample_rate = self.player.sample_rate();
+        let frame_count = input.get_realtime_required_samples(sample_rate);
+        if frame_count == 0 {
+            return;
+        }
+
+        let buffer = self.player.next_frames(frame_count);
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(buffer, sample_rate)),
+            )
+            .unwrap();
+
+        // 状態確認
+        if self.player.is_finished() {
+            self.common.state = EProcessState::Finished;
+        } else {
+            self.common.state = EProcessState::Playing;
+        }
+    }
+}
+
+impl TProcessItem for EmitterPluckProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterPluck(v) = setting.node {
+            if v.sample_rate == 0 {
+                return Err(anyhow::anyhow!("`sample_rate` must be bigger than 0."));
+            }
+            if v.polyphony == Some(0) {
+                return Err(anyhow::anyhow!("`polyphony` must be bigger than 0."));
+            }
+            if v.damping.map_or(false, |v| v <= 0.0 || v >= 1.0) {
+                return Err(anyhow::anyhow!("`damping` must be in (0, 1)."));
+            }
+
+            let setting = PluckSetting {
+                excitation: v.excitation,
+                damping: v.damping.unwrap_or(DEFAULT_DAMPING),
+                decay_time: v.decay_time,
+                seed: v.seed,
+            };
+            let player = PluckSequencePlayer::new(
+                setting,
+                v.intensity,
+                v.polyphony.unwrap_or(DEFAULT_POLYPHONY),
+                v.trigger.create_notes(v.sample_rate)?,
+                v.sample_rate,
+            );
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterPluck,
+                    systems: &system_setting,
+                }),
+                player,
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+impl EPluckTrigger {
+    /// `sample_rate`で弾くノートを作る。
+    fn create_notes(&self, sample_rate: usize) -> anyhow::Result<Vec<PluckNote>> {
+        match self {
+            EPluckTrigger::Notes { notes } => notes
+                .iter()
+                .map(|v| {
+                    let frequency = v.frequency.to_frequency();
+                    if !PluckString::can_pluck(frequency, sample_rate) {
+                        return Err(anyhow::anyhow!(
+                            "`frequency` {}Hz of the note at {}s must be in (0, {}Hz) for sample rate {}Hz.",
+                            frequency,
+                            v.time,
+                            sample_rate as f64 * 0.25,
+                            sample_rate
+                        ));
+                    }
+
+                    Ok(PluckNote {
+                        time: v.time,
+                        frequency,
+                        intensity: v.intensity,
+                        length: v.length,
+                    })
+                })
+                .collect(),
+            EPluckTrigger::MidiFile { path, channel } => {
+                if channel.map_or(false, |v| v >= 16) {
+                    return Err(anyhow::anyhow!("`channel` must be in [0, 15]."));
+                }
+
+                // MIDIファイルは小さいので、最初に全部読み込んでおく。
+                let midi_file = MidiFile::from_path(path)?;
+                let mut notes = notes_from_messages(&midi_file.timed_messages(), *channel);
+                notes.retain(|v| PluckString::can_pluck(v.frequency, sample_rate));
+                Ok(notes)
+            }
+        }
+    }
+}
+
+/// MIDIのノートオンとノートオフの組をノートにする。ノートオフがないノートは自然に減衰させる。
+fn notes_from_messages(messages: &[MidiTimedMessage], channel: Option<u8>) -> Vec<PluckNote> {
+    let mut notes: Vec<PluckNote> = vec![];
+    // (チャンネル, ノート番号)から、鳴らしているノートのインデックス
+    let mut playing_notes: HashMap<(u8, u8), usize> = HashMap::new();
+
+    for timed_message in messages {
+        let message = timed_message.message;
+        if channel.map_or(false, |v| v != message.channel()) {
+            continue;
+        }
+
+        match message {
+            EMidiMessage::NoteOn { channel, key, velocity } => {
+                playing_notes.insert((channel, key), notes.len());
+                notes.push(PluckNote {
+                    time: timed_message.time,
+                    frequency: key_to_frequency(key),
+                    intensity: velocity as f64 / 127.0,
+                    length: None,
+                });
+            }
+            EMidiMessage::NoteOff { channel, key, .. } => {
+                if let Some(note_i) = playing_notes.remove(&(channel, key)) {
+                    let note = &mut notes[note_i];
+                    note.length = Some(timed_message.time - note.time);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    notes
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -12,6 +12,8 @@ use crate::carg::v2::{
 use crate::nz_define_time_tick_for;
 use crate::wave::sample::UniformedSample;
 use crate::wave::sine::emitter::SineUnitSampleEmitter;
+use crate::wave::sine::sweep::{ESineSweepMode, SineSweepSetting};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use soundprog::math::frequency::EFrequency;
 
@@ -20,9 +22,36 @@ use soundprog::math::frequency::EFrequency;
 pub struct MetaSineSweepInfo {
     from_frequency: EFrequency,
     to_frequency: EFrequency,
+    /// 周波数の変え方。指定しなければ`linear`。
+    #[serde(default)]
+    mode: ESineSweepMode,
+    /// 最初にかけるフェードインの長さ（秒）
+    #[serde(default)]
+    fade_in: f64,
+    /// 最後にかけるフェードアウトの長さ（秒）
+    #[serde(default)]
+    fade_out: f64,
+    /// `true`ならスイープの代わりに、スイープを逆畳み込みするための逆フィルターを出力する。
+    /// 逆フィルターは振幅の最大が`intensity`になるように正規化する。
+    #[serde(default)]
+    inverse: bool,
     range: EmitterRange,
     intensity: f64,
-    sample_rate: usize,
+    pub(crate) sample_rate: usize,
+}
+
+impl MetaSineSweepInfo {
+    /// スイープの長さは`range`の長さにする。
+    fn sweep_setting(&self) -> SineSweepSetting {
+        SineSweepSetting {
+            from_frequency: self.from_frequency.to_frequency(),
+            to_frequency: self.to_frequency.to_frequency(),
+            length: self.range.length,
+            mode: self.mode,
+            fade_in: self.fade_in,
+            fade_out: self.fade_out,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -34,6 +63,8 @@ pub struct SineSweepEmitterProcessData {
     sample_elapsed_time: f64,
     /// 波形を出力するEmitter。
     emitter: Option<SineUnitSampleEmitter>,
+    /// `inverse`の時に出力する逆フィルターと、次に出力するサンプルのインデックス。
+    inverse_filter: Option<(Vec<f64>, usize)>,
 }
 
 const INPUT_IN: &'static str = "in";
@@ -91,6 +122,7 @@ impl TProcessItem for SineSweepEmitterProcessData {
                 info: v.clone(),
                 sample_elapsed_time: 0.0,
                 emitter: None,
+                inverse_filter: None,
             };
 
             return Ok(SItemSPtr::new(item));
@@ -165,13 +197,18 @@ impl TProcess for SineSweepEmitterProcessData {
 impl SineSweepEmitterProcessData {
     /// 初期化する
     fn initialize(&mut self) {
-        let emitter = SineUnitSampleEmitter::new_sinesweep(
-            self.info.from_frequency.to_frequency(),
-            self.info.to_frequency.to_frequency(),
-            self.info.range.length,
-            self.info.intensity,
-            self.info.sample_rate,
-        );
+        let setting = self.info.sweep_setting();
+        if self.info.inverse {
+            // そのままだと振幅がとても小さくてWAVに書き出すと精度が落ちるので、最大を1にする。
+            let mut filter = setting.inverse_filter(self.info.sample_rate);
+            let peak = filter.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+            if peak > 0.0 {
+                filter.iter_mut().for_each(|v| *v /= peak);
+            }
+            self.inverse_filter = Some((filter, 0));
+        }
+
+        let emitter = SineUnitSampleEmitter::new_sinesweep_with(setting, self.info.intensity, self.info.sample_rate);
         self.emitter = Some(emitter);
     }
 
@@ -197,13 +234,22 @@ impl SineSweepEmitterProcessData {
                 add_time = range_length - self.common.elapsed_time;
             }
 
+            // 時間からサンプル数に戻すと誤差で1つ多くなることがあるので、要求した分までにする。
             let samples = (add_time * sample_rate as f64).ceil() as usize;
-
-            assert!(samples <= required_sample_count);
-            samples
+            samples.min(required_sample_count)
         };
 
-        let mut samples = self.emitter.as_mut().unwrap().next_samples(required_sample_count);
+        let mut samples = match self.inverse_filter.as_mut() {
+            Some((filter, filter_i)) => {
+                let intensity = self.info.intensity;
+                let samples = (*filter_i..(*filter_i + required_sample_count))
+                    .map(|i| UniformedSample::from_f64(filter.get(i).map_or(0.0, |v| v * intensity)))
+                    .collect_vec();
+                *filter_i += required_sample_count;
+                samples
+            }
+            None => self.emitter.as_mut().unwrap().next_samples(required_sample_count),
+        };
         if end_sample_index < samples.len() {
             // [end_sample_index, len())までに0に埋める。
             samples
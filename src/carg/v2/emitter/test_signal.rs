@@ -0,0 +1,238 @@
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, EmitterRange, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessProcessorInput, SItemSPtr,
+    TProcess, TProcessItem, TProcessItemPtr,
+};
+use crate::math::frequency::EFrequency;
+use crate::nz_define_time_tick_for;
+use crate::wave::sample::UniformedSample;
+use crate::wave::sine::test_signal::{ETestSignal, TestSignalEmitter, TestTone};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// 出力するテスト信号の指定
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum EMetaTestSignal {
+    /// `delay`秒の位置に1サンプルだけのインパルスを1回出す。
+    #[serde(rename = "impulse")]
+    Impulse {
+        #[serde(default)]
+        delay: f64,
+    },
+    /// 1秒に`rate`回、1サンプルだけのインパルスを繰り返して出す。
+    #[serde(rename = "pulse_train")]
+    PulseTrain { rate: f64 },
+    /// ずっと`intensity`の値を出す。
+    #[serde(rename = "dc")]
+    Dc,
+    /// ずっと0を出す。
+    #[serde(rename = "silence")]
+    Silence,
+    /// 複数のサイン波を足して出す。
+    #[serde(rename = "multi_tone")]
+    MultiTone { tones: Vec<MetaTestTone> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaTestTone {
+    pub frequency: EFrequency,
+    /// サイン波の振幅（dBFS）。`0`なら振幅`1`。
+    pub level_db: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaTestSignalInfo {
+    pub signal: EMetaTestSignal,
+    /// 全体の振幅`[-1, 1]`。`dc`ではこの値をそのまま出力する。
+    pub intensity: f64,
+    pub range: EmitterRange,
+    pub sample_rate: usize,
+}
+
+/// インパルスやパルス列などの、応答の測定に使うテスト信号を出すエミッター。
+#[derive(Debug)]
+pub struct EmitterTestSignalProcessData {
+    common: ProcessControlItem,
+    info: MetaTestSignalInfo,
+    sample_elapsed_time: f64,
+    emitter: Option<TestSignalEmitter>,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+
+impl TPinCategory for EmitterTestSignalProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterTestSignalProcessData {}
+nz_define_time_tick_for!(EmitterTestSignalProcessData, true, true);
+
+impl TProcess for EmitterTestSignalProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。またInputピンのリソース更新はしなくてもいい。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+        if self.common.state == EProcessState::Stopped {
+            // 初期化する。
+            self.initialize();
+            assert!(self.emitter.is_some());
+        }
+
+        let buffer = self.next_samples(input);
+        if buffer.is_empty() {
+            return;
+        }
+
+        let sample_rate = self.info.sample_rate;
+        let elapsed_time = buffer.len() as f64 / sample_rate as f64;
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(buffer, sample_rate)),
+            )
+            .unwrap();
+
+        // 状態確認
+        self.sample_elapsed_time += elapsed_time;
+        if self.sample_elapsed_time < self.info.range.length {
+            self.common.state = EProcessState::Playing;
+        } else {
+            self.common.state = EProcessState::Finished;
+        }
+    }
+}
+
+impl TProcessItem for EmitterTestSignalProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterTestSignal(v) = setting.node {
+            if v.sample_rate == 0 {
+                return Err(anyhow::anyhow!("`sample_rate` must be bigger than 0."));
+            }
+            match &v.signal {
+                EMetaTestSignal::Impulse { delay } if !(*delay >= 0.0) => {
+                    return Err(anyhow::anyhow!("`delay` must not be negative."));
+                }
+                EMetaTestSignal::PulseTrain { rate } if !(*rate > 0.0 && *rate <= v.sample_rate as f64) => {
+                    return Err(anyhow::anyhow!("`rate` must be in (0, sample_rate]."));
+                }
+                EMetaTestSignal::MultiTone { tones } if tones.is_empty() => {
+                    return Err(anyhow::anyhow!("`tones` must not be empty."));
+                }
+                _ => (),
+            }
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterTestSignal,
+                    systems: &system_setting,
+                }),
+                info: v.clone(),
+                sample_elapsed_time: 0.0,
+                emitter: None,
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+impl EmitterTestSignalProcessData {
+    fn initialize(&mut self) {
+        let signal = match &self.info.signal {
+            EMetaTestSignal::Impulse { delay } => ETestSignal::Impulse { delay: *delay },
+            EMetaTestSignal::PulseTrain { rate } => ETestSignal::PulseTrain { rate: *rate },
+            EMetaTestSignal::Dc => ETestSignal::Dc,
+            EMetaTestSignal::Silence => ETestSignal::Silence,
+            EMetaTestSignal::MultiTone { tones } => ETestSignal::MultiTone {
+                tones: tones
+                    .iter()
+                    .map(|v| TestTone {
+                        frequency: v.frequency.to_frequency(),
+                        amplitude: 10f64.powf(v.level_db / 20.0),
+                    })
+                    .collect_vec(),
+            },
+        };
+
+        self.emitter = Some(TestSignalEmitter::new(signal, self.info.intensity, self.info.sample_rate));
+    }
+
+    /// 設定のサンプル数ずつ吐き出す。ただし`range`の長さを超える分は0に埋める。
+    fn next_samples(&mut self, input: &ProcessProcessorInput) -> Vec<UniformedSample> {
+        let sample_rate = self.info.sample_rate;
+        let required_sample_count = input.get_realtime_required_samples(sample_rate);
+        if required_sample_count == 0 {
+            return vec![];
+        }
+
+        let remained_time = (self.info.range.length - self.common.elapsed_time).max(0.0);
+        let end_sample_index = ((remained_time * sample_rate as f64).ceil() as usize).min(required_sample_count);
+
+        let mut samples = self.emitter.as_mut().unwrap().next_samples(required_sample_count);
+        samples
+            .iter_mut()
+            .skip(end_sample_index)
+            .for_each(|v| *v = UniformedSample::MIN);
+        samples
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -1,7 +1,7 @@
 use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
 use crate::carg::v2::meta::node::ENode;
 use crate::carg::v2::meta::setting::Setting;
-use crate::carg::v2::meta::system::{InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::system::{system_category, ESystemCategoryFlag, InitializeSystemAccessor, TSystemCategory};
 use crate::carg::v2::meta::tick::TTimeTickCategory;
 use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
 use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
@@ -9,18 +9,24 @@ use crate::carg::v2::{
     EProcessOutput, ProcessItemCreateSetting, ProcessOutputBufferStereo, ProcessProcessorInput, SItemSPtr, TProcess,
     TProcessItem, TProcessItemPtr,
 };
+use crate::file::handle::FileHandle;
+use crate::file::reader::FileReaderSetting;
+use crate::file::EFileAccessSetting;
 use crate::nz_define_time_tick_for;
-use crate::wave::container::WaveContainer;
+use crate::wave::container::stream::EStreamReader;
 use crate::wave::sample::UniformedSample;
-use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::BufReader;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MetaWavStereoInfo {
-    /// ファイルのパス
+    /// ファイルのパス。WAV・AIFF・FLACファイルを指定する。
+    /// WAVは8・16・24・32ビットのLPCMとADPCMだけ読み込めて、それ以外はノードを作る時のエラーになる。
     pub path: String,
+    /// 再生を開始するファイル上の位置（秒）
+    #[serde(default)]
+    pub start_time: f64,
 }
 
 #[derive(Debug)]
@@ -33,9 +39,11 @@ pub struct EmitterWavStereoProcessData {
 
 #[derive(Default, Debug)]
 struct InternalInfo {
-    container: Option<WaveContainer>,
-    /// 次のサンプル出力でバッファのスタート地点インデックス
-    next_start_i: usize,
+    /// [`FileIO`](crate::file::FileIO)から取得したファイルのハンドル。
+    /// 最後まで読み込んだら破棄する。
+    handle: Option<FileHandle>,
+    /// サンプルを前から順番に読み込むためのリーダー
+    stream: Option<EStreamReader>,
     /// PCMのサンプルレート
     sample_rate: usize,
 }
@@ -68,7 +76,11 @@ impl TPinCategory for EmitterWavStereoProcessData {
     }
 }
 
-impl TSystemCategory for EmitterWavStereoProcessData {}
+impl TSystemCategory for EmitterWavStereoProcessData {
+    fn get_dependent_system_categories() -> ESystemCategoryFlag {
+        system_category::FILE_IO_SYSTEM
+    }
+}
 nz_define_time_tick_for!(EmitterWavStereoProcessData, true, true);
 
 impl TProcess for EmitterWavStereoProcessData {
@@ -99,27 +111,25 @@ impl TProcess for EmitterWavStereoProcessData {
         if self.common.state == EProcessState::Stopped {
             // 初期化する。
             self.initialize();
-            assert!(self.internal.container.is_some());
+            assert!(self.internal.handle.is_some());
         }
 
         let result = self.next_samples(input);
-        if result.is_empty() {
-            return;
+        if !result.is_empty() {
+            self.common
+                .insert_to_output_pin(
+                    OUTPUT_OUT,
+                    EProcessOutput::BufferStereo(ProcessOutputBufferStereo {
+                        ch_left: result.left,
+                        ch_right: result.right,
+                        sample_rate: self.internal.sample_rate,
+                    }),
+                )
+                .unwrap();
         }
 
-        self.common
-            .insert_to_output_pin(
-                OUTPUT_OUT,
-                EProcessOutput::BufferStereo(ProcessOutputBufferStereo {
-                    ch_left: result.left,
-                    ch_right: result.right,
-                    sample_rate: self.internal.sample_rate,
-                }),
-            )
-            .unwrap();
-
         // 状態確認
-        if self.internal.container.is_some() {
+        if self.internal.handle.is_some() {
             self.common.state = EProcessState::Playing;
         } else {
             self.common.state = EProcessState::Finished;
@@ -137,6 +147,17 @@ impl TProcessItem for EmitterWavStereoProcessData {
         system_setting: &InitializeSystemAccessor,
     ) -> anyhow::Result<TProcessItemPtr> {
         if let ENode::EmitterWavStereo(v) = setting.node {
+            // パスが間違っていたり、ヘッダーが読めなければ処理を始める前にエラーにする。
+            let stream = {
+                let file = fs::File::open(&v.path)
+                    .map_err(|e| anyhow::anyhow!("Could not open sound file `{}`: {}", v.path, e))?;
+                let mut reader = BufReader::new(file);
+                let mut stream = EStreamReader::from_bufread(&mut reader)
+                    .ok_or_else(|| anyhow::anyhow!("`{}` is not a supported WAV, AIFF or FLAC file.", v.path))?;
+                stream.skip_frames(&mut reader, stream.frame_index_of_time(v.start_time));
+                stream
+            };
+
             let item = Self {
                 setting: setting.setting.clone(),
                 common: ProcessControlItem::new(ProcessControlItemSetting {
@@ -144,7 +165,11 @@ impl TProcessItem for EmitterWavStereoProcessData {
                     systems: &system_setting,
                 }),
                 info: v.clone(),
-                internal: InternalInfo::default(),
+                internal: InternalInfo {
+                    handle: None,
+                    sample_rate: stream.samples_per_second() as usize,
+                    stream: Some(stream),
+                },
             };
             return Ok(SItemSPtr::new(item));
         }
@@ -155,75 +180,51 @@ impl TProcessItem for EmitterWavStereoProcessData {
 
 impl EmitterWavStereoProcessData {
     fn initialize(&mut self) {
-        let container = {
-            //let this_pointer = UnsafeCell::new(self as *mut Self);
-            //self.common.systems.access_file_io_fn(move |system| {
-            //    // これ絶対よくない。
-            //    let this = unsafe { &mut **this_pointer.get() };
-
-            //    // 書き込み。
-            //    let file_setting = EFileAccessSetting::Read { path: this.info.path.clone() };
-            //    let file_handle = system.create_handle(file_setting);
-
-            //    let setting = FileReaderSetting {
-            //        seek_to_first_when_drop: true,
-            //    };
-            //    let mut reader = file_handle.try_read(setting).unwrap();
-            //    let container = WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.");
-
-            //});
-
-            let file = fs::File::open(&self.info.path).expect(&format!("Could not find {}.", &self.info.path));
-            let mut reader = BufReader::new(file);
-            WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
-        };
-
-        // 移動して終わり。
-        self.internal.sample_rate = container.samples_per_second() as usize;
-        self.internal.container = Some(container);
+        // ファイル全体を読み込まず、ハンドルだけを持ってフレームごとに必要な分だけ読み込む。
+        // ヘッダーはノードを作る時に読み込んである。
+        let mut handle = None;
+        self.common.systems.access_file_io_fn(|system| {
+            let file_setting = EFileAccessSetting::Read { path: self.info.path.clone() };
+            handle = Some(system.create_handle(file_setting));
+        });
+        self.internal.handle = Some(handle.expect("FileIO system must be initialized."));
     }
 
     /// 初期化した情報から設定分のOutputを更新する。
     fn next_samples(&mut self, input: &ProcessProcessorInput) -> NextSamplesResult {
-        assert!(self.internal.container.is_some());
+        assert!(self.internal.handle.is_some());
 
         let required_sample_count = input.get_realtime_required_samples(self.internal.sample_rate);
         if required_sample_count <= 0 {
             return NextSamplesResult::default();
         }
-        let buffer = self.internal.container.as_ref().unwrap().uniformed_sample_buffer();
-
-        let (each_ch_sample_count, end) = {
-            let next_start_i = self.internal.next_start_i + (required_sample_count * 2);
-            if next_start_i >= buffer.len() {
-                let raw_samples = buffer.len() - self.internal.next_start_i;
-                (raw_samples >> 1, true)
-            } else {
-                (required_sample_count >> 1, false)
-            }
+
+        // 必要な分だけファイルから汲み取る。
+        let stream = self.internal.stream.as_mut().unwrap();
+        let frames = {
+            let setting = FileReaderSetting {
+                seek_to_first_when_drop: false,
+            };
+            let mut reader = self.internal.handle.as_ref().unwrap().try_read(setting).unwrap();
+            stream.read_frames(&mut reader, required_sample_count)
         };
 
-        // 汲み取る
+        // 各チャンネルに分ける。
+        // モノラルの場合には両方のチャンネルに同じサンプルを入れて、3チャンネル以上なら最初の2つだけを使う。
+        let channels = stream.channel().max(1) as usize;
+        let frame_count = frames.len() / channels;
         let mut result = NextSamplesResult::default();
-        result.left.reserve(each_ch_sample_count);
-        result.right.reserve(each_ch_sample_count);
-        buffer
-            .iter()
-            .skip(self.internal.next_start_i)
-            .take(each_ch_sample_count << 1)
-            .tuples::<(_, _)>()
-            .for_each(|(l, r)| {
-                result.left.push(*l);
-                result.right.push(*r);
-            });
-
-        // もし最後まで到達したら、containerを破棄する。
-        if end {
-            self.internal.container = None;
+        result.left.reserve(frame_count);
+        result.right.reserve(frame_count);
+        for frame in frames.chunks_exact(channels) {
+            result.left.push(frame[0]);
+            result.right.push(if channels >= 2 { frame[1] } else { frame[0] });
         }
 
-        // インデックス更新
-        self.internal.next_start_i += required_sample_count * 2;
+        // もし最後まで到達したら、ハンドルを破棄する。
+        if frame_count < required_sample_count {
+            self.internal.handle = None;
+        }
 
         result
     }
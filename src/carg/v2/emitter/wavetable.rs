@@ -0,0 +1,237 @@
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItem, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, EmitterRange, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessProcessorInput, SItemSPtr,
+    TProcess, TProcessItem, TProcessItemPtr,
+};
+use crate::math::frequency::EFrequency;
+use crate::nz_define_time_tick_for;
+use crate::wave::container::WaveContainer;
+use crate::wave::sample::UniformedSample;
+use crate::wave::sine::wavetable::{Wavetable, WavetableUnitSampleEmitter, DEFAULT_FRAME_SIZE};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::BufReader;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaWavetableInfo {
+    /// 1周期の波形（フレーム）を並べたWAVファイルのパス。ステレオなら左チャンネルだけを使う。
+    pub path: String,
+    /// 1フレームのサンプル数。2のべき乗を指定する。指定しなければ`2048`。
+    #[serde(default)]
+    pub frame_size: Option<usize>,
+    pub frequency: EFrequency,
+    /// 振幅`[0, 1]`
+    pub intensity: f64,
+    /// フレームをスキャンする位置`[0, 1]`。`0`なら最初のフレーム、`1`なら最後のフレームになる。
+    #[serde(default)]
+    pub morph: f64,
+    /// 指定すると、`range`の長さをかけて`morph`からこの位置まで線形にスキャンする。
+    #[serde(default)]
+    pub morph_end: Option<f64>,
+    pub range: EmitterRange,
+    pub sample_rate: usize,
+}
+
+/// WAVから読み込んだウェーブテーブルを帯域制限して再生するエミッター。
+#[derive(Debug)]
+pub struct EmitterWavetableProcessData {
+    common: ProcessControlItem,
+    info: MetaWavetableInfo,
+    sample_elapsed_time: f64,
+    /// 最初の処理で[`Wavetable`]を持たせる。
+    wavetable: Option<Wavetable>,
+    emitter: Option<WavetableUnitSampleEmitter>,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+
+impl TPinCategory for EmitterWavetableProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::START),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::EMPTY),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for EmitterWavetableProcessData {}
+nz_define_time_tick_for!(EmitterWavetableProcessData, true, true);
+
+impl TProcess for EmitterWavetableProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        // 時間更新。またInputピンのリソース更新はしなくてもいい。
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+        if self.common.state == EProcessState::Stopped {
+            // 初期化する。
+            self.initialize();
+            assert!(self.emitter.is_some());
+        }
+
+        let buffer = self.next_samples(input);
+        if buffer.is_empty() {
+            return;
+        }
+
+        let sample_rate = self.info.sample_rate;
+        let elapsed_time = buffer.len() as f64 / sample_rate as f64;
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(buffer, sample_rate)),
+            )
+            .unwrap();
+
+        // 状態確認
+        self.sample_elapsed_time += elapsed_time;
+        if self.sample_elapsed_time < self.info.range.length {
+            self.common.state = EProcessState::Playing;
+        } else {
+            self.common.state = EProcessState::Finished;
+        }
+    }
+}
+
+impl TProcessItem for EmitterWavetableProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::EmitterWavetable(v) = setting.node {
+            if v.sample_rate == 0 {
+                return Err(anyhow::anyhow!("`sample_rate` must be bigger than 0."));
+            }
+
+            // mipmapを作るのに時間がかかるので、処理を始める前に作っておく。
+            let wavetable = {
+                let file = fs::File::open(&v.path)?;
+                let mut reader = BufReader::new(file);
+                let container = WaveContainer::from_bufread(&mut reader)
+                    .ok_or_else(|| anyhow::anyhow!("Could not create WaveContainer from {}.", v.path))?;
+
+                let channel = container.channel().max(1) as usize;
+                let samples = container
+                    .uniformed_sample_buffer()
+                    .iter()
+                    .step_by(channel)
+                    .copied()
+                    .collect_vec();
+                Wavetable::from_samples(&samples, v.frame_size.unwrap_or(DEFAULT_FRAME_SIZE))?
+            };
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::EmitterWavetable,
+                    systems: &system_setting,
+                }),
+                info: v.clone(),
+                sample_elapsed_time: 0.0,
+                wavetable: Some(wavetable),
+                emitter: None,
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+impl EmitterWavetableProcessData {
+    fn initialize(&mut self) {
+        self.emitter = Some(WavetableUnitSampleEmitter::new(
+            self.wavetable.take().unwrap(),
+            self.info.frequency.to_frequency(),
+            self.info.intensity,
+            self.info.morph,
+            self.info.sample_rate,
+        ));
+    }
+
+    /// `time`秒の時点のスキャン位置を返す。
+    fn morph_of(&self, time: f64) -> f64 {
+        match self.info.morph_end {
+            Some(morph_end) if self.info.range.length > 0.0 => {
+                let rate = (time / self.info.range.length).clamp(0.0, 1.0);
+                self.info.morph + ((morph_end - self.info.morph) * rate)
+            }
+            _ => self.info.morph,
+        }
+    }
+
+    /// 設定のサンプル数ずつ吐き出す。ただし`range`の長さを超える分は0に埋める。
+    fn next_samples(&mut self, input: &ProcessProcessorInput) -> Vec<UniformedSample> {
+        let sample_rate = self.info.sample_rate;
+        let required_sample_count = input.get_realtime_required_samples(sample_rate);
+        if required_sample_count == 0 {
+            return vec![];
+        }
+
+        let remained_time = (self.info.range.length - self.common.elapsed_time).max(0.0);
+        let end_sample_index = ((remained_time * sample_rate as f64).ceil() as usize).min(required_sample_count);
+
+        let mut samples = Vec::with_capacity(required_sample_count);
+        for sample_i in 0..required_sample_count {
+            let time = self.sample_elapsed_time + (sample_i as f64 / sample_rate as f64);
+            let morph = self.morph_of(time);
+
+            let emitter = self.emitter.as_mut().unwrap();
+            let sample = emitter.set_morph(morph).next_sample();
+            match sample_i < end_sample_index {
+                true => samples.push(sample),
+                false => samples.push(UniformedSample::MIN),
+            }
+        }
+        samples
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
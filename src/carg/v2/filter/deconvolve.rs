@@ -0,0 +1,211 @@
+use crate::carg::v2::meta::input::EInputContainerCategoryFlag;
+use crate::carg::v2::meta::node::ENode;
+use crate::carg::v2::meta::system::{InitializeSystemAccessor, TSystemCategory};
+use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
+use crate::carg::v2::node::common::{EProcessState, ProcessControlItemSetting};
+use crate::carg::v2::{
+    EProcessOutput, ProcessControlItem, ProcessItemCreateSetting, ProcessOutputBuffer, ProcessProcessorInput,
+    SItemSPtr, TProcess, TProcessItem, TProcessItemPtr,
+};
+use crate::math::frequency::EFrequency;
+use crate::nz_define_time_tick_for;
+use crate::wave::sample::UniformedSample;
+use crate::wave::sine::sweep::{deconvolve, ESineSweepMode, SineSweepSetting};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MetaDeconvolveInfo {
+    /// 録音に使ったスイープの開始周波数
+    pub from_frequency: EFrequency,
+    /// 録音に使ったスイープの終了周波数
+    pub to_frequency: EFrequency,
+    /// 録音に使ったスイープの長さ（秒）
+    pub sweep_length: f64,
+    /// 録音に使ったスイープの周波数の変え方。違うと逆フィルターも変わるので、省略できない。
+    pub mode: ESineSweepMode,
+    /// 録音に使ったスイープのフェードインの長さ（秒）
+    #[serde(default)]
+    pub fade_in: f64,
+    /// 録音に使ったスイープのフェードアウトの長さ（秒）
+    #[serde(default)]
+    pub fade_out: f64,
+    /// 出力するインパルス応答の長さ（秒）
+    pub ir_length: f64,
+}
+
+/// 録音したスイープを全部受け取ってから、逆フィルターを畳み込んでインパルス応答を出力する。
+#[derive(Debug)]
+pub struct DeconvolveProcessData {
+    common: ProcessControlItem,
+    info: MetaDeconvolveInfo,
+    /// 今まで受け取った録音
+    recorded: Vec<f64>,
+    /// 録音のサンプルレート。まだ受け取っていなければ`None`。
+    sample_rate: Option<usize>,
+}
+
+const INPUT_IN: &'static str = "in";
+const OUTPUT_OUT: &'static str = "out";
+
+impl TPinCategory for DeconvolveProcessData {
+    fn get_input_pin_names() -> Vec<&'static str> {
+        vec![INPUT_IN]
+    }
+
+    fn get_output_pin_names() -> Vec<&'static str> {
+        vec![OUTPUT_OUT]
+    }
+
+    fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(pin_category::BUFFER_MONO),
+            OUTPUT_OUT => Some(pin_category::BUFFER_MONO),
+            _ => None,
+        }
+    }
+
+    fn get_input_container_flag(pin_name: &str) -> Option<EInputContainerCategoryFlag> {
+        match pin_name {
+            INPUT_IN => Some(input::container_category::BUFFER_MONO_DYNAMIC),
+            _ => None,
+        }
+    }
+}
+
+impl TSystemCategory for DeconvolveProcessData {}
+nz_define_time_tick_for!(DeconvolveProcessData, true, true);
+
+impl TProcess for DeconvolveProcessData {
+    fn is_finished(&self) -> bool {
+        self.common.state == EProcessState::Finished
+    }
+
+    fn can_process(&self) -> bool {
+        true
+    }
+
+    fn get_common_ref(&self) -> &ProcessControlItem {
+        &self.common
+    }
+
+    fn get_common_mut(&mut self) -> &mut ProcessControlItem {
+        &mut self.common
+    }
+
+    fn try_process(&mut self, input: &ProcessProcessorInput) {
+        self.common.elapsed_time = input.common.elapsed_time;
+        self.common.process_input_pins_deprecated();
+
+        if self.common.state == EProcessState::Finished {
+            return;
+        }
+
+        // 入力を全部溜めておく。
+        self.drain_input();
+        if !input.is_children_all_finished() {
+            self.common.state = EProcessState::Playing;
+            return;
+        }
+
+        // 録音が全部揃ったので、インパルス応答を一度に出力して終わる。
+        let Some(sample_rate) = self.sample_rate else {
+            self.common.state = EProcessState::Finished;
+            return;
+        };
+        let response = self.compute_response(sample_rate);
+        self.common
+            .insert_to_output_pin(
+                OUTPUT_OUT,
+                EProcessOutput::BufferMono(ProcessOutputBuffer::new(response, sample_rate)),
+            )
+            .unwrap();
+        self.common.state = EProcessState::Finished;
+    }
+}
+
+impl TProcessItem for DeconvolveProcessData {
+    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn create_item(
+        setting: &ProcessItemCreateSetting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
+        if let ENode::FilterDeconvolve(v) = setting.node {
+            if !(v.sweep_length > 0.0) {
+                return Err(anyhow::anyhow!("`sweep_length` must be bigger than 0."));
+            }
+            if !(v.ir_length > 0.0) {
+                return Err(anyhow::anyhow!("`ir_length` must be bigger than 0."));
+            }
+            // 指数スイープの逆フィルターは周波数の比の対数を使う。
+            if v.mode == ESineSweepMode::Exponential {
+                let from_frequency = v.from_frequency.to_frequency();
+                let to_frequency = v.to_frequency.to_frequency();
+                if !(from_frequency > 0.0) || !(to_frequency > 0.0) {
+                    return Err(anyhow::anyhow!(
+                        "`from_frequency` and `to_frequency` must be bigger than 0 for the exponential mode."
+                    ));
+                }
+                if from_frequency == to_frequency {
+                    return Err(anyhow::anyhow!(
+                        "`from_frequency` and `to_frequency` must be different for the exponential mode."
+                    ));
+                }
+            }
+
+            let item = Self {
+                common: ProcessControlItem::new(ProcessControlItemSetting {
+                    specifier: ENodeSpecifier::FilterDeconvolve,
+                    systems: &system_setting,
+                }),
+                info: v.clone(),
+                recorded: vec![],
+                sample_rate: None,
+            };
+            return Ok(SItemSPtr::new(item));
+        }
+
+        unreachable!("Unexpected branch");
+    }
+}
+
+impl DeconvolveProcessData {
+    /// `in`に溜まったバッファを全部録音に移す。
+    fn drain_input(&mut self) {
+        let mut input_internal = self.common.get_input_internal_mut(INPUT_IN).unwrap();
+        let input = input_internal.buffer_mono_dynamic_mut().unwrap();
+        if !input.can_process() || input.buffer.is_empty() {
+            return;
+        }
+
+        self.sample_rate = Some(input.sample_rate);
+        self.recorded.extend(input.buffer.drain(..).map(|v| v.to_f64()));
+    }
+
+    /// 録音と同じサンプルレートで逆フィルターを作って、インパルス応答を計算する。
+    fn compute_response(&self, sample_rate: usize) -> Vec<UniformedSample> {
+        let setting = SineSweepSetting {
+            from_frequency: self.info.from_frequency.to_frequency(),
+            to_frequency: self.info.to_frequency.to_frequency(),
+            length: self.info.sweep_length,
+            mode: self.info.mode,
+            fade_in: self.info.fade_in,
+            fade_out: self.info.fade_out,
+        };
+        let inverse = setting.inverse_filter(sample_rate);
+        let length = (self.info.ir_length * sample_rate as f64).ceil() as usize;
+
+        deconvolve(&self.recorded, &inverse, length)
+            .into_iter()
+            .map(UniformedSample::from_f64)
+            .collect_vec()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -5,6 +5,7 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
 
+pub mod deconvolve;
 pub mod fir;
 pub mod iir;
 pub mod irconv;
@@ -149,6 +149,9 @@ pub struct BufferMonoDynamicItem {
     pub sync_sample_rate: usize,
     /// @todo ここに仕込む。
     pub resampler: Option<InputResampleController>,
+    /// 最後に受け取ったバッファの`sample_offset`。
+    /// 次のバッファもこの分だけ今のバッファの後ろと重なる可能性がある。
+    pub last_sample_offset: usize,
 }
 
 impl BufferMonoDynamicItem {
@@ -158,6 +161,7 @@ impl BufferMonoDynamicItem {
             sample_rate: 0,
             sync_sample_rate,
             resampler: None,
+            last_sample_offset: 0,
         }
     }
 
@@ -175,6 +179,7 @@ impl BufferMonoDynamicItem {
         // WaveBufferであるかをチェック。
         if let EProcessOutputContainer::BufferMono(v) = output {
             self.sample_rate = v.sample_rate;
+            self.last_sample_offset = v.sample_offset;
 
             // 24-09-27 `sample_offset`に気をつける。
             let sample_offset = v.sample_offset.min(self.buffer.len());
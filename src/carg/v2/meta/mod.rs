@@ -33,6 +33,17 @@ use crate::carg::v2::analyzer::lufs::AnalyzeLUFSProcessData;
 use crate::carg::v2::emitter::sine_sweep::SineSweepEmitterProcessData;
 use crate::carg::v2::emitter::wav_mono::EmitterWavMonoProcessData;
 use crate::carg::v2::emitter::wav_stereo::EmitterWavStereoProcessData;
+use crate::carg::v2::emitter::device_input::EmitterDeviceInputProcessData;
+use crate::carg::v2::emitter::device_metrics::EmitterDeviceMetricsProcessData;
+use crate::carg::v2::emitter::midi_file::EmitterMidiFileProcessData;
+use crate::carg::v2::emitter::midi_input::EmitterMidiInputProcessData;
+use crate::carg::v2::emitter::fm::EmitterFmProcessData;
+use crate::carg::v2::emitter::wavetable::EmitterWavetableProcessData;
+use crate::carg::v2::emitter::additive::EmitterAdditiveProcessData;
+use crate::carg::v2::emitter::pluck::EmitterPluckProcessData;
+use crate::carg::v2::emitter::granular::EmitterGranularProcessData;
+use crate::carg::v2::emitter::test_signal::EmitterTestSignalProcessData;
+use crate::carg::v2::filter::deconvolve::DeconvolveProcessData;
 use crate::carg::v2::filter::irconv::IRConvolutionProcessData;
 use crate::carg::v2::meta::node::ENode;
 use crate::carg::v2::meta::process::{process_category, EProcessCategoryFlag, TProcessCategory};
@@ -97,6 +108,10 @@ pub enum ENodeSpecifier {
     InternalDummy,
     EmitterPinkNoise,
     EmitterWhiteNoise,
+    EmitterBrownNoise,
+    EmitterBlueNoise,
+    EmitterVioletNoise,
+    EmitterVelvetNoise,
     EmitterSineWave,
     EmitterSawtooth,
     EmitterTriangle,
@@ -106,6 +121,16 @@ pub enum ENodeSpecifier {
     EmitterSineSweep,
     EmitterWavMono,
     EmitterWavStereo,
+    EmitterDeviceInput,
+    EmitterDeviceMetrics,
+    EmitterMidiFile,
+    EmitterMidiInput,
+    EmitterFm,
+    EmitterWavetable,
+    EmitterAdditive,
+    EmitterPluck,
+    EmitterGranular,
+    EmitterTestSignal,
     AnalyzerDFT,
     AnalyzerFFT,
     AnalyzerLUFS,
@@ -122,6 +147,7 @@ pub enum ENodeSpecifier {
     FilterIIRBandPass,
     FilterIIRBandStop,
     FilterIRConvolution,
+    FilterDeconvolve,
     MixStereo,
     MixSeparator,
     OutputFile,
@@ -137,6 +163,10 @@ impl ENodeSpecifier {
             ENode::InternalDummy => Self::InternalDummy,
             ENode::EmitterPinkNoise { .. } => Self::EmitterPinkNoise,
             ENode::EmitterWhiteNoise { .. } => Self::EmitterWhiteNoise,
+            ENode::EmitterBrownNoise { .. } => Self::EmitterBrownNoise,
+            ENode::EmitterBlueNoise { .. } => Self::EmitterBlueNoise,
+            ENode::EmitterVioletNoise { .. } => Self::EmitterVioletNoise,
+            ENode::EmitterVelvetNoise { .. } => Self::EmitterVelvetNoise,
             ENode::EmitterSineWave { .. } => Self::EmitterSineWave,
             ENode::EmitterSawtooth { .. } => Self::EmitterSawtooth,
             ENode::EmitterTriangle { .. } => Self::EmitterTriangle,
@@ -146,6 +176,16 @@ impl ENodeSpecifier {
             ENode::EmitterSineSweep { .. } => Self::EmitterSineSweep,
             ENode::EmitterWavMono(_) => Self::EmitterWavMono,
             ENode::EmitterWavStereo(_) => Self::EmitterWavStereo,
+            ENode::EmitterDeviceInput(_) => Self::EmitterDeviceInput,
+            ENode::EmitterDeviceMetrics(_) => Self::EmitterDeviceMetrics,
+            ENode::EmitterMidiFile(_) => Self::EmitterMidiFile,
+            ENode::EmitterMidiInput(_) => Self::EmitterMidiInput,
+            ENode::EmitterFm(_) => Self::EmitterFm,
+            ENode::EmitterWavetable(_) => Self::EmitterWavetable,
+            ENode::EmitterAdditive(_) => Self::EmitterAdditive,
+            ENode::EmitterPluck(_) => Self::EmitterPluck,
+            ENode::EmitterGranular(_) => Self::EmitterGranular,
+            ENode::EmitterTestSignal(_) => Self::EmitterTestSignal,
             ENode::AnalyzerDFT { .. } => Self::AnalyzerDFT,
             ENode::AnalyzerFFT { .. } => Self::AnalyzerFFT,
             ENode::AnalyzerLUFS(_) => Self::AnalyzerLUFS,
@@ -164,6 +204,7 @@ impl ENodeSpecifier {
             ENode::FilterIIRBandPass(_) => Self::FilterIIRBandPass,
             ENode::FilterIIRBandStop(_) => Self::FilterIIRBandStop,
             ENode::FilterIRConvolution(_) => Self::FilterIRConvolution,
+            ENode::FilterDeconvolve(_) => Self::FilterDeconvolve,
             ENode::AdapterLimiter(_) => Self::AdapterLimiter,
             ENode::AdapterResample(_) => Self::AdapterResample,
             ENode::AdapterDelay(_) => Self::AdapterDelay,
@@ -185,9 +226,23 @@ impl ENodeSpecifier {
             | Self::EmitterSquare
             | Self::EmitterTriangle
             | Self::EmitterWhiteNoise
+            | Self::EmitterBrownNoise
+            | Self::EmitterBlueNoise
+            | Self::EmitterVioletNoise
+            | Self::EmitterVelvetNoise
             | Self::EmitterSineWave => SineWaveEmitterProcessData::get_input_pin_names(),
             Self::EmitterWavMono => EmitterWavMonoProcessData::get_input_pin_names(),
             Self::EmitterWavStereo => EmitterWavStereoProcessData::get_input_pin_names(),
+            Self::EmitterDeviceInput => EmitterDeviceInputProcessData::get_input_pin_names(),
+            Self::EmitterDeviceMetrics => EmitterDeviceMetricsProcessData::get_input_pin_names(),
+            Self::EmitterMidiFile => EmitterMidiFileProcessData::get_input_pin_names(),
+            Self::EmitterMidiInput => EmitterMidiInputProcessData::get_input_pin_names(),
+            Self::EmitterFm => EmitterFmProcessData::get_input_pin_names(),
+            Self::EmitterWavetable => EmitterWavetableProcessData::get_input_pin_names(),
+            Self::EmitterAdditive => EmitterAdditiveProcessData::get_input_pin_names(),
+            Self::EmitterPluck => EmitterPluckProcessData::get_input_pin_names(),
+            Self::EmitterGranular => EmitterGranularProcessData::get_input_pin_names(),
+            Self::EmitterTestSignal => EmitterTestSignalProcessData::get_input_pin_names(),
             Self::AnalyzerDFT => AnalyzerDFTProcessData::get_input_pin_names(),
             Self::AnalyzerFFT => AnalyzerFFTProcessData::get_input_pin_names(),
             Self::AnalyzerLUFS => AnalyzeLUFSProcessData::get_input_pin_names(),
@@ -203,6 +258,7 @@ impl ENodeSpecifier {
                 IIRProcessData::get_input_pin_names()
             }
             Self::FilterIRConvolution => IRConvolutionProcessData::get_input_pin_names(),
+            Self::FilterDeconvolve => DeconvolveProcessData::get_input_pin_names(),
             Self::AdapterResample => ResampleProcessData::get_input_pin_names(),
             Self::EmitterSineSweep => SineSweepEmitterProcessData::get_input_pin_names(),
             Self::AdapterDelay => AdapterDelayProcessData::get_input_pin_names(),
@@ -224,9 +280,23 @@ impl ENodeSpecifier {
             | Self::EmitterSquare
             | Self::EmitterTriangle
             | Self::EmitterWhiteNoise
+            | Self::EmitterBrownNoise
+            | Self::EmitterBlueNoise
+            | Self::EmitterVioletNoise
+            | Self::EmitterVelvetNoise
             | Self::EmitterSineWave => SineWaveEmitterProcessData::get_output_pin_names(),
             Self::EmitterWavMono => EmitterWavMonoProcessData::get_output_pin_names(),
             Self::EmitterWavStereo => EmitterWavStereoProcessData::get_output_pin_names(),
+            Self::EmitterDeviceInput => EmitterDeviceInputProcessData::get_output_pin_names(),
+            Self::EmitterDeviceMetrics => EmitterDeviceMetricsProcessData::get_output_pin_names(),
+            Self::EmitterMidiFile => EmitterMidiFileProcessData::get_output_pin_names(),
+            Self::EmitterMidiInput => EmitterMidiInputProcessData::get_output_pin_names(),
+            Self::EmitterFm => EmitterFmProcessData::get_output_pin_names(),
+            Self::EmitterWavetable => EmitterWavetableProcessData::get_output_pin_names(),
+            Self::EmitterAdditive => EmitterAdditiveProcessData::get_output_pin_names(),
+            Self::EmitterPluck => EmitterPluckProcessData::get_output_pin_names(),
+            Self::EmitterGranular => EmitterGranularProcessData::get_output_pin_names(),
+            Self::EmitterTestSignal => EmitterTestSignalProcessData::get_output_pin_names(),
             Self::AnalyzerDFT => AnalyzerDFTProcessData::get_output_pin_names(),
             Self::AnalyzerFFT => AnalyzerFFTProcessData::get_output_pin_names(),
             Self::AnalyzerLUFS => AnalyzeLUFSProcessData::get_output_pin_names(),
@@ -242,6 +312,7 @@ impl ENodeSpecifier {
                 IIRProcessData::get_output_pin_names()
             }
             Self::FilterIRConvolution => IRConvolutionProcessData::get_output_pin_names(),
+            Self::FilterDeconvolve => DeconvolveProcessData::get_output_pin_names(),
             Self::AdapterResample => ResampleProcessData::get_output_pin_names(),
             Self::EmitterSineSweep => SineSweepEmitterProcessData::get_output_pin_names(),
             Self::AdapterDelay => AdapterDelayProcessData::get_output_pin_names(),
@@ -312,9 +383,23 @@ impl ENodeSpecifier {
             | Self::EmitterSquare
             | Self::EmitterTriangle
             | Self::EmitterWhiteNoise
+            | Self::EmitterBrownNoise
+            | Self::EmitterBlueNoise
+            | Self::EmitterVioletNoise
+            | Self::EmitterVelvetNoise
             | Self::EmitterSineWave => SineWaveEmitterProcessData::get_pin_categories(pin_name),
             Self::EmitterWavMono => EmitterWavMonoProcessData::get_pin_categories(pin_name),
             Self::EmitterWavStereo => EmitterWavStereoProcessData::get_pin_categories(pin_name),
+            Self::EmitterDeviceInput => EmitterDeviceInputProcessData::get_pin_categories(pin_name),
+            Self::EmitterDeviceMetrics => EmitterDeviceMetricsProcessData::get_pin_categories(pin_name),
+            Self::EmitterMidiFile => EmitterMidiFileProcessData::get_pin_categories(pin_name),
+            Self::EmitterMidiInput => EmitterMidiInputProcessData::get_pin_categories(pin_name),
+            Self::EmitterFm => EmitterFmProcessData::get_pin_categories(pin_name),
+            Self::EmitterWavetable => EmitterWavetableProcessData::get_pin_categories(pin_name),
+            Self::EmitterAdditive => EmitterAdditiveProcessData::get_pin_categories(pin_name),
+            Self::EmitterPluck => EmitterPluckProcessData::get_pin_categories(pin_name),
+            Self::EmitterGranular => EmitterGranularProcessData::get_pin_categories(pin_name),
+            Self::EmitterTestSignal => EmitterTestSignalProcessData::get_pin_categories(pin_name),
             Self::AnalyzerDFT => AnalyzerDFTProcessData::get_pin_categories(pin_name),
             Self::AnalyzerFFT => AnalyzerFFTProcessData::get_pin_categories(pin_name),
             Self::AnalyzerLUFS => AnalyzeLUFSProcessData::get_pin_categories(pin_name),
@@ -330,6 +415,7 @@ impl ENodeSpecifier {
                 IIRProcessData::get_pin_categories(pin_name)
             },
             Self::FilterIRConvolution => IRConvolutionProcessData::get_pin_categories(pin_name),
+            Self::FilterDeconvolve => DeconvolveProcessData::get_pin_categories(pin_name),
             Self::AdapterResample => ResampleProcessData::get_pin_categories(pin_name),
             Self::EmitterSineSweep => SineSweepEmitterProcessData::get_pin_categories(pin_name),
             Self::AdapterDelay => AdapterDelayProcessData::get_pin_categories(pin_name),
@@ -351,9 +437,23 @@ impl ENodeSpecifier {
             | Self::EmitterSquare
             | Self::EmitterTriangle
             | Self::EmitterWhiteNoise
+            | Self::EmitterBrownNoise
+            | Self::EmitterBlueNoise
+            | Self::EmitterVioletNoise
+            | Self::EmitterVelvetNoise
             | Self::EmitterSineWave => SineWaveEmitterProcessData::get_input_container_flag(pin_name),
             Self::EmitterWavMono => EmitterWavMonoProcessData::get_input_container_flag(pin_name),
             Self::EmitterWavStereo => EmitterWavStereoProcessData::get_input_container_flag(pin_name),
+            Self::EmitterDeviceInput => EmitterDeviceInputProcessData::get_input_container_flag(pin_name),
+            Self::EmitterDeviceMetrics => EmitterDeviceMetricsProcessData::get_input_container_flag(pin_name),
+            Self::EmitterMidiFile => EmitterMidiFileProcessData::get_input_container_flag(pin_name),
+            Self::EmitterMidiInput => EmitterMidiInputProcessData::get_input_container_flag(pin_name),
+            Self::EmitterFm => EmitterFmProcessData::get_input_container_flag(pin_name),
+            Self::EmitterWavetable => EmitterWavetableProcessData::get_input_container_flag(pin_name),
+            Self::EmitterAdditive => EmitterAdditiveProcessData::get_input_container_flag(pin_name),
+            Self::EmitterPluck => EmitterPluckProcessData::get_input_container_flag(pin_name),
+            Self::EmitterGranular => EmitterGranularProcessData::get_input_container_flag(pin_name),
+            Self::EmitterTestSignal => EmitterTestSignalProcessData::get_input_container_flag(pin_name),
             Self::AnalyzerDFT => AnalyzerDFTProcessData::get_input_container_flag(pin_name),
             Self::AnalyzerFFT => AnalyzerFFTProcessData::get_input_container_flag(pin_name),
             Self::AnalyzerLUFS => AnalyzeLUFSProcessData::get_input_container_flag(pin_name),
@@ -369,6 +469,7 @@ impl ENodeSpecifier {
                 IIRProcessData::get_input_container_flag(pin_name)
             },
             Self::FilterIRConvolution => IRConvolutionProcessData::get_input_container_flag(pin_name),
+            Self::FilterDeconvolve => DeconvolveProcessData::get_input_container_flag(pin_name),
             Self::AdapterResample => ResampleProcessData::get_input_container_flag(pin_name),
             Self::EmitterSineSweep => SineSweepEmitterProcessData::get_input_container_flag(pin_name),
             Self::AdapterDelay => AdapterDelayProcessData::get_input_container_flag(pin_name),
@@ -391,9 +492,23 @@ impl ENodeSpecifier {
             | Self::EmitterSquare
             | Self::EmitterTriangle
             | Self::EmitterWhiteNoise
+            | Self::EmitterBrownNoise
+            | Self::EmitterBlueNoise
+            | Self::EmitterVioletNoise
+            | Self::EmitterVelvetNoise
             | Self::EmitterSineWave => SineWaveEmitterProcessData::get_dependent_system_categories(),
             Self::EmitterWavMono => EmitterWavMonoProcessData::get_dependent_system_categories(),
             Self::EmitterWavStereo => EmitterWavStereoProcessData::get_dependent_system_categories(),
+            Self::EmitterDeviceInput => EmitterDeviceInputProcessData::get_dependent_system_categories(),
+            Self::EmitterDeviceMetrics => EmitterDeviceMetricsProcessData::get_dependent_system_categories(),
+            Self::EmitterMidiFile => EmitterMidiFileProcessData::get_dependent_system_categories(),
+            Self::EmitterMidiInput => EmitterMidiInputProcessData::get_dependent_system_categories(),
+            Self::EmitterFm => EmitterFmProcessData::get_dependent_system_categories(),
+            Self::EmitterWavetable => EmitterWavetableProcessData::get_dependent_system_categories(),
+            Self::EmitterAdditive => EmitterAdditiveProcessData::get_dependent_system_categories(),
+            Self::EmitterPluck => EmitterPluckProcessData::get_dependent_system_categories(),
+            Self::EmitterGranular => EmitterGranularProcessData::get_dependent_system_categories(),
+            Self::EmitterTestSignal => EmitterTestSignalProcessData::get_dependent_system_categories(),
             Self::AnalyzerDFT => AnalyzerDFTProcessData::get_dependent_system_categories(),
             Self::AnalyzerFFT => AnalyzerFFTProcessData::get_dependent_system_categories(),
             Self::AnalyzerLUFS => AnalyzeLUFSProcessData::get_dependent_system_categories(),
@@ -409,6 +524,7 @@ impl ENodeSpecifier {
                 IIRProcessData::get_dependent_system_categories()
             }
             Self::FilterIRConvolution => IRConvolutionProcessData::get_dependent_system_categories(),
+            Self::FilterDeconvolve => DeconvolveProcessData::get_dependent_system_categories(),
             Self::AdapterResample => ResampleProcessData::get_dependent_system_categories(),
             Self::EmitterSineSweep => SineSweepEmitterProcessData::get_dependent_system_categories(),
             Self::AdapterDelay => AdapterDelayProcessData::get_dependent_system_categories(),
@@ -437,6 +553,7 @@ impl ENodeSpecifier {
                 IIRProcessData::can_support_offline()
             },
             Self::FilterIRConvolution => IRConvolutionProcessData::can_support_offline(),
+            Self::FilterDeconvolve => DeconvolveProcessData::can_support_offline(),
             Self::AnalyzerDFT => AnalyzerDFTProcessData::can_support_offline(),
             Self::AnalyzerFFT => AnalyzerFFTProcessData::can_support_offline(),
             Self::AnalyzerLUFS => AnalyzeLUFSProcessData::can_support_offline(),
@@ -445,9 +562,23 @@ impl ENodeSpecifier {
             | Self::EmitterSquare
             | Self::EmitterTriangle
             | Self::EmitterWhiteNoise
+            | Self::EmitterBrownNoise
+            | Self::EmitterBlueNoise
+            | Self::EmitterVioletNoise
+            | Self::EmitterVelvetNoise
             | Self::EmitterSineWave => SineWaveEmitterProcessData::can_support_offline(),
             Self::EmitterWavMono => EmitterWavMonoProcessData::can_support_offline(),
             Self::EmitterWavStereo => EmitterWavStereoProcessData::can_support_offline(),
+            Self::EmitterDeviceInput => EmitterDeviceInputProcessData::can_support_offline(),
+            Self::EmitterDeviceMetrics => EmitterDeviceMetricsProcessData::can_support_offline(),
+            Self::EmitterMidiFile => EmitterMidiFileProcessData::can_support_offline(),
+            Self::EmitterMidiInput => EmitterMidiInputProcessData::can_support_offline(),
+            Self::EmitterFm => EmitterFmProcessData::can_support_offline(),
+            Self::EmitterWavetable => EmitterWavetableProcessData::can_support_offline(),
+            Self::EmitterAdditive => EmitterAdditiveProcessData::can_support_offline(),
+            Self::EmitterPluck => EmitterPluckProcessData::can_support_offline(),
+            Self::EmitterGranular => EmitterGranularProcessData::can_support_offline(),
+            Self::EmitterTestSignal => EmitterTestSignalProcessData::can_support_offline(),
             Self::EmitterIDFT => IDFTEmitterProcessData::can_support_offline(),
             Self::EmitterIFFT => IFFTEmitterProcessData::can_support_offline(),
             Self::AdapterResample => ResampleProcessData::can_support_offline(),
@@ -473,6 +604,7 @@ impl ENodeSpecifier {
                 IIRProcessData::can_support_realtime()
             },
             Self::FilterIRConvolution => IRConvolutionProcessData::can_support_realtime(),
+            Self::FilterDeconvolve => DeconvolveProcessData::can_support_realtime(),
             Self::AnalyzerDFT => AnalyzerDFTProcessData::can_support_realtime(),
             Self::AnalyzerFFT => AnalyzerFFTProcessData::can_support_realtime(),
             Self::AnalyzerLUFS => AnalyzeLUFSProcessData::can_support_realtime(),
@@ -481,9 +613,23 @@ impl ENodeSpecifier {
             | Self::EmitterSquare
             | Self::EmitterTriangle
             | Self::EmitterWhiteNoise
+            | Self::EmitterBrownNoise
+            | Self::EmitterBlueNoise
+            | Self::EmitterVioletNoise
+            | Self::EmitterVelvetNoise
             | Self::EmitterSineWave => SineWaveEmitterProcessData::can_support_realtime(),
             Self::EmitterWavMono => EmitterWavMonoProcessData::can_support_realtime(),
             Self::EmitterWavStereo => EmitterWavStereoProcessData::can_support_realtime(),
+            Self::EmitterDeviceInput => EmitterDeviceInputProcessData::can_support_realtime(),
+            Self::EmitterDeviceMetrics => EmitterDeviceMetricsProcessData::can_support_realtime(),
+            Self::EmitterMidiFile => EmitterMidiFileProcessData::can_support_realtime(),
+            Self::EmitterMidiInput => EmitterMidiInputProcessData::can_support_realtime(),
+            Self::EmitterFm => EmitterFmProcessData::can_support_realtime(),
+            Self::EmitterWavetable => EmitterWavetableProcessData::can_support_realtime(),
+            Self::EmitterAdditive => EmitterAdditiveProcessData::can_support_realtime(),
+            Self::EmitterPluck => EmitterPluckProcessData::can_support_realtime(),
+            Self::EmitterGranular => EmitterGranularProcessData::can_support_realtime(),
+            Self::EmitterTestSignal => EmitterTestSignalProcessData::can_support_realtime(),
             Self::EmitterIDFT => IDFTEmitterProcessData::can_support_realtime(),
             Self::EmitterIFFT => IFFTEmitterProcessData::can_support_realtime(),
             Self::AdapterResample => ResampleProcessData::can_support_realtime(),
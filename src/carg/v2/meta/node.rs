@@ -13,6 +13,7 @@ use crate::carg::v2::emitter::oscilo::{MetaSineEmitterInfo, MetaSineNoiseInfo, M
 use crate::carg::v2::emitter::wav_mono::{EmitterWavMonoProcessData, MetaWavMonoInfo};
 use crate::carg::v2::filter::fir::{FIRProcessData, MetaFIRInfo};
 use crate::carg::v2::filter::iir::{IIRProcessData, MetaIIRInfo};
+use crate::carg::v2::filter::deconvolve::{DeconvolveProcessData, MetaDeconvolveInfo};
 use crate::carg::v2::filter::irconv::{IRConvolutionProcessData, MetaIRConvInfo};
 use crate::carg::v2::meta::process::{process_category, EProcessCategoryFlag};
 use crate::carg::v2::meta::relation::{Relation, RelationItemPin};
@@ -34,6 +35,16 @@ use std::collections::HashMap;
 use crate::carg::v2::adapter::delay::{AdapterDelayProcessData, MetaDelayInfo};
 use crate::carg::v2::emitter::sine_sweep::{MetaSineSweepInfo, SineSweepEmitterProcessData};
 use crate::carg::v2::emitter::wav_stereo::{EmitterWavStereoProcessData, MetaWavStereoInfo};
+use crate::carg::v2::emitter::device_input::{EmitterDeviceInputProcessData, MetaDeviceInputInfo};
+use crate::carg::v2::emitter::device_metrics::{EmitterDeviceMetricsProcessData, MetaDeviceMetricsInfo};
+use crate::carg::v2::emitter::midi_file::{EmitterMidiFileProcessData, MetaMidiFileInfo};
+use crate::carg::v2::emitter::midi_input::{EmitterMidiInputProcessData, MetaMidiInputInfo};
+use crate::carg::v2::emitter::fm::{EmitterFmProcessData, MetaFmInfo};
+use crate::carg::v2::emitter::wavetable::{EmitterWavetableProcessData, MetaWavetableInfo};
+use crate::carg::v2::emitter::additive::{EmitterAdditiveProcessData, MetaAdditiveInfo};
+use crate::carg::v2::emitter::pluck::{EmitterPluckProcessData, MetaPluckInfo};
+use crate::carg::v2::emitter::granular::{EmitterGranularProcessData, MetaGranularInfo};
+use crate::carg::v2::emitter::test_signal::{EmitterTestSignalProcessData, MetaTestSignalInfo};
 use crate::carg::v2::mix::separator::{MetaSeparatorInfo, MixSeparatorProcessData};
 // ----------------------------------------------------------------------------
 // ENode
@@ -54,6 +65,18 @@ pub enum ENode {
     /// ホワイトノイズを出力する。
     #[serde(rename = "emitter-whitenoise")]
     EmitterWhiteNoise(MetaSineNoiseInfo),
+    /// ブラウンノイズ（レッドノイズ）を出力する。
+    #[serde(rename = "emitter-brownnoise", alias = "emitter-rednoise")]
+    EmitterBrownNoise(MetaSineNoiseInfo),
+    /// ブルーノイズを出力する。
+    #[serde(rename = "emitter-bluenoise")]
+    EmitterBlueNoise(MetaSineNoiseInfo),
+    /// バイオレットノイズを出力する。
+    #[serde(rename = "emitter-violetnoise")]
+    EmitterVioletNoise(MetaSineNoiseInfo),
+    /// ベルベットノイズを出力する。
+    #[serde(rename = "emitter-velvetnoise")]
+    EmitterVelvetNoise(MetaSineNoiseInfo),
     /// サイン波形（正弦波）を出力する。
     #[serde(rename = "emitter-sine")]
     EmitterSineWave(MetaSineEmitterInfo),
@@ -85,6 +108,36 @@ pub enum ENode {
     EmitterWavMono(MetaWavMonoInfo),
     #[serde(rename = "emitter-wav-stereo")]
     EmitterWavStereo(MetaWavStereoInfo),
+    /// オーディオデバイスの入力（マイク・ライン入力）をバッファで出力する。
+    #[serde(rename = "emitter-device-input")]
+    EmitterDeviceInput(MetaDeviceInputInfo),
+    /// オーディオデバイスの再生状態の測定値をテキストで出力する。
+    #[serde(rename = "emitter-device-metrics")]
+    EmitterDeviceMetrics(MetaDeviceMetricsInfo),
+    /// MIDIファイルのノートでオシレーターを鳴らして、バッファで出力する。
+    #[serde(rename = "emitter-midi-file")]
+    EmitterMidiFile(MetaMidiFileInfo),
+    /// ライブのMIDI入力のノートでオシレーターを鳴らして、バッファで出力する。
+    #[serde(rename = "emitter-midi-input")]
+    EmitterMidiInput(MetaMidiInputInfo),
+    /// 複数のオペレーターでFM合成して、バッファで出力する。
+    #[serde(rename = "emitter-fm")]
+    EmitterFm(MetaFmInfo),
+    /// WAVから読み込んだウェーブテーブルを再生して、バッファで出力する。
+    #[serde(rename = "emitter-wavetable")]
+    EmitterWavetable(MetaWavetableInfo),
+    /// 部分音のリストから加算合成して、バッファで出力する。
+    #[serde(rename = "emitter-additive")]
+    EmitterAdditive(MetaAdditiveInfo),
+    /// 弦を弾いた音をKarplus-Strong法で合成して、バッファで出力する。
+    #[serde(rename = "emitter-pluck")]
+    EmitterPluck(MetaPluckInfo),
+    /// WAVファイルか入力のバッファから切り出したグレインを重ねて、バッファで出力する。
+    #[serde(rename = "emitter-granular")]
+    EmitterGranular(MetaGranularInfo),
+    /// インパルス、パルス列、DC、無音、マルチトーンのテスト信号を出力する。
+    #[serde(rename = "emitter-test-signal")]
+    EmitterTestSignal(MetaTestSignalInfo),
     #[serde(rename = "emitter-sinesweep")]
     EmitterSineSweep(MetaSineSweepInfo),
     /// DFTで音波を分析する。
@@ -155,6 +208,9 @@ pub enum ENode {
     FilterIIRBandStop(MetaIIRInfo),
     #[serde(rename = "filter-irconv")]
     FilterIRConvolution(MetaIRConvInfo),
+    /// 録音したスイープに逆フィルターを畳み込んで、`filter-irconv`で使えるインパルス応答を出力する。
+    #[serde(rename = "filter-deconvolve")]
+    FilterDeconvolve(MetaDeconvolveInfo),
     #[serde(rename = "mix-stereo")]
     MixStereo(MetaStereoInfo),
     #[serde(rename = "mix-separator")]
@@ -164,104 +220,173 @@ pub enum ENode {
     OutputFile(MetaOutputFileInfo),
     #[serde(rename = "output-log")]
     OutputLog { mode: EParsedOutputLogMode },
+    /// モノラルかステレオのバッファをオーディオデバイスに流す。
     #[serde(rename = "output-device")]
     OutputDevice(MetaOutputDeviceInfo),
 }
 
 impl ENode {
     /// ノードから処理アイテムを生成する。
-    pub fn create_from(&self, setting: &Setting, system_setting: &InitializeSystemAccessor) -> TProcessItemPtr {
+    /// ノードの設定が正しくなければエラーを返す。
+    pub fn create_from(
+        &self,
+        setting: &Setting,
+        system_setting: &InitializeSystemAccessor,
+    ) -> anyhow::Result<TProcessItemPtr> {
         let setting = ProcessItemCreateSetting { node: &self, setting };
 
         match self {
             ENode::EmitterPinkNoise { .. }
             | ENode::EmitterWhiteNoise { .. }
+            | ENode::EmitterBrownNoise { .. }
+            | ENode::EmitterBlueNoise { .. }
+            | ENode::EmitterVioletNoise { .. }
+            | ENode::EmitterVelvetNoise { .. }
             | ENode::EmitterSineWave { .. }
             | ENode::EmitterTriangle { .. }
             | ENode::EmitterSquare { .. }
             | ENode::EmitterSawtooth { .. } => {
-                SineWaveEmitterProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                SineWaveEmitterProcessData::create_item(&setting, &system_setting)
             }
             ENode::AdapterEnvelopeAd { .. } => {
-                AdapterEnvelopeAdProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AdapterEnvelopeAdProcessData::create_item(&setting, &system_setting)
             },
             ENode::AdapterEnvelopeAdsr { .. } => {
-                AdapterEnvelopeAdsrProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AdapterEnvelopeAdsrProcessData::create_item(&setting, &system_setting)
             },
             ENode::AdapterCompressor(_) => {
-                AdapterCompressorProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AdapterCompressorProcessData::create_item(&setting, &system_setting)
             },
             ENode::AdapterLimiter(_) => {
-                AdapterLimiterProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AdapterLimiterProcessData::create_item(&setting, &system_setting)
             },
             ENode::AdapterWaveSum => {
-                AdapterWaveSumProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AdapterWaveSumProcessData::create_item(&setting, &system_setting)
             },
             ENode::AdapterResample(_) => {
-                ResampleProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                ResampleProcessData::create_item(&setting, &system_setting)
             }
             ENode::AdapterDelay(_) => {
-                AdapterDelayProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AdapterDelayProcessData::create_item(&setting, &system_setting)
             }
             ENode::AnalyzerDFT { .. } => {
-                AnalyzerDFTProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AnalyzerDFTProcessData::create_item(&setting, &system_setting)
             },
             ENode::AnalyzerFFT { .. } => {
-                AnalyzerFFTProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AnalyzerFFTProcessData::create_item(&setting, &system_setting)
             },
             ENode::InternalStartPin => {
-                StartProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                StartProcessData::create_item(&setting, &system_setting)
             },
             ENode::EmitterIDFT { .. } => {
-                IDFTEmitterProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                IDFTEmitterProcessData::create_item(&setting, &system_setting)
             },
             ENode::EmitterIFFT { .. } => {
-                IFFTEmitterProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                IFFTEmitterProcessData::create_item(&setting, &system_setting)
             }
             ENode::EmitterWavMono(_) => {
-                EmitterWavMonoProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                EmitterWavMonoProcessData::create_item(&setting, &system_setting)
             },
             ENode::EmitterWavStereo(_) => {
-                EmitterWavStereoProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                EmitterWavStereoProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterDeviceInput(_) => {
+                EmitterDeviceInputProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterDeviceMetrics(_) => {
+                EmitterDeviceMetricsProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterMidiFile(_) => {
+                EmitterMidiFileProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterMidiInput(_) => {
+                EmitterMidiInputProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterFm(_) => {
+                EmitterFmProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterWavetable(_) => {
+                EmitterWavetableProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterAdditive(_) => {
+                EmitterAdditiveProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterPluck(_) => {
+                EmitterPluckProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterGranular(_) => {
+                EmitterGranularProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::EmitterTestSignal(_) => {
+                EmitterTestSignalProcessData::create_item(&setting, &system_setting)
             }
             ENode::InternalDummy => {
-                DummyProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                DummyProcessData::create_item(&setting, &system_setting)
             }
             ENode::MixStereo { .. } => {
-                MixStereoProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                MixStereoProcessData::create_item(&setting, &system_setting)
             },
             ENode::MixSeparator(_) => {
-                MixSeparatorProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                MixSeparatorProcessData::create_item(&setting, &system_setting)
             }
             ENode::FilterFIR(_) => {
-                FIRProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                FIRProcessData::create_item(&setting, &system_setting)
             }
             ENode::FilterIIRHPF(_) |
             ENode::FilterIIRBandPass(_) |
             ENode::FilterIIRBandStop(_) |
             ENode::FilterIIRLPF(_) => {
-                IIRProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                IIRProcessData::create_item(&setting, &system_setting)
             }
             ENode::FilterIRConvolution(_) => {
-                IRConvolutionProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                IRConvolutionProcessData::create_item(&setting, &system_setting)
+            }
+            ENode::FilterDeconvolve(_) => {
+                DeconvolveProcessData::create_item(&setting, &system_setting)
             }
             ENode::OutputLog { .. } => {
-                OutputLogProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                OutputLogProcessData::create_item(&setting, &system_setting)
             }
             ENode::OutputFile(_) => {
-                OutputFileProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                OutputFileProcessData::create_item(&setting, &system_setting)
             }
             ENode::AnalyzerLUFS(_) => {
-                AnalyzeLUFSProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                AnalyzeLUFSProcessData::create_item(&setting, &system_setting)
             }
             ENode::OutputDevice(_) => {
-                OutputDeviceProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                OutputDeviceProcessData::create_item(&setting, &system_setting)
             }
             ENode::EmitterSineSweep(_) => {
-                SineSweepEmitterProcessData::create_item(&setting, &system_setting).expect("Failed to create item")
+                SineSweepEmitterProcessData::create_item(&setting, &system_setting)
             }
         }
     }
+
+    /// 設定だけで出力のサンプルレートが決まるノードなら、そのサンプルレートを返す。
+    /// ファイルや入力によって変わるノードは`None`を返す。
+    pub fn static_sample_rate(&self) -> Option<usize> {
+        match self {
+            ENode::EmitterPinkNoise(v)
+            | ENode::EmitterWhiteNoise(v)
+            | ENode::EmitterBrownNoise(v)
+            | ENode::EmitterBlueNoise(v)
+            | ENode::EmitterVioletNoise(v)
+            | ENode::EmitterVelvetNoise(v) => Some(v.sample_rate),
+            ENode::EmitterSineWave(v) | ENode::EmitterSawtooth(v) | ENode::EmitterTriangle(v) => Some(v.sample_rate),
+            ENode::EmitterSquare(v) => Some(v.sample_rate),
+            ENode::EmitterSineSweep(v) => Some(v.sample_rate),
+            ENode::EmitterMidiFile(v) => Some(v.synth.sample_rate),
+            ENode::EmitterMidiInput(v) => Some(v.synth.sample_rate),
+            ENode::EmitterFm(v) => Some(v.sample_rate),
+            ENode::EmitterWavetable(v) => Some(v.sample_rate),
+            ENode::EmitterAdditive(v) => Some(v.sample_rate),
+            ENode::EmitterPluck(v) => Some(v.sample_rate),
+            ENode::EmitterGranular(v) => Some(v.sample_rate),
+            ENode::EmitterTestSignal(v) => Some(v.sample_rate),
+            ENode::AdapterResample(v) => Some(v.to_sample_rate),
+            _ => None,
+        }
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use crate::carg::v2::meta::tick::ETimeTickMode;
+use crate::carg::v2::meta::tick::{ETimeTickMode, ETimeTickScheduler};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Setting {
@@ -8,6 +8,9 @@ pub struct Setting {
     /// [`ETimeTickMode::Realtime`]処理モードで、
     /// フレーム時間が多くなっても指定した時間より多くのサンプルを処理しないようにする。
     pub process_limit_time: f64,
+    /// [`ETimeTickMode::Realtime`]処理モードのフレームの進め方。指定しなければ[`ETimeTickScheduler::Push`]にする。
+    #[serde(default)]
+    pub scheduler: ETimeTickScheduler,
 }
 
 impl Setting {
@@ -18,6 +21,11 @@ impl Setting {
             "Given `process_limit_time` must be positive second value"
             ));
         }
+        if setting.scheduler == ETimeTickScheduler::Pull && setting.time_tick_mode != ETimeTickMode::Realtime {
+            return Err(anyhow::anyhow!(
+            "Given `scheduler` pull can only be used with `realtime` time_tick_mode"
+            ));
+        }
 
         Ok(setting)
     }
@@ -1,5 +1,8 @@
+use crate::carg::v2::meta::setting::Setting;
+use crate::carg::v2::meta::tick::ETimeTickScheduler;
 use crate::device::{AudioDevice, AudioDeviceConfig, AudioDeviceProxyWeakPtr, AudioDeviceSetting};
 use crate::file::{FileIO, FileIOProxy, FileIOProxyWeakPtr, FileIOSetting};
+use crate::midi::input::{MidiInput, MidiInputProxyWeakPtr, MidiInputSetting};
 use crate::resample::{ResampleSystem, ResampleSystemConfig, ResampleSystemProxyWeakPtr};
 use num_traits::Zero;
 use serde::{Deserialize, Serialize};
@@ -20,8 +23,7 @@ pub mod system_category {
     /// @todo 実装すること。
     pub const FILE_IO_SYSTEM: u32 = 1 << 2;
 
-    /// @todo Emitter系ターゲットにして実装。
-    #[allow(dead_code)]
+    /// ライブのMIDI入力でEmitter系ノードのボイスを鳴らしたり止めたりするシステム
     pub const REALTIME_TRIGGER_SYSTEM: u32 = 1 << 3;
 }
 
@@ -43,6 +45,8 @@ pub struct SystemSetting {
     pub audio_device: Option<AudioDeviceSetting>,
     /// [`FileIO`]の設定
     pub file_io: Option<FileIOSetting>,
+    /// [`MidiInput`]の設定
+    pub midi_input: Option<MidiInputSetting>,
 }
 
 impl SystemSetting {
@@ -63,6 +67,8 @@ pub struct InitializeSystemAccessor {
     pub resample_system: Option<ResampleSystemProxyWeakPtr>,
     /// [`FileIO`]システムに接近できるアクセサー
     pub file_io: Option<FileIOProxyWeakPtr>,
+    /// [`MidiInput`]システムに接近できるアクセサー
+    pub midi_input: Option<MidiInputProxyWeakPtr>,
 }
 
 impl InitializeSystemAccessor {
@@ -80,23 +86,53 @@ impl InitializeSystemAccessor {
 
 /// `flags`から関連システムを初期化する。
 /// 一回きりで実行すべき。
-pub fn initialize_systems(flags: ESystemCategoryFlag, system_setting: &SystemSetting) -> InitializeSystemAccessor {
+///
+/// 途中でシステムの初期化に失敗したら、それまで初期化したシステムを解放してからエラーを返す。
+pub fn initialize_systems(
+    flags: ESystemCategoryFlag,
+    setting: &Setting,
+    system_setting: &SystemSetting,
+) -> anyhow::Result<InitializeSystemAccessor> {
     let mut result = InitializeSystemAccessor::default();
 
     // FileIOSystemの初期化
     if !(flags & system_category::FILE_IO_SYSTEM).is_zero() {
-        let setting = system_setting.file_io.as_ref().expect("FileIOSetting not set.");
-        result.file_io = Some(FileIO::initialize(setting.clone()));
+        // `file_io`を書いていない昔のグラフでも動くように、なければデフォルトの設定にする。
+        let setting = system_setting.file_io.clone().unwrap_or_default();
+        result.file_io = Some(FileIO::initialize(setting));
     }
 
     // AudioDeviceの初期化
     if !(flags & system_category::AUDIO_DEVICE).is_zero() {
-        let setting = system_setting.audio_device.as_ref().expect("AudioDeviceSetting not set");
-        assert!(setting.channels > 0);
+        if let Err(e) = initialize_audio_device(&mut result, setting, system_setting) {
+            // 先に初期化したシステムを解放する。
+            if result.file_io.is_some() {
+                FileIO::cleanup();
+            }
+            return Err(e);
+        }
+    }
 
-        let mut config = AudioDeviceConfig::new();
-        config.set_channels(setting.channels).set_sample_rate(setting.sample_rate);
-        result.audio_device = Some(AudioDevice::initialize(config));
+    // MidiInputの初期化
+    if !(flags & system_category::REALTIME_TRIGGER_SYSTEM).is_zero() {
+        let midi_input = system_setting
+            .midi_input
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("`midi_input` of `system_setting` must be set to use the midi input."))
+            .and_then(|v| MidiInput::initialize(v.clone()));
+        match midi_input {
+            Ok(v) => result.midi_input = Some(v),
+            Err(e) => {
+                // 先に初期化したシステムを解放する。
+                if result.audio_device.is_some() {
+                    AudioDevice::cleanup();
+                }
+                if result.file_io.is_some() {
+                    FileIO::cleanup();
+                }
+                return Err(e);
+            }
+        }
     }
 
     // ResampleSystemの初期化
@@ -105,7 +141,44 @@ pub fn initialize_systems(flags: ESystemCategoryFlag, system_setting: &SystemSet
         result.resample_system = Some(ResampleSystem::initialize(config));
     }
 
-    result
+    Ok(result)
+}
+
+/// [`AudioDevice`]を初期化して`result`に入れる。
+fn initialize_audio_device(
+    result: &mut InitializeSystemAccessor,
+    setting: &Setting,
+    system_setting: &SystemSetting,
+) -> anyhow::Result<()> {
+    let scheduler = setting.scheduler;
+    let setting = system_setting
+        .audio_device
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("`audio_device` of `system_setting` must be set to use the audio device."))?;
+    let capture_channels = setting.capture_channels.unwrap_or(0);
+    if setting.channels == 0 && capture_channels == 0 {
+        return Err(anyhow::anyhow!(
+            "Either `channels` or `capture_channels` of `audio_device` must be bigger than 0."
+        ));
+    }
+    if setting.sample_rate == 0 {
+        return Err(anyhow::anyhow!("`sample_rate` of `audio_device` must be bigger than 0."));
+    }
+
+    let mut config = AudioDeviceConfig::new();
+    config
+        .set_channels(setting.channels)
+        .set_sample_rate(setting.sample_rate)
+        .set_capture_channels(capture_channels)
+        .set_backend(setting.backend)
+        .set_device(setting.device.clone())
+        .set_capture_device(setting.capture_device.clone())
+        .set_period_size(setting.period_size)
+        .set_buffer_count(setting.buffer_count)
+        .set_virtual_device(setting.virtual_device.clone())
+        .set_use_frame_demand(scheduler == ETimeTickScheduler::Pull);
+    result.audio_device = Some(AudioDevice::initialize(config)?);
+    Ok(())
 }
 
 /// グラフ処理前のシステムの前処理
@@ -143,6 +216,11 @@ pub fn cleanup_systems(flags: ESystemCategoryFlag) {
     if !(flags & system_category::FILE_IO_SYSTEM).is_zero() {
         FileIO::cleanup();
     }
+
+    // MidiInputの解放
+    if !(flags & system_category::REALTIME_TRIGGER_SYSTEM).is_zero() {
+        MidiInput::cleanup();
+    }
 }
 
 // ----------------------------------------------------------------------------
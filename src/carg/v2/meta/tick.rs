@@ -9,6 +9,19 @@ pub enum ETimeTickMode {
     Realtime,
 }
 
+/// [`ETimeTickMode::Realtime`]のフレームをどうやって進めるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ETimeTickScheduler {
+    /// メインスレッドで一定時間ごとにグラフを処理して、リングバッファに押し込む。
+    #[default]
+    #[serde(rename = "push")]
+    Push,
+    /// 専用のレンダースレッドで、デバイスのコールバックが要求した分だけグラフを処理する。
+    /// オーディオデバイスを使うグラフでしか使えない。
+    #[serde(rename = "pull")]
+    Pull,
+}
+
 /// メタTrait。
 /// 各ノードの[`ETimeTickMode`]のサポート可否を指定する。
 pub trait TTimeTickCategory {
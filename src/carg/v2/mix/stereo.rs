@@ -115,7 +115,13 @@ impl MixStereoProcessData {
         debug_assert_eq!(sample_rate_1, sample_rate_2);
 
         let time_result = self.timer.process_time(input.common.sample_frame_time, sample_rate_1);
-        if time_result.required_sample_count <= 0 {
+        // プル型なら入力のエミッターと同じく、デバイスが要求した分だけを処理する。
+        // タイマーから計算すると誤差で入力と数がずれて、足りない分が0で埋められてしまう。
+        let required_sample_count = match input.common.frame_demand {
+            Some(demand) => demand.required_samples(sample_rate_1),
+            None => time_result.required_sample_count,
+        };
+        if required_sample_count <= 0 {
             return;
         }
 
@@ -123,7 +129,7 @@ impl MixStereoProcessData {
         let old_internal_time = time_result.old_time;
         if self.timer.internal_time() <= 0.0 {
             // ゼロ入りのバッファだけを作る。
-            let buffer = vec![UniformedSample::MIN; time_result.required_sample_count];
+            let buffer = vec![UniformedSample::MIN; required_sample_count];
             self.common
                 .insert_to_output_pin(
                     OUTPUT_OUT,
@@ -146,11 +152,11 @@ impl MixStereoProcessData {
         } else {
             0
         };
-        debug_assert!(time_result.required_sample_count >= pre_blank_counts);
+        debug_assert!(required_sample_count >= pre_blank_counts);
 
         // 処理したものを渡す。
-        let result_1 = self.drain_buffer(input, time_result.required_sample_count, pre_blank_counts, INPUT_IN_1);
-        let result_2 = self.drain_buffer(input, time_result.required_sample_count, pre_blank_counts, INPUT_IN_2);
+        let result_1 = self.drain_buffer(input, required_sample_count, pre_blank_counts, INPUT_IN_1);
+        let result_2 = self.drain_buffer(input, required_sample_count, pre_blank_counts, INPUT_IN_2);
 
         // outputのどこかに保持する。
         self.common
@@ -3,14 +3,15 @@ use crate::carg::v2::meta::node::{ENode, MetaNodeContainer};
 use crate::carg::v2::meta::process::{process_category, EProcessCategoryFlag, StartItemGroup};
 use crate::carg::v2::meta::setting::Setting;
 use crate::carg::v2::meta::system::{
-    cleanup_systems, initialize_systems, postprocess_systems, preprocess_systems,
+    cleanup_systems, initialize_systems, postprocess_systems, preprocess_systems, system_category,
     InitializeSystemAccessor, SystemSetting,
 };
-use crate::carg::v2::meta::tick::ETimeTickMode;
+use crate::carg::v2::meta::tick::{ETimeTickMode, ETimeTickScheduler};
 use crate::carg::v2::meta::{pin_category, EPinCategoryFlag};
 use crate::carg::v2::node::common::ProcessControlItem;
 use crate::carg::v2::node::{process_result, RelationTreeNode};
-use crate::carg::v2::utility::{update_process_graph_connection, validate_node_relations};
+use crate::carg::v2::utility::{update_process_graph_connection, validate_device_sample_rates, validate_node_relations};
+use crate::device::AudioDevice;
 use crate::wave::analyze::sine_freq::SineFrequency;
 use crate::{math::timer::Timer, wave::sample::UniformedSample};
 use itertools::Itertools;
@@ -37,6 +38,9 @@ pub mod output;
 mod special;
 mod utility;
 
+/// [`ETimeTickScheduler::Pull`]でデバイスの要求をこれだけ待っても来なければ、デバイスが止まったとみなす。
+const FRAME_DEMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// シングルスレッド、通常参照
 pub type ItemSPtr<T> = Rc<RefCell<T>>;
 
@@ -106,6 +110,33 @@ pub struct ProcessCommonInput {
     pub category: EProcessCategoryFlag,
     /// フレームの処理カウント
     pub process_counter: usize,
+    /// [`ETimeTickScheduler::Pull`]の時に、デバイスが要求したフレームの区間。
+    /// [`ETimeTickScheduler::Push`]なら`None`。
+    pub frame_demand: Option<FrameDemand>,
+}
+
+/// [`ETimeTickScheduler::Pull`]でデバイスのコールバックが要求したフレームの区間。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameDemand {
+    /// 今までに処理したデバイスのフレーム数
+    pub start_frame: usize,
+    /// このフレームで処理するデバイスのフレーム数
+    pub frame_count: usize,
+    /// デバイスのサンプルレート
+    pub sample_rate: usize,
+}
+
+impl FrameDemand {
+    /// `sample_rate`のノードがこの区間で処理すべきサンプル数を返す。
+    /// デバイスとサンプルレートが違っても、区間の境目ごとに切り捨てるので誤差は溜まらない。
+    pub fn required_samples(&self, sample_rate: usize) -> usize {
+        if sample_rate == self.sample_rate {
+            return self.frame_count;
+        }
+
+        let to_samples = |frame: usize| (frame as u128 * sample_rate as u128 / self.sample_rate as u128) as usize;
+        to_samples(self.start_frame + self.frame_count) - to_samples(self.start_frame)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -125,7 +156,12 @@ impl ProcessProcessorInput {
     }
 
     /// `sample_rate`から`frame_time`分のサンプル数を取得する。
+    /// [`ETimeTickScheduler::Pull`]ならデバイスが要求したフレーム数ちょうどのサンプル数を返す。
     pub fn get_realtime_required_samples(&self, sample_rate: usize) -> usize {
+        if let Some(demand) = self.common.frame_demand {
+            return demand.required_samples(sample_rate);
+        }
+
         // 余裕分をとる
         (sample_rate as f64 * self.common.sample_frame_time).floor() as usize
     }
@@ -248,6 +284,32 @@ pub fn process_v2(
     system_setting: &SystemSetting,
     nodes: HashMap<String, ENode>,
     relations: &[Relation],
+) -> anyhow::Result<()> {
+    match setting.scheduler {
+        ETimeTickScheduler::Push => process_graph(setting, system_setting, nodes, relations),
+        ETimeTickScheduler::Pull => {
+            // グラフのノードはスレッドをまたげないので、
+            // システムの初期化からグラフの生成と処理まで全部レンダースレッドで行う。
+            std::thread::scope(|scope| {
+                let handle = std::thread::Builder::new()
+                    .name("render".to_owned())
+                    .spawn_scoped(scope, || process_graph(setting, system_setting, nodes, relations))
+                    .expect("Failed to spawn render thread");
+
+                match handle.join() {
+                    Ok(result) => result,
+                    Err(payload) => std::panic::resume_unwind(payload),
+                }
+            })
+        }
+    }
+}
+
+fn process_graph(
+    setting: &Setting,
+    system_setting: &SystemSetting,
+    nodes: HashMap<String, ENode>,
+    relations: &[Relation],
 ) -> anyhow::Result<()> {
     // 下で`_start_pin`のチェックもやってくれる。
     let node_container = MetaNodeContainer { map: nodes };
@@ -255,7 +317,25 @@ pub fn process_v2(
 
     // 依存システムの初期化
     let system_flags = node_container.get_dependent_system_categories();
-    let systems = initialize_systems(system_flags, &system_setting);
+    if setting.scheduler == ETimeTickScheduler::Pull && (system_flags & system_category::AUDIO_DEVICE).is_zero() {
+        return Err(anyhow::anyhow!(
+            "Given `scheduler` pull needs at least one node which uses the audio device."
+        ));
+    }
+    let systems = initialize_systems(system_flags, &setting, &system_setting)?;
+
+    // デバイスのサンプルレートが分かったので、処理を始める前に設定で決まるサンプルレートを確認する。
+    let device_sample_rate = systems
+        .audio_device
+        .as_ref()
+        .and_then(|v| v.upgrade())
+        .map_or(0, |v| v.lock().unwrap().get_sample_rate());
+    if device_sample_rate > 0 {
+        if let Err(e) = validate_device_sample_rates(&node_container, &relations, device_sample_rate) {
+            cleanup_systems(system_flags);
+            return Err(e);
+        }
+    }
 
     // チェックができたので(validation)、relationを元にGraphを生成する。
     // ただしそれぞれの独立したoutputをルートにして必要となるinputを子としてツリーを構成する。
@@ -265,7 +345,14 @@ pub fn process_v2(
         // 各ノードから処理に使うためのアイテムを全部生成しておく。
         // 中でinputピンとoutputピンを作る。
         for (node_name, node) in &node_container.map {
-            let processor = node.create_from(setting, &systems);
+            // 設定が正しくないノードがあれば、初期化したシステムを解放してからエラーを返す。
+            let processor = match node.create_from(setting, &systems) {
+                Ok(v) => v,
+                Err(e) => {
+                    cleanup_systems(system_flags);
+                    return Err(e.context(format!("Failed to create node `{}`.", node_name)));
+                }
+            };
             let node = RelationTreeNode::new_item(&node_name, processor);
             map.insert(node_name.clone(), node);
         }
@@ -317,9 +404,54 @@ pub fn process_v2(
     let mut node_queue = VecDeque::new();
     let mut elapsed_time = 0.0;
     let mut process_counter = 0;
+
+    // プル型ならデバイスのサンプルレートでフレーム数を時間にする。
+    let mut demanded_frame_count = 0usize;
+
+    // プッシュ型で仮想デバイスを使うなら、実際に経った時間とは関係なく仮想デバイスの1周期ずつ時間を進める。
+    // こうすると処理の速さに関係なく、毎回同じ結果になる。
+    let simulated_frame_time = match setting.scheduler {
+        ETimeTickScheduler::Push => AudioDevice::get_virtual_period_time(),
+        ETimeTickScheduler::Pull => None,
+    };
     loop {
-        let prev_to_now_time = tick_timer.tick().as_secs_f64();
-        elapsed_time += tick_timer.tick().as_secs_f64();
+        let (prev_to_now_time, frame_demand) = match setting.scheduler {
+            ETimeTickScheduler::Push => {
+                let prev_to_now_time = match simulated_frame_time {
+                    Some(frame_time) => frame_time,
+                    None => tick_timer.tick().as_secs_f64(),
+                };
+                (prev_to_now_time, None)
+            }
+            ETimeTickScheduler::Pull => {
+                // デバイスのコールバックが要求したフレーム数だけ処理する。
+                // 長い間要求が来なければ、デバイスが止まったとみなしてエラーにする。
+                let frame_count = match AudioDevice::wait_frame_demand(FRAME_DEMAND_TIMEOUT) {
+                    Some(v) => v,
+                    None => {
+                        cleanup_systems(system_flags);
+                        return Err(anyhow::anyhow!(
+                            "Audio device did not request any frames for {} seconds.",
+                            FRAME_DEMAND_TIMEOUT.as_secs_f64()
+                        ));
+                    }
+                };
+                let demand = FrameDemand {
+                    start_frame: demanded_frame_count,
+                    frame_count,
+                    sample_rate: device_sample_rate,
+                };
+                demanded_frame_count += frame_count;
+                (frame_count as f64 / device_sample_rate as f64, Some(demand))
+            }
+        };
+        // デバイスの要求かシミュレーションした時間で進める場合は、このフレームが始まる時点の時間を渡す。
+        // 時間を足していくと誤差が溜まるので、フレーム数から計算する。
+        elapsed_time = match (frame_demand, simulated_frame_time) {
+            (Some(demand), _) => demand.start_frame as f64 / demand.sample_rate as f64,
+            (None, Some(frame_time)) => process_counter as f64 * frame_time,
+            (None, None) => elapsed_time + tick_timer.tick().as_secs_f64(),
+        };
         process_counter += 1;
 
         // 24-12-12 依存システムの処理。
@@ -330,9 +462,14 @@ pub fn process_v2(
             time_tick_mode: setting.time_tick_mode,
             elapsed_time,
             frame_time: prev_to_now_time,
-            sample_frame_time: prev_to_now_time.min(setting.process_limit_time),
+            // プル型ならデバイスが要求した分は全部処理しないと途切れるので、制限しない。
+            sample_frame_time: match frame_demand {
+                Some(_) => prev_to_now_time,
+                None => prev_to_now_time.min(setting.process_limit_time),
+            },
             category: process_category::NORMAL,
             process_counter,
+            frame_demand,
         };
 
         let mut end_node_processed = false;
@@ -353,6 +490,12 @@ pub fn process_v2(
                     continue;
                 }
 
+                // 処理中にエラーが起きたら、初期化したシステムを解放してからエラーを返す。
+                if let Some(e) = node.borrow_mut().take_error() {
+                    cleanup_systems(system_flags);
+                    return Err(e);
+                }
+
                 // 次ノードをQueueに入れる。
                 let next_nodes = node.borrow().get_next_nodes();
                 let is_node_end = next_nodes.is_empty();
@@ -375,7 +518,11 @@ pub fn process_v2(
             break;
         }
 
-        sleep(Duration::from_millis(1));
+        // プル型ならデバイスの要求を待つので、ここでは待たない。
+        // シミュレーションした時間で進める場合も待たなくていい。
+        if setting.scheduler == ETimeTickScheduler::Push && simulated_frame_time.is_none() {
+            sleep(Duration::from_millis(1));
+        }
     }
 
     // 依存システムの解放
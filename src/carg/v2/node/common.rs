@@ -24,6 +24,8 @@ pub struct ProcessControlItem {
     pub output_pins: NodePinItemList,
     /// あらゆるシステムに接近できるためのアクセサー
     pub systems: InitializeSystemAccessor,
+    /// 処理中に起きたエラー。入れるとグラフの処理を止めてエラーとして返す。
+    pub error: Option<String>,
 }
 
 /// [`ProcessControlItem`]を生成するための設定構造体
@@ -57,6 +59,7 @@ impl ProcessControlItem {
             input_pins: setting.specifier.create_input_pins(),
             output_pins: setting.specifier.create_output_pins(),
             systems: setting.systems.clone(),
+            error: None,
         }
     }
 
@@ -190,6 +193,14 @@ impl ProcessControlItem {
         }
     }
 
+    /// `pin_name`のInputピンが他のノードのピンに繋がっているかを確認。
+    pub fn is_input_pin_connected(&self, pin_name: &str) -> bool {
+        match self.input_pins.get(pin_name) {
+            None => false,
+            Some(v) => v.borrow().linked_pins.is_empty() == false,
+        }
+    }
+
     /// `pin_name`のOutputピンが他のノードのピンに繋がっているかを確認。
     pub fn is_output_pin_connected(&self, pin_name: &str) -> bool {
         match self.output_pins.get(pin_name) {
@@ -156,6 +156,12 @@ impl RelationTreeNode {
     pub fn is_finished(&self) -> bool {
         self.processor.borrow().is_finished()
     }
+
+    /// 処理中にエラーが起きていれば、取り出して返す。
+    pub fn take_error(&mut self) -> Option<anyhow::Error> {
+        let error = self.processor.borrow_mut().get_common_mut().error.take()?;
+        Some(anyhow::anyhow!(error).context(format!("Failed to process node `{}`.", self.name)))
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -1,3 +1,10 @@
+use crate::wave::container::aiff::comm::EAiffCompression;
+use crate::wave::container::aiff::is_writable_bits_per_sample;
+use crate::wave::container::aiff::stream::AiffStreamWriter;
+use crate::wave::container::flac::is_supported_bits_per_sample;
+use crate::wave::container::flac::stream::FlacStreamWriter;
+use crate::wave::container::stream::{EStreamWriter, WaveStreamWriter};
+use crate::wave::container::wav::adpcm::ImaAdpcmStreamWriter;
 use serde::{Deserialize, Serialize};
 
 pub mod output_file;
@@ -10,6 +17,132 @@ pub mod output_device;
 pub enum EOutputFileFormat {
     #[serde(rename = "wav_lpcm16")]
     WavLPCM16 { sample_rate: u64 },
+    /// IMA-ADPCMで圧縮したWAV
+    #[serde(rename = "wav_ima_adpcm")]
+    WavImaAdpcm { sample_rate: u64 },
+    /// 16ビットのビッグエンディアンLPCMのAIFF
+    #[serde(rename = "aiff")]
+    Aiff { sample_rate: u64 },
+    /// 16ビットの非圧縮LPCMのAIFC
+    #[serde(rename = "aifc")]
+    Aifc {
+        sample_rate: u64,
+        /// サンプルのエンディアン。指定しなければビッグエンディアン（`NONE`）。
+        #[serde(default)]
+        compression: EAifcCompression,
+    },
+    /// 16ビットのFLAC
+    #[serde(rename = "flac")]
+    Flac { sample_rate: u64 },
+}
+
+impl EOutputFileFormat {
+    /// 出力するファイルのサンプルレートを返す。
+    pub fn sample_rate(&self) -> u64 {
+        match self {
+            Self::WavLPCM16 { sample_rate } => *sample_rate,
+            Self::WavImaAdpcm { sample_rate } => *sample_rate,
+            Self::Aiff { sample_rate } => *sample_rate,
+            Self::Aifc { sample_rate, .. } => *sample_rate,
+            Self::Flac { sample_rate } => *sample_rate,
+        }
+    }
+
+    /// ファイルの拡張子を返す。
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::WavLPCM16 { .. } | Self::WavImaAdpcm { .. } => ".wav",
+            Self::Aiff { .. } => ".aiff",
+            Self::Aifc { .. } => ".aifc",
+            Self::Flac { .. } => ".flac",
+        }
+    }
+
+    /// 書き込むサンプルのビット深度を返す。今はどのフォーマットも16ビット。
+    pub fn bits_per_sample(&self) -> u16 {
+        16
+    }
+
+    /// 今の設定でファイルを書き込めるかを確認する。
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let sample_rate = self.sample_rate();
+        if sample_rate == 0 {
+            return Err(anyhow::anyhow!("`sample_rate` of `format` must be bigger than 0."));
+        }
+        if sample_rate > (u32::MAX as u64) {
+            return Err(anyhow::anyhow!("`sample_rate` of `format` must be {} or less.", u32::MAX));
+        }
+
+        let bits_per_sample = self.bits_per_sample();
+        let is_supported = match self {
+            Self::WavLPCM16 { .. } => matches!(bits_per_sample, 8 | 16),
+            // IMA-ADPCMは入力を16ビットとして圧縮する。
+            Self::WavImaAdpcm { .. } => bits_per_sample == 16,
+            Self::Aiff { .. } | Self::Aifc { .. } => is_writable_bits_per_sample(bits_per_sample),
+            Self::Flac { .. } => is_supported_bits_per_sample(bits_per_sample as u32),
+        };
+        if !is_supported {
+            return Err(anyhow::anyhow!(
+                "{} bits per sample is not supported for `{}` files.",
+                bits_per_sample,
+                self.extension()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// `channels`チャンネルのサンプルを書き込むためのライターを作る。
+    pub fn create_stream_writer(&self, channels: usize) -> anyhow::Result<EStreamWriter> {
+        let sample_rate = self.sample_rate() as u32;
+        let bits_per_sample = self.bits_per_sample();
+        let writer = match self {
+            Self::WavLPCM16 { .. } => {
+                WaveStreamWriter::new(sample_rate, bits_per_sample, channels).map(EStreamWriter::Wav)
+            }
+            Self::WavImaAdpcm { .. } => {
+                ImaAdpcmStreamWriter::new(sample_rate, channels).map(EStreamWriter::WavImaAdpcm)
+            }
+            Self::Aiff { .. } => AiffStreamWriter::new(sample_rate, bits_per_sample, channels).map(EStreamWriter::Aiff),
+            Self::Aifc { compression, .. } => {
+                AiffStreamWriter::new_aifc(sample_rate, bits_per_sample, channels, compression.to_aiff_compression())
+                    .map(EStreamWriter::Aiff)
+            }
+            Self::Flac { .. } => {
+                FlacStreamWriter::new(sample_rate, bits_per_sample as u32, channels).map(EStreamWriter::Flac)
+            }
+        };
+
+        writer.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not create a `{}` writer for {} channels at {}Hz.",
+                self.extension(),
+                channels,
+                sample_rate
+            )
+        })
+    }
+}
+
+/// AIFCで出力するときのサンプルの形式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EAifcCompression {
+    /// ビッグエンディアンのLPCM
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// リトルエンディアンのLPCM
+    #[serde(rename = "sowt")]
+    Sowt,
+}
+
+impl EAifcCompression {
+    fn to_aiff_compression(self) -> EAiffCompression {
+        match self {
+            Self::None => EAiffCompression::BigEndian,
+            Self::Sowt => EAiffCompression::LittleEndian,
+        }
+    }
 }
 
 // ----------------------------------------------------------------------------
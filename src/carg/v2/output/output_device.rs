@@ -3,37 +3,121 @@ use crate::carg::v2::meta::node::ENode;
 use crate::carg::v2::meta::output::EProcessOutputContainer;
 use crate::carg::v2::meta::process::{process_category, EProcessCategoryFlag, TProcessCategory};
 use crate::carg::v2::meta::system::{system_category, ESystemCategoryFlag, InitializeSystemAccessor, TSystemCategory};
-use crate::carg::v2::meta::tick::TTimeTickCategory;
+use crate::carg::v2::meta::tick::{ETimeTickScheduler, TTimeTickCategory};
 use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
 use crate::carg::v2::node::common::{EProcessState, ProcessControlItemSetting};
 use crate::carg::v2::{
     ProcessControlItem, ProcessItemCreateSetting, ProcessProcessorInput, SItemSPtr,
     TProcess, TProcessItem, TProcessItemPtr,
 };
-use crate::device::EDrainedChannelBuffers;
+use crate::device::remix::{ChannelRemixSetting, EChannelLayout};
+use crate::device::{AudioDeviceProxy, EDrainedChannelBuffers};
 use crate::nz_define_time_tick_for;
+use crate::resample::stream::ResampleStream;
+use crate::resample::ResampleHeaderSetting;
 use crate::wave::sample::UniformedSample;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::cell::UnsafeCell;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct MetaOutputDeviceInfo {}
+pub struct MetaOutputDeviceInfo {
+    /// デバイスのチャンネル配置。指定しなければデバイスのチャンネル数から決める。
+    #[serde(default)]
+    pub channel_layout: Option<EChannelLayout>,
+    /// デバイスの各チャンネルに流す入力チャンネルの番号。`null`のチャンネルは無音になる。
+    /// 指定した場合は`channel_layout`によるアップミックス・ダウンミックスは行わない。
+    /// 入力はモノラルかステレオだけなので、`0`か`1`しか指定できない。
+    #[serde(default)]
+    pub routing: Option<Vec<Option<usize>>>,
+    /// `true`なら入力のサンプルレートがデバイスと違うときにリサンプリングせずにエラーにする。
+    #[serde(default)]
+    pub strict_sample_rate: bool,
+}
+
+#[derive(Debug)]
+struct InternalData {
+    /// デバイスのチャンネルに合わせてミックスするための設定
+    remix_setting: ChannelRemixSetting,
+    /// 入力のサンプルレートがデバイスと違うときにエラーにするか
+    strict_sample_rate: bool,
+    /// 入力のサンプルレートがデバイスと違うときに使う。
+    resampler: Option<DeviceResampler>,
+    /// フレームの進め方
+    scheduler: ETimeTickScheduler,
+    /// [`ETimeTickScheduler::Pull`]の時に、リングバッファがいっぱいで送れなかった各チャンネルのサンプル
+    pending_buffers: Vec<Vec<UniformedSample>>,
+}
 
+/// 入力をデバイスのサンプルレートに合わせるためのアイテム。
 #[derive(Debug)]
-struct InternalData {}
+struct DeviceResampler {
+    /// 各チャンネルのリサンプラー
+    streams: Vec<ResampleStream>,
+    /// 各チャンネルのリサンプリングが終わってデバイスに送るのを待っているサンプル
+    buffers: Vec<Vec<UniformedSample>>,
+    /// 残りのサンプルを全部処理したか
+    is_flushed: bool,
+}
+
+impl DeviceResampler {
+    fn new(from_fs: usize, to_fs: usize, channels: usize) -> Self {
+        let setting = ResampleHeaderSetting {
+            from_fs,
+            to_fs,
+            is_high_quality: true,
+        };
+
+        Self {
+            streams: (0..channels).map(|_| ResampleStream::new(&setting)).collect_vec(),
+            buffers: vec![vec![]; channels],
+            is_flushed: false,
+        }
+    }
+
+    /// `from_fs`の`channels`チャンネルの入力に使えるか？
+    fn can_support(&self, from_fs: usize, channels: usize) -> bool {
+        self.streams.len() == channels && self.streams[0].setting().from_fs == from_fs
+    }
+
+    /// `channel_i`チャンネルの`samples`をリサンプリングして送信待ちのバッファに入れる。
+    fn process(&mut self, channel_i: usize, samples: &[UniformedSample]) {
+        let mut outputs = self.streams[channel_i].process(samples);
+        self.buffers[channel_i].append(&mut outputs);
+    }
+
+    /// 各チャンネルに残っているサンプルを全部処理する。
+    fn flush(&mut self) {
+        if self.is_flushed {
+            return;
+        }
+
+        for (stream, buffer) in self.streams.iter_mut().zip(self.buffers.iter_mut()) {
+            buffer.append(&mut stream.flush());
+        }
+        self.is_flushed = true;
+    }
+
+    /// 各チャンネルに残っているサンプルを全部処理して、送信待ちのサンプルと一緒に取り出す。
+    fn take_flushed(&mut self) -> Vec<Vec<UniformedSample>> {
+        self.flush();
+        self.buffers.iter_mut().map(std::mem::take).collect_vec()
+    }
+}
 
 #[derive(Debug)]
 pub struct OutputDeviceProcessData {
     /// 共通アイテム
     common: ProcessControlItem,
     /// 内部用データ
-    #[allow(dead_code)]
     internal: InternalData,
 }
 
 const INPUT_IN: &'static str = "in";
 
+/// 入力できる最大のチャンネル数。入力はモノラルかステレオのどちらかになる。
+const MAX_INPUT_CHANNELS: usize = 2;
+
 impl TPinCategory for OutputDeviceProcessData {
     fn get_input_pin_names() -> Vec<&'static str> {
         vec![INPUT_IN]
@@ -45,7 +129,7 @@ impl TPinCategory for OutputDeviceProcessData {
 
     fn get_pin_categories(pin_name: &str) -> Option<EPinCategoryFlag> {
         match pin_name {
-            INPUT_IN => Some(pin_category::BUFFER_MONO),
+            INPUT_IN => Some(pin_category::BUFFER_MONO | pin_category::BUFFER_STEREO),
             _ => None,
         }
     }
@@ -128,6 +212,33 @@ impl OutputDeviceProcessData {
             }
         }
 
+        // 入力のサンプルレートがデバイスと違ったら、デバイスに合わせてリサンプリングする。
+        // デバイスに何か送る前に確認するので、合わなければ何も再生しないでグラフの処理を止める。
+        let device_sample_rate = device.as_ref().unwrap().lock().unwrap().get_sample_rate();
+        if let Err(e) = self.update_resampler(device_sample_rate, input.is_children_all_finished()) {
+            self.common.error = Some(e.to_string());
+            self.common.state = EProcessState::Finished;
+            return;
+        }
+
+        // プル型ならデバイスが要求した分だけグラフが処理されているので、
+        // 0で埋めずに持っている分だけを送る。
+        if self.internal.scheduler == ETimeTickScheduler::Pull {
+            {
+                let device = device.as_ref().unwrap();
+                let mut proxy = device.lock().unwrap();
+                self.send_pending_frames(&mut proxy);
+            }
+
+            let is_all_sent = self.internal.pending_buffers.iter().all(|v| v.is_empty());
+            if input.is_children_all_finished() && is_all_sent {
+                self.common.state = EProcessState::Finished;
+            } else {
+                self.common.state = EProcessState::Playing;
+            }
+            return;
+        }
+
         // ただしサンプルフォーマットはここで変更しない。
         // 送った先でなんとかやってくれる。
         // いったん[`UniformedSample`]自体はf32だとみなす。
@@ -143,7 +254,25 @@ impl OutputDeviceProcessData {
             let this = unsafe { &mut **this_pointer.get() };
 
             // 送信用のバッファとすべてゼロかを取得。
-            let (channel_buffers, is_all_zero) = {
+            let (channel_buffers, is_all_zero) = if let Some(resampler) = this.internal.resampler.as_mut() {
+                match resampler.buffers.as_mut_slice() {
+                    [channel] => {
+                        let (channel, is_all_zero) = get_drained_buffer_from(channel, frame_count);
+
+                        (EDrainedChannelBuffers::Mono { channel }, is_all_zero)
+                    }
+                    [ch_left, ch_right] => {
+                        let (ch_left, left_all_zero) = get_drained_buffer_from(ch_left, frame_count);
+                        let (ch_right, right_all_zero) = get_drained_buffer_from(ch_right, frame_count);
+
+                        (
+                            EDrainedChannelBuffers::Stereo { ch_left, ch_right },
+                            left_all_zero & right_all_zero,
+                        )
+                    }
+                    _ => unreachable!("Input of output-device has at most {} channels.", MAX_INPUT_CHANNELS),
+                }
+            } else {
                 let mut item = this.common.get_input_internal_mut(INPUT_IN).unwrap();
                 let item = item.output_dynamic_mut().unwrap();
 
@@ -175,8 +304,8 @@ impl OutputDeviceProcessData {
         // critical section
         {
             let device = device.as_ref().unwrap();
-            let proxy = device.lock().unwrap();
-            proxy.send_sample_buffer_with(send_buffer_fn);
+            let mut proxy = device.lock().unwrap();
+            proxy.send_sample_buffer_with(&self.internal.remix_setting, send_buffer_fn);
         }
 
         // 24-12-11
@@ -190,6 +319,113 @@ impl OutputDeviceProcessData {
             return;
         }
     }
+
+    /// 入力（かリサンプラー）に溜まっているサンプルを全部デバイスに送る。
+    /// リングバッファがいっぱいで送れなかった分は次のフレームで送る。
+    fn send_pending_frames(&mut self, proxy: &mut AudioDeviceProxy) {
+        let buffers = self.drain_input_buffers();
+        if self.internal.pending_buffers.len() != buffers.len() {
+            self.internal.pending_buffers = vec![vec![]; buffers.len()];
+        }
+        for (pending, mut buffer) in self.internal.pending_buffers.iter_mut().zip(buffers) {
+            pending.append(&mut buffer);
+        }
+
+        loop {
+            let frame_count = self.internal.pending_buffers.first().map_or(0, |v| v.len());
+            if frame_count == 0 {
+                break;
+            }
+
+            let pending_buffers = &mut self.internal.pending_buffers;
+            let sent_count = proxy.send_sample_frames_with(&self.internal.remix_setting, frame_count, |frame_count| {
+                match pending_buffers.as_mut_slice() {
+                    [channel] => EDrainedChannelBuffers::Mono {
+                        channel: channel.drain(..frame_count.min(channel.len())).collect_vec(),
+                    },
+                    [ch_left, ch_right] => EDrainedChannelBuffers::Stereo {
+                        ch_left: ch_left.drain(..frame_count.min(ch_left.len())).collect_vec(),
+                        ch_right: ch_right.drain(..frame_count.min(ch_right.len())).collect_vec(),
+                    },
+                    _ => unreachable!("Input of output-device has at most {} channels.", MAX_INPUT_CHANNELS),
+                }
+            });
+            if sent_count == 0 {
+                break;
+            }
+        }
+    }
+
+    /// 送信するために入力（リサンプリングしているならリサンプラー）の各チャンネルのサンプルを全部取り出す。
+    fn drain_input_buffers(&mut self) -> Vec<Vec<UniformedSample>> {
+        if let Some(resampler) = self.internal.resampler.as_mut() {
+            return resampler.buffers.iter_mut().map(std::mem::take).collect_vec();
+        }
+
+        let mut item = self.common.get_input_internal_mut(INPUT_IN).unwrap();
+        match item.output_dynamic_mut().unwrap() {
+            EOutputDeviceInput::Mono(v) => vec![std::mem::take(&mut v.buffer)],
+            EOutputDeviceInput::Stereo(v) => vec![std::mem::take(&mut v.ch_left), std::mem::take(&mut v.ch_right)],
+        }
+    }
+
+    /// 入力のサンプルレートを確認して、デバイスと違ったら入力のバッファをリサンプリングする。
+    /// `strict_sample_rate`が`true`ならリサンプリングせずにエラーを返す。
+    /// 設定でサンプルレートが決まる入力は、グラフを検査する時に先に確認している。
+    fn update_resampler(&mut self, device_sample_rate: usize, is_children_all_finished: bool) -> anyhow::Result<()> {
+        let mut item = self.common.get_input_internal_mut(INPUT_IN).unwrap();
+        let item = item.output_dynamic_mut().unwrap();
+
+        let sample_rate = item.sample_rate();
+        if sample_rate == 0 || sample_rate == device_sample_rate {
+            // リサンプリングしなくなったら、リサンプラーに残っている分を入力の前に戻して先に送る。
+            if let Some(mut resampler) = self.internal.resampler.take() {
+                item.prepend(resampler.take_flushed());
+            }
+            return Ok(());
+        }
+
+        if self.internal.strict_sample_rate {
+            return Err(anyhow::anyhow!(
+                "Input sample rate {}Hz does not match audio device sample rate {}Hz. \
+                Disable `strict_sample_rate` of output-device or add adapter-resample before it.",
+                sample_rate,
+                device_sample_rate
+            ));
+        }
+
+        let channels = item.channels();
+        let resampler = match self.internal.resampler.take() {
+            Some(v) if v.can_support(sample_rate, channels) => v,
+            Some(mut old) => {
+                // 入力が変わっても、前のリサンプラーに残っている分は捨てずに先に送る。
+                let mut resampler = DeviceResampler::new(sample_rate, device_sample_rate, channels);
+                resampler.buffers = remix_channel_buffers(old.take_flushed(), channels);
+                resampler
+            }
+            None => DeviceResampler::new(sample_rate, device_sample_rate, channels),
+        };
+        let resampler = self.internal.resampler.insert(resampler);
+
+        match item {
+            EOutputDeviceInput::Mono(v) => {
+                resampler.process(0, &v.buffer);
+                v.buffer.clear();
+            }
+            EOutputDeviceInput::Stereo(v) => {
+                resampler.process(0, &v.ch_left);
+                resampler.process(1, &v.ch_right);
+                v.ch_left.clear();
+                v.ch_right.clear();
+            }
+        }
+
+        // もう入力が来ないなら、リサンプラーに残っている分も流す。
+        if is_children_all_finished {
+            resampler.flush();
+        }
+        Ok(())
+    }
 }
 
 impl TProcessItem for OutputDeviceProcessData {
@@ -202,13 +438,50 @@ impl TProcessItem for OutputDeviceProcessData {
         system_setting: &InitializeSystemAccessor,
     ) -> anyhow::Result<TProcessItemPtr> {
         match setting.node {
-            ENode::OutputDevice(_info) => {
+            ENode::OutputDevice(info) => {
+                // チャンネル配置はデバイスのチャンネル数と合わなきゃならない。
+                if let Some(layout) = info.channel_layout {
+                    let channels = system_setting
+                        .audio_device
+                        .as_ref()
+                        .and_then(|v| v.upgrade())
+                        .map_or(0, |v| v.lock().unwrap().get_channels());
+                    if layout.channels() != channels {
+                        return Err(anyhow::anyhow!(
+                            "Given `channel_layout` {:?} has {} channels, but audio device has {} channels.",
+                            layout,
+                            layout.channels(),
+                            channels
+                        ));
+                    }
+                }
+
+                // 入力はステレオまでなので、それ以上のチャンネルはルーティングできない。
+                if let Some(routing) = &info.routing {
+                    if let Some(channel) = routing.iter().flatten().find(|v| **v >= MAX_INPUT_CHANNELS) {
+                        return Err(anyhow::anyhow!(
+                            "Given `routing` refers to input channel {}, but output-device accepts at most {} input channels.",
+                            channel,
+                            MAX_INPUT_CHANNELS
+                        ));
+                    }
+                }
+
                 let item = Self {
                     common: ProcessControlItem::new(ProcessControlItemSetting {
                         specifier: ENodeSpecifier::OutputDevice,
                         systems: &system_setting,
                     }),
-                    internal: InternalData {},
+                    internal: InternalData {
+                        remix_setting: ChannelRemixSetting {
+                            layout: info.channel_layout,
+                            routing: info.routing.clone(),
+                        },
+                        strict_sample_rate: info.strict_sample_rate,
+                        resampler: None,
+                        scheduler: setting.setting.scheduler,
+                        pending_buffers: vec![],
+                    },
                 };
                 Ok(SItemSPtr::new(item))
             }
@@ -239,6 +512,20 @@ fn get_drained_buffer_from(buffer: &mut Vec<UniformedSample>, required_samples:
     (result_buffer, zero_length == required_samples)
 }
 
+/// モノラルかステレオの`buffers`を`channels`チャンネルにする。
+/// ステレオからモノラルには左右を半分ずつ混ぜて、モノラルからステレオには同じものを左右に入れる。
+fn remix_channel_buffers(buffers: Vec<Vec<UniformedSample>>, channels: usize) -> Vec<Vec<UniformedSample>> {
+    if buffers.len() == channels {
+        return buffers;
+    }
+
+    match (buffers.as_slice(), channels) {
+        ([channel], 2) => vec![channel.clone(), channel.clone()],
+        ([ch_left, ch_right], 1) => vec![ch_left.iter().zip(ch_right).map(|(l, r)| 0.5 * (*l + *r)).collect_vec()],
+        _ => unreachable!("Input of output-device has at most {} channels.", MAX_INPUT_CHANNELS),
+    }
+}
+
 // ----------------------------------------------------------------------------
 // EOutputDeviceInput
 // ----------------------------------------------------------------------------
@@ -282,6 +569,36 @@ impl EOutputDeviceInput {
         }
     }
 
+    /// 入力のサンプルレートを返す。まだ何も受け取ってなければ0を返す。
+    pub fn sample_rate(&self) -> usize {
+        match self {
+            Self::Mono(v) => v.sample_rate,
+            Self::Stereo(v) => v.sample_rate,
+        }
+    }
+
+    /// 入力のチャンネル数を返す。
+    pub fn channels(&self) -> usize {
+        match self {
+            Self::Mono(_) => 1,
+            Self::Stereo(_) => 2,
+        }
+    }
+
+    /// 各チャンネルの`buffers`を、入力の各チャンネルのバッファの前に入れる。
+    /// チャンネル数が違えば、入力のチャンネル数に合わせてから入れる。
+    fn prepend(&mut self, buffers: Vec<Vec<UniformedSample>>) {
+        let channels = self.channels();
+        let targets = match self {
+            Self::Mono(v) => vec![&mut v.buffer],
+            Self::Stereo(v) => vec![&mut v.ch_left, &mut v.ch_right],
+        };
+        for (target, mut buffer) in targets.into_iter().zip(remix_channel_buffers(buffers, channels)) {
+            buffer.append(target);
+            *target = buffer;
+        }
+    }
+
     /// 種類をかえずに中身だけをリセットする。
     pub fn reset(&mut self) {
         match self {
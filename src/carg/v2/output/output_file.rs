@@ -1,23 +1,19 @@
-use crate::carg::v2::meta::input::{
-    BufferMonoDynamicItem, BufferStereoDynamicItem, EInputContainerCategoryFlag, EProcessInputContainer,
-};
+use crate::carg::v2::meta::input::{BufferMonoDynamicItem, BufferStereoDynamicItem, EInputContainerCategoryFlag};
 use crate::carg::v2::meta::output::EProcessOutputContainer;
 use crate::carg::v2::meta::system::{system_category, ESystemCategoryFlag, InitializeSystemAccessor, TSystemCategory};
 use crate::carg::v2::meta::{input, pin_category, ENodeSpecifier, EPinCategoryFlag, TPinCategory};
 use crate::carg::v2::node::common::{EProcessState, ProcessControlItemSetting};
 use crate::carg::v2::output::EOutputFileFormat;
 use crate::carg::v2::{ENode, ProcessItemCreateSetting, SItemSPtr, TProcessItem, TProcessItemPtr};
+use crate::carg::v2::{ProcessControlItem, ProcessProcessorInput, TProcess};
+use crate::file::handle::FileHandle;
 use crate::file::EFileAccessSetting;
-use crate::math::window::EWindowFunction;
+use crate::resample::stream::ResampleStream;
+use crate::resample::ResampleHeaderSetting;
+use crate::wave::container::stream::EStreamWriter;
 use crate::wave::sample::UniformedSample;
-use crate::{
-    carg::v2::{ProcessControlItem, ProcessProcessorInput, TProcess},
-    wave::{
-        container::WaveBuilder,
-        stretch::pitch::{PitchShifterBufferSetting, PitchShifterBuilder},
-    },
-};
 use chrono::Local;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,16 +21,38 @@ pub struct MetaOutputFileInfo {
     /// 音源ファイルの出力タイプ
     format: EOutputFileFormat,
     /// 音源ファイル名
-    /// もし`.wav`が最後についていなければ、自動で`.wav`をファイル名につけて適用する。
+    /// もし`.wav`（AIFFなら`.aiff`、FLACなら`.flac`）が最後についていなければ、自動で拡張子をファイル名につけて適用する。
     file_name: String,
     /// `true`ならファイル名の`.wav`の前にファイル出力の時間を`%Y-%m-%d_%H%m%s`形式で追加する。
     add_date_time: bool,
+    /// ファイルのヘッダーのサイズ情報を更新する間隔（秒）。
+    /// 指定しなければ[`DEFAULT_HEADER_UPDATE_INTERVAL`]を使う。
+    #[serde(default)]
+    header_update_interval: Option<f64>,
 }
 
+/// ヘッダーのサイズ情報を更新する間隔のデフォルト値（秒）
+const DEFAULT_HEADER_UPDATE_INTERVAL: f64 = 1.0;
+
 #[derive(Debug)]
 pub struct OutputFileProcessData {
     common: ProcessControlItem,
     info: MetaOutputFileInfo,
+    internal: InternalInfo,
+}
+
+#[derive(Debug, Default)]
+struct InternalInfo {
+    /// [`FileIO`](crate::file::FileIO)から取得した書き込み用のハンドル。
+    /// 最初の入力が来た時に作って、終わるまで保持する。
+    handle: Option<FileHandle>,
+    /// ヘッダーとサンプルの書き込み用
+    writer: Option<EStreamWriter>,
+    /// 最後にヘッダーを更新した時に書き込み済みだったフレームの数
+    header_updated_frame_count: usize,
+    /// 入力のサンプルレートがファイルと違うときに使う、各チャンネルのリサンプラー。
+    /// 同じなら空。
+    resamplers: Vec<ResampleStream>,
 }
 
 const INPUT_IN: &'static str = "in";
@@ -46,8 +64,11 @@ impl TSystemCategory for OutputFileProcessData {
 }
 
 impl TProcessItem for OutputFileProcessData {
-    fn can_create_item(_setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
-        Ok(())
+    fn can_create_item(setting: &ProcessItemCreateSetting) -> anyhow::Result<()> {
+        match setting.node {
+            ENode::OutputFile(v) => v.format.validate(),
+            _ => unreachable!("Unexpected branch"),
+        }
     }
 
     fn create_item(
@@ -64,6 +85,7 @@ impl TProcessItem for OutputFileProcessData {
                     systems: &system_setting,
                 }),
                 info: v.clone(),
+                internal: InternalInfo::default(),
             };
 
             return Ok(SItemSPtr::new(item));
@@ -103,35 +125,158 @@ impl TPinCategory for OutputFileProcessData {
 
 impl OutputFileProcessData {
     fn update_state(&mut self, input: &ProcessProcessorInput) {
-        // Childrenが全部送信完了したら処理が行える。
-        // commonで初期Childrenの数を比較するだけでいいかも。
-        if !input.is_children_all_finished() {
+        let is_children_finished = input.is_children_all_finished();
+        let sample_rate = self.info.format.sample_rate();
+
+        // まだ入力が一度も来てなければ、チャンネル数もサンプルレートもわからないので何もしない。
+        // ただ入力が来ないまま全部終わったら、空のファイルだけは作っておく。
+        let in_sample_rate = {
+            let input = self.common.get_input_internal(INPUT_IN).unwrap();
+            match input.output_file().unwrap() {
+                EOutputFileInput::Mono(v) => v.sample_rate,
+                EOutputFileInput::Stereo(v) => v.sample_rate,
+            }
+        };
+        if in_sample_rate == 0 && !is_children_finished {
             return;
         }
 
-        let file_name = self.get_applied_file_name();
-        let format = self.info.format.clone();
-        let systems = self.common.systems.clone();
+        // 来たサンプルをそのままファイルの後ろに追記する。
+        // サンプルレートが違う場合には、チャンネルごとにリサンプリングしながら追記する。
+        if self.internal.handle.is_none() {
+            let in_sample_rate = if in_sample_rate == 0 { sample_rate as usize } else { in_sample_rate };
+            if let Err(e) = self.open_file(in_sample_rate) {
+                self.common.error = Some(e.to_string());
+                self.common.state = EProcessState::Finished;
+                return;
+            }
+        }
+        self.write_drained_samples(is_children_finished);
+
+        if is_children_finished {
+            // 残りを全部書き込んで、ヘッダーのサイズを更新してからハンドルを破棄する。
+            {
+                let writer = self.internal.writer.as_mut().unwrap();
+                let mut file_writer = self.internal.handle.as_ref().unwrap().try_write().unwrap();
+                writer.finish(&mut file_writer);
+            }
+            self.internal.writer = None;
+            self.internal.handle = None;
+            self.common.state = EProcessState::Finished;
+        } else {
+            // 定期的にヘッダーのサイズを更新する。
+            let interval = self.info.header_update_interval.unwrap_or(DEFAULT_HEADER_UPDATE_INTERVAL);
+            let interval_frame_count = ((sample_rate as f64) * interval.max(0.0)) as usize;
+            let written_frame_count = self.internal.writer.as_ref().unwrap().written_frame_count();
+            if written_frame_count - self.internal.header_updated_frame_count >= interval_frame_count {
+                self.update_header();
+            }
+            self.common.state = EProcessState::Playing;
+        }
+    }
 
-        {
-            let input = self.common.get_input_internal_mut(INPUT_IN).unwrap();
+    /// 入力のチャンネル数から書き込み先のファイルを開いて、空のヘッダーを書き込む。
+    /// `in_sample_rate`がファイルのサンプルレートと違えば、チャンネルごとのリサンプラーも作る。
+    fn open_file(&mut self, in_sample_rate: usize) -> anyhow::Result<()> {
+        let channels = {
+            let input = self.common.get_input_internal(INPUT_IN).unwrap();
             match input.output_file().unwrap() {
-                EOutputFileInput::Mono(v) => {
-                    process_mono(systems, format, v.sample_rate, v.buffer.clone(), file_name);
-                }
-                EOutputFileInput::Stereo(v) => {
-                    process_stereo(systems, format, v.sample_rate, v.ch_left.clone(), v.ch_right.clone(), file_name);
-                }
+                EOutputFileInput::Mono(_) => 1,
+                EOutputFileInput::Stereo(_) => 2,
+            }
+        };
+        let writer = self.info.format.create_stream_writer(channels)?;
+
+        let file_name = self.get_applied_file_name();
+        let mut handle = None;
+        self.common.systems.access_file_io_fn(|system| {
+            let file_setting = EFileAccessSetting::Write { path: file_name };
+            handle = Some(system.create_handle(file_setting));
+        });
+
+        self.internal.handle = Some(handle.expect("FileIO system must be initialized."));
+        self.internal.writer = Some(writer);
+        self.internal.header_updated_frame_count = 0;
+
+        let sample_rate = self.info.format.sample_rate() as usize;
+        self.internal.resamplers = if in_sample_rate == sample_rate {
+            vec![]
+        } else {
+            let setting = ResampleHeaderSetting {
+                from_fs: in_sample_rate,
+                to_fs: sample_rate,
+                is_high_quality: true,
             };
+            (0..channels).map(|_| ResampleStream::new(&setting)).collect_vec()
+        };
+        self.update_header();
+        Ok(())
+    }
+
+    /// 入力に溜まっているサンプルを取り出してファイルに追記する。
+    ///
+    /// `is_last`が`false`なら、次の入力と重なるかもしれない分は残しておく。
+    fn write_drained_samples(&mut self, is_last: bool) {
+        let mut channels = self.drain_channels(is_last);
+        if !self.internal.resamplers.is_empty() {
+            for (channel, resampler) in channels.iter_mut().zip(self.internal.resamplers.iter_mut()) {
+                let mut outputs = resampler.process(channel);
+                // もう入力が来ないなら、リサンプラーに残っている分も流す。
+                if is_last {
+                    outputs.append(&mut resampler.flush());
+                }
+                *channel = outputs;
+            }
         }
 
-        // 状態変更。
-        self.common.state = EProcessState::Finished;
+        // チャンネルをインターリーブする。
+        let frame_count = channels.iter().map(|v| v.len()).min().unwrap_or(0);
+        if frame_count == 0 {
+            return;
+        }
+        let samples = (0..frame_count)
+            .flat_map(|frame_i| channels.iter().map(move |channel| channel[frame_i]))
+            .collect_vec();
+
+        let writer = self.internal.writer.as_mut().unwrap();
+        let mut file_writer = self.internal.handle.as_ref().unwrap().try_write().unwrap();
+        writer.write_frames(&mut file_writer, &samples);
+    }
+
+    /// 入力の各チャンネルから、全チャンネルで揃っている分のサンプルを取り出す。
+    ///
+    /// `is_last`が`false`なら、次の入力と重なるかもしれない`sample_offset`の分は残しておく。
+    /// ステレオの入力には`sample_offset`がないので、揃っている分を全部取り出す。
+    fn drain_channels(&mut self, is_last: bool) -> Vec<Vec<UniformedSample>> {
+        let mut input = self.common.get_input_internal_mut(INPUT_IN).unwrap();
+        let (channels, reserved) = match input.output_file_mut().unwrap() {
+            EOutputFileInput::Mono(v) => {
+                let reserved = v.last_sample_offset;
+                (vec![&mut v.buffer], reserved)
+            }
+            EOutputFileInput::Stereo(v) => (vec![&mut v.ch_left, &mut v.ch_right], 0),
+        };
+
+        let reserved = if is_last { 0 } else { reserved };
+        let drain_length = channels.iter().map(|v| v.len()).min().unwrap_or(0).saturating_sub(reserved);
+        channels
+            .into_iter()
+            .map(|v| v.drain(..drain_length).collect_vec())
+            .collect_vec()
+    }
+
+    /// 今まで書き込んだ分でファイルのヘッダーを更新する。
+    fn update_header(&mut self) {
+        let writer = self.internal.writer.as_ref().unwrap();
+        let mut file_writer = self.internal.handle.as_ref().unwrap().try_write().unwrap();
+        writer.write_header(&mut file_writer);
+        self.internal.header_updated_frame_count = writer.written_frame_count();
     }
 
     fn get_applied_file_name(&self) -> String {
-        // 最後の`.wav`を切り取る
-        let mut file_name = match self.info.file_name.rfind(".wav") {
+        // 最後の拡張子を切り取る
+        let extension = self.info.format.extension();
+        let mut file_name = match self.info.file_name.rfind(extension) {
             None => self.info.file_name.clone(),
             Some(i) => self.info.file_name.split_at(i).0.to_string(),
         };
@@ -141,7 +286,7 @@ impl OutputFileProcessData {
             file_name.push_str(&Local::now().format(" %Y-%m-%d %H%M%S").to_string());
         }
 
-        file_name.push_str(".wav");
+        file_name.push_str(extension);
         file_name
     }
 }
@@ -152,8 +297,9 @@ impl TProcess for OutputFileProcessData {
         self.common.state == EProcessState::Finished
     }
 
+    /// 入力が一度も来ないまま終わった時にも空のファイルを作るので、いつでも処理する。
     fn can_process(&self) -> bool {
-        self.common.is_all_input_pins_update_notified()
+        true
     }
 
     fn get_common_ref(&self) -> &ProcessControlItem {
@@ -175,113 +321,6 @@ impl TProcess for OutputFileProcessData {
     }
 }
 
-fn process_mono(
-    systems: InitializeSystemAccessor,
-    format: EOutputFileFormat,
-    in_sample_rate: usize,
-    buffer: Vec<UniformedSample>,
-    file_name: String,
-) {
-    let container = match format {
-        EOutputFileFormat::WavLPCM16 { sample_rate } => {
-            // もしsettingのsampling_rateがoutputのsampling_rateと違ったら、リサンプリングをしなきゃならない。
-            let dest_sample_rate = sample_rate as f64;
-            let processed_container = {
-                let pitch_rate = (in_sample_rate as f64) / dest_sample_rate;
-                if pitch_rate == 1.0 {
-                    buffer
-                } else {
-                    PitchShifterBuilder::default()
-                        .pitch_rate(pitch_rate)
-                        .window_size(128)
-                        .window_function(EWindowFunction::None)
-                        .build()
-                        .unwrap()
-                        .process_with_buffer(&PitchShifterBufferSetting { buffer: &buffer })
-                        .unwrap()
-                }
-            };
-
-            WaveBuilder {
-                samples_per_sec: sample_rate as u32,
-                bits_per_sample: 16,
-            }
-            .build_mono(processed_container)
-            .unwrap()
-        }
-    };
-
-    // 書き込み。
-    systems.access_file_io_fn(move |system| {
-        // 書き込み。
-        let file_setting = EFileAccessSetting::Write { path: file_name };
-        let file_handle = system.create_handle(file_setting);
-        let mut writer = file_handle.try_write().unwrap();
-        container.write(&mut writer);
-    });
-}
-
-fn process_stereo(
-    systems: InitializeSystemAccessor,
-    format: EOutputFileFormat,
-    in_sample_rate: usize,
-    ch_left: Vec<UniformedSample>,
-    ch_right: Vec<UniformedSample>,
-    file_name: String,
-) {
-    let source_sample_rate = in_sample_rate as f64;
-
-    let container = match format {
-        EOutputFileFormat::WavLPCM16 { sample_rate } => {
-            // もしsettingのsampling_rateがoutputのsampling_rateと違ったら、リサンプリングをしなきゃならない。
-            let dest_sample_rate = sample_rate as f64;
-            let pitch_rate = source_sample_rate / dest_sample_rate;
-
-            // Left Right 全部それぞれPitchShiftする。
-            let (left, right) = {
-                if pitch_rate == 1.0 {
-                    (ch_left, ch_right)
-                } else {
-                    let left = PitchShifterBuilder::default()
-                        .pitch_rate(pitch_rate)
-                        .window_size(128)
-                        .window_function(EWindowFunction::None)
-                        .build()
-                        .unwrap()
-                        .process_with_buffer(&PitchShifterBufferSetting { buffer: &ch_left })
-                        .unwrap();
-                    let right = PitchShifterBuilder::default()
-                        .pitch_rate(pitch_rate)
-                        .window_size(128)
-                        .window_function(EWindowFunction::None)
-                        .build()
-                        .unwrap()
-                        .process_with_buffer(&PitchShifterBufferSetting { buffer: &ch_right })
-                        .unwrap();
-                    (left, right)
-                }
-            };
-
-            WaveBuilder {
-                samples_per_sec: sample_rate as u32,
-                bits_per_sample: 16,
-            }
-            .build_stereo(left, right)
-            .unwrap()
-        }
-    };
-
-    // 書き込み。
-
-    systems.access_file_io_fn(move |system| {
-        // 書き込み。
-        let file_setting = EFileAccessSetting::Write { path: file_name };
-        let file_handle = system.create_handle(file_setting);
-        let mut writer = file_handle.try_write().unwrap();
-        container.write(&mut writer);
-    });
-}
-
 // ----------------------------------------------------------------------------
 // EOutputFileInput
 // ----------------------------------------------------------------------------
@@ -78,15 +78,22 @@ impl OutputLogProcessData {
             EParsedOutputLogMode::Print => {
                 let string = match &mut self.common.input_pins.get("in").unwrap().borrow_mut().input {
                     EProcessInputContainer::OutputLog(v) => {
-                        let string = format!("{:?}", v);
-                        v.reset(); // Drain。
-                        string
+                        // 毎フレーム出力しないノードもあるので、受け取ったものがなければ出力しない。
+                        if v.is_empty() {
+                            None
+                        } else {
+                            let string = format!("{:?}", v);
+                            v.reset(); // Drain。
+                            Some(string)
+                        }
                     }
                     _ => unreachable!("Unexpected input."),
                 };
 
-                println!("{}", string);
-                println!();
+                if let Some(string) = string {
+                    println!("{}", string);
+                    println!();
+                }
             }
         }
 
@@ -168,6 +175,14 @@ impl EOutputLogItem {
         }
     }
 
+    /// 受け取ったものがなければ`true`を返す。
+    pub fn is_empty(&self) -> bool {
+        match self {
+            EOutputLogItem::BuffersDynamic(v) => v.buffer.is_empty(),
+            EOutputLogItem::TextDynamic(v) => v.buffer.is_empty(),
+        }
+    }
+
     /// 種類をかえずに中身だけをリセットする。
     pub fn reset(&mut self) {
         match self {
@@ -1,3 +1,4 @@
+use crate::carg::v2::meta::node::ENode;
 use crate::carg::v2::meta::relation::Relation;
 use crate::carg::v2::meta::setting::Setting;
 use crate::carg::v2::node::RelationTreeNodePtr;
@@ -105,6 +106,42 @@ pub fn validate_node_relations(
     Ok(())
 }
 
+/// `strict_sample_rate`の`output-device`に直接繋がっているノードのうち、
+/// 設定でサンプルレートが決まるものがデバイスのサンプルレート`device_sample_rate`と違えばエラーを返す。
+///
+/// ファイルや入力でサンプルレートが変わるノードは、処理中に`output-device`で確認する。
+pub fn validate_device_sample_rates(
+    nodes: &MetaNodeContainer,
+    relations: &[Relation],
+    device_sample_rate: usize,
+) -> anyhow::Result<()> {
+    for relation in relations {
+        let is_strict = match nodes.map.get(&relation.next.node) {
+            Some(ENode::OutputDevice(v)) => v.strict_sample_rate,
+            _ => false,
+        };
+        if !is_strict {
+            continue;
+        }
+
+        let sample_rate = nodes.map.get(&relation.prev.node).and_then(|v| v.static_sample_rate());
+        match sample_rate {
+            Some(sample_rate) if sample_rate != device_sample_rate => {
+                return Err(anyhow::anyhow!(
+                    "Sample rate {}Hz of node `{}` does not match audio device sample rate {}Hz. \
+                    Disable `strict_sample_rate` of output-device or add adapter-resample before it.",
+                    sample_rate,
+                    relation.prev.node,
+                    device_sample_rate
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
 /// [`validate_node_relations`]の関数だけでしか使わないもの。経路を表す。
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 struct GraphNodeRoute {
@@ -0,0 +1,149 @@
+use miniaudio::{Backend, Context, DeviceId, DeviceType, ShareMode};
+use serde::{Deserialize, Serialize};
+
+/// [`AudioDevice`](super::AudioDevice)で使うminiaudioのバックエンド。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum EAudioDeviceBackend {
+    #[serde(rename = "wasapi")]
+    Wasapi,
+    #[serde(rename = "dsound")]
+    DSound,
+    #[serde(rename = "winmm")]
+    WinMM,
+    #[serde(rename = "coreaudio")]
+    CoreAudio,
+    #[serde(rename = "sndio")]
+    Sndio,
+    #[serde(rename = "audio4")]
+    Audio4,
+    #[serde(rename = "oss")]
+    Oss,
+    #[serde(rename = "pulseaudio")]
+    PulseAudio,
+    #[serde(rename = "alsa")]
+    Alsa,
+    #[serde(rename = "jack")]
+    Jack,
+    #[serde(rename = "aaudio")]
+    AAudio,
+    #[serde(rename = "opensl")]
+    OpenSL,
+    #[serde(rename = "webaudio")]
+    WebAudio,
+    /// 何も鳴らさず、何も録らないバックエンド。
+    /// サウンドカードがない環境（CIなど）でもデバイスのコールバックだけは一定の間隔で呼ばれる。
+    #[serde(rename = "null")]
+    Null,
+}
+
+impl EAudioDeviceBackend {
+    /// miniaudioのバックエンドに変換する。
+    pub fn to_backend(self) -> Backend {
+        match self {
+            Self::Wasapi => Backend::Wasapi,
+            Self::DSound => Backend::DSound,
+            Self::WinMM => Backend::WinMM,
+            Self::CoreAudio => Backend::CoreAudio,
+            Self::Sndio => Backend::SNDIO,
+            Self::Audio4 => Backend::Audio4,
+            Self::Oss => Backend::OSS,
+            Self::PulseAudio => Backend::PulseAudio,
+            Self::Alsa => Backend::Alsa,
+            Self::Jack => Backend::Jack,
+            Self::AAudio => Backend::AAudio,
+            Self::OpenSL => Backend::OpenSL,
+            Self::WebAudio => Backend::WebAudio,
+            Self::Null => Backend::Null,
+        }
+    }
+}
+
+/// 使うデバイスの指定。
+/// `devices`コマンドで表示される番号か、デバイスの名前で指定する。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EAudioDeviceSelector {
+    /// `devices`コマンドで表示される番号
+    Index(usize),
+    /// デバイスの名前。完全に一致するものがなければ、名前に含まれているものを選ぶ。
+    Name(String),
+}
+
+/// `backend`のコンテキストを作る。
+/// `backend`が`None`ならminiaudioの既定の順番で使えるバックエンドを選ぶ。
+pub fn create_context(backend: Option<EAudioDeviceBackend>) -> anyhow::Result<Context> {
+    let backends = match backend {
+        None => vec![],
+        Some(v) => vec![v.to_backend()],
+    };
+
+    Context::new(&backends, None).map_err(|e| anyhow::anyhow!("Failed to create audio context. {}", e))
+}
+
+/// `context`から`selector`に合う`device_type`のデバイスのIDを探す。
+pub fn find_device_id(
+    context: &Context,
+    device_type: DeviceType,
+    selector: &EAudioDeviceSelector,
+) -> anyhow::Result<DeviceId> {
+    let mut result = None;
+    context
+        .with_devices(|playback_devices, capture_devices| {
+            let devices = match device_type {
+                DeviceType::Capture => capture_devices,
+                _ => playback_devices,
+            };
+
+            let found = match selector {
+                EAudioDeviceSelector::Index(index) => devices.get(*index),
+                EAudioDeviceSelector::Name(name) => devices
+                    .iter()
+                    .find(|v| v.name() == name)
+                    .or_else(|| devices.iter().find(|v| v.name().contains(name.as_str()))),
+            };
+            result = found.map(|v| v.id().clone());
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to enumerate audio devices. {}", e))?;
+
+    result.ok_or_else(|| anyhow::anyhow!("Could not find {:?} device of {:?}.", device_type, selector))
+}
+
+/// `backend`のデバイスの一覧と、対応しているフォーマットなどを表示する。
+pub fn print_devices(backend: Option<EAudioDeviceBackend>) -> anyhow::Result<()> {
+    let context = create_context(backend)?;
+    println!("Backend: {:?}", context.backend());
+
+    context
+        .with_devices(|playback_devices, capture_devices| {
+            println!("Playback Devices");
+            for (index, device) in playback_devices.iter().enumerate() {
+                println!("\t#{}: {}", index, device.name());
+                print_device_info(&context, DeviceType::Playback, device.id());
+            }
+
+            println!("Capture Devices");
+            for (index, device) in capture_devices.iter().enumerate() {
+                println!("\t#{}: {}", index, device.name());
+                print_device_info(&context, DeviceType::Capture, device.id());
+            }
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to enumerate audio devices. {}", e))
+}
+
+fn print_device_info(context: &Context, device_type: DeviceType, device_id: &DeviceId) {
+    let info = match context.get_device_info(device_type, device_id, ShareMode::Shared) {
+        Ok(info) => info,
+        Err(err) => {
+            println!("\t\tFailed to get device info: {}", err);
+            return;
+        }
+    };
+
+    println!("\t\tSample Rate: {}-{}Hz", info.min_sample_rate(), info.max_sample_rate());
+    println!("\t\tChannels: {}-{}", info.min_channels(), info.max_channels());
+    println!("\t\tFormats: {:?}", info.formats());
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,83 @@
+use std::fmt;
+use std::time::Duration;
+
+/// [`AudioDevice`](super::AudioDevice)のリアルタイム再生の状態を測定した値。
+///
+/// `process_limit_time`の調整や、グラフの処理が間に合っているかの確認に使う。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioDeviceMetrics {
+    /// デバイスのチャンネル数
+    pub channels: usize,
+    /// デバイスのサンプルレート
+    pub sample_rate: usize,
+    /// 処理したフレーム（Tick）の数
+    pub frame_count: usize,
+    /// 再生するサンプルが足りなかった（xrun）デバイスのコールバックの数
+    pub xrun_count: usize,
+    /// 直前のフレームのグラフの処理時間
+    pub last_process_time: Duration,
+    /// 一番長かったフレームのグラフの処理時間
+    pub max_process_time: Duration,
+    /// 全フレームのグラフの処理時間の合計
+    pub total_process_time: Duration,
+    /// リングバッファに溜まっていて、まだデバイスに読まれていないサンプルの数
+    pub ring_fill_samples: usize,
+    /// リングバッファに入れられるサンプルの最大数
+    pub ring_capacity_samples: usize,
+    /// デバイスのコールバックが1回で読み込むサンプルの数
+    pub callback_samples: usize,
+    /// キャプチャーのリングバッファがいっぱいで捨てた入力のサンプルの数
+    pub capture_dropped_samples: usize,
+}
+
+impl AudioDeviceMetrics {
+    /// フレームのグラフの処理時間の平均を返す。
+    pub fn average_process_time(&self) -> Duration {
+        if self.frame_count == 0 {
+            return Duration::ZERO;
+        }
+        self.total_process_time / self.frame_count as u32
+    }
+
+    /// リングバッファの使用率を`[0, 1]`で返す。
+    pub fn ring_fill_ratio(&self) -> f64 {
+        if self.ring_capacity_samples == 0 {
+            return 0.0;
+        }
+        self.ring_fill_samples as f64 / self.ring_capacity_samples as f64
+    }
+
+    /// 今グラフが流したサンプルが実際に鳴るまでの推定時間を返す。
+    /// リングバッファに溜まっている分と、デバイスのコールバック1回分を足して計算する。
+    pub fn estimated_latency(&self) -> Duration {
+        if self.channels == 0 || self.sample_rate == 0 {
+            return Duration::ZERO;
+        }
+
+        let frames = (self.ring_fill_samples + self.callback_samples) / self.channels;
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+}
+
+impl fmt::Display for AudioDeviceMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "frames: {}, xruns: {}, process time: {:.3}ms (avg {:.3}ms, max {:.3}ms), ring buffer: {}/{} ({:.1}%), latency: {:.2}ms, capture drops: {}",
+            self.frame_count,
+            self.xrun_count,
+            self.last_process_time.as_secs_f64() * 1e3,
+            self.average_process_time().as_secs_f64() * 1e3,
+            self.max_process_time.as_secs_f64() * 1e3,
+            self.ring_fill_samples,
+            self.ring_capacity_samples,
+            self.ring_fill_ratio() * 100.0,
+            self.estimated_latency().as_secs_f64() * 1e3,
+            self.capture_dropped_samples,
+        )
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -1,8 +1,18 @@
 use crate::wave::sample::UniformedSample;
+use backend::{create_context, find_device_id, EAudioDeviceBackend, EAudioDeviceSelector};
 use itertools::Itertools;
+use metrics::AudioDeviceMetrics;
 use miniaudio::{DeviceType, FramesMut, RingBufferRecv, RingBufferSend};
+use remix::{ChannelRemixMatrix, ChannelRemixSetting, EChannelLayout};
 use std::sync::{mpsc, Arc, Mutex, OnceLock, Weak};
+use std::time::Instant;
 use serde::{Deserialize, Serialize};
+use virtual_device::{VirtualAudioDevice, VirtualAudioDeviceSetting};
+
+pub mod backend;
+pub mod metrics;
+pub mod remix;
+pub mod virtual_device;
 
 /// 24-12-10
 /// mutにしているのは、[`AudioDevice::cleanup()`]で値をTakeするため。
@@ -11,16 +21,37 @@ use serde::{Deserialize, Serialize};
 /// 24-12-23
 /// Takeしなくても、中にInternal変数を持たせてOptionにすることでTakeせずに解放できるようになった。
 /// なので`mut`しなくても更新できるようになった。
-static AUDIO_DEVICE: OnceLock<Arc<Mutex<AudioDevice>>> = OnceLock::new();
+///
+/// [`AudioDevice::cleanup()`]で`None`に戻すので、解放したあとにもう一度初期化できる。
+static AUDIO_DEVICE: OnceLock<Mutex<Option<Arc<Mutex<AudioDevice>>>>> = OnceLock::new();
 
 /// コールバックから取得する必要があるので、[`AudioDevice`]には入れない。
 /// リングバッファのレシーバー
 static BUFFER_RECEIVER: OnceLock<Mutex<Option<RingBufferRecv<f32>>>> = OnceLock::new();
 
+/// [`BUFFER_RECEIVER`]と同じくコールバックから接近するので、[`AudioDevice`]には入れない。
+/// 入力デバイスからキャプチャーしたサンプルを送るリングバッファのセンダー
+static CAPTURE_SENDER: OnceLock<Mutex<Option<RingBufferSend<f32>>>> = OnceLock::new();
+
+/// プル型で処理する時に、デバイスのコールバックが要求したフレーム数をレンダースレッドに伝える。
+/// コールバックから送るので、[`AudioDevice`]には入れない。
+static FRAME_DEMAND_SENDER: OnceLock<Mutex<Option<mpsc::Sender<usize>>>> = OnceLock::new();
+
+/// [`FRAME_DEMAND_SENDER`]から送られた要求を受け取るレシーバー。
+/// レンダースレッドが待っている間もデバイスに接近できるように、[`AudioDevice`]には入れない。
+static FRAME_DEMAND_RECEIVER: OnceLock<Mutex<Option<mpsc::Receiver<usize>>>> = OnceLock::new();
+
 /// デバイスの処理関数でデバイスに接近するためのItem。
 /// デバイスの初期化時に登録される。
-/// WeakPtrなので解放はしなくてもいいかもしれない。
-static PROXY_ACCESSOR: OnceLock<AudioDeviceProxyWeakPtr> = OnceLock::new();
+/// WeakPtrだけど、もう一度初期化した時に古いものが残らないように解放時に`None`に戻す。
+static PROXY_ACCESSOR: OnceLock<Mutex<Option<AudioDeviceProxyWeakPtr>>> = OnceLock::new();
+
+/// `OnceLock<Mutex<Option<T>>>`のグローバル変数の中身を`value`に置き換える。
+/// [`OnceLock::set`]は2回目から失敗するので、解放してからもう一度初期化できるようにこれを使う。
+pub(crate) fn replace_global<T>(global: &OnceLock<Mutex<Option<T>>>, value: Option<T>) -> Option<T> {
+    let mut accessor = global.get_or_init(|| Mutex::new(None)).lock().unwrap();
+    std::mem::replace(&mut *accessor, value)
+}
 
 /// 依存システム全体からの処理の結果
 #[derive(Debug)]
@@ -47,6 +78,8 @@ pub enum EAudioDeviceMessage {
     LastProcessedLength(usize),
     /// オーディオ処理のバッファに`usize`分のサンプルを送信した。
     SendSamplesToBuffer(usize),
+    /// キャプチャーのリングバッファがいっぱいで、`usize`分のサンプルを捨てた。
+    CaptureOverflowed(usize),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -55,6 +88,28 @@ pub struct AudioDeviceSetting {
     pub channels: usize,
     /// 初期サンプルレート
     pub sample_rate: usize,
+    /// 入力（キャプチャー）デバイスのチャンネル数。
+    /// 指定した場合は入力も受け取るデバイスを作る。`channels`が`0`ならキャプチャー専用になる。
+    #[serde(default)]
+    pub capture_channels: Option<usize>,
+    /// 使うバックエンド。指定しなければminiaudioの既定の順番で使えるものを選ぶ。
+    #[serde(default)]
+    pub backend: Option<EAudioDeviceBackend>,
+    /// 出力デバイスの番号か名前。指定しなければ既定のデバイスを使う。
+    #[serde(default)]
+    pub device: Option<EAudioDeviceSelector>,
+    /// 入力デバイスの番号か名前。指定しなければ既定のデバイスを使う。
+    #[serde(default)]
+    pub capture_device: Option<EAudioDeviceSelector>,
+    /// デバイスの1周期（ピリオド）のフレーム数。指定しなければバックエンドに任せる。
+    #[serde(default)]
+    pub period_size: Option<u32>,
+    /// デバイス内部のバッファ（ピリオド）の数。指定しなければバックエンドに任せる。
+    #[serde(default)]
+    pub buffer_count: Option<u32>,
+    /// 指定した場合はサウンドカードを使わずに、仮想デバイスで出力を受け取る。
+    #[serde(default, rename = "virtual")]
+    pub virtual_device: Option<VirtualAudioDeviceSetting>,
 }
 
 /// [`AudioDevice`]を生成するための初期設定のための構造体。
@@ -64,6 +119,22 @@ pub struct AudioDeviceConfig {
     channels: usize,
     /// 初期サンプルレート
     sample_rate: usize,
+    /// 入力デバイスのチャンネル数。`0`なら入力を受け取らない。
+    capture_channels: usize,
+    /// 使うバックエンド
+    backend: Option<EAudioDeviceBackend>,
+    /// 出力デバイスの指定
+    device: Option<EAudioDeviceSelector>,
+    /// 入力デバイスの指定
+    capture_device: Option<EAudioDeviceSelector>,
+    /// デバイスの1周期のフレーム数
+    period_size: Option<u32>,
+    /// デバイス内部のバッファの数
+    buffer_count: Option<u32>,
+    /// 仮想デバイスの設定。指定した場合はローレベルのデバイスを作らない。
+    virtual_device: Option<VirtualAudioDeviceSetting>,
+    /// コールバックが要求したフレーム数を[`AudioDevice::wait_frame_demand`]で受け取れるようにするか
+    use_frame_demand: bool,
     /// リングバッファの1フレーム処理推定時間 (ms単位)
     frame_ideal_milliseconds: std::time::Duration,
 }
@@ -74,6 +145,14 @@ impl AudioDeviceConfig {
         Self {
             channels: 0,
             sample_rate: 0,
+            capture_channels: 0,
+            backend: None,
+            device: None,
+            capture_device: None,
+            period_size: None,
+            buffer_count: None,
+            virtual_device: None,
+            use_frame_demand: false,
             frame_ideal_milliseconds: std::time::Duration::from_millis(5),
         }
     }
@@ -89,6 +168,54 @@ impl AudioDeviceConfig {
         self.sample_rate = sample_rate;
         self
     }
+
+    /// 入力デバイスのチャンネル数の指定
+    pub fn set_capture_channels(&mut self, capture_channels: usize) -> &mut Self {
+        self.capture_channels = capture_channels;
+        self
+    }
+
+    /// バックエンドの指定
+    pub fn set_backend(&mut self, backend: Option<EAudioDeviceBackend>) -> &mut Self {
+        self.backend = backend;
+        self
+    }
+
+    /// 出力デバイスの指定
+    pub fn set_device(&mut self, device: Option<EAudioDeviceSelector>) -> &mut Self {
+        self.device = device;
+        self
+    }
+
+    /// 入力デバイスの指定
+    pub fn set_capture_device(&mut self, capture_device: Option<EAudioDeviceSelector>) -> &mut Self {
+        self.capture_device = capture_device;
+        self
+    }
+
+    /// デバイスの1周期のフレーム数の指定
+    pub fn set_period_size(&mut self, period_size: Option<u32>) -> &mut Self {
+        self.period_size = period_size;
+        self
+    }
+
+    /// デバイス内部のバッファの数の指定
+    pub fn set_buffer_count(&mut self, buffer_count: Option<u32>) -> &mut Self {
+        self.buffer_count = buffer_count;
+        self
+    }
+
+    /// 仮想デバイスの指定
+    pub fn set_virtual_device(&mut self, virtual_device: Option<VirtualAudioDeviceSetting>) -> &mut Self {
+        self.virtual_device = virtual_device;
+        self
+    }
+
+    /// プル型で処理するために、コールバックが要求したフレームの時間を受け取るかの指定
+    pub fn set_use_frame_demand(&mut self, use_frame_demand: bool) -> &mut Self {
+        self.use_frame_demand = use_frame_demand;
+        self
+    }
 }
 
 /// [`AUdioDevice`]の内部更新情報をまとめた構造体。
@@ -109,11 +236,19 @@ struct AudioDeviceStateInfo {
     total_required_samples: usize,
     /// 前フレームでStarvationが起きているか？
     is_starvation: bool,
+    /// 今のフレームのグラフ処理を始めた時間
+    frame_start_time: Option<Instant>,
+    /// 再生状態の測定値
+    metrics: AudioDeviceMetrics,
 }
 
 pub struct AudioDeviceInternal {
-    /// ローレベルのデバイス
-    low_device: miniaudio::Device,
+    /// ローレベルのデバイス。仮想デバイスを使う時は`None`。
+    low_device: Option<miniaudio::Device>,
+    /// サウンドカードの代わりに使う仮想デバイス。
+    virtual_device: Option<VirtualAudioDevice>,
+    /// 仮想デバイスのコールバックの結果を[`Self::rx`]に送るためのもの。
+    virtual_tx: Option<mpsc::Sender<EAudioDeviceMessage>>,
     /// プロキシの親元。ほかのところでは全部Weakタイプで共有する。
     original_proxy: Option<AudioDeviceProxyPtr>,
     /// [`AudioDevice::process`]から取得して特定の処理を行うためのもの。
@@ -121,59 +256,196 @@ pub struct AudioDeviceInternal {
     /// 更新情報
     info: AudioDeviceStateInfo,
     /// 初期設定
-    #[allow(dead_code)]
     initial_config: AudioDeviceConfig,
 }
 
 impl AudioDeviceInternal {
-    fn new(config: AudioDeviceConfig) -> Self {
-        assert!(config.channels > 0);
-        assert!(config.sample_rate > 0);
+    /// 周期を指定しない時にminiaudioが使う周期の長さ（ミリ秒、`MA_DEFAULT_PERIOD_SIZE_IN_MILLISECONDS_LOW_LATENCY`）
+    const DEFAULT_PERIOD_MILLISECONDS: usize = 10;
 
-        let mut low_device_config = miniaudio::DeviceConfig::new(DeviceType::Playback);
-        low_device_config.playback_mut().set_format(miniaudio::Format::F32);
-        low_device_config.playback_mut().set_channels(config.channels as u32);
-        low_device_config.set_sample_rate(config.sample_rate as u32);
-        low_device_config.set_data_callback(AudioDevice::on_update_device_callback);
-        low_device_config.set_stop_callback(AudioDevice::on_stop_device_callback);
+    /// `config`からデバイスを作る。バックエンドやデバイスが見つからなければエラーを返す。
+    fn new(config: AudioDeviceConfig) -> anyhow::Result<Self> {
+        if config.channels == 0 && config.capture_channels == 0 {
+            return Err(anyhow::anyhow!("Either channels or capture channels must be bigger than 0."));
+        }
+        if config.sample_rate == 0 {
+            return Err(anyhow::anyhow!("Sample rate of audio device must be bigger than 0."));
+        }
 
-        let low_device = miniaudio::Device::new(None, &low_device_config).expect("failed to create audio device");
-        Self {
+        // 仮想デバイスならローレベルのデバイスは作らない。
+        let (low_device, virtual_device) = match &config.virtual_device {
+            Some(setting) => {
+                let virtual_device = VirtualAudioDevice::new(
+                    setting,
+                    config.channels,
+                    config.capture_channels,
+                    config.sample_rate,
+                    config.period_size,
+                )?;
+                (None, Some(virtual_device))
+            }
+            None => (Some(Self::create_low_device(&config)?), None),
+        };
+
+        let sub_buffer_len = AudioDevice::calculate_ring_sub_buffer_length(&config, config.channels);
+        Ok(Self {
             low_device,
+            virtual_device,
+            virtual_tx: None, // これもあとで初期化する。
             original_proxy: None, // これはあとで初期化する。
             rx: None,
             info: AudioDeviceStateInfo {
                 state: EAudioDeviceState::NotStarted,
-                sub_buffer_len: AudioDevice::calculate_ring_sub_buffer_length(&config),
+                sub_buffer_len,
                 remained_samples_count: 0,
                 prev_processed_samples_count: 0,
                 total_required_samples: 0,
                 is_starvation: true, // 最初はStarvationありにして最大限のサンプル数を取得させる。
+                frame_start_time: None,
+                metrics: AudioDeviceMetrics {
+                    channels: config.channels,
+                    sample_rate: config.sample_rate,
+                    ring_capacity_samples: sub_buffer_len * AudioDevice::RING_SUB_BUFFER_COUNT,
+                    ..Default::default()
+                },
             },
             initial_config: config.clone(),
+        })
+    }
+
+    /// `config`からminiaudioのデバイスを作る。
+    fn create_low_device(config: &AudioDeviceConfig) -> anyhow::Result<miniaudio::Device> {
+        // 入力も必要ならDuplexかCaptureにする。
+        let device_type = match (config.channels > 0, config.capture_channels > 0) {
+            (true, false) => DeviceType::Playback,
+            (true, true) => DeviceType::Duplex,
+            (false, _) => DeviceType::Capture,
+        };
+
+        // バックエンドとデバイスを指定できるように、コンテキストは自分で作る。
+        let context = create_context(config.backend)?;
+
+        let mut low_device_config = miniaudio::DeviceConfig::new(device_type);
+        if config.channels > 0 {
+            low_device_config.playback_mut().set_format(miniaudio::Format::F32);
+            low_device_config.playback_mut().set_channels(config.channels as u32);
+            if let Some(selector) = &config.device {
+                let id = find_device_id(&context, DeviceType::Playback, selector)?;
+                low_device_config.playback_mut().set_device_id(Some(id));
+            }
+        }
+        if config.capture_channels > 0 {
+            low_device_config.capture_mut().set_format(miniaudio::Format::F32);
+            low_device_config.capture_mut().set_channels(config.capture_channels as u32);
+            if let Some(selector) = &config.capture_device {
+                let id = find_device_id(&context, DeviceType::Capture, selector)?;
+                low_device_config.capture_mut().set_device_id(Some(id));
+            }
+        }
+        if let Some(period_size) = config.period_size {
+            low_device_config.set_period_size_in_frames(period_size);
         }
+        if let Some(buffer_count) = config.buffer_count {
+            low_device_config.set_periods(buffer_count);
+        }
+        low_device_config.set_sample_rate(config.sample_rate as u32);
+        low_device_config.set_data_callback(AudioDevice::on_update_device_callback);
+        low_device_config.set_stop_callback(AudioDevice::on_stop_device_callback);
+
+        miniaudio::Device::new(Some(context), &low_device_config)
+            .map_err(|e| anyhow::anyhow!("Failed to create audio device. {}", e))
     }
 
     /// 今デバイスに設定しているチャンネルの数を返す。
     /// もしデバイスが無効になっているのであれば、`0`を返す。
     pub fn get_channels(&self) -> usize {
-        self.low_device.playback().channels() as usize
+        if self.initial_config.channels == 0 {
+            return 0;
+        }
+        match (&self.low_device, &self.virtual_device) {
+            (Some(low_device), _) => low_device.playback().channels() as usize,
+            (None, Some(virtual_device)) => virtual_device.channels(),
+            (None, None) => unreachable!("Unexpected branch"),
+        }
+    }
+
+    /// 今デバイスに設定している入力のチャンネルの数を返す。
+    /// 入力を受け取らないデバイスであれば、`0`を返す。
+    pub fn get_capture_channels(&self) -> usize {
+        if self.initial_config.capture_channels == 0 {
+            return 0;
+        }
+        match (&self.low_device, &self.virtual_device) {
+            (Some(low_device), _) => low_device.capture().channels() as usize,
+            (None, Some(virtual_device)) => virtual_device.capture_channels(),
+            (None, None) => unreachable!("Unexpected branch"),
+        }
+    }
+
+    /// デバイスのサンプルレートを返す。
+    pub fn get_sample_rate(&self) -> usize {
+        match (&self.low_device, &self.virtual_device) {
+            (Some(low_device), _) => low_device.sample_rate() as usize,
+            (None, Some(virtual_device)) => virtual_device.sample_rate(),
+            (None, None) => unreachable!("Unexpected branch"),
+        }
+    }
+
+    /// コールバック1回分のフレーム数を返す。
+    ///
+    /// miniaudioは決まった周期を教えてくれないので、本物のデバイスで周期を指定してなければ
+    /// miniaudioのデフォルトの周期（[`Self::DEFAULT_PERIOD_MILLISECONDS`]）から計算する。
+    pub fn get_period_size(&self) -> usize {
+        match (&self.low_device, &self.virtual_device) {
+            (_, Some(virtual_device)) => virtual_device.period_size(),
+            (Some(low_device), None) => match self.initial_config.period_size {
+                Some(period_size) => period_size as usize,
+                None => (low_device.sample_rate() as usize * Self::DEFAULT_PERIOD_MILLISECONDS).div_ceil(1000),
+            },
+            (None, None) => unreachable!("Unexpected branch"),
+        }
+    }
+
+    /// 今までの再生状態の測定値を返す。
+    pub fn get_metrics(&self) -> AudioDeviceMetrics {
+        self.info.metrics.clone()
+    }
+
+    /// 仮想デバイスがメモリに溜めたサンプルを全部取り出す。
+    pub fn take_virtual_samples(&mut self) -> Vec<f32> {
+        match self.virtual_device.as_mut() {
+            Some(v) => v.take_samples(),
+            None => vec![],
+        }
+    }
+
+    /// 仮想デバイスを使う場合、コールバックの結果を送るためのセンダーを持たせる。
+    fn attach_virtual_sender(&mut self, tx: mpsc::Sender<EAudioDeviceMessage>) {
+        if self.virtual_device.is_some() {
+            self.virtual_tx = Some(tx);
+        }
     }
 
     pub fn pre_process(&mut self, _frame_time: f64) {
         match self.info.state {
             EAudioDeviceState::NotStarted => {
-                self.low_device.start().expect("Failed to start audio device");
+                if let Some(low_device) = self.low_device.as_ref() {
+                    low_device.start().expect("Failed to start audio device");
+                }
                 self.info.state = EAudioDeviceState::Started;
             }
             _ => {}
         }
+
+        // グラフの処理時間を測るため。
+        self.info.frame_start_time = Some(Instant::now());
     }
 
     /// Tick関数。
-    pub fn post_process(&mut self, _frame_time: f64) -> ESystemProcessResult {
+    pub fn post_process(&mut self, frame_time: f64) -> ESystemProcessResult {
         match self.info.state {
             EAudioDeviceState::Started => {
+                self.process_virtual_device(frame_time);
                 self.process_started();
             }
             _ => {}
@@ -182,6 +454,41 @@ impl AudioDeviceInternal {
         ESystemProcessResult::Nothing
     }
 
+    /// 仮想デバイスを使っているなら、`frame_time`の分だけコールバックを処理する。
+    fn process_virtual_device(&mut self, frame_time: f64) {
+        let virtual_device = match self.virtual_device.as_mut() {
+            Some(v) => v,
+            None => return,
+        };
+
+        let period_size = virtual_device.period_size();
+        let mut dropped_capture_samples = 0;
+        let results = virtual_device.process(frame_time, |output, input| {
+            if !input.is_empty() {
+                dropped_capture_samples += AudioDevice::write_capture_samples(input);
+            }
+
+            let read_count = AudioDevice::read_playback_samples(output);
+            AudioDevice::send_frame_demand(period_size);
+            read_count
+        });
+
+        // 本物のコールバックと同じくメッセージで結果を送る。
+        let tx = self.virtual_tx.as_ref().expect("Virtual device sender must be attached");
+        for result in results.into_iter().filter(|v| v.required_samples > 0) {
+            let message = if result.read_samples < result.required_samples {
+                EAudioDeviceMessage::StarvationNotified(result.required_samples)
+            } else {
+                EAudioDeviceMessage::LastProcessedLength(result.required_samples)
+            };
+            tx.send(message).expect("Message could not send.");
+        }
+        if dropped_capture_samples > 0 {
+            tx.send(EAudioDeviceMessage::CaptureOverflowed(dropped_capture_samples))
+                .expect("Message could not send.");
+        }
+    }
+
     /// デバイスの状態が[`EAudioDeviceState::Started`]な時の専用処理関数。
     fn process_started(&mut self) {
         // 関連変数を初期化する。
@@ -211,14 +518,20 @@ impl AudioDeviceInternal {
                     // Starvation上での処理を優先する。
                     is_starvation = true;
                     required_length += required_count;
+                    self.info.metrics.xrun_count += 1;
+                    self.info.metrics.callback_samples = self.info.metrics.callback_samples.max(required_count);
                 }
                 EAudioDeviceMessage::LastProcessedLength(samples_count) => {
                     last_processed_samples_length += samples_count;
                     required_length += samples_count;
+                    self.info.metrics.callback_samples = self.info.metrics.callback_samples.max(samples_count);
                 }
                 EAudioDeviceMessage::SendSamplesToBuffer(samples_count) => {
                     last_send_buffer_length += samples_count;
                 }
+                EAudioDeviceMessage::CaptureOverflowed(samples_count) => {
+                    self.info.metrics.capture_dropped_samples += samples_count;
+                }
             }
         }
 
@@ -232,11 +545,32 @@ impl AudioDeviceInternal {
         self.info.prev_processed_samples_count = last_processed_samples_length;
         self.info.is_starvation = is_starvation;
         self.info.total_required_samples += required_length;
+        self.update_metrics();
 
         if self.info.is_starvation {
             println!("Starved!");
         }
     }
+
+    /// フレームの処理が終わった時点で測定値を更新する。
+    fn update_metrics(&mut self) {
+        let metrics = &mut self.info.metrics;
+        metrics.frame_count += 1;
+
+        if let Some(start_time) = self.info.frame_start_time.take() {
+            let process_time = start_time.elapsed();
+            metrics.last_process_time = process_time;
+            metrics.max_process_time = metrics.max_process_time.max(process_time);
+            metrics.total_process_time += process_time;
+        }
+
+        // リングバッファにまだ残っているサンプル数。
+        if let Some(receiver) = BUFFER_RECEIVER.get() {
+            if let Some(receiver) = receiver.lock().unwrap().as_mut() {
+                metrics.ring_fill_samples = receiver.available();
+            }
+        }
+    }
 }
 
 pub struct AudioDevice {
@@ -248,10 +582,13 @@ impl AudioDevice {
     /// フレーム理想処理時間からの換算のサンプル数がこれ未満でも、これを適用する。
     const BUFFER_MINIMUM_SAMPLES: usize = 1024;
 
-    /// サブバッファのサンプル数の数を計算する。
-    fn calculate_ring_sub_buffer_length(config: &AudioDeviceConfig) -> usize {
+    /// リングバッファのサブバッファの数。
+    const RING_SUB_BUFFER_COUNT: usize = 16;
+
+    /// `channels`チャンネルのリングバッファのサブバッファのサンプル数の数を計算する。
+    fn calculate_ring_sub_buffer_length(config: &AudioDeviceConfig, channels: usize) -> usize {
         let ideal_seconds = config.frame_ideal_milliseconds.as_secs_f64();
-        let raw_required_samples = (config.sample_rate as f64 * config.channels as f64 * ideal_seconds).ceil() as usize;
+        let raw_required_samples = (config.sample_rate as f64 * channels as f64 * ideal_seconds).ceil() as usize;
 
         let required_samples = raw_required_samples.next_power_of_two();
         required_samples.max(Self::BUFFER_MINIMUM_SAMPLES)
@@ -259,38 +596,71 @@ impl AudioDevice {
 
     /// システムを初期化する。
     /// すべての処理（レンダリング）が始まる前に処理すべき。
-    pub fn initialize(config: AudioDeviceConfig) -> AudioDeviceProxyWeakPtr {
+    ///
+    /// バックエンドや指定したデバイスが見つからないなど、デバイスが作れなければエラーを返す。
+    pub fn initialize(config: AudioDeviceConfig) -> anyhow::Result<AudioDeviceProxyWeakPtr> {
+        if Self::instance().is_some() {
+            return Err(anyhow::anyhow!("AudioDevice is already initialized. Call cleanup() first."));
+        }
+
+        // 先にデバイスを作っておいて、失敗したら何も登録せずに返す。
+        // コールバックはデバイスを始めるまで呼ばれないので、リングバッファの登録より先に作ってもいい。
+        let device = Self::new(config.clone())?;
+
         // RingBufferの登録。
         // RingBufferのタイプはf32にして、あとで受け取る側でいい変換して送る。
         //
         // ただし生成したままではちゃんと扱えないので、sendだけはArc<Mutex<>>にはさむ。
-        let sub_buffer_len = Self::calculate_ring_sub_buffer_length(&config);
+        let sub_buffer_len = Self::calculate_ring_sub_buffer_length(&config, config.channels);
         let (send, recv) =
-            miniaudio::ring_buffer::<f32>(sub_buffer_len, 16).expect("Failed to create audio ring buffer.");
-        let _result = BUFFER_RECEIVER.set(Mutex::new(Some(recv)));
+            miniaudio::ring_buffer::<f32>(sub_buffer_len, Self::RING_SUB_BUFFER_COUNT).expect("Failed to create audio ring buffer.");
+        replace_global(&BUFFER_RECEIVER, Some(recv));
+
+        // 入力を受け取る場合には、逆方向（コールバック→プロキシ）のリングバッファも作る。
+        let capture_receiver = if config.capture_channels > 0 {
+            let sub_buffer_len = Self::calculate_ring_sub_buffer_length(&config, config.capture_channels);
+            let (send, recv) =
+                miniaudio::ring_buffer::<f32>(sub_buffer_len, Self::RING_SUB_BUFFER_COUNT).expect("Failed to create capture ring buffer.");
+            replace_global(&CAPTURE_SENDER, Some(send));
+            Some(recv)
+        } else {
+            replace_global(&CAPTURE_SENDER, None);
+            None
+        };
+
+        // プル型で処理する場合には、コールバックからの要求を受け取るチャンネルも作る。
+        // デバイスが始まるまではコールバックから要求が来ないので、最初の1周期分だけは先に要求しておく。
+        if config.use_frame_demand {
+            let (send, recv) = mpsc::channel();
+            let period_size = device.v.as_ref().expect("AudioDevice internal must be valid").get_period_size();
+            let _result = send.send(period_size);
+            replace_global(&FRAME_DEMAND_SENDER, Some(send));
+            replace_global(&FRAME_DEMAND_RECEIVER, Some(recv));
+        } else {
+            replace_global(&FRAME_DEMAND_SENDER, None);
+            replace_global(&FRAME_DEMAND_RECEIVER, None);
+        }
 
         // メッセージチャンネルの生成と登録。
         let (tx, rx) = mpsc::channel();
+        let virtual_tx = tx.clone();
 
         // @todo 24-12-10 ここら辺のコード、結構危なっかしいのであとでちゃんとしたものに書き換えしたい。
         // こっからProxyを作って、weakを渡してから
-        let original_proxy = {
-            assert!(AUDIO_DEVICE.get().is_none());
-
+        let (instance, original_proxy) = {
             // デバイスの初期化
-            let _result = AUDIO_DEVICE.set(Arc::new(Mutex::new(Self::new(config))));
-            let device = AUDIO_DEVICE.get().unwrap();
+            let device = Arc::new(Mutex::new(device));
+            replace_global(&AUDIO_DEVICE, Some(device.clone()));
             let weak_device = Arc::downgrade(&device);
 
-            let original_proxy = AudioDeviceProxy::new(weak_device, send, tx);
-            original_proxy
+            let original_proxy = AudioDeviceProxy::new(weak_device, send, capture_receiver, tx);
+            (device, original_proxy)
         };
 
         // Proxyの登録。
         let weak_proxy = Arc::downgrade(&original_proxy);
         {
             // Mutexがおそらく内部Internal Mutabilityを実装しているかと。
-            let instance = AUDIO_DEVICE.get().expect("AudioDevice instance must be valid");
             let mut accessor = instance.lock().unwrap();
             debug_assert!(accessor.v.is_some());
 
@@ -298,30 +668,37 @@ impl AudioDevice {
             let v = accessor.v.as_mut().unwrap();
             v.original_proxy = Some(original_proxy);
             v.rx = Some(rx);
+            v.attach_virtual_sender(virtual_tx);
         }
 
         // Proxyを返す。本体は絶対返さない。
-        assert!(AUDIO_DEVICE.get().is_some());
-
         // 24-12-15 登録。
-        let _result = PROXY_ACCESSOR.set(weak_proxy.clone());
-        weak_proxy
+        replace_global(&PROXY_ACCESSOR, Some(weak_proxy.clone()));
+        Ok(weak_proxy)
+    }
+
+    /// 初期化したデバイスの本体を返す。
+    /// まだ初期化してないか、もう解放したなら`None`を返す。
+    fn instance() -> Option<Arc<Mutex<AudioDevice>>> {
+        AUDIO_DEVICE.get()?.lock().unwrap().clone()
     }
 
     /// システムの対応。
     pub fn get_proxy() -> Option<AudioDeviceProxyWeakPtr> {
-        // これは大丈夫か。。。。
-        match PROXY_ACCESSOR.get() {
-            None => None,
-            Some(v) => Some(v.clone()),
-        }
+        PROXY_ACCESSOR.get()?.lock().unwrap().clone()
     }
 
-    pub fn pre_process(_frame_time: f64) {
-        assert!(AUDIO_DEVICE.get().is_some());
+    /// 仮想デバイスを使っていれば、コールバック1回分の時間（秒）を返す。
+    /// 仮想デバイスを使っていないか、デバイスを初期化していなければ`None`を返す。
+    pub fn get_virtual_period_time() -> Option<f64> {
+        let instance = Self::instance()?;
+        let accessor = instance.lock().unwrap();
+        accessor.v.as_ref()?.virtual_device.as_ref().map(|v| v.period_time())
+    }
 
+    pub fn pre_process(_frame_time: f64) {
         {
-            let instance = AUDIO_DEVICE.get().expect("AudioDevice instance must be valid");
+            let instance = Self::instance().expect("AudioDevice instance must be valid");
             let mut accessor = instance.lock().unwrap();
             debug_assert!(accessor.v.is_some());
             let v = accessor.v.as_mut().unwrap();
@@ -332,10 +709,8 @@ impl AudioDevice {
 
     /// Tick関数。
     pub fn post_process(_frame_time: f64) -> ESystemProcessResult {
-        assert!(AUDIO_DEVICE.get().is_some());
-
         {
-            let instance = AUDIO_DEVICE.get().expect("AudioDevice instance must be valid");
+            let instance = Self::instance().expect("AudioDevice instance must be valid");
             let mut accessor = instance.lock().unwrap();
             debug_assert!(accessor.v.is_some());
             let v = accessor.v.as_mut().unwrap();
@@ -345,13 +720,23 @@ impl AudioDevice {
 
     /// システムを解放する。
     /// すべての関連処理が終わった後に解放すべき。
+    /// 解放したあとは、また[`Self::initialize`]で初期化できる。
     pub fn cleanup() {
-        assert!(AUDIO_DEVICE.get().is_some());
-
         // 12-11-xx ここでdropするので、もう1回解放してはいけない。
         // 12-12-23 Optionおdropすればいいだけ。
-        if let Some(device) = AUDIO_DEVICE.get() {
+        let device = replace_global(&AUDIO_DEVICE, None);
+        assert!(device.is_some());
+        replace_global(&PROXY_ACCESSOR, None);
+        if let Some(device) = device {
             let mut device = device.lock().unwrap();
+
+            // 終了時に再生状態のまとめを出力する。
+            if let Some(v) = device.v.as_ref() {
+                let metrics = v.get_metrics();
+                if metrics.frame_count > 0 {
+                    println!("Audio device metrics: {}", metrics);
+                }
+            }
             device.v = None;
         }
 
@@ -360,30 +745,161 @@ impl AudioDevice {
             let mut rv = rv.lock().unwrap();
             *rv = None;
         }
+        if let Some(sd) = CAPTURE_SENDER.get() {
+            let mut sd = sd.lock().unwrap();
+            *sd = None;
+        }
+        if let Some(sd) = FRAME_DEMAND_SENDER.get() {
+            let mut sd = sd.lock().unwrap();
+            *sd = None;
+        }
+        if let Some(rv) = FRAME_DEMAND_RECEIVER.get() {
+            let mut rv = rv.lock().unwrap();
+            *rv = None;
+        }
     }
 
-    fn new(config: AudioDeviceConfig) -> Self {
-        assert!(config.channels > 0);
-        assert!(config.sample_rate > 0);
+    /// デバイスのコールバックが次に再生するために要求したフレーム数を`timeout`まで待って返す。
+    /// 複数の要求が溜まっていたら、全部足して返す。
+    ///
+    /// [`AudioDeviceConfig::set_use_frame_demand`]を指定してないか、`timeout`まで要求がなければ`None`を返す。
+    pub fn wait_frame_demand(timeout: std::time::Duration) -> Option<usize> {
+        let receiver = FRAME_DEMAND_RECEIVER.get()?.lock().unwrap();
+        let receiver = receiver.as_ref()?;
+
+        let mut demand = receiver.recv_timeout(timeout).ok()?;
+        while let Ok(v) = receiver.try_recv() {
+            demand += v;
+        }
+        Some(demand)
+    }
 
-        Self {
-            v: Some(AudioDeviceInternal::new(config)),
+    /// 仮想デバイスがメモリに溜めたサンプルを全部取り出す。
+    /// 返すバッファはチャンネルがインターリーブされている。
+    /// 仮想デバイスを使っていないか、WAVファイルに書き込んでいる場合は空のバッファを返す。
+    pub fn take_virtual_samples() -> Vec<f32> {
+        let instance = Self::instance().expect("AudioDevice instance must be valid");
+        let mut accessor = instance.lock().unwrap();
+        match accessor.v.as_mut() {
+            Some(v) => v.take_virtual_samples(),
+            None => vec![],
         }
     }
 
+    fn new(config: AudioDeviceConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            v: Some(AudioDeviceInternal::new(config)?),
+        })
+    }
+
     /// 今デバイスに設定しているチャンネルの数を返す。
     /// もしデバイスが無効になっているのであれば、`0`を返す。
     pub fn get_channels(&self) -> usize {
         self.v.as_ref().unwrap().get_channels()
     }
 
-    fn on_update_device_callback(device: &miniaudio::RawDevice, output: &mut FramesMut, _input: &miniaudio::Frames) {
+    /// 今デバイスに設定している入力のチャンネルの数を返す。
+    /// 入力を受け取らないデバイスであれば、`0`を返す。
+    pub fn get_capture_channels(&self) -> usize {
+        self.v.as_ref().unwrap().get_capture_channels()
+    }
+
+    /// デバイスのサンプルレートを返す。
+    pub fn get_sample_rate(&self) -> usize {
+        self.v.as_ref().unwrap().get_sample_rate()
+    }
+
+    /// 今までの再生状態の測定値を返す。
+    pub fn get_metrics(&self) -> AudioDeviceMetrics {
+        self.v.as_ref().unwrap().get_metrics()
+    }
+
+    fn on_update_device_callback(device: &miniaudio::RawDevice, output: &mut FramesMut, input: &miniaudio::Frames) {
+        // キャプチャー専用のデバイスなら出力のバッファは空になっている。
+        if input.byte_count() > 0 {
+            Self::on_capture_device_callback(input);
+        }
+        if output.byte_count() > 0 {
+            Self::on_playback_device_callback(device, output);
+        } else if input.byte_count() > 0 {
+            // 入力専用のデバイスなら、プル型ではキャプチャーした分のフレームを処理するように要求する。
+            Self::send_frame_demand(input.frame_count());
+        }
+    }
+
+    /// 入力デバイスから受け取った`input`をリングバッファに送る。
+    /// 入りきらなくて捨てたサンプルがあれば、測定値に入れるようにメッセージで知らせる。
+    fn on_capture_device_callback(input: &miniaudio::Frames) {
+        let dropped_count = Self::write_capture_samples(input.as_samples::<f32>());
+        if dropped_count == 0 {
+            return;
+        }
+
+        if let Some(proxy) = Self::get_proxy().and_then(|v| v.upgrade()) {
+            let accessor = proxy.lock().unwrap();
+            let _result = accessor.tx.send(EAudioDeviceMessage::CaptureOverflowed(dropped_count));
+        }
+    }
+
+    /// キャプチャーした`samples`をリングバッファに送って、入りきらなくて捨てたサンプル数を返す。
+    fn write_capture_samples(samples: &[f32]) -> usize {
+        debug_assert!(CAPTURE_SENDER.get().is_some());
+
+        // 読み込む側が追いついていなくてリングバッファがいっぱいの場合、入りきらない分は捨てる。
+        let sender = CAPTURE_SENDER.get().unwrap().lock().unwrap();
+        let mut write_count = 0;
+        if let Some(sender) = sender.as_ref() {
+            while write_count < samples.len() {
+                let written = sender.write(&samples[write_count..]);
+                if written == 0 {
+                    break;
+                }
+                write_count += written;
+            }
+        }
+        samples.len() - write_count
+    }
+
+    /// リングバッファからできるだけ`outputs`を埋めて、読み込めたサンプル数を返す。
+    /// 読み込めなかった部分は`0`にする。
+    fn read_playback_samples(outputs: &mut [f32]) -> usize {
         const ATTEMPTS_COUNT: usize = 8;
         debug_assert!(BUFFER_RECEIVER.get().is_some());
 
+        // Here we try reading at most 8 sub buffers to attempt to read enough outputs to
+        // fill the playback output buffer. We don't allow infinite attempts because we can't be
+        // sure how long that would take.
         let mut read_count = 0;
         let mut attempts = 0;
-        let mut required_output_length = 0usize;
+        while read_count < outputs.len() && attempts < ATTEMPTS_COUNT {
+            read_count += BUFFER_RECEIVER
+                .get()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .read(&mut outputs[read_count..]);
+            attempts += 1;
+        }
+
+        // If we're starved, just repeat the last sample on all channels:
+        (&mut outputs[read_count..]).iter_mut().for_each(|s| *s = 0.0);
+        read_count
+    }
+
+    /// プル型ならレンダースレッドに`frame_count`フレーム分を処理するように要求する。
+    fn send_frame_demand(frame_count: usize) {
+        if let Some(sender) = FRAME_DEMAND_SENDER.get() {
+            if let Some(sender) = sender.lock().unwrap().as_ref() {
+                let _result = sender.send(frame_count);
+            }
+        }
+    }
+
+    fn on_playback_device_callback(device: &miniaudio::RawDevice, output: &mut FramesMut) {
+        let read_count;
+        let required_output_length;
 
         match device.playback().format() {
             miniaudio::Format::S16 => {
@@ -395,55 +911,32 @@ impl AudioDevice {
                 raw_samples.resize(required_output_length, 0.0f32);
 
                 // できるだけ読み切る。
-                while read_count < required_output_length && attempts < ATTEMPTS_COUNT {
-                    read_count += BUFFER_RECEIVER
-                        .get()
-                        .unwrap()
-                        .lock()
-                        .unwrap()
-                        .as_ref()
-                        .unwrap()
-                        .read(&mut raw_samples[read_count..]);
-                    attempts += 1;
-                }
+                read_count = Self::read_playback_samples(&mut raw_samples);
 
                 // raw_samplesをoutputに変換する。
                 for (i, sample) in raw_samples.iter().enumerate() {
                     outputs[i] = UniformedSample::from_f64(*sample as f64).to_16bits();
                 }
-
-                // If we're starved, just repeat the last sample on all channels:
-                (&mut outputs[read_count..]).iter_mut().for_each(|s| *s = 0);
             }
             miniaudio::Format::F32 => {
                 // f32 → f32なので、そのままにしてもいい。
                 let outputs = output.as_samples_mut::<f32>();
                 required_output_length = outputs.len();
-
-                // Here we try reading at most 8 sub buffers to attempt to read enough outputs to
-                // fill the playback output buffer. We don't allow infinite attempts because we can't be
-                // sure how long that would take.
-                while read_count < required_output_length && attempts < ATTEMPTS_COUNT {
-                    read_count += BUFFER_RECEIVER
-                        .get()
-                        .unwrap()
-                        .lock()
-                        .unwrap()
-                        .as_ref()
-                        .unwrap()
-                        .read(&mut outputs[read_count..]);
-                    attempts += 1;
-                }
-
-                // If we're starved, just repeat the last sample on all channels:
-                (&mut outputs[read_count..]).iter_mut().for_each(|s| *s = 0.0);
+                read_count = Self::read_playback_samples(outputs);
             }
             _ => unreachable!(),
         }
 
-        // もしStarvationが発生したら、次のバッファ取得値は最大限にする。
+        // プル型ならレンダースレッドに今読み込んだ分のフレームを処理するように要求する。
         if required_output_length > 0 {
-            let proxy = PROXY_ACCESSOR.get().unwrap().upgrade().unwrap();
+            let frame_count = required_output_length / (device.playback().channels() as usize).max(1);
+            Self::send_frame_demand(frame_count);
+        }
+
+        // もしStarvationが発生したら、次のバッファ取得値は最大限にする。
+        // 解放中ならプロキシがもうないので、何も送らない。
+        let proxy = Self::get_proxy().and_then(|v| v.upgrade());
+        if let Some(proxy) = proxy.filter(|_| required_output_length > 0) {
             let accessor = proxy.lock().unwrap();
 
             if read_count < required_output_length {
@@ -471,9 +964,45 @@ pub struct AudioDeviceProxy {
     device: Weak<Mutex<AudioDevice>>,
     /// 最終オーディオレンダリングに使うためのリングバッファ。
     buffer_sender: RingBufferSend<f32>,
+    /// 入力デバイスからキャプチャーしたサンプルを受け取るためのリングバッファ。
+    /// 入力を受け取らない設定なら`None`。
+    capture_receiver: Option<RingBufferRecv<f32>>,
     /// Multi-producerなので、おそらく内部でThread-safeなはず。
     /// [`AudioDevice`]の処理までに特定の動作を送るため。
     tx: mpsc::Sender<EAudioDeviceMessage>,
+    /// 最後にサンプルを送る時に使ったチャンネルのミックスの行列。
+    remix_cache: Option<RemixMatrixCache>,
+}
+
+/// [`AudioDeviceProxy`]でサンプルを送るたびに行列を作り直さないように、設定と一緒に持っておくもの。
+#[derive(Debug)]
+struct RemixMatrixCache {
+    setting: ChannelRemixSetting,
+    layout: EChannelLayout,
+    channels: usize,
+    matrix: ChannelRemixMatrix,
+}
+
+impl RemixMatrixCache {
+    /// `cache`の行列が`setting`、`layout`、`channels`のものならそのまま返して、違えば作り直してから返す。
+    fn get_or_update<'a>(
+        cache: &'a mut Option<Self>,
+        setting: &ChannelRemixSetting,
+        layout: EChannelLayout,
+        channels: usize,
+    ) -> &'a ChannelRemixMatrix {
+        let is_valid =
+            matches!(cache, Some(v) if v.layout == layout && v.channels == channels && v.setting == *setting);
+        if !is_valid {
+            *cache = Some(Self {
+                setting: setting.clone(),
+                layout,
+                channels,
+                matrix: ChannelRemixMatrix::new(setting, layout, channels),
+            });
+        }
+        &cache.as_ref().unwrap().matrix
+    }
 }
 
 impl AudioDeviceProxy {
@@ -481,12 +1010,15 @@ impl AudioDeviceProxy {
     fn new(
         device: Weak<Mutex<AudioDevice>>,
         buffer_sender: RingBufferSend<f32>,
+        capture_receiver: Option<RingBufferRecv<f32>>,
         tx: mpsc::Sender<EAudioDeviceMessage>,
     ) -> AudioDeviceProxyPtr {
         let instance = Self {
             device,
             buffer_sender,
+            capture_receiver,
             tx,
+            remix_cache: None,
         };
         Arc::new(Mutex::new(instance))
     }
@@ -500,17 +1032,81 @@ impl AudioDeviceProxy {
         }
     }
 
+    /// 今デバイスに設定している入力のチャンネルの数を返す。
+    /// もしデバイスが無効か、入力を受け取らないのであれば`0`を返す。
+    pub fn get_capture_channels(&self) -> usize {
+        match self.device.upgrade() {
+            None => 0,
+            Some(v) => v.lock().unwrap().get_capture_channels(),
+        }
+    }
+
+    /// デバイスのサンプルレートを返す。
+    /// もしデバイスが無効になっているのであれば、`0`を返す。
+    pub fn get_sample_rate(&self) -> usize {
+        match self.device.upgrade() {
+            None => 0,
+            Some(v) => v.lock().unwrap().get_sample_rate(),
+        }
+    }
+
+    /// 今までの再生状態の測定値を返す。
+    /// もしデバイスが無効になっているのであれば、`None`を返す。
+    pub fn get_metrics(&self) -> Option<AudioDeviceMetrics> {
+        self.device.upgrade().map(|v| v.lock().unwrap().get_metrics())
+    }
+
+    /// 入力デバイスからキャプチャーされて溜まっているサンプルを全部取り出す。
+    /// 返すバッファはチャンネルがインターリーブされている。
+    pub fn receive_captured_samples(&self) -> Vec<f32> {
+        let mut result = vec![];
+        let receiver = match self.capture_receiver.as_ref() {
+            None => return result,
+            Some(v) => v,
+        };
+
+        let mut buffer = [0.0f32; 1024];
+        loop {
+            let read_count = receiver.read(&mut buffer);
+            if read_count == 0 {
+                break;
+            }
+            result.extend_from_slice(&buffer[..read_count]);
+        }
+        result
+    }
+
     /// デバイスの設定に合わせて適切にサンプルを送信する。
-    pub fn send_sample_buffer_with<F>(&self, f: F) -> usize
+    /// デバイスのチャンネル数が入力と違う場合は`remix_setting`に従ってミックスする。
+    pub fn send_sample_buffer_with<F>(&mut self, remix_setting: &ChannelRemixSetting, f: F) -> usize
+    where
+        F: FnOnce(/*frame_count:*/ usize) -> EDrainedChannelBuffers,
+    {
+        self.write_sample_buffer_with(remix_setting, 1024, f)
+    }
+
+    /// [`Self::send_sample_buffer_with`]と同じだが、最大`frame_count`フレームだけを送信する。
+    /// `f`が受け取るフレーム数はリングバッファの空きによって`frame_count`より少ないこともあるので、
+    /// 全部送るまで繰り返して呼ぶこと。送信したサンプルの数を返す。
+    pub fn send_sample_frames_with<F>(&mut self, remix_setting: &ChannelRemixSetting, frame_count: usize, f: F) -> usize
     where
         F: FnOnce(/*frame_count:*/ usize) -> EDrainedChannelBuffers,
     {
         let channels = self.get_channels();
-        if channels == 0 {
+        self.write_sample_buffer_with(remix_setting, frame_count * channels, f)
+    }
+
+    fn write_sample_buffer_with<F>(&mut self, remix_setting: &ChannelRemixSetting, sample_count: usize, f: F) -> usize
+    where
+        F: FnOnce(/*frame_count:*/ usize) -> EDrainedChannelBuffers,
+    {
+        let channels = self.get_channels();
+        if channels == 0 || sample_count == 0 {
             return 0;
         }
 
-        self.buffer_sender.write_with(1024, move |buffer| {
+        let remix_cache = &mut self.remix_cache;
+        self.buffer_sender.write_with(sample_count, move |buffer| {
             let buffer_len = buffer.len();
             let frame_count = buffer_len / channels;
             if frame_count <= 0 {
@@ -522,10 +1118,14 @@ impl AudioDeviceProxy {
             let mut frame_i = 0usize;
             match channel_buffers {
                 EDrainedChannelBuffers::Mono { channel } => {
+                    let matrix =
+                        RemixMatrixCache::get_or_update(remix_cache, remix_setting, EChannelLayout::Mono, channels);
+
                     for sample in channel {
                         let start_i = frame_i * channels;
                         let end_i = start_i + channels;
-                        remix_mono_to(sample, channels, &mut buffer[start_i..end_i]);
+                        let input = [sample.to_f64_clamped() as f32];
+                        matrix.apply(&input, &mut buffer[start_i..end_i]);
 
                         frame_i += 1;
                         if frame_i >= frame_count {
@@ -535,11 +1135,14 @@ impl AudioDeviceProxy {
                 }
                 EDrainedChannelBuffers::Stereo { ch_left, ch_right } => {
                     debug_assert_eq!(ch_left.len(), ch_right.len());
+                    let matrix =
+                        RemixMatrixCache::get_or_update(remix_cache, remix_setting, EChannelLayout::Stereo, channels);
 
                     for (l_sample, r_sample) in ch_left.into_iter().zip_eq(ch_right) {
                         let start_i = frame_i * channels;
                         let end_i = start_i + channels;
-                        remix_stereo_to(l_sample, r_sample, channels, &mut buffer[start_i..end_i]);
+                        let input = [l_sample.to_f64_clamped() as f32, r_sample.to_f64_clamped() as f32];
+                        matrix.apply(&input, &mut buffer[start_i..end_i]);
 
                         frame_i += 1;
                         if frame_i >= frame_count {
@@ -552,42 +1155,6 @@ impl AudioDeviceProxy {
     }
 }
 
-/// `sample`のモノ音源を任意でミックスして`buffer`に入れる。
-fn remix_mono_to(sample: UniformedSample, channels: usize, buffer: &mut [f32]) {
-    debug_assert!(channels > 0);
-    debug_assert!(buffer.len() >= channels);
-
-    let sample = sample.to_f64_clamped() as f32;
-    for i in 0..channels {
-        buffer[i] = sample;
-    }
-}
-
-/// `left`, `right`のステレオ音源を任意でミックスして`buffer`に入れる。
-fn remix_stereo_to(left: UniformedSample, right: UniformedSample, channels: usize, buffer: &mut [f32]) {
-    debug_assert!(channels > 0);
-    debug_assert!(buffer.len() >= channels);
-
-    // 24-12-12 ダウンミックスとそのままだけ。
-    // @todo 多重チャンネルも対応しよう。
-    match channels {
-        1 => {
-            // ダウンミックスしよう。
-            //
-            // https://www.sonible.com/blog/stereo-to-mono/
-            // でも足して割り算するだけだとフェーズ相殺によるコムフィルタになってしまうけど、
-            // しょうがないか。。。
-            let downmixed = (left.to_f64_clamped() + right.to_f64_clamped()) * 0.5;
-            buffer[0] = downmixed as f32;
-        }
-        2 => {
-            buffer[0] = left.to_f64_clamped() as f32;
-            buffer[1] = right.to_f64_clamped() as f32;
-        }
-        _ => unreachable!(),
-    }
-}
-
 type AudioDeviceProxyPtr = Arc<Mutex<AudioDeviceProxy>>;
 pub type AudioDeviceProxyWeakPtr = Weak<Mutex<AudioDeviceProxy>>;
 
@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+
+/// -3dB（`1/√2`）のゲイン。ITU-R BS.775のダウンミックスで使う。
+const MINUS_3DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+/// -6dB（`1/2`）のゲイン。後ろと横のサラウンドを1つにまとめる時に、フルスケールでも1を超えないように使う。
+const MINUS_6DB: f32 = 0.5;
+
+/// デバイスのチャンネル配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EChannelLayout {
+    #[serde(rename = "mono")]
+    Mono,
+    #[serde(rename = "stereo")]
+    Stereo,
+    /// FL, FR, BL, BR
+    #[serde(rename = "quad")]
+    Quad,
+    /// FL, FR, FC, LFE, SL, SR
+    #[serde(rename = "5.1")]
+    Surround51,
+    /// FL, FR, FC, LFE, BL, BR, SL, SR
+    #[serde(rename = "7.1")]
+    Surround71,
+}
+
+impl EChannelLayout {
+    /// チャンネル数から一般的な配置を返す。対応する配置がなければ`None`を返す。
+    pub fn from_channels(channels: usize) -> Option<Self> {
+        match channels {
+            1 => Some(Self::Mono),
+            2 => Some(Self::Stereo),
+            4 => Some(Self::Quad),
+            6 => Some(Self::Surround51),
+            8 => Some(Self::Surround71),
+            _ => None,
+        }
+    }
+
+    /// 配置のチャンネル数を返す。
+    pub fn channels(self) -> usize {
+        self.positions().len()
+    }
+
+    /// 各チャンネルの位置をインターリーブの順番で返す。
+    pub fn positions(self) -> &'static [EChannelPosition] {
+        use EChannelPosition::*;
+
+        match self {
+            Self::Mono => &[Mono],
+            Self::Stereo => &[FrontLeft, FrontRight],
+            Self::Quad => &[FrontLeft, FrontRight, BackLeft, BackRight],
+            Self::Surround51 => &[FrontLeft, FrontRight, FrontCenter, Lfe, SideLeft, SideRight],
+            Self::Surround71 => &[
+                FrontLeft,
+                FrontRight,
+                FrontCenter,
+                Lfe,
+                BackLeft,
+                BackRight,
+                SideLeft,
+                SideRight,
+            ],
+        }
+    }
+
+    fn contains(self, position: EChannelPosition) -> bool {
+        self.positions().contains(&position)
+    }
+
+    fn index_of(self, position: EChannelPosition) -> Option<usize> {
+        self.positions().iter().position(|v| *v == position)
+    }
+}
+
+/// チャンネルのスピーカーの位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EChannelPosition {
+    /// モノラル。どの位置でもない。
+    Mono,
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    Lfe,
+    BackLeft,
+    BackRight,
+    SideLeft,
+    SideRight,
+}
+
+impl EChannelPosition {
+    /// 後ろか横のサラウンドのチャンネルか？
+    fn is_surround(self) -> bool {
+        matches!(self, Self::BackLeft | Self::BackRight | Self::SideLeft | Self::SideRight)
+    }
+}
+
+/// [`ChannelRemixMatrix`]を作るための設定。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChannelRemixSetting {
+    /// 出力のチャンネル配置。指定しなければデバイスのチャンネル数から決める。
+    pub layout: Option<EChannelLayout>,
+    /// 出力の各チャンネルにどの入力チャンネルを流すかを直接指定する。
+    /// `None`か、入力にないチャンネルを指定したチャンネルは無音にする。
+    /// 指定した場合は`layout`によるミックスは行わない。
+    pub routing: Option<Vec<Option<usize>>>,
+}
+
+/// 入力チャンネルを出力チャンネルに混ぜるためのゲインの行列。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelRemixMatrix {
+    input_channels: usize,
+    output_channels: usize,
+    /// `[出力チャンネル][入力チャンネル]`の順番で並んでいる。
+    gains: Vec<f32>,
+}
+
+impl ChannelRemixMatrix {
+    /// `setting`から`input`の配置を`output_channels`チャンネルに変換する行列を作る。
+    pub fn new(setting: &ChannelRemixSetting, input: EChannelLayout, output_channels: usize) -> Self {
+        if let Some(routing) = &setting.routing {
+            return Self::from_routing(input.channels(), output_channels, routing);
+        }
+
+        match setting
+            .layout
+            .filter(|v| v.channels() == output_channels)
+            .or_else(|| EChannelLayout::from_channels(output_channels))
+        {
+            Some(output) => Self::from_layouts(input, output),
+            None => {
+                // 配置が分からないチャンネル数なら、最初の2チャンネルをステレオとして使って残りは無音にする。
+                let stereo = Self::from_layouts(input, EChannelLayout::Stereo);
+                let mut result = Self::zero(input.channels(), output_channels);
+                for out_i in 0..stereo.output_channels.min(output_channels) {
+                    for in_i in 0..stereo.input_channels {
+                        result.set_gain(out_i, in_i, stereo.gain(out_i, in_i));
+                    }
+                }
+                result
+            }
+        }
+    }
+
+    /// ITU-R BS.775に沿って`input`の配置から`output`の配置に変換する行列を作る。
+    ///
+    /// * モノラルはセンターがあればセンターに、なければ左右に同じく流す。
+    /// * センターがない配置へのダウンミックスではセンターとサラウンドを-3dBで前の左右に混ぜる。
+    /// * 後ろと横のサラウンドを片方だけのサラウンドにまとめる時は、それぞれ-6dBで混ぜる。
+    /// * LFEはアップミックスでは無音にして、ダウンミックスでは捨てる。
+    /// * モノラルへのダウンミックスはステレオにしてから左右を半分ずつ混ぜる。
+    pub fn from_layouts(input: EChannelLayout, output: EChannelLayout) -> Self {
+        use EChannelPosition::*;
+
+        let folds_surround = input.contains(BackLeft)
+            && input.contains(SideLeft)
+            && (output.contains(BackLeft) != output.contains(SideLeft));
+
+        let mut result = Self::zero(input.channels(), output.channels());
+        for (in_i, in_position) in input.positions().iter().enumerate() {
+            for (out_position, gain) in route_position(*in_position, output) {
+                let gain = if folds_surround && in_position.is_surround() && out_position.is_surround() {
+                    gain * MINUS_6DB
+                } else {
+                    gain
+                };
+                let out_i = output.index_of(out_position).unwrap();
+                result.set_gain(out_i, in_i, result.gain(out_i, in_i) + gain);
+            }
+        }
+        result
+    }
+
+    /// 出力の各チャンネルに`routing`で指定した入力チャンネルをそのまま流す行列を作る。
+    pub fn from_routing(input_channels: usize, output_channels: usize, routing: &[Option<usize>]) -> Self {
+        let mut result = Self::zero(input_channels, output_channels);
+        for (out_i, in_i) in routing.iter().enumerate().take(output_channels) {
+            if let Some(in_i) = in_i.filter(|v| *v < input_channels) {
+                result.set_gain(out_i, in_i, 1.0);
+            }
+        }
+        result
+    }
+
+    fn zero(input_channels: usize, output_channels: usize) -> Self {
+        Self {
+            input_channels,
+            output_channels,
+            gains: vec![0.0; input_channels * output_channels],
+        }
+    }
+
+    /// 入力チャンネル数を返す。
+    pub fn input_channels(&self) -> usize {
+        self.input_channels
+    }
+
+    /// 出力チャンネル数を返す。
+    pub fn output_channels(&self) -> usize {
+        self.output_channels
+    }
+
+    /// `in_i`入力チャンネルから`out_i`出力チャンネルに流すゲインを返す。
+    pub fn gain(&self, out_i: usize, in_i: usize) -> f32 {
+        self.gains[(out_i * self.input_channels) + in_i]
+    }
+
+    fn set_gain(&mut self, out_i: usize, in_i: usize, gain: f32) {
+        self.gains[(out_i * self.input_channels) + in_i] = gain;
+    }
+
+    /// 1フレーム分の`input`を変換して`output`に入れる。
+    pub fn apply(&self, input: &[f32], output: &mut [f32]) {
+        debug_assert!(input.len() >= self.input_channels);
+        debug_assert!(output.len() >= self.output_channels);
+
+        for (out_i, output_sample) in output.iter_mut().take(self.output_channels).enumerate() {
+            *output_sample = (0..self.input_channels).map(|in_i| self.gain(out_i, in_i) * input[in_i]).sum();
+        }
+    }
+}
+
+/// `position`のチャンネルを`output`配置のどのチャンネルにどのぐらいのゲインで流すかを返す。
+fn route_position(position: EChannelPosition, output: EChannelLayout) -> Vec<(EChannelPosition, f32)> {
+    use EChannelPosition::*;
+
+    if output.contains(position) {
+        return vec![(position, 1.0)];
+    }
+
+    // モノラルにするときはいったんステレオにしてから左右を混ぜる。
+    if output == EChannelLayout::Mono {
+        return route_position(position, EChannelLayout::Stereo)
+            .into_iter()
+            .map(|(_, gain)| (Mono, gain * 0.5))
+            .collect();
+    }
+
+    match position {
+        Mono => {
+            if output.contains(FrontCenter) {
+                vec![(FrontCenter, 1.0)]
+            } else {
+                vec![(FrontLeft, 1.0), (FrontRight, 1.0)]
+            }
+        }
+        FrontCenter => vec![(FrontLeft, MINUS_3DB), (FrontRight, MINUS_3DB)],
+        Lfe => vec![],
+        BackLeft if output.contains(SideLeft) => vec![(SideLeft, 1.0)],
+        BackRight if output.contains(SideRight) => vec![(SideRight, 1.0)],
+        SideLeft if output.contains(BackLeft) => vec![(BackLeft, 1.0)],
+        SideRight if output.contains(BackRight) => vec![(BackRight, 1.0)],
+        BackLeft | SideLeft => vec![(FrontLeft, MINUS_3DB)],
+        BackRight | SideRight => vec![(FrontRight, MINUS_3DB)],
+        FrontLeft | FrontRight => unreachable!("All layouts except mono have front left and right."),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
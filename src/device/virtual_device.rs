@@ -0,0 +1,182 @@
+use crate::wave::container::stream::WaveStreamWriter;
+use crate::wave::sample::UniformedSample;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufWriter};
+
+/// サウンドカードを使わずに、シミュレーションした時間でリングバッファを読み込む仮想デバイスの設定。
+///
+/// 読み込んだサンプルはメモリか、`file_name`のWAVファイル（16ビットLPCM）に書き込む。
+/// `scheduler`をプル型にすると毎フレーム同じ量だけ処理されるので、結果が毎回同じになる。
+/// プッシュ型の場合も実際の時間は使わずに、毎フレーム1周期分の時間だけ進めたことにして処理する。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct VirtualAudioDeviceSetting {
+    /// 読み込んだサンプルを書き込むWAVファイルのパス。
+    /// 指定しなければメモリに溜めて、[`AudioDevice::take_virtual_samples`](super::AudioDevice::take_virtual_samples)で取り出す。
+    #[serde(default)]
+    pub file_name: Option<String>,
+}
+
+/// [`VirtualAudioDevice`]が読み込んだサンプルの書き込み先
+#[derive(Debug)]
+enum EVirtualDeviceSink {
+    Memory {
+        samples: Vec<f32>,
+    },
+    Wav {
+        file: BufWriter<fs::File>,
+        writer: WaveStreamWriter,
+    },
+}
+
+/// 仮想デバイスのコールバックを1回処理した結果。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualCallbackResult {
+    /// コールバックが要求したサンプル数
+    pub required_samples: usize,
+    /// リングバッファから実際に読み込めたサンプル数
+    pub read_samples: usize,
+}
+
+/// ローレベルのデバイスの代わりに、[`super::AudioDevice`]のフレームの時間を足していって
+/// 1周期（ピリオド）分の時間が経つたびにコールバックを処理する仮想デバイス。
+#[derive(Debug)]
+pub struct VirtualAudioDevice {
+    channels: usize,
+    capture_channels: usize,
+    sample_rate: usize,
+    /// 1回のコールバックで処理するフレーム数
+    period_size: usize,
+    /// シミュレーションしている経過時間（秒）
+    elapsed_time: f64,
+    /// 今までコールバックで処理したフレーム数
+    processed_frame_count: usize,
+    sink: EVirtualDeviceSink,
+}
+
+impl VirtualAudioDevice {
+    /// 周期のフレーム数を指定しなかった時の値。
+    pub const DEFAULT_PERIOD_SIZE: usize = 512;
+
+    pub fn new(
+        setting: &VirtualAudioDeviceSetting,
+        channels: usize,
+        capture_channels: usize,
+        sample_rate: usize,
+        period_size: Option<u32>,
+    ) -> anyhow::Result<Self> {
+        let period_size = period_size.map_or(Self::DEFAULT_PERIOD_SIZE, |v| v as usize);
+        if period_size == 0 {
+            return Err(anyhow::anyhow!("Period size of virtual audio device must be bigger than 0."));
+        }
+
+        let sink = match &setting.file_name {
+            Some(file_name) if channels > 0 => {
+                let writer = WaveStreamWriter::new(sample_rate as u32, 16, channels)
+                    .ok_or_else(|| anyhow::anyhow!("Failed to create wave writer of virtual audio device."))?;
+                let file = fs::File::create(file_name)
+                    .map_err(|e| anyhow::anyhow!("Could not create {} for virtual audio device. {}", file_name, e))?;
+                let mut file = BufWriter::new(file);
+                writer.write_header(&mut file);
+                EVirtualDeviceSink::Wav { file, writer }
+            }
+            _ => EVirtualDeviceSink::Memory { samples: vec![] },
+        };
+
+        Ok(Self {
+            channels,
+            capture_channels,
+            sample_rate,
+            period_size,
+            elapsed_time: 0.0,
+            processed_frame_count: 0,
+            sink,
+        })
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn capture_channels(&self) -> usize {
+        self.capture_channels
+    }
+
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    /// コールバック1回で処理するフレーム数を返す。
+    pub fn period_size(&self) -> usize {
+        self.period_size
+    }
+
+    /// コールバック1回分の時間（秒）を返す。
+    pub fn period_time(&self) -> f64 {
+        self.period_size as f64 / self.sample_rate as f64
+    }
+
+    /// `frame_time`だけ時間を進めて、経った時間分のコールバックの数だけ`callback`を呼ぶ。
+    ///
+    /// `callback`には出力用のバッファと、入力デバイスから受け取ったことにするバッファが渡される。
+    /// 出力用のバッファを埋めて、読み込めたサンプル数を返すこと。
+    pub fn process<F>(&mut self, frame_time: f64, mut callback: F) -> Vec<VirtualCallbackResult>
+    where
+        F: FnMut(/*output:*/ &mut [f32], /*input:*/ &[f32]) -> usize,
+    {
+        // 時間を足していくと誤差が溜まるので、少しだけ余裕を持たせてフレーム数にする。
+        self.elapsed_time += frame_time;
+        let target_frame_count = (self.elapsed_time * self.sample_rate as f64 + 1e-6).floor() as usize;
+
+        let mut results = vec![];
+        let mut output = vec![0.0f32; self.period_size * self.channels];
+        let input = vec![0.0f32; self.period_size * self.capture_channels];
+        while self.processed_frame_count + self.period_size <= target_frame_count {
+            output.fill(0.0);
+            let read_samples = callback(&mut output, &input);
+            debug_assert!(read_samples <= output.len());
+
+            self.write_samples(&output);
+            self.processed_frame_count += self.period_size;
+            results.push(VirtualCallbackResult {
+                required_samples: output.len(),
+                read_samples,
+            });
+        }
+
+        results
+    }
+
+    /// メモリに溜まっているサンプルを全部取り出す。
+    /// WAVファイルに書き込んでいる場合は空のバッファを返す。
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        match &mut self.sink {
+            EVirtualDeviceSink::Memory { samples } => std::mem::take(samples),
+            EVirtualDeviceSink::Wav { .. } => vec![],
+        }
+    }
+
+    fn write_samples(&mut self, samples: &[f32]) {
+        match &mut self.sink {
+            EVirtualDeviceSink::Memory { samples: buffer } => buffer.extend_from_slice(samples),
+            EVirtualDeviceSink::Wav { file, writer } => {
+                let samples = samples.iter().map(|v| UniformedSample::from_f64(*v as f64)).collect::<Vec<_>>();
+                writer.write_frames(file, &samples);
+            }
+        }
+    }
+}
+
+impl Drop for VirtualAudioDevice {
+    fn drop(&mut self) {
+        // 書き込んだサイズでヘッダーを更新しておく。
+        if let EVirtualDeviceSink::Wav { file, writer } = &mut self.sink {
+            writer.write_header(file);
+            io::Write::flush(file).expect("Failed to flush file.");
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
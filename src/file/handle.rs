@@ -16,6 +16,15 @@ pub struct FileHandle {
     pub(super) is_internal: bool,
 }
 
+impl std::fmt::Debug for FileHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileHandle")
+            .field("id", &self.id)
+            .field("is_internal", &self.is_internal)
+            .finish()
+    }
+}
+
 impl PartialEq for FileHandle {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -50,8 +59,10 @@ impl Drop for FileHandle {
         // 内部用のハンドルなら何もしない。
         if self.is_internal { return; }
 
-        // 落とす。
-        self.v.upgrade().unwrap().lock().unwrap().handle_count -= 1;
+        // 落とす。エラーで先にシステムが解放されていれば何もしない。
+        if let Some(v) = self.v.upgrade() {
+            v.lock().unwrap().handle_count -= 1;
+        }
     }
 }
 
@@ -1,9 +1,9 @@
-mod handle;
+pub mod handle;
 mod writer;
 mod internal;
 pub mod reader;
 
-use crate::device::ESystemProcessResult;
+use crate::device::{replace_global, ESystemProcessResult};
 use crate::file::handle::FileHandle;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -13,14 +13,16 @@ use crate::file::internal::EInternalData;
 
 /// 24-12-31
 /// ファイルIO制御のシステム
-static SYSTEM: OnceLock<Arc<Mutex<FileIO>>> = OnceLock::new();
+///
+/// [`FileIO::cleanup()`]で`None`に戻すので、解放したあとにもう一度初期化できる。
+static SYSTEM: OnceLock<Mutex<Option<Arc<Mutex<FileIO>>>>> = OnceLock::new();
 
 /// システムアクセス用。
 /// デバイスの初期化時に登録される。
-/// WeakPtrなので解放はしなくてもいいかもしれない。
-static PROXY_ACCESSOR: OnceLock<FileIOProxyWeakPtr> = OnceLock::new();
+/// WeakPtrだけど、もう一度初期化した時に古いものが残らないように解放時に`None`に戻す。
+static PROXY_ACCESSOR: OnceLock<Mutex<Option<FileIOProxyWeakPtr>>> = OnceLock::new();
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct FileIOSetting {
 
 }
@@ -31,23 +33,24 @@ pub struct FileIO {
 }
 
 impl FileIO {
+    /// システムを初期化する。
+    /// 解放したあとは、また初期化できる。
     pub fn initialize(setting: FileIOSetting) -> FileIOProxyWeakPtr {
-        let original_proxy = {
-            assert!(SYSTEM.get().is_none());
+        assert!(Self::instance().is_none(), "FileIO is already initialized. Call cleanup() first.");
 
-            let _result = SYSTEM.set(Arc::new(Mutex::new(Self::new(setting))));
-            let system = SYSTEM.get().unwrap();
+        let (instance, original_proxy) = {
+            let system = Arc::new(Mutex::new(Self::new(setting)));
+            replace_global(&SYSTEM, Some(system.clone()));
             let weak_system = Arc::downgrade(&system);
 
             let original_proxy = FileIOProxy::new(weak_system);
-            original_proxy
+            (system, original_proxy)
         };
 
         // Proxyの登録
         let weak_proxy = Arc::downgrade(&original_proxy);
         {
             // Mutexがおそらく内部Internal Mutabilityを実装しているかと。
-            let instance = SYSTEM.get().expect("AudioDevice instance must be valid");
             let mut accessor = instance.lock().unwrap();
             debug_assert!(accessor.v.is_some());
 
@@ -57,11 +60,16 @@ impl FileIO {
         }
 
         // Proxyを返す。本体は絶対かえさない。
-        assert!(SYSTEM.get().is_some());
-        let _result = PROXY_ACCESSOR.set(weak_proxy.clone());
+        replace_global(&PROXY_ACCESSOR, Some(weak_proxy.clone()));
         weak_proxy
     }
 
+    /// 初期化したシステムの本体を返す。
+    /// まだ初期化してないか、もう解放したなら`None`を返す。
+    fn instance() -> Option<Arc<Mutex<FileIO>>> {
+        SYSTEM.get()?.lock().unwrap().clone()
+    }
+
     fn new(setting: FileIOSetting) -> FileIO {
         Self {
             v: Some(FileIOInternal::new(setting)),
@@ -70,18 +78,12 @@ impl FileIO {
 
     /// システムの対応。
     pub fn get_proxy() -> Option<FileIOProxyWeakPtr> {
-        // これは大丈夫か。。。。
-        match PROXY_ACCESSOR.get() {
-            None => None,
-            Some(v) => Some(v.clone()),
-        }
+        PROXY_ACCESSOR.get()?.lock().unwrap().clone()
     }
 
     /// Tick関数。
     pub fn post_process(_frame_time: f64) -> ESystemProcessResult {
-        assert!(SYSTEM.get().is_some());
-
-        let instance = SYSTEM.get().unwrap();
+        let instance = Self::instance().expect("FileIO instance must be valid");
         let mut instance = instance.lock().unwrap();
         debug_assert!(instance.v.is_some());
 
@@ -105,10 +107,12 @@ impl FileIO {
 
     /// システムを解放する。
     /// すべての関連処理が終わった後に解放すべき。
+    /// 解放したあとは、また[`Self::initialize`]で初期化できる。
     pub fn cleanup() {
-        assert!(SYSTEM.get().is_some());
-
-        if let Some(system) = SYSTEM.get() {
+        let system = replace_global(&SYSTEM, None);
+        assert!(system.is_some());
+        replace_global(&PROXY_ACCESSOR, None);
+        if let Some(system) = system {
             let mut system = system.lock().unwrap();
             system.v = None;
         }
@@ -7,6 +7,7 @@ pub mod wave;
 pub mod device;
 pub mod resample;
 pub mod file;
+pub mod midi;
 
 // ----------------------------------------------------------------------------
 // EOF
@@ -13,6 +13,7 @@ pub mod wave;
 pub mod device;
 pub mod resample;
 pub mod file;
+pub mod midi;
 
 fn main() -> anyhow::Result<()> {
     // @todo 24-12-05 後でParseを非同期で行うなど。
@@ -0,0 +1,283 @@
+use super::EMidiMessage;
+use std::io;
+
+/// ヘッダーチャンクのID
+const HEADER_CHUNK_ID: &[u8; 4] = b"MThd";
+/// トラックチャンクのID
+const TRACK_CHUNK_ID: &[u8; 4] = b"MTrk";
+/// テンポが指定されてない時の四分音符の長さ（マイクロ秒）。120BPM。
+const DEFAULT_TEMPO: u32 = 500_000;
+
+/// トラックの中の1つのイベント
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EMidiTrackEvent {
+    Message(EMidiMessage),
+    /// 四分音符の長さ（マイクロ秒）
+    Tempo(u32),
+    EndOfTrack,
+}
+
+/// トラックの中のイベントと、トラックの最初からのティック
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiTrackEvent {
+    pub tick: u64,
+    pub event: EMidiTrackEvent,
+}
+
+/// 時間（秒）に変換したメッセージ
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiTimedMessage {
+    pub time: f64,
+    pub message: EMidiMessage,
+}
+
+/// Standard MIDI File（フォーマット0と1）を読み込んだもの。
+///
+/// SysExや、テンポとトラックの終わり以外のメタイベントは読み飛ばす。
+#[derive(Debug, Clone)]
+pub struct MidiFile {
+    /// SMFのフォーマット。`0`か`1`。
+    pub format: u16,
+    /// 四分音符あたりのティック数
+    pub ticks_per_quarter: u16,
+    pub tracks: Vec<Vec<MidiTrackEvent>>,
+}
+
+impl MidiFile {
+    /// `reader`から全部読み込んで解析する。
+    pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
+    where
+        T: io::Read,
+    {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).ok()?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// `path`のファイルを全部読み込んで解析する。
+    pub fn from_path(path: &str) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path).map_err(|e| anyhow::anyhow!("Could not read MIDI file `{}`: {}", path, e))?;
+        Self::from_bytes(&bytes).ok_or_else(|| anyhow::anyhow!("`{}` is not a supported Standard MIDI File.", path))
+    }
+
+    /// ファイルの内容`bytes`を解析する。
+    /// 壊れているか、対応してないフォーマット（フォーマット2・SMPTEタイムコード）なら`None`を返す。
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut cursor = ByteCursor { bytes, position: 0 };
+
+        // ヘッダーチャンク
+        if cursor.read_bytes(4)? != HEADER_CHUNK_ID {
+            return None;
+        }
+        let header_length = cursor.read_u32()? as usize;
+        if header_length < 6 {
+            return None;
+        }
+        let header = cursor.read_bytes(header_length)?;
+        let format = u16::from_be_bytes([header[0], header[1]]);
+        let track_count = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let division = u16::from_be_bytes([header[4], header[5]]);
+        if format > 1 || (division & 0x8000) != 0 || division == 0 {
+            return None;
+        }
+
+        // トラックチャンク。知らないチャンクは飛ばす。
+        let mut tracks = Vec::with_capacity(track_count);
+        while tracks.len() < track_count {
+            let chunk_id = cursor.read_bytes(4)?;
+            let chunk_length = cursor.read_u32()? as usize;
+            let chunk = cursor.read_bytes(chunk_length)?;
+            if chunk_id == TRACK_CHUNK_ID {
+                tracks.push(Self::parse_track(chunk)?);
+            }
+        }
+
+        Some(Self {
+            format,
+            ticks_per_quarter: division,
+            tracks,
+        })
+    }
+
+    fn parse_track(bytes: &[u8]) -> Option<Vec<MidiTrackEvent>> {
+        let mut cursor = ByteCursor { bytes, position: 0 };
+        let mut events = vec![];
+        let mut tick = 0u64;
+        let mut running_status: Option<u8> = None;
+
+        while !cursor.is_end() {
+            tick += cursor.read_variable_length()? as u64;
+
+            let first = cursor.read_u8()?;
+            match first {
+                0xFF => {
+                    // メタイベント。SysExと同じでランニングステータスを取り消す。
+                    running_status = None;
+                    let meta_type = cursor.read_u8()?;
+                    let length = cursor.read_variable_length()? as usize;
+                    let data = cursor.read_bytes(length)?;
+                    match meta_type {
+                        0x51 if length == 3 => {
+                            let tempo = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+                            events.push(MidiTrackEvent {
+                                tick,
+                                event: EMidiTrackEvent::Tempo(tempo),
+                            });
+                        }
+                        0x2F => {
+                            events.push(MidiTrackEvent {
+                                tick,
+                                event: EMidiTrackEvent::EndOfTrack,
+                            });
+                            break;
+                        }
+                        _ => (),
+                    }
+                }
+                0xF0 | 0xF7 => {
+                    // SysExは読み飛ばす。
+                    let length = cursor.read_variable_length()? as usize;
+                    cursor.read_bytes(length)?;
+                    running_status = None;
+                }
+                0xF1..=0xFE => {
+                    // ファイルには本来入らないシステムコモン・リアルタイムメッセージは読み飛ばす。
+                    // リアルタイムメッセージはランニングステータスを変えない。
+                    let length = match first {
+                        0xF1 | 0xF3 => 1,
+                        0xF2 => 2,
+                        _ => 0,
+                    };
+                    cursor.read_bytes(length)?;
+                    if first < 0xF8 {
+                        running_status = None;
+                    }
+                }
+                _ => {
+                    // ランニングステータスなら最初のバイトはデータになる。
+                    let (status, first_data) = if first & 0x80 != 0 {
+                        (first, None)
+                    } else {
+                        (running_status?, Some(first))
+                    };
+                    running_status = Some(status);
+
+                    let length = EMidiMessage::data_length(status)?;
+                    let mut data = [0u8; 2];
+                    for (i, v) in data.iter_mut().take(length).enumerate() {
+                        *v = match (i, first_data) {
+                            (0, Some(first_data)) => first_data,
+                            _ => cursor.read_u8()?,
+                        };
+                    }
+
+                    if let Some(message) = EMidiMessage::from_bytes(status, &data[..length]) {
+                        events.push(MidiTrackEvent {
+                            tick,
+                            event: EMidiTrackEvent::Message(message),
+                        });
+                    }
+                }
+            }
+        }
+
+        Some(events)
+    }
+
+    /// 全トラックのメッセージをテンポを反映した時間順に並べて返す。
+    /// 同じティックのメッセージは、トラックの順番とトラックの中の順番を維持する。
+    pub fn timed_messages(&self) -> Vec<MidiTimedMessage> {
+        let mut result = vec![];
+        self.visit_timed_events(|time, event| {
+            if let EMidiTrackEvent::Message(message) = event {
+                result.push(MidiTimedMessage {
+                    time,
+                    message: *message,
+                });
+            }
+        });
+        result
+    }
+
+    /// 一番最後のイベント（トラックの終わりも含む）までの長さ（秒）を返す。
+    pub fn duration(&self) -> f64 {
+        let mut duration = 0.0f64;
+        self.visit_timed_events(|time, _| duration = duration.max(time));
+        duration
+    }
+
+    fn visit_timed_events<F>(&self, mut f: F)
+    where
+        F: FnMut(/*time:*/ f64, /*event:*/ &EMidiTrackEvent),
+    {
+        // フォーマット1ではテンポは最初のトラックにあるけど、全トラックに反映させる。
+        let mut events = self
+            .tracks
+            .iter()
+            .enumerate()
+            .flat_map(|(track_i, track)| {
+                track.iter().enumerate().map(move |(event_i, v)| (v.tick, track_i, event_i, v))
+            })
+            .collect::<Vec<_>>();
+        events.sort_by_key(|(tick, track_i, event_i, _)| (*tick, *track_i, *event_i));
+
+        let mut tempo = DEFAULT_TEMPO;
+        let mut last_tick = 0u64;
+        let mut last_time = 0.0f64;
+        for (tick, _, _, event) in events {
+            let seconds_per_tick = tempo as f64 * 1e-6 / self.ticks_per_quarter as f64;
+            last_time += (tick - last_tick) as f64 * seconds_per_tick;
+            last_tick = tick;
+
+            if let EMidiTrackEvent::Tempo(new_tempo) = event.event {
+                tempo = new_tempo;
+            }
+            f(last_time, &event.event);
+        }
+    }
+}
+
+/// バイト列を前から順番に読み込むためのもの。
+struct ByteCursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteCursor<'a> {
+    fn is_end(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let end = self.position.checked_add(length)?;
+        let result = self.bytes.get(self.position..end)?;
+        self.position = end;
+        Some(result)
+    }
+
+    fn read_u8(&mut self) -> Option<u8> {
+        Some(self.read_bytes(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        let bytes = self.read_bytes(4)?;
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// 可変長数値（最大4バイト）を読み込む。
+    fn read_variable_length(&mut self) -> Option<u32> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.read_u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,398 @@
+use super::sequencer::{parse_sequencer_events, SequencerPort, SEQUENCER_EVENT_SIZE};
+use super::EMidiMessage;
+use crate::device::replace_global;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// ライブのMIDI入力を受け取るシステム。
+/// 解放したあとにもう一度初期化できるように、`None`に戻せるようにしておく。
+static SYSTEM: OnceLock<Mutex<Option<Arc<Mutex<MidiInput>>>>> = OnceLock::new();
+
+/// システムアクセス用。
+/// WeakPtrだけど、もう一度初期化した時に古いものが残らないように解放時に`None`に戻す。
+static PROXY_ACCESSOR: OnceLock<Mutex<Option<MidiInputProxyWeakPtr>>> = OnceLock::new();
+
+/// 読み込みスレッドで一度に読み込むバイト数
+const READ_CHUNK_SIZE: usize = 256;
+/// FIFOの書き込み側が閉じられた時に、開き直すまで待つ時間
+const REOPEN_WAIT_TIME: Duration = Duration::from_millis(100);
+/// ALSAシーケンサーで一度に読み込むイベントの数
+const SEQUENCER_READ_EVENT_COUNT: usize = 64;
+/// ALSAシーケンサーでイベントを待つ時間。止める通知はこの間隔で確認する。
+const SEQUENCER_WAIT_TIME: Duration = Duration::from_millis(100);
+
+/// MIDI入力をどこから受け取るか。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum EMidiInputSource {
+    /// ALSAのrawmidiデバイス（`/dev/snd/midiC1D0`など）や、名前付きパイプ（FIFO）からMIDIのバイト列を読み込む。
+    #[serde(rename = "raw")]
+    Raw { path: String },
+    /// ALSAシーケンサーに`port_name`のポートを作って、つながったクライアントからイベントを受け取る。Linuxだけ対応する。
+    ///
+    /// `connect_from`に`"20:0"`のような`クライアント:ポート`を指定すると、そのポートからつなぐ。
+    /// 指定しなければ`aconnect`などで外からつなぐ。
+    #[serde(rename = "alsa_seq")]
+    AlsaSequencer {
+        port_name: String,
+        #[serde(default)]
+        connect_from: Option<String>,
+    },
+    /// 外部からは何も読み込まない。[`MidiInputProxy::send_message`]で送ったメッセージだけを流す。
+    #[serde(rename = "virtual")]
+    Virtual,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MidiInputSetting {
+    pub source: EMidiInputSource,
+}
+
+/// MIDI入力の処理構造体
+pub struct MidiInput {
+    v: Option<MidiInputInternal>,
+}
+
+impl MidiInput {
+    /// `setting`の入力を開いて、読み込みを始める。
+    /// 入力が開けないか、もう初期化されていればエラーを返す。
+    pub fn initialize(setting: MidiInputSetting) -> anyhow::Result<MidiInputProxyWeakPtr> {
+        if Self::instance().is_some() {
+            return Err(anyhow::anyhow!("MidiInput is already initialized. Call cleanup() first."));
+        }
+
+        // 読み込みスレッドで失敗しないように、ここで入力を開いておく。
+        let source = ERawSource::open(&setting.source)?;
+
+        let (instance, original_proxy) = {
+            let system = Arc::new(Mutex::new(Self::new()));
+            replace_global(&SYSTEM, Some(system.clone()));
+            let weak_system = Arc::downgrade(&system);
+
+            let original_proxy = MidiInputProxy::new(weak_system);
+            (system, original_proxy)
+        };
+
+        // Proxyの登録と、読み込みスレッドの開始。
+        let weak_proxy = Arc::downgrade(&original_proxy);
+        {
+            let mut accessor = instance.lock().unwrap();
+            debug_assert!(accessor.v.is_some());
+
+            let v = accessor.v.as_mut().unwrap();
+            v.original_proxy = Some(original_proxy);
+            if let Some(source) = source {
+                v.start_reader(source);
+            }
+        }
+
+        // Proxyを返す。本体は絶対かえさない。
+        replace_global(&PROXY_ACCESSOR, Some(weak_proxy.clone()));
+        Ok(weak_proxy)
+    }
+
+    /// 初期化したシステムの本体を返す。
+    /// まだ初期化してないか、もう解放したなら`None`を返す。
+    fn instance() -> Option<Arc<Mutex<MidiInput>>> {
+        SYSTEM.get()?.lock().unwrap().clone()
+    }
+
+    fn new() -> Self {
+        Self {
+            v: Some(MidiInputInternal::new()),
+        }
+    }
+
+    /// システムに接近できるプロキシーを取得する。
+    pub fn get_proxy() -> Option<MidiInputProxyWeakPtr> {
+        PROXY_ACCESSOR.get()?.lock().unwrap().clone()
+    }
+
+    /// システムを解放する。
+    /// すべての関連処理が終わった後に解放すべき。
+    ///
+    /// 読み込みスレッドは読み込み待ちでブロックしているかもしれないので、止める通知だけしてjoinはしない。
+    pub fn cleanup() {
+        let system = replace_global(&SYSTEM, None);
+        assert!(system.is_some());
+        replace_global(&PROXY_ACCESSOR, None);
+        if let Some(system) = system {
+            let mut system = system.lock().unwrap();
+            if let Some(v) = system.v.as_ref() {
+                v.stop_flag.store(true, Ordering::Release);
+            }
+            system.v = None;
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Proxy
+// ----------------------------------------------------------------------------
+
+pub struct MidiInputProxy {
+    /// システムに接近するための変数。
+    system: Weak<Mutex<MidiInput>>,
+}
+
+impl MidiInputProxy {
+    fn new(system: Weak<Mutex<MidiInput>>) -> MidiInputProxyPtr {
+        Arc::new(Mutex::new(Self { system }))
+    }
+
+    /// これから入ってくるメッセージを受け取るレシーバーを作る。
+    /// 各レシーバーは全部のメッセージを受け取る。
+    pub fn subscribe(&self) -> mpsc::Receiver<EMidiMessage> {
+        let system = self.system.upgrade().unwrap();
+        let system = system.lock().unwrap();
+        let system = system.v.as_ref().unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        system.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// 入力から受け取ったように`message`を全部のレシーバーに送る。
+    pub fn send_message(&self, message: EMidiMessage) {
+        let system = self.system.upgrade().unwrap();
+        let system = system.lock().unwrap();
+        let system = system.v.as_ref().unwrap();
+
+        broadcast(&system.subscribers, message);
+    }
+}
+
+type MidiInputProxyPtr = Arc<Mutex<MidiInputProxy>>;
+pub type MidiInputProxyWeakPtr = Weak<Mutex<MidiInputProxy>>;
+
+// ----------------------------------------------------------------------------
+// Internal
+// ----------------------------------------------------------------------------
+
+type SubscriberList = Arc<Mutex<Vec<mpsc::Sender<EMidiMessage>>>>;
+
+struct MidiInputInternal {
+    /// プロキシの親元。ほかのところでは全部Weakタイプで共有する。
+    #[allow(dead_code)]
+    original_proxy: Option<MidiInputProxyPtr>,
+    /// メッセージを受け取るレシーバーにつながっているセンダー。読み込みスレッドと共有する。
+    subscribers: SubscriberList,
+    /// 読み込みスレッドを止めるためのフラグ
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl MidiInputInternal {
+    fn new() -> Self {
+        Self {
+            original_proxy: None,
+            subscribers: Default::default(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// 開いた`source`から読み込むスレッドを立ち上げる。
+    fn start_reader(&self, source: ERawSource) {
+        let subscribers = self.subscribers.clone();
+        let stop_flag = self.stop_flag.clone();
+        thread::Builder::new()
+            .name("nz-midi-input".to_owned())
+            .spawn(move || read_raw_source(source, &subscribers, &stop_flag))
+            .expect("Failed to spawn midi input thread.");
+    }
+}
+
+/// [`EMidiInputSource`]の読み込み元
+enum ERawSource {
+    /// rawmidiデバイスや通常のファイル。開いたものをそのまま読む。
+    File { path: String, file: fs::File },
+    /// 名前付きパイプ。書き込み側が開くまで開けないので、読み込みスレッドで開く。
+    Fifo { path: String },
+    /// ALSAシーケンサーに作ったポート。
+    Sequencer(SequencerPort),
+}
+
+impl ERawSource {
+    /// `source`の読み込み元を開く。外部から読み込まないなら`None`を返す。
+    fn open(source: &EMidiInputSource) -> anyhow::Result<Option<Self>> {
+        let path = match source {
+            EMidiInputSource::Raw { path } => path.clone(),
+            EMidiInputSource::AlsaSequencer {
+                port_name,
+                connect_from,
+            } => {
+                let port = SequencerPort::open(port_name, connect_from.as_deref())?;
+                return Ok(Some(Self::Sequencer(port)));
+            }
+            EMidiInputSource::Virtual => return Ok(None),
+        };
+
+        let metadata =
+            fs::metadata(&path).map_err(|e| anyhow::anyhow!("Could not open midi input `{}`: {}", path, e))?;
+        if is_fifo(&metadata) {
+            return Ok(Some(Self::Fifo { path }));
+        }
+
+        let file = fs::File::open(&path).map_err(|e| anyhow::anyhow!("Could not open midi input `{}`: {}", path, e))?;
+        Ok(Some(Self::File { path, file }))
+    }
+}
+
+/// 名前付きパイプ（FIFO）か？
+fn is_fifo(metadata: &fs::Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        metadata.file_type().is_fifo()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// `message`を全部のレシーバーに送る。レシーバーが消えたセンダーはここで外す。
+fn broadcast(subscribers: &SubscriberList, message: EMidiMessage) {
+    subscribers.lock().unwrap().retain(|v| v.send(message).is_ok());
+}
+
+/// 読み込みスレッドの処理。
+/// FIFOは書き込み側が閉じられたら開き直して、それ以外は最後まで読んだら終わる。
+fn read_raw_source(source: ERawSource, subscribers: &SubscriberList, stop_flag: &AtomicBool) {
+    let mut parser = MidiStreamParser::new();
+    let mut buffer = [0u8; READ_CHUNK_SIZE];
+
+    let (path, mut opened_file, is_fifo) = match source {
+        ERawSource::File { path, file } => (path, Some(file), false),
+        ERawSource::Fifo { path } => (path, None, true),
+        ERawSource::Sequencer(port) => return read_sequencer(port, subscribers, stop_flag),
+    };
+    while !stop_flag.load(Ordering::Acquire) {
+        let mut file = match opened_file.take().map_or_else(|| fs::File::open(&path), Ok) {
+            Ok(v) => v,
+            Err(e) => {
+                // 途中で消されたりして開き直せなければ、パニックせずに読み込みをやめる。
+                eprintln!("Stopped reading midi input {}: {}", path, e);
+                return;
+            }
+        };
+
+        loop {
+            let read_size = match file.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(v) => v,
+            };
+            if stop_flag.load(Ordering::Acquire) {
+                return;
+            }
+
+            for byte in &buffer[..read_size] {
+                if let Some(message) = parser.push(*byte) {
+                    broadcast(subscribers, message);
+                }
+            }
+        }
+
+        if !is_fifo {
+            return;
+        }
+        thread::sleep(REOPEN_WAIT_TIME);
+    }
+}
+
+/// ALSAシーケンサーの読み込みスレッドの処理。
+/// イベントを待ちながら止める通知を確認して、止められるまで読み込む。
+fn read_sequencer(mut port: SequencerPort, subscribers: &SubscriberList, stop_flag: &AtomicBool) {
+    let mut buffer = vec![0u8; SEQUENCER_EVENT_SIZE * SEQUENCER_READ_EVENT_COUNT];
+    while !stop_flag.load(Ordering::Acquire) {
+        let read_size = match port.read(&mut buffer, SEQUENCER_WAIT_TIME) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Stopped reading midi input from ALSA sequencer: {}", e);
+                return;
+            }
+        };
+        if stop_flag.load(Ordering::Acquire) {
+            return;
+        }
+
+        for message in parse_sequencer_events(&buffer[..read_size]) {
+            broadcast(subscribers, message);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Parser
+// ----------------------------------------------------------------------------
+
+/// ライブで流れてくるMIDIのバイト列を1バイトずつ受け取ってメッセージにする。
+///
+/// ランニングステータスに対応する。リアルタイムメッセージ（`0xF8`以降）とSysExは読み飛ばす。
+#[derive(Debug, Default)]
+pub struct MidiStreamParser {
+    running_status: Option<u8>,
+    data: [u8; 2],
+    data_count: usize,
+    is_in_sysex: bool,
+}
+
+impl MidiStreamParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `byte`を受け取って、メッセージが完成したら返す。
+    pub fn push(&mut self, byte: u8) -> Option<EMidiMessage> {
+        match byte {
+            // リアルタイムメッセージは他のメッセージの間にも入るけど、状態は変えない。
+            0xF8..=0xFF => None,
+            0xF0 => {
+                self.is_in_sysex = true;
+                self.running_status = None;
+                None
+            }
+            0xF7 => {
+                self.is_in_sysex = false;
+                None
+            }
+            // システムコモンメッセージはランニングステータスを解除して、続くデータバイトは捨てる。
+            0xF1..=0xF6 => {
+                self.is_in_sysex = false;
+                self.running_status = None;
+                None
+            }
+            0x80..=0xEF => {
+                self.is_in_sysex = false;
+                self.running_status = Some(byte);
+                self.data_count = 0;
+                None
+            }
+            _ => {
+                if self.is_in_sysex {
+                    return None;
+                }
+                let status = self.running_status?;
+                let length = EMidiMessage::data_length(status)?;
+
+                self.data[self.data_count] = byte;
+                self.data_count += 1;
+                if self.data_count < length {
+                    return None;
+                }
+
+                self.data_count = 0;
+                EMidiMessage::from_bytes(status, &self.data[..length])
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
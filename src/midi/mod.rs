@@ -0,0 +1,118 @@
+pub mod file;
+pub mod input;
+pub mod sequencer;
+pub mod synth;
+
+/// ノート番号`69`（A4）の周波数
+const A4_FREQUENCY: f64 = 440.0;
+/// 周波数が[`A4_FREQUENCY`]になるノート番号
+const A4_KEY: u8 = 69;
+
+/// ノートやコントロールの変化を表すMIDIのチャンネルメッセージ。
+///
+/// シンセサイザーで使わないメッセージ（アフタータッチなど）は持たない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EMidiMessage {
+    NoteOff {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    /// `velocity`が`0`のノートオンは[`EMidiMessage::NoteOff`]として扱う。
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    /// `value`は`[-8192, 8191]`まで。
+    PitchBend {
+        channel: u8,
+        value: i16,
+    },
+}
+
+impl EMidiMessage {
+    /// チャンネルメッセージの`status`の後ろに続くデータバイトの数を返す。
+    /// チャンネルメッセージではない場合は`None`を返す。
+    pub fn data_length(status: u8) -> Option<usize> {
+        match status & 0xF0 {
+            0x80 | 0x90 | 0xA0 | 0xB0 | 0xE0 => Some(2),
+            0xC0 | 0xD0 => Some(1),
+            _ => None,
+        }
+    }
+
+    /// `status`とデータバイトからメッセージを作る。
+    /// 使わないメッセージか、データバイトが足りなければ`None`を返す。
+    pub fn from_bytes(status: u8, data: &[u8]) -> Option<Self> {
+        let length = Self::data_length(status)?;
+        if data.len() < length {
+            return None;
+        }
+
+        let channel = status & 0x0F;
+        match status & 0xF0 {
+            0x80 => Some(Self::NoteOff {
+                channel,
+                key: data[0] & 0x7F,
+                velocity: data[1] & 0x7F,
+            }),
+            0x90 if (data[1] & 0x7F) == 0 => Some(Self::NoteOff {
+                channel,
+                key: data[0] & 0x7F,
+                velocity: 0,
+            }),
+            0x90 => Some(Self::NoteOn {
+                channel,
+                key: data[0] & 0x7F,
+                velocity: data[1] & 0x7F,
+            }),
+            0xB0 => Some(Self::ControlChange {
+                channel,
+                controller: data[0] & 0x7F,
+                value: data[1] & 0x7F,
+            }),
+            0xC0 => Some(Self::ProgramChange {
+                channel,
+                program: data[0] & 0x7F,
+            }),
+            0xE0 => {
+                let value = (((data[1] & 0x7F) as i16) << 7) | ((data[0] & 0x7F) as i16);
+                Some(Self::PitchBend {
+                    channel,
+                    value: value - 8192,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// メッセージのチャンネル`[0, 15]`を返す。
+    pub fn channel(&self) -> u8 {
+        match self {
+            Self::NoteOff { channel, .. }
+            | Self::NoteOn { channel, .. }
+            | Self::ControlChange { channel, .. }
+            | Self::ProgramChange { channel, .. }
+            | Self::PitchBend { channel, .. } => *channel,
+        }
+    }
+}
+
+/// 平均律でノート番号`key`の周波数を返す。
+pub fn key_to_frequency(key: u8) -> f64 {
+    A4_FREQUENCY * 2f64.powf((key as f64 - A4_KEY as f64) / 12.0)
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,349 @@
+use super::EMidiMessage;
+
+/// カーネルから読み込む`struct snd_seq_event`のバイト数
+pub const SEQUENCER_EVENT_SIZE: usize = 28;
+
+/// イベントの種類（`SNDRV_SEQ_EVENT_*`）
+const EVENT_NOTEON: u8 = 6;
+const EVENT_NOTEOFF: u8 = 7;
+const EVENT_CONTROLLER: u8 = 10;
+const EVENT_PGMCHANGE: u8 = 11;
+const EVENT_PITCHBEND: u8 = 13;
+/// 受け取るイベントの種類。これ以外はカーネルで捨ててもらう。
+const RECEIVE_EVENTS: [u8; 5] = [EVENT_NOTEON, EVENT_NOTEOFF, EVENT_CONTROLLER, EVENT_PGMCHANGE, EVENT_PITCHBEND];
+
+/// イベントのフラグのうち、データの長さの種類を表すビット
+const EVENT_LENGTH_MASK: u8 = 3 << 2;
+/// データがイベントの後ろに続く可変長のイベント
+const EVENT_LENGTH_VARIABLE: u8 = 1 << 2;
+/// 可変長のデータの長さのうち、長さではないビット
+const EXT_MASK: u32 = 0xC000_0000;
+
+/// イベントの中で、データ（`data`）が始まる位置
+const EVENT_DATA_OFFSET: usize = 16;
+
+/// ALSAシーケンサーから読み込んだイベントのバイト列をメッセージにする。
+///
+/// カーネルから1回で読み込んだバイト列をそのまま渡す。使わないイベントは読み飛ばす。
+pub fn parse_sequencer_events(bytes: &[u8]) -> Vec<EMidiMessage> {
+    let mut result = vec![];
+    let mut offset = 0;
+    while offset + SEQUENCER_EVENT_SIZE <= bytes.len() {
+        let event = &bytes[offset..(offset + SEQUENCER_EVENT_SIZE)];
+        offset += SEQUENCER_EVENT_SIZE;
+
+        // 可変長のデータはイベントのサイズに揃えて続くので、その分も飛ばす。
+        if (event[1] & EVENT_LENGTH_MASK) == EVENT_LENGTH_VARIABLE {
+            let length = (read_u32(event, EVENT_DATA_OFFSET) & !EXT_MASK) as usize;
+            offset += length.div_ceil(SEQUENCER_EVENT_SIZE) * SEQUENCER_EVENT_SIZE;
+            continue;
+        }
+
+        if let Some(message) = convert_event(event) {
+            result.push(message);
+        }
+    }
+
+    result
+}
+
+/// 1つのイベントをメッセージにする。使わないイベントは`None`を返す。
+fn convert_event(event: &[u8]) -> Option<EMidiMessage> {
+    let data = &event[EVENT_DATA_OFFSET..];
+    let channel = data[0] & 0x0F;
+    match event[0] {
+        EVENT_NOTEON => EMidiMessage::from_bytes(0x90 | channel, &data[1..3]),
+        EVENT_NOTEOFF => EMidiMessage::from_bytes(0x80 | channel, &data[1..3]),
+        EVENT_CONTROLLER => {
+            let param = read_u32(data, 4) as u8;
+            let value = read_u32(data, 8) as u8;
+            EMidiMessage::from_bytes(0xB0 | channel, &[param, value])
+        }
+        EVENT_PGMCHANGE => EMidiMessage::from_bytes(0xC0 | channel, &[read_u32(data, 8) as u8]),
+        EVENT_PITCHBEND => {
+            // シーケンサーでは`[-8192, 8191]`の値で来るので、14ビットのデータバイトに戻す。
+            let value = (read_u32(data, 8) as i32 + 8192).clamp(0, 0x3FFF);
+            EMidiMessage::from_bytes(0xE0 | channel, &[(value & 0x7F) as u8, (value >> 7) as u8])
+        }
+        _ => None,
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes(bytes[offset..(offset + 4)].try_into().unwrap())
+}
+
+/// `"20:0"`のような`クライアント:ポート`のアドレスを読み込む。
+fn parse_address(address: &str) -> anyhow::Result<(u8, u8)> {
+    let parse = || {
+        let (client, port) = address.split_once(':')?;
+        Some((client.trim().parse().ok()?, port.trim().parse().ok()?))
+    };
+    parse().ok_or_else(|| anyhow::anyhow!("`connect_from` must be `client:port` like `20:0`, but `{}`.", address))
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) use linux::SequencerPort;
+
+/// Linux以外ではALSAシーケンサーが使えないので、開こうとしたらエラーを返す。
+#[cfg(not(target_os = "linux"))]
+pub(crate) struct SequencerPort;
+
+#[cfg(not(target_os = "linux"))]
+impl SequencerPort {
+    pub(crate) fn open(_port_name: &str, connect_from: Option<&str>) -> anyhow::Result<Self> {
+        if let Some(address) = connect_from {
+            parse_address(address)?;
+        }
+        Err(anyhow::anyhow!("ALSA sequencer midi input is only supported on Linux."))
+    }
+
+    pub(crate) fn read(&mut self, _buffer: &mut [u8], _timeout: std::time::Duration) -> std::io::Result<usize> {
+        Ok(0)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Linux
+// ----------------------------------------------------------------------------
+
+/// ALSAシーケンサーのカーネルインターフェース（`/dev/snd/seq`）を直接使う。
+/// 構造体はカーネルの`sound/asequencer.h`と同じ並びにする。
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{parse_address, RECEIVE_EVENTS};
+    use std::fs;
+    use std::io::{self, Read};
+    use std::mem::size_of;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+    use std::time::Duration;
+
+    /// シーケンサーのデバイス
+    const SEQUENCER_PATH: &str = "/dev/snd/seq";
+
+    /// 作ったクライアントの名前
+    const CLIENT_NAME: &str = "soundprog";
+    /// ユーザーのクライアント（`SNDRV_SEQ_CLIENT_USER`）
+    const CLIENT_TYPE_USER: i32 = 1;
+    /// `event_filter`で受け取るイベントを絞る（`SNDRV_SEQ_FILTER_USE_EVENT`）
+    const FILTER_USE_EVENT: u32 = 1 << 31;
+
+    /// ほかのクライアントから書き込める（`SNDRV_SEQ_PORT_CAP_WRITE | SNDRV_SEQ_PORT_CAP_SUBS_WRITE`）
+    const PORT_CAPABILITY: u32 = (1 << 1) | (1 << 6);
+    /// 一般的なMIDIのアプリケーションのポート（`SNDRV_SEQ_PORT_TYPE_MIDI_GENERIC | SNDRV_SEQ_PORT_TYPE_APPLICATION`）
+    const PORT_TYPE: u32 = (1 << 1) | (1 << 20);
+
+    const IOCTL_CLIENT_ID: u32 = ioctl_code(IOCTL_READ, 0x01, size_of::<i32>());
+    const IOCTL_GET_CLIENT_INFO: u32 = ioctl_code(IOCTL_READ | IOCTL_WRITE, 0x10, size_of::<ClientInfo>());
+    const IOCTL_SET_CLIENT_INFO: u32 = ioctl_code(IOCTL_WRITE, 0x11, size_of::<ClientInfo>());
+    const IOCTL_CREATE_PORT: u32 = ioctl_code(IOCTL_READ | IOCTL_WRITE, 0x20, size_of::<PortInfo>());
+    const IOCTL_SUBSCRIBE_PORT: u32 = ioctl_code(IOCTL_WRITE, 0x30, size_of::<PortSubscribe>());
+
+    const IOCTL_WRITE: u32 = 1;
+    const IOCTL_READ: u32 = 2;
+
+    /// `_IOC(direction, 'S', number, size)`
+    const fn ioctl_code(direction: u32, number: u32, size: usize) -> u32 {
+        (direction << 30) | ((size as u32) << 16) | ((b'S' as u32) << 8) | number
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct Address {
+        client: u8,
+        port: u8,
+    }
+
+    #[repr(C)]
+    struct ClientInfo {
+        client: i32,
+        client_type: i32,
+        name: [u8; 64],
+        filter: u32,
+        multicast_filter: [u8; 8],
+        event_filter: [u8; 32],
+        num_ports: i32,
+        event_lost: i32,
+        card: i32,
+        pid: i32,
+        reserved: [u8; 56],
+    }
+
+    #[repr(C)]
+    struct PortInfo {
+        addr: Address,
+        name: [u8; 64],
+        capability: u32,
+        port_type: u32,
+        midi_channels: i32,
+        midi_voices: i32,
+        synth_voices: i32,
+        read_use: i32,
+        write_use: i32,
+        kernel: *mut libc::c_void,
+        flags: u32,
+        time_queue: u8,
+        reserved: [u8; 59],
+    }
+
+    #[repr(C)]
+    struct PortSubscribe {
+        sender: Address,
+        dest: Address,
+        voices: u32,
+        flags: u32,
+        queue: u8,
+        pad: [u8; 3],
+        reserved: [u8; 64],
+    }
+
+    /// `name`を最後に`0`が残るように`buffer`に書き込む。
+    fn write_name(buffer: &mut [u8; 64], name: &str) {
+        let length = name.len().min(buffer.len() - 1);
+        buffer.fill(0);
+        buffer[..length].copy_from_slice(&name.as_bytes()[..length]);
+    }
+
+    /// `ioctl`を呼んで、失敗したら`errno`のエラーを返す。
+    ///
+    /// # Safety
+    /// `value`は`request`が期待する構造体でなければならない。
+    unsafe fn ioctl<T>(file: &fs::File, request: u32, value: &mut T) -> io::Result<()> {
+        if libc::ioctl(file.as_raw_fd(), request as _, value as *mut T) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// ALSAシーケンサーに作った、書き込みを受け取るポート
+    pub(crate) struct SequencerPort {
+        file: fs::File,
+    }
+
+    impl SequencerPort {
+        /// シーケンサーを開いて`port_name`のポートを作る。
+        /// `connect_from`があれば、そのアドレスのポートからつなぐ。
+        pub(crate) fn open(port_name: &str, connect_from: Option<&str>) -> anyhow::Result<Self> {
+            let sender = connect_from.map(parse_address).transpose()?;
+
+            // 読み込みスレッドで止める通知を確認できるように、ブロックしないで開く。
+            let file = fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(SEQUENCER_PATH)
+                .map_err(|e| anyhow::anyhow!("Could not open midi input `{}`: {}", SEQUENCER_PATH, e))?;
+
+            let port = Self::create_port(&file, port_name)
+                .map_err(|e| anyhow::anyhow!("Could not create ALSA sequencer port `{}`: {}", port_name, e))?;
+            if let Some(sender) = sender {
+                Self::connect(&file, sender, port).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Could not connect ALSA sequencer port `{}` to `{}:{}`: {}",
+                        port_name,
+                        sender.0,
+                        sender.1,
+                        e
+                    )
+                })?;
+            }
+
+            Ok(Self { file })
+        }
+
+        /// クライアントの設定をして、ポートを作る。作ったポートのアドレスを返す。
+        fn create_port(file: &fs::File, port_name: &str) -> io::Result<Address> {
+            let mut client = 0i32;
+            unsafe { ioctl(file, IOCTL_CLIENT_ID, &mut client)? };
+
+            // 名前を付けて、メッセージにならないイベントは受け取らないようにする。
+            let mut info = ClientInfo {
+                client,
+                client_type: CLIENT_TYPE_USER,
+                name: [0; 64],
+                filter: 0,
+                multicast_filter: [0; 8],
+                event_filter: [0; 32],
+                num_ports: 0,
+                event_lost: 0,
+                card: 0,
+                pid: 0,
+                reserved: [0; 56],
+            };
+            unsafe { ioctl(file, IOCTL_GET_CLIENT_INFO, &mut info)? };
+            write_name(&mut info.name, CLIENT_NAME);
+            info.filter |= FILTER_USE_EVENT;
+            info.event_filter = [0; 32];
+            for event in RECEIVE_EVENTS {
+                info.event_filter[(event / 8) as usize] |= 1 << (event % 8);
+            }
+            unsafe { ioctl(file, IOCTL_SET_CLIENT_INFO, &mut info)? };
+
+            let mut port = PortInfo {
+                addr: Address {
+                    client: client as u8,
+                    port: 0,
+                },
+                name: [0; 64],
+                capability: PORT_CAPABILITY,
+                port_type: PORT_TYPE,
+                midi_channels: 16,
+                midi_voices: 0,
+                synth_voices: 0,
+                read_use: 0,
+                write_use: 0,
+                kernel: std::ptr::null_mut(),
+                flags: 0,
+                time_queue: 0,
+                reserved: [0; 59],
+            };
+            write_name(&mut port.name, port_name);
+            unsafe { ioctl(file, IOCTL_CREATE_PORT, &mut port)? };
+            Ok(port.addr)
+        }
+
+        /// `sender`のポートから`dest`のポートにつなぐ。
+        fn connect(file: &fs::File, sender: (u8, u8), dest: Address) -> io::Result<()> {
+            let mut subscribe = PortSubscribe {
+                sender: Address {
+                    client: sender.0,
+                    port: sender.1,
+                },
+                dest,
+                voices: 0,
+                flags: 0,
+                queue: 0,
+                pad: [0; 3],
+                reserved: [0; 64],
+            };
+            unsafe { ioctl(file, IOCTL_SUBSCRIBE_PORT, &mut subscribe) }
+        }
+
+        /// 最大`timeout`まで待って、届いたイベントを`buffer`に読み込む。
+        /// 何も届かなければ`0`を返す。
+        pub(crate) fn read(&mut self, buffer: &mut [u8], timeout: Duration) -> io::Result<usize> {
+            let mut poll_fd = libc::pollfd {
+                fd: self.file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis() as libc::c_int) } < 0 {
+                let error = io::Error::last_os_error();
+                return match error.kind() {
+                    io::ErrorKind::Interrupted => Ok(0),
+                    _ => Err(error),
+                };
+            }
+
+            match self.file.read(buffer) {
+                Ok(v) => Ok(v),
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted) => Ok(0),
+                Err(e) => Err(e),
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
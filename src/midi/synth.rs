@@ -0,0 +1,365 @@
+use super::file::MidiTimedMessage;
+use super::{key_to_frequency, EMidiMessage};
+use crate::wave::envelope::AdsrEnvelope;
+use crate::wave::sample::UniformedSample;
+use crate::wave::sine::emitter::{EOscillatorAntialias, SineUnitSampleEmitter};
+use serde::{Deserialize, Serialize};
+
+/// MIDIのチャンネル数
+const CHANNEL_COUNT: usize = 16;
+/// パンのコントロールチェンジ番号
+const CC_PAN: u8 = 10;
+/// オールサウンドオフのコントロールチェンジ番号
+const CC_ALL_SOUND_OFF: u8 = 120;
+/// オールノートオフのコントロールチェンジ番号
+const CC_ALL_NOTES_OFF: u8 = 123;
+/// ピッチベンドを最大にした時に変わる音程（半音）
+const PITCH_BEND_RANGE: f64 = 2.0;
+
+/// ボイスに使うオシレーターの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EMidiVoiceWaveform {
+    #[default]
+    #[serde(rename = "sine")]
+    Sine,
+    #[serde(rename = "saw")]
+    Saw,
+    #[serde(rename = "square")]
+    Square,
+    #[serde(rename = "triangle")]
+    Triangle,
+}
+
+/// [`MidiSynthesizer`]の設定
+#[derive(Debug, Clone)]
+pub struct MidiSynthSetting {
+    pub waveform: EMidiVoiceWaveform,
+    /// 矩形波の場合のデューティー比`[0, 1]`
+    pub duty_rate: f64,
+    /// ノコギリ波・矩形波・三角波のエイリアシングの抑え方
+    pub antialias: EOscillatorAntialias,
+    /// 全体の振幅`[0, 1]`
+    pub intensity: f64,
+    pub envelope: AdsrEnvelope,
+    /// 同時に鳴らせるボイスの最大数
+    pub polyphony: usize,
+    /// ベロシティを振幅に反映する割合`[0, 1]`。
+    /// `0`ならベロシティに関係なく同じ振幅で、`1`ならベロシティに比例する。
+    pub velocity_sensitivity: f64,
+    pub sample_rate: usize,
+}
+
+/// 1つのノートを鳴らしているボイス
+#[derive(Debug)]
+struct MidiVoice {
+    channel: u8,
+    key: u8,
+    /// ベロシティから計算した振幅
+    velocity_gain: f64,
+    emitter: SineUnitSampleEmitter,
+    /// ノートオンから経った時間（秒）
+    note_on_elapsed_time: f64,
+    /// リリースを始めた時の振幅と、リリースから経った時間（秒）
+    release: Option<(f64, f64)>,
+    /// ボイスを鳴らし始めた順番。古いボイスから奪うために使う。
+    order: u64,
+}
+
+impl MidiVoice {
+    /// 今のエンベロープの振幅を返す。
+    fn envelope_gain(&self, envelope: &AdsrEnvelope) -> f64 {
+        match self.release {
+            None => envelope.note_on_gain(self.note_on_elapsed_time),
+            Some((from_gain, elapsed_time)) => envelope.release_gain(from_gain, elapsed_time),
+        }
+    }
+
+    fn release(&mut self, envelope: &AdsrEnvelope) {
+        if self.release.is_none() {
+            self.release = Some((self.envelope_gain(envelope), 0.0));
+        }
+    }
+
+    fn is_finished(&self, envelope: &AdsrEnvelope) -> bool {
+        match self.release {
+            None => false,
+            Some((_, elapsed_time)) => envelope.is_release_finished(elapsed_time),
+        }
+    }
+}
+
+/// [`MidiSynthesizer`]が出力したバッファ
+#[derive(Debug, Default)]
+pub struct MidiSynthOutput {
+    /// パンを反映しないモノラルのバッファ
+    pub mono: Vec<UniformedSample>,
+    pub left: Vec<UniformedSample>,
+    pub right: Vec<UniformedSample>,
+}
+
+impl MidiSynthOutput {
+    fn append(&mut self, other: MidiSynthOutput) {
+        self.mono.extend(other.mono);
+        self.left.extend(other.left);
+        self.right.extend(other.right);
+    }
+}
+
+/// MIDIメッセージでオシレーターのボイスを鳴らして、ADSRエンベロープをかけるシンセサイザー。
+///
+/// プログラムチェンジは今は無視する。
+#[derive(Debug)]
+pub struct MidiSynthesizer {
+    setting: MidiSynthSetting,
+    voices: Vec<MidiVoice>,
+    /// チャンネルごとのパン`[-1, 1]`
+    channel_pans: [f64; CHANNEL_COUNT],
+    /// チャンネルごとのピッチベンド（半音）
+    channel_pitch_bends: [f64; CHANNEL_COUNT],
+    next_voice_order: u64,
+}
+
+impl MidiSynthesizer {
+    pub fn new(setting: MidiSynthSetting) -> Self {
+        assert!(setting.polyphony > 0, "Polyphony must be bigger than 0.");
+        assert!(setting.sample_rate > 0);
+
+        Self {
+            setting,
+            voices: vec![],
+            channel_pans: [0.0; CHANNEL_COUNT],
+            channel_pitch_bends: [0.0; CHANNEL_COUNT],
+            next_voice_order: 0,
+        }
+    }
+
+    /// 鳴っている（リリース中も含む）ボイスの数を返す。
+    pub fn active_voice_count(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// `message`を反映する。
+    pub fn handle_message(&mut self, message: &EMidiMessage) {
+        match *message {
+            EMidiMessage::NoteOn { channel, key, velocity } => self.note_on(channel, key, velocity),
+            EMidiMessage::NoteOff { channel, key, .. } => self.note_off(channel, key),
+            EMidiMessage::ControlChange {
+                channel,
+                controller,
+                value,
+            } => match controller {
+                CC_PAN => {
+                    self.channel_pans[channel as usize % CHANNEL_COUNT] =
+                        ((value as f64 - 64.0) / 63.0).clamp(-1.0, 1.0);
+                }
+                CC_ALL_SOUND_OFF => self.voices.retain(|v| v.channel != channel),
+                CC_ALL_NOTES_OFF => {
+                    let envelope = self.setting.envelope;
+                    self.voices
+                        .iter_mut()
+                        .filter(|v| v.channel == channel)
+                        .for_each(|v| v.release(&envelope));
+                }
+                _ => (),
+            },
+            EMidiMessage::PitchBend { channel, value } => {
+                // 鳴っているボイスも位相を保ったまま周波数を変える。
+                let pitch_bend = (value as f64 / 8192.0) * PITCH_BEND_RANGE;
+                self.channel_pitch_bends[channel as usize % CHANNEL_COUNT] = pitch_bend;
+                self.voices.iter_mut().filter(|v| v.channel == channel).for_each(|v| {
+                    v.emitter.set_frequency(bent_frequency(v.key, pitch_bend));
+                });
+            }
+            EMidiMessage::ProgramChange { .. } => (),
+        }
+    }
+
+    /// 全部のボイスをリリースする。
+    pub fn release_all(&mut self) {
+        let envelope = self.setting.envelope;
+        self.voices.iter_mut().for_each(|v| v.release(&envelope));
+    }
+
+    fn note_on(&mut self, channel: u8, key: u8, velocity: u8) {
+        let envelope = self.setting.envelope;
+
+        // 同じノートがまだ鳴っていたらリリースして、新しいボイスで鳴らし直す。
+        self.voices
+            .iter_mut()
+            .filter(|v| v.channel == channel && v.key == key)
+            .for_each(|v| v.release(&envelope));
+
+        // ボイスが足りなければ、リリース中のボイスの中で一番古いものを優先して奪う。
+        if self.voices.len() >= self.setting.polyphony {
+            let stolen_i = self
+                .voices
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, v)| (v.release.is_none(), v.order))
+                .map(|(i, _)| i)
+                .unwrap();
+            self.voices.remove(stolen_i);
+        }
+
+        let frequency = bent_frequency(key, self.channel_pitch_bends[channel as usize % CHANNEL_COUNT]);
+        let sample_rate = self.setting.sample_rate;
+        let mut emitter = match self.setting.waveform {
+            EMidiVoiceWaveform::Sine => SineUnitSampleEmitter::new_sine(frequency, 0.0, 1.0, sample_rate),
+            EMidiVoiceWaveform::Saw => SineUnitSampleEmitter::new_sawtooth(frequency, 0.0, 1.0, sample_rate),
+            EMidiVoiceWaveform::Square => {
+                SineUnitSampleEmitter::new_square(frequency, self.setting.duty_rate, 0.0, 1.0, sample_rate)
+            }
+            EMidiVoiceWaveform::Triangle => SineUnitSampleEmitter::new_triangle(frequency, 0.0, 1.0, sample_rate),
+        };
+        emitter.set_antialias(self.setting.antialias);
+
+        let sensitivity = self.setting.velocity_sensitivity.clamp(0.0, 1.0);
+        let velocity_gain = (1.0 - sensitivity) + sensitivity * (velocity as f64 / 127.0);
+        self.voices.push(MidiVoice {
+            channel,
+            key,
+            velocity_gain,
+            emitter,
+            note_on_elapsed_time: 0.0,
+            release: None,
+            order: self.next_voice_order,
+        });
+        self.next_voice_order += 1;
+    }
+
+    fn note_off(&mut self, channel: u8, key: u8) {
+        let envelope = self.setting.envelope;
+        self.voices
+            .iter_mut()
+            .filter(|v| v.channel == channel && v.key == key)
+            .for_each(|v| v.release(&envelope));
+    }
+
+    /// `frame_count`フレーム分のサンプルを生成する。
+    /// ステレオのパンはバランス式で、真ん中ならモノラルと同じ振幅になる。
+    pub fn next_frames(&mut self, frame_count: usize) -> MidiSynthOutput {
+        let mut mono = vec![0.0f64; frame_count];
+        let mut left = vec![0.0f64; frame_count];
+        let mut right = vec![0.0f64; frame_count];
+
+        let envelope = self.setting.envelope;
+        let unit_time = (self.setting.sample_rate as f64).recip();
+        for voice in &mut self.voices {
+            let pan = self.channel_pans[voice.channel as usize % CHANNEL_COUNT];
+            let left_gain = (1.0 - pan).min(1.0);
+            let right_gain = (1.0 + pan).min(1.0);
+
+            for frame_i in 0..frame_count {
+                let gain = voice.envelope_gain(&envelope) * voice.velocity_gain * self.setting.intensity;
+                let value = voice.emitter.next_sample().to_f64() * gain;
+                mono[frame_i] += value;
+                left[frame_i] += value * left_gain;
+                right[frame_i] += value * right_gain;
+
+                match voice.release.as_mut() {
+                    None => voice.note_on_elapsed_time += unit_time,
+                    Some((_, elapsed_time)) => *elapsed_time += unit_time,
+                }
+            }
+        }
+        self.voices.retain(|v| !v.is_finished(&envelope));
+
+        let convert = |buffer: Vec<f64>| buffer.into_iter().map(UniformedSample::from_f64).collect::<Vec<_>>();
+        MidiSynthOutput {
+            mono: convert(mono),
+            left: convert(left),
+            right: convert(right),
+        }
+    }
+}
+
+/// ノート番号`key`を`pitch_bend`半音だけずらした周波数を返す。
+fn bent_frequency(key: u8, pitch_bend: f64) -> f64 {
+    key_to_frequency(key) * 2f64.powf(pitch_bend / 12.0)
+}
+
+/// 時間順に並んだメッセージを、サンプル単位のタイミングで[`MidiSynthesizer`]に流して再生する。
+#[derive(Debug)]
+pub struct MidiSequencePlayer {
+    synthesizer: MidiSynthesizer,
+    messages: Vec<MidiTimedMessage>,
+    /// 次に処理するメッセージのインデックス
+    next_message_i: usize,
+    /// 今まで生成したフレーム数
+    elapsed_frame_count: usize,
+    /// 曲が終わるフレームの位置。ここで鳴っているボイスを全部リリースする。
+    end_frame_i: usize,
+    /// 曲が終わって全部のボイスをリリースしたか
+    is_ended: bool,
+}
+
+impl MidiSequencePlayer {
+    /// `messages`は時間順に並んでいること。
+    /// `duration`（秒）が過ぎたら、ノートオフがないノートも含めて全部のボイスをリリースする。
+    pub fn new(setting: MidiSynthSetting, messages: Vec<MidiTimedMessage>, duration: f64) -> Self {
+        debug_assert!(messages.windows(2).all(|v| v[0].time <= v[1].time));
+
+        // 最後のメッセージより前に終わらないようにする。
+        let sample_rate = setting.sample_rate as f64;
+        let end_time = messages.last().map_or(0.0, |v| v.time).max(duration);
+        Self {
+            synthesizer: MidiSynthesizer::new(setting),
+            messages,
+            next_message_i: 0,
+            elapsed_frame_count: 0,
+            end_frame_i: (end_time * sample_rate).round() as usize,
+            is_ended: false,
+        }
+    }
+
+    /// サンプルレートを返す。
+    pub fn sample_rate(&self) -> usize {
+        self.synthesizer.setting.sample_rate
+    }
+
+    /// 曲が終わって、全部のボイスのリリースが終わったか？
+    pub fn is_finished(&self) -> bool {
+        self.is_ended && self.next_message_i >= self.messages.len() && self.synthesizer.active_voice_count() == 0
+    }
+
+    /// `frame_count`フレーム分のサンプルを生成する。
+    /// 途中にあるメッセージはそのタイミングのサンプルから反映する。
+    pub fn next_frames(&mut self, frame_count: usize) -> MidiSynthOutput {
+        let sample_rate = self.synthesizer.setting.sample_rate as f64;
+        let end_frame_count = self.elapsed_frame_count + frame_count;
+
+        let mut output = MidiSynthOutput::default();
+        while let Some(message) = self.messages.get(self.next_message_i) {
+            let message_frame_i = (message.time * sample_rate).round() as usize;
+            if message_frame_i >= end_frame_count {
+                break;
+            }
+
+            // メッセージのタイミングまで生成してから反映する。
+            if message_frame_i > self.elapsed_frame_count {
+                output.append(self.synthesizer.next_frames(message_frame_i - self.elapsed_frame_count));
+                self.elapsed_frame_count = message_frame_i;
+            }
+            self.synthesizer.handle_message(&message.message);
+            self.next_message_i += 1;
+        }
+
+        // 曲が終わったら、ノートオフが来ないまま鳴り続けるボイスがないように全部リリースする。
+        if !self.is_ended && self.end_frame_i < end_frame_count {
+            if self.end_frame_i > self.elapsed_frame_count {
+                output.append(self.synthesizer.next_frames(self.end_frame_i - self.elapsed_frame_count));
+                self.elapsed_frame_count = self.end_frame_i;
+            }
+            self.synthesizer.release_all();
+            self.is_ended = true;
+        }
+
+        output.append(self.synthesizer.next_frames(end_frame_count - self.elapsed_frame_count));
+        self.elapsed_frame_count = end_frame_count;
+        output
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -4,6 +4,8 @@ use std::collections::HashMap;
 use std::f64::consts::PI;
 use std::sync::{Arc, Mutex, OnceLock, Weak};
 
+pub mod stream;
+
 /// 24-12-23
 /// リサンプリングするためのテンプレートや接近するための仕組みを用意している。
 ///
@@ -0,0 +1,74 @@
+use super::{ProcessSamplingSetting, ResampleHeaderSetting, ResampleProcessHeader};
+use crate::wave::sample::UniformedSample;
+
+/// フィルターの前後で参照するサンプルの数。
+/// [`ResampleProcessHeader`]のフィルターの片側の長さより長くすること。
+const OFFSET: usize = 512;
+
+/// 少しずつ入ってくるサンプルを続けてリサンプリングするためのアイテム。
+///
+/// 入力の後ろの[`OFFSET`]分は次の入力が来るまで処理しないので、その分だけ遅延が起きる。
+#[derive(Debug)]
+pub struct ResampleStream {
+    header: ResampleProcessHeader,
+    setting: ResampleHeaderSetting,
+    /// 前のオフセットと、まだ処理してないサンプル
+    buffer: Vec<UniformedSample>,
+    /// 次の処理を始めるときのPhase
+    next_phase_time: f64,
+}
+
+impl ResampleStream {
+    pub fn new(setting: &ResampleHeaderSetting) -> Self {
+        assert!(setting.from_fs > 0);
+        assert!(setting.to_fs > 0);
+
+        Self {
+            header: setting.create_header(),
+            setting: *setting,
+            buffer: vec![UniformedSample::MIN; OFFSET],
+            next_phase_time: 0.0,
+        }
+    }
+
+    /// 作成時の設定を返す。
+    pub fn setting(&self) -> &ResampleHeaderSetting {
+        &self.setting
+    }
+
+    /// `samples`を入れて、処理できた分のリサンプリング結果を返す。
+    pub fn process(&mut self, samples: &[UniformedSample]) -> Vec<UniformedSample> {
+        self.buffer.extend_from_slice(samples);
+        if self.setting.from_fs == self.setting.to_fs {
+            return self.buffer.drain(OFFSET..).collect();
+        }
+
+        // 前と次のオフセットを除いた分だけ処理できる。
+        let process_length = self.buffer.len().saturating_sub(OFFSET * 2);
+        if process_length == 0 {
+            return vec![];
+        }
+
+        let result = self.header.process(&ProcessSamplingSetting {
+            src_buffer: &self.buffer,
+            start_phase_time: self.next_phase_time,
+            start_sample_i: OFFSET,
+            process_length,
+            use_interp: true,
+        });
+        self.next_phase_time = result.next_phase_time;
+
+        // 処理した分を捨てて、最後の部分を次の前オフセットにする。
+        self.buffer.drain(..process_length);
+        result.outputs
+    }
+
+    /// 残っているサンプルを全部処理して返す。
+    pub fn flush(&mut self) -> Vec<UniformedSample> {
+        self.process(&[UniformedSample::MIN; OFFSET])
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
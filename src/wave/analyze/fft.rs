@@ -0,0 +1,73 @@
+use crate::wave::{complex::Complex, PI2};
+
+/// `lhs`と`rhs`をFFTで線形畳み込みする。
+pub fn convolve(lhs: &[f64], rhs: &[f64]) -> Vec<f64> {
+    if lhs.is_empty() || rhs.is_empty() {
+        return vec![];
+    }
+
+    // 循環畳み込みにならないように、結果の長さ以上の2のべき乗にする。
+    let result_length = lhs.len() + rhs.len() - 1;
+    let fft_length = result_length.next_power_of_two();
+    let to_signals = |buffer: &[f64]| {
+        let mut signals = vec![Complex::<f64>::default(); fft_length];
+        for (signal, value) in signals.iter_mut().zip(buffer) {
+            signal.real = *value;
+        }
+        signals
+    };
+
+    let mut lhs_signals = to_signals(lhs);
+    let mut rhs_signals = to_signals(rhs);
+    fft(&mut lhs_signals, false);
+    fft(&mut rhs_signals, false);
+    for (lhs_signal, rhs_signal) in lhs_signals.iter_mut().zip(&rhs_signals) {
+        *lhs_signal *= *rhs_signal;
+    }
+    fft(&mut lhs_signals, true);
+
+    let scale = (fft_length as f64).recip();
+    lhs_signals.iter().take(result_length).map(|v| v.real * scale).collect()
+}
+
+/// `signals`をその場でFFTする。`is_inverse`なら逆変換するが、`1/N`はかけない。
+/// `signals`の長さは2のべき乗であること。
+pub fn fft(signals: &mut [Complex<f64>], is_inverse: bool) {
+    let length = signals.len();
+    debug_assert!(length.is_power_of_two());
+
+    // ビットリバースの順に並べ替える。
+    let mut reversed_i = 0;
+    for signal_i in 1..length {
+        let mut bit = length >> 1;
+        while reversed_i & bit != 0 {
+            reversed_i ^= bit;
+            bit >>= 1;
+        }
+        reversed_i |= bit;
+        if signal_i < reversed_i {
+            signals.swap(signal_i, reversed_i);
+        }
+    }
+
+    // バタフライ演算を短い周期から重ねていく。
+    let sign = if is_inverse { 1.0 } else { -1.0 };
+    let mut period = 2;
+    while period <= length {
+        let half_period = period >> 1;
+        for period_i in (0..length).step_by(period) {
+            for local_i in 0..half_period {
+                let coefficient = Complex::<f64>::from_exp(sign * PI2 * (local_i as f64) / (period as f64));
+                let lhs = signals[period_i + local_i];
+                let rhs = coefficient * signals[period_i + local_i + half_period];
+                signals[period_i + local_i] = lhs + rhs;
+                signals[period_i + local_i + half_period] = lhs - rhs;
+            }
+        }
+        period <<= 1;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
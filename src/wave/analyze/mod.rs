@@ -1,6 +1,7 @@
 use method::*;
 
 pub mod analyzer;
+pub mod fft;
 pub mod method;
 pub mod sine_freq;
 pub mod transformer;
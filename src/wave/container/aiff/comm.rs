@@ -0,0 +1,166 @@
+use std::io;
+
+/// AIFCのサンプルの圧縮形式。今は非圧縮のものだけ対応する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EAiffCompression {
+    /// ビッグエンディアンのLPCM（AIFFの`NONE`とAIFCの`twos`）
+    BigEndian,
+    /// リトルエンディアンのLPCM（`sowt`）
+    LittleEndian,
+    /// ビッグエンディアンの32ビット浮動小数点（`fl32`）
+    Float32,
+    /// ビッグエンディアンの64ビット浮動小数点（`fl64`）
+    Float64,
+}
+
+impl EAiffCompression {
+    fn from_id(id: &[u8; 4]) -> Option<Self> {
+        match id {
+            b"NONE" | b"twos" => Some(Self::BigEndian),
+            b"sowt" => Some(Self::LittleEndian),
+            b"fl32" | b"FL32" => Some(Self::Float32),
+            b"fl64" | b"FL64" => Some(Self::Float64),
+            _ => None,
+        }
+    }
+
+    /// AIFCの`COMM`チャンクに書き込む圧縮形式のIDを返す。
+    fn id(&self) -> [u8; 4] {
+        match self {
+            Self::BigEndian => *b"NONE",
+            Self::LittleEndian => *b"sowt",
+            Self::Float32 => *b"fl32",
+            Self::Float64 => *b"fl64",
+        }
+    }
+
+    /// LPCMとして書き込める形式か？
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Self::BigEndian | Self::LittleEndian)
+    }
+}
+
+/// AIFF・AIFCの`COMM`チャンクの情報。
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LowAiffCommonChunk {
+    pub channels: u16,
+    /// 1チャンネルあたりのサンプル数
+    pub sample_frames: u32,
+    /// 各サンプルの量子化ビット。浮動小数点の場合は32か64。
+    pub bits_per_sample: u16,
+    pub sample_rate: f64,
+    pub compression: EAiffCompression,
+}
+
+impl LowAiffCommonChunk {
+    pub const ID_SPECIFIER: [u8; 4] = *b"COMM";
+    /// AIFFの`COMM`チャンクの中身のサイズ
+    pub const AIFF_CHUNK_SIZE: u32 = 18;
+    /// AIFCの`COMM`チャンクの中身のサイズ。圧縮形式の名前は空文字列で書き込む。
+    pub const AIFC_CHUNK_SIZE: u32 = Self::AIFF_CHUNK_SIZE + 4 + 2;
+
+    /// チャンクの中身から`Self`を作る。`is_aifc`なら圧縮形式も読み込む。
+    /// 対応してない圧縮形式なら`None`を返す。
+    pub fn from_bytes(buffer: &[u8], is_aifc: bool) -> Option<Self> {
+        if buffer.len() < (Self::AIFF_CHUNK_SIZE as usize) {
+            return None;
+        }
+
+        let compression = if is_aifc {
+            let id: [u8; 4] = buffer.get(18..22)?.try_into().ok()?;
+            EAiffCompression::from_id(&id)?
+        } else {
+            EAiffCompression::BigEndian
+        };
+
+        Some(Self {
+            channels: u16::from_be_bytes([buffer[0], buffer[1]]),
+            sample_frames: u32::from_be_bytes([buffer[2], buffer[3], buffer[4], buffer[5]]),
+            bits_per_sample: u16::from_be_bytes([buffer[6], buffer[7]]),
+            sample_rate: convert_extended_to_f64(buffer[8..18].try_into().unwrap()),
+            compression,
+        })
+    }
+
+    /// 1フレーム（全チャンネル分）のバイト数を返す。
+    pub fn block_size(&self) -> usize {
+        let sample_size = match self.compression {
+            EAiffCompression::Float32 => 4,
+            EAiffCompression::Float64 => 8,
+            _ => (self.bits_per_sample as usize).div_ceil(8),
+        };
+        sample_size * (self.channels as usize)
+    }
+
+    /// `COMM`チャンクの中身のサイズを返す。
+    pub fn chunk_size(is_aifc: bool) -> u32 {
+        match is_aifc {
+            true => Self::AIFC_CHUNK_SIZE,
+            false => Self::AIFF_CHUNK_SIZE,
+        }
+    }
+
+    /// `COMM`チャンクとして[`io::Write`]ストリームに書き込む。
+    /// `is_aifc`ならAIFCの形式で圧縮形式も書き込む。
+    ///
+    /// AIFFで書き込めるのはビッグエンディアンのLPCMだけ。
+    pub fn write<T>(&self, writer: &mut T, is_aifc: bool)
+    where
+        T: io::Write + io::Seek,
+    {
+        assert!(is_aifc || self.compression == EAiffCompression::BigEndian);
+
+        let chunk_size = Self::chunk_size(is_aifc);
+        let mut buffer = Vec::with_capacity(8 + (chunk_size as usize));
+        buffer.extend_from_slice(&Self::ID_SPECIFIER);
+        buffer.extend_from_slice(&chunk_size.to_be_bytes());
+        buffer.extend_from_slice(&self.channels.to_be_bytes());
+        buffer.extend_from_slice(&self.sample_frames.to_be_bytes());
+        buffer.extend_from_slice(&self.bits_per_sample.to_be_bytes());
+        buffer.extend_from_slice(&convert_f64_to_extended(self.sample_rate));
+        if is_aifc {
+            buffer.extend_from_slice(&self.compression.id());
+            // 長さ0のPascal文字列と、偶数バイトに揃えるためのパディング
+            buffer.extend_from_slice(&[0u8; 2]);
+        }
+
+        writer
+            .write_all(&buffer)
+            .expect("Failed to write LowAiffCommonChunk to writer.");
+    }
+}
+
+/// 80ビットのIEEE 754拡張倍精度浮動小数点を[`f64`]に変換する。
+fn convert_extended_to_f64(bytes: &[u8; 10]) -> f64 {
+    let sign = if (bytes[0] & 0x80) != 0 { -1.0 } else { 1.0 };
+    let exponent = (((bytes[0] & 0x7F) as i32) << 8) | (bytes[1] as i32);
+    let mantissa = u64::from_be_bytes(bytes[2..10].try_into().unwrap());
+    if exponent == 0 && mantissa == 0 {
+        return 0.0;
+    }
+
+    // 拡張倍精度は整数部のビットを明示的に持つので、仮数部を63ビットの固定小数点として扱う。
+    sign * (mantissa as f64) * 2f64.powi(exponent - 16383 - 63)
+}
+
+/// [`f64`]を80ビットのIEEE 754拡張倍精度浮動小数点に変換する。
+/// サンプルレートにしか使わないので、非正規化数などは考慮しない。
+fn convert_f64_to_extended(value: f64) -> [u8; 10] {
+    let mut result = [0u8; 10];
+    if value == 0.0 || !value.is_finite() {
+        return result;
+    }
+
+    let bits = value.to_bits();
+    let sign = ((bits >> 63) as u16) << 15;
+    let exponent = (((bits >> 52) & 0x7FF) as i32) - 1023 + 16383;
+    let mantissa = ((bits & ((1u64 << 52) - 1)) | (1u64 << 52)) << 11;
+
+    result[..2].copy_from_slice(&(sign | (exponent as u16)).to_be_bytes());
+    result[2..].copy_from_slice(&mantissa.to_be_bytes());
+    result
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
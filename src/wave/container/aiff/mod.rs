@@ -0,0 +1,330 @@
+use crate::wave::container::aiff::comm::{EAiffCompression, LowAiffCommonChunk};
+use crate::wave::container::{convert_i32_to_uniformed_sample, convert_uniformed_sample_to_i32};
+use crate::wave::sample::UniformedSample;
+use itertools::Itertools;
+use std::io;
+
+// AIFF (Audio Interchange File Format) / AIFC
+// 中身はWAVと同じくチャンクの集まりだが、全部ビッグエンディアンで入っている。
+pub mod comm;
+pub mod stream;
+
+/// ファイルの先頭の`FORM`チャンクのID
+pub(crate) const FORM_ID: [u8; 4] = *b"FORM";
+/// `SSND`チャンクのID
+const SSND_ID: [u8; 4] = *b"SSND";
+/// AIFCの`FVER`チャンクのID
+const FVER_ID: [u8; 4] = *b"FVER";
+/// AIFCの`FVER`チャンクに書き込むバージョン（AIFC Version 1）
+const AIFC_VERSION: u32 = 0xA280_5140;
+
+/// `FORM`・`COMM`・`SSND`（AIFCなら`FVER`も）のヘッダーを合わせたサイズ。書き込む時に使う。
+const fn aiff_header_size(is_aifc: bool) -> usize {
+    match is_aifc {
+        true => 12 + 12 + 8 + (LowAiffCommonChunk::AIFC_CHUNK_SIZE as usize) + 16,
+        false => 12 + 8 + (LowAiffCommonChunk::AIFF_CHUNK_SIZE as usize) + 16,
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+// CONTAINER
+//
+// ----------------------------------------------------------------------------
+
+/// AIFF・AIFCの音源の情報を保持するコンテナ。
+/// [`WaveContainer`](super::WaveContainer)と同じく、サンプルは平準化して全部メモリに持つ。
+#[derive(Debug)]
+pub struct AiffContainer {
+    comm: LowAiffCommonChunk,
+    /// 音源のバッファを平準化して保持する。チャンネルはインターリーブされている。
+    uniformed_buffer: Vec<UniformedSample>,
+}
+
+impl AiffContainer {
+    /// `reader`からAIFF・AIFCファイルを全部読み込む。
+    pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
+    where
+        T: io::Read + io::Seek,
+    {
+        let headers = read_aiff_headers(reader)?;
+
+        let mut buffer = vec![0u8; headers.data_size];
+        reader.read_exact(&mut buffer).ok()?;
+        let uniformed_buffer = convert_aiff_to_uniformed_samples(&buffer, &headers.comm);
+
+        Some(Self {
+            comm: headers.comm,
+            uniformed_buffer,
+        })
+    }
+
+    /// [`AiffContainer`]の情報を[`io::Write`]ストリームにAIFFとして書き込む。
+    ///
+    /// `writer`のflush動作などは行わない。
+    pub fn write<T>(&self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let stream_writer = stream::AiffStreamWriter::new(
+            self.samples_per_second(),
+            self.comm.bits_per_sample,
+            self.comm.channels as usize,
+        )
+        .unwrap();
+        self.write_with(writer, stream_writer);
+    }
+
+    /// [`AiffContainer`]の情報を[`io::Write`]ストリームに`compression`のAIFCとして書き込む。
+    /// `compression`はLPCMの[`EAiffCompression::BigEndian`]（`NONE`）か[`EAiffCompression::LittleEndian`]（`sowt`）であること。
+    ///
+    /// `writer`のflush動作などは行わない。
+    pub fn write_aifc<T>(&self, writer: &mut T, compression: EAiffCompression)
+    where
+        T: io::Write + io::Seek,
+    {
+        let stream_writer = stream::AiffStreamWriter::new_aifc(
+            self.samples_per_second(),
+            self.comm.bits_per_sample,
+            self.comm.channels as usize,
+            compression,
+        )
+        .expect("Unsupported AIFC compression.");
+        self.write_with(writer, stream_writer);
+    }
+
+    fn write_with<T>(&self, writer: &mut T, mut stream_writer: stream::AiffStreamWriter)
+    where
+        T: io::Write + io::Seek,
+    {
+        stream_writer.write_header(writer);
+        stream_writer.write_frames(writer, &self.uniformed_buffer);
+        stream_writer.finish(writer);
+    }
+
+    /// 各サンプルに適用する量子化ビットを返す。
+    pub fn bits_per_sample(&self) -> u32 {
+        self.comm.bits_per_sample as u32
+    }
+
+    /// サウンドの秒ごとのサンプル数を返す。
+    pub fn samples_per_second(&self) -> u32 {
+        self.comm.sample_rate.round() as u32
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.comm.channels as u32
+    }
+
+    /// サウンドの全体長さを秒数で返す。
+    pub fn sound_length(&self) -> f64 {
+        let items_per_sec = (self.samples_per_second() as usize) * (self.channel() as usize);
+        (self.uniformed_buffer.len() as f64) / (items_per_sec as f64)
+    }
+
+    /// 平準化したサンプルのバッファを返す。チャンネルはインターリーブされている。
+    pub fn uniformed_sample_buffer(&self) -> &'_ [UniformedSample] {
+        &self.uniformed_buffer
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+// BUILDER
+//
+// ----------------------------------------------------------------------------
+
+pub struct AiffBuilder {
+    pub samples_per_sec: u32,
+    pub bits_per_sample: u16,
+}
+
+impl AiffBuilder {
+    pub fn build_mono(&self, uniformed_samples: Vec<UniformedSample>) -> Option<AiffContainer> {
+        self.build(uniformed_samples, 1)
+    }
+
+    pub fn build_stereo(&self, left: Vec<UniformedSample>, right: Vec<UniformedSample>) -> Option<AiffContainer> {
+        assert_eq!(left.len(), right.len());
+        let uniformed_buffer = left.into_iter().interleave(right).collect_vec();
+        self.build(uniformed_buffer, 2)
+    }
+
+    fn build(&self, uniformed_buffer: Vec<UniformedSample>, channels: u16) -> Option<AiffContainer> {
+        if !is_writable_bits_per_sample(self.bits_per_sample) {
+            return None;
+        }
+        if self.samples_per_sec == 0 {
+            return None;
+        }
+
+        let comm = LowAiffCommonChunk {
+            channels,
+            sample_frames: (uniformed_buffer.len() / (channels as usize)) as u32,
+            bits_per_sample: self.bits_per_sample,
+            sample_rate: self.samples_per_sec as f64,
+            compression: EAiffCompression::BigEndian,
+        };
+        Some(AiffContainer {
+            comm,
+            uniformed_buffer,
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+// HEADERS
+//
+// ----------------------------------------------------------------------------
+
+/// [`read_aiff_headers`]で読み込んだヘッダーの情報。
+pub(crate) struct AiffHeaders {
+    pub comm: LowAiffCommonChunk,
+    /// `SSND`チャンクのサンプルが始まるファイル上のバイト位置
+    pub data_start_position: u64,
+    /// サンプルのバイト数
+    pub data_size: usize,
+}
+
+/// `reader`から`FORM`・`COMM`・`SSND`のチャンクを読み込む。
+/// 読み込んだ後のカーソルは`SSND`チャンクのサンプルの先頭になる。
+///
+/// 対応してない圧縮形式のAIFCや、必要なチャンクがない場合は`None`を返す。
+pub(crate) fn read_aiff_headers<T>(reader: &mut T) -> Option<AiffHeaders>
+where
+    T: io::Read + io::Seek,
+{
+    let file_size = reader.seek(io::SeekFrom::End(0)).ok()?;
+    reader.rewind().ok()?;
+
+    let mut form = [0u8; 12];
+    reader.read_exact(&mut form).ok()?;
+    if form[..4] != FORM_ID {
+        return None;
+    }
+    let is_aifc = match &form[8..12] {
+        b"AIFF" => false,
+        b"AIFC" => true,
+        _ => return None,
+    };
+
+    let mut comm = None;
+    let mut ssnd = None;
+    loop {
+        let mut chunk_header = [0u8; 8];
+        if reader.read_exact(&mut chunk_header).is_err() {
+            break;
+        }
+        let chunk_size = u32::from_be_bytes(chunk_header[4..8].try_into().unwrap()) as u64;
+        let chunk_start = reader.stream_position().ok()?;
+
+        if chunk_header[..4] == LowAiffCommonChunk::ID_SPECIFIER {
+            let mut buffer = vec![0u8; chunk_size as usize];
+            reader.read_exact(&mut buffer).ok()?;
+            comm = Some(LowAiffCommonChunk::from_bytes(&buffer, is_aifc)?);
+        } else if chunk_header[..4] == SSND_ID {
+            let mut buffer = [0u8; 8];
+            reader.read_exact(&mut buffer).ok()?;
+            let offset = u32::from_be_bytes(buffer[..4].try_into().unwrap()) as u64;
+            let data_start_position = chunk_start + 8 + offset;
+
+            // 書き込み途中のファイルはチャンクのサイズが合ってないこともあるので、ファイルの大きさで制限する。
+            let data_size = chunk_size
+                .saturating_sub(8 + offset)
+                .min(file_size.saturating_sub(data_start_position));
+            ssnd = Some((data_start_position, data_size as usize));
+        }
+
+        // チャンクは偶数バイトに揃えられている。
+        let next_chunk_position = chunk_start + chunk_size + (chunk_size & 1);
+        if next_chunk_position >= file_size {
+            break;
+        }
+        reader.seek(io::SeekFrom::Start(next_chunk_position)).ok()?;
+    }
+
+    let comm = comm?;
+    let (data_start_position, data_size) = ssnd?;
+    if comm.channels == 0 || comm.bits_per_sample == 0 || comm.bits_per_sample > 32 {
+        return None;
+    }
+
+    // 1フレームに満たない端数は読まない。
+    let block_size = comm.block_size();
+    let data_size = data_size - (data_size % block_size);
+
+    reader.seek(io::SeekFrom::Start(data_start_position)).ok()?;
+    Some(AiffHeaders {
+        comm,
+        data_start_position,
+        data_size,
+    })
+}
+
+// ----------------------------------------------------------------------------
+//
+// HELPERS
+//
+// ----------------------------------------------------------------------------
+
+/// 書き込める量子化ビットか？
+pub(crate) fn is_writable_bits_per_sample(bits_per_sample: u16) -> bool {
+    matches!(bits_per_sample, 8 | 16 | 24)
+}
+
+/// `SSND`チャンクのサンプルを[`UniformedSample`]に変換する。
+pub(crate) fn convert_aiff_to_uniformed_samples(buffer: &[u8], comm: &LowAiffCommonChunk) -> Vec<UniformedSample> {
+    match comm.compression {
+        EAiffCompression::Float32 => buffer
+            .chunks_exact(4)
+            .map(|v| UniformedSample::from_f64(f32::from_be_bytes([v[0], v[1], v[2], v[3]]) as f64))
+            .collect_vec(),
+        EAiffCompression::Float64 => buffer
+            .chunks_exact(8)
+            .map(|v| UniformedSample::from_f64(f64::from_be_bytes(v.try_into().unwrap())))
+            .collect_vec(),
+        compression => {
+            let bits_per_sample = comm.bits_per_sample as u32;
+            let width = bits_per_sample.div_ceil(8) as usize;
+            let is_little_endian = compression == EAiffCompression::LittleEndian;
+
+            buffer
+                .chunks_exact(width)
+                .map(|raw_sample| {
+                    let mut value = 0u32;
+                    for i in 0..width {
+                        let byte = if is_little_endian { raw_sample[width - 1 - i] } else { raw_sample[i] };
+                        value = (value << 8) | (byte as u32);
+                    }
+
+                    // サンプルは左詰めで入っているので、上位に詰めてから右シフトで符号を拡張する。
+                    let value = ((value << (32 - (width * 8))) as i32) >> (32 - bits_per_sample);
+                    convert_i32_to_uniformed_sample(value, bits_per_sample)
+                })
+                .collect_vec()
+        }
+    }
+}
+
+/// [`UniformedSample`]を`comm`の量子化ビットとエンディアンのLPCMに変換する。
+pub(crate) fn convert_uniformed_samples_to_aiff(samples: &[UniformedSample], comm: &LowAiffCommonChunk) -> Vec<u8> {
+    assert!(comm.compression.is_writable());
+
+    let bits_per_sample = comm.bits_per_sample;
+    let width = (bits_per_sample as usize) / 8;
+    let mut buffer = Vec::with_capacity(samples.len() * width);
+    for sample in samples {
+        let value = convert_uniformed_sample_to_i32(*sample, bits_per_sample as u32);
+        match comm.compression {
+            EAiffCompression::LittleEndian => buffer.extend_from_slice(&value.to_le_bytes()[..width]),
+            _ => buffer.extend_from_slice(&value.to_be_bytes()[(4 - width)..]),
+        }
+    }
+    buffer
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
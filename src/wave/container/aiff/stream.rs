@@ -0,0 +1,230 @@
+use crate::wave::container::aiff::comm::{EAiffCompression, LowAiffCommonChunk};
+use crate::wave::container::aiff::{
+    aiff_header_size, convert_aiff_to_uniformed_samples, convert_uniformed_samples_to_aiff,
+    is_writable_bits_per_sample, read_aiff_headers, AIFC_VERSION, FORM_ID, FVER_ID, SSND_ID,
+};
+use crate::wave::sample::UniformedSample;
+use std::io;
+
+// ----------------------------------------------------------------------------
+// AiffStreamHeader
+// ----------------------------------------------------------------------------
+
+/// [`AiffContainer`](super::AiffContainer)と違ってヘッダーだけを保持し、
+/// サンプルは必要な時に必要な分だけファイルから読み込んで変換するための構造体。
+#[derive(Debug, Clone)]
+pub struct AiffStreamHeader {
+    comm: LowAiffCommonChunk,
+    /// `SSND`チャンクのサンプルが始まるファイル上のバイト位置
+    data_start_position: u64,
+    /// サンプルのバイト数
+    data_size: usize,
+}
+
+impl AiffStreamHeader {
+    /// `reader`からヘッダーだけを読み込む。
+    pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
+    where
+        T: io::Read + io::Seek,
+    {
+        let headers = read_aiff_headers(reader)?;
+
+        Some(Self {
+            comm: headers.comm,
+            data_start_position: headers.data_start_position,
+            data_size: headers.data_size,
+        })
+    }
+
+    /// サウンドの秒ごとのサンプル数を返す。
+    pub fn samples_per_second(&self) -> u32 {
+        self.comm.sample_rate.round() as u32
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.comm.channels as u32
+    }
+
+    /// 各サンプルに適用する量子化ビットを返す。
+    pub fn bits_per_sample(&self) -> u32 {
+        self.comm.bits_per_sample as u32
+    }
+
+    /// 全チャンネルを含むフレームの総数を返す。
+    pub fn frame_count(&self) -> usize {
+        self.data_size / self.comm.block_size()
+    }
+
+    /// サウンドの全体長さを秒数で返す。
+    pub fn sound_length(&self) -> f64 {
+        (self.frame_count() as f64) / (self.samples_per_second() as f64)
+    }
+
+    /// 秒数`time`から一番近いフレームのインデックスを返す。
+    pub fn frame_index_of_time(&self, time: f64) -> usize {
+        ((self.samples_per_second() as f64) * time.max(0.0)).floor() as usize
+    }
+
+    /// `start_frame`から`frame_count`分のフレームを`reader`から読み込んで変換する。
+    ///
+    /// 複数チャンネルの場合、返すバッファはチャンネルがインターリーブされている。
+    /// ファイルの最後を超える分は読み込まないので、返すバッファは要求分より短くなることがある。
+    pub fn read_frames<T>(&self, reader: &mut T, start_frame: usize, frame_count: usize) -> Vec<UniformedSample>
+    where
+        T: io::Read + io::Seek,
+    {
+        let frame_count = frame_count.min(self.frame_count().saturating_sub(start_frame));
+        if frame_count == 0 {
+            return vec![];
+        }
+
+        let block_size = self.comm.block_size();
+        let start_position = self.data_start_position + ((start_frame * block_size) as u64);
+        reader
+            .seek(io::SeekFrom::Start(start_position))
+            .expect("Failed to seek reader.");
+
+        let mut buffer = vec![0u8; frame_count * block_size];
+        reader.read_exact(&mut buffer).expect("Failed to read buffer.");
+
+        convert_aiff_to_uniformed_samples(&buffer, &self.comm)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// AiffStreamWriter
+// ----------------------------------------------------------------------------
+
+/// 先にヘッダーだけを書き込んでおいて、サンプルが来るたびにファイルの後ろに追記していくための構造体。
+///
+/// `FORM`・`SSND`チャンクのサイズと`COMM`のフレーム数は[`AiffStreamWriter::write_header`]で更新する。
+#[derive(Debug, Clone)]
+pub struct AiffStreamWriter {
+    comm: LowAiffCommonChunk,
+    /// AIFCとして書き込むか
+    is_aifc: bool,
+}
+
+impl AiffStreamWriter {
+    /// LPCMの設定からAIFFとして書き込むライターを作る。8・16・24ビットに対応。
+    pub fn new(samples_per_sec: u32, bits_per_sample: u16, channels: usize) -> Option<Self> {
+        let comm = Self::create_comm(samples_per_sec, bits_per_sample, channels, EAiffCompression::BigEndian)?;
+        Some(Self { comm, is_aifc: false })
+    }
+
+    /// LPCMの設定からAIFCとして書き込むライターを作る。8・16・24ビットに対応。
+    /// `compression`は[`EAiffCompression::BigEndian`]（`NONE`）か[`EAiffCompression::LittleEndian`]（`sowt`）だけ対応。
+    pub fn new_aifc(
+        samples_per_sec: u32,
+        bits_per_sample: u16,
+        channels: usize,
+        compression: EAiffCompression,
+    ) -> Option<Self> {
+        let comm = Self::create_comm(samples_per_sec, bits_per_sample, channels, compression)?;
+        Some(Self { comm, is_aifc: true })
+    }
+
+    fn create_comm(
+        samples_per_sec: u32,
+        bits_per_sample: u16,
+        channels: usize,
+        compression: EAiffCompression,
+    ) -> Option<LowAiffCommonChunk> {
+        if !is_writable_bits_per_sample(bits_per_sample) || !compression.is_writable() {
+            return None;
+        }
+        if samples_per_sec == 0 || channels == 0 || channels > (u16::MAX as usize) {
+            return None;
+        }
+
+        Some(LowAiffCommonChunk {
+            channels: channels as u16,
+            sample_frames: 0,
+            bits_per_sample,
+            sample_rate: samples_per_sec as f64,
+            compression,
+        })
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.comm.channels as u32
+    }
+
+    /// 今まで書き込んだフレームの数を返す。
+    pub fn written_frame_count(&self) -> usize {
+        self.comm.sample_frames as usize
+    }
+
+    /// 今まで書き込んだサンプルのサイズで`FORM`・`COMM`・`SSND`（AIFCなら`FVER`も）ヘッダーをファイルの先頭に書き込む。
+    /// 書き込んだ後はカーソルをファイルの最後に移動する。
+    pub fn write_header<T>(&self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let data_size = (self.written_frame_count() * self.comm.block_size()) as u32;
+        // サンプルの後ろのパディングも含めて、ファイル全体から`FORM`のヘッダー分を引いたサイズ。
+        let form_size = (aiff_header_size(self.is_aifc) as u32) - 8 + data_size + (data_size & 1);
+
+        let mut form = Vec::with_capacity(24);
+        form.extend_from_slice(&FORM_ID);
+        form.extend_from_slice(&form_size.to_be_bytes());
+        if self.is_aifc {
+            form.extend_from_slice(b"AIFC");
+            form.extend_from_slice(&FVER_ID);
+            form.extend_from_slice(&4u32.to_be_bytes());
+            form.extend_from_slice(&AIFC_VERSION.to_be_bytes());
+        } else {
+            form.extend_from_slice(b"AIFF");
+        }
+
+        let mut ssnd = Vec::with_capacity(16);
+        ssnd.extend_from_slice(&SSND_ID);
+        ssnd.extend_from_slice(&(8 + data_size).to_be_bytes());
+        // offsetとblockSizeは使わないので0にする。
+        ssnd.extend_from_slice(&[0u8; 8]);
+
+        writer.seek(io::SeekFrom::Start(0)).expect("Failed to seek writer.");
+        writer.write_all(&form).expect("Failed to write FORM chunk to writer.");
+        self.comm.write(writer, self.is_aifc);
+        writer.write_all(&ssnd).expect("Failed to write SSND chunk to writer.");
+        writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+    }
+
+    /// インターリーブされた`samples`をファイルの最後に追記する。
+    /// `samples`の長さはチャンネル数の倍数であること。
+    pub fn write_frames<T>(&mut self, writer: &mut T, samples: &[UniformedSample])
+    where
+        T: io::Write + io::Seek,
+    {
+        let channels = self.comm.channels as usize;
+        assert_eq!(samples.len() % channels, 0);
+        if samples.is_empty() {
+            return;
+        }
+
+        let converted_buffer = convert_uniformed_samples_to_aiff(samples, &self.comm);
+        writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+        writer.write_all(&converted_buffer).expect("Failed to write Buffer to writer.");
+        self.comm.sample_frames += (samples.len() / channels) as u32;
+    }
+
+    /// サンプルのサイズが奇数ならパディングを追記して、最後にヘッダーを更新する。
+    pub fn finish<T>(&mut self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let data_size = self.written_frame_count() * self.comm.block_size();
+        if (data_size & 1) != 0 {
+            writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+            writer.write_all(&[0u8]).expect("Failed to write padding to writer.");
+        }
+
+        self.write_header(writer);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,220 @@
+/// FLACのビットストリームをMSBから順番に読み込むための構造体。
+///
+/// 読み込みに失敗した場合（バッファが足りない場合）は`None`を返す。
+pub(crate) struct BitReader<'a> {
+    buffer: &'a [u8],
+    /// 次に読み込むビットの位置
+    bit_position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self {
+            buffer,
+            bit_position: 0,
+        }
+    }
+
+    /// 今まで読み込んだバイト数を返す。途中のバイトも1バイトとして数える。
+    pub fn byte_position(&self) -> usize {
+        (self.bit_position + 7) >> 3
+    }
+
+    /// 読み込み元のバッファの`[0, end)`を返す。
+    pub fn buffer_until(&self, end: usize) -> &'a [u8] {
+        &self.buffer[..end]
+    }
+
+    /// 次のバイトの境界までスキップする。
+    pub fn align_to_byte(&mut self) {
+        self.bit_position = self.byte_position() << 3;
+    }
+
+    pub fn read_bit(&mut self) -> Option<bool> {
+        let byte = *self.buffer.get(self.bit_position >> 3)?;
+        let shift = 7 - (self.bit_position & 7);
+        self.bit_position += 1;
+        Some(((byte >> shift) & 1) == 1)
+    }
+
+    /// `bits`分（最大64ビット）を符号なしの整数として読み込む。
+    pub fn read_bits(&mut self, bits: u32) -> Option<u64> {
+        assert!(bits <= 64);
+        if self.bit_position + (bits as usize) > (self.buffer.len() << 3) {
+            return None;
+        }
+
+        let mut value = 0u64;
+        let mut remained = bits;
+        while remained > 0 {
+            let byte = self.buffer[self.bit_position >> 3];
+            let offset = (self.bit_position & 7) as u32;
+            let available = 8 - offset;
+            let take = available.min(remained);
+
+            // バイトの中から必要なビットだけを切り取る。
+            let chunk = ((byte as u32) >> (available - take)) & ((1u32 << take) - 1);
+            value = (value << take) | (chunk as u64);
+
+            remained -= take;
+            self.bit_position += take as usize;
+        }
+
+        Some(value)
+    }
+
+    /// `bits`分を2の補数の符号ありの整数として読み込む。
+    pub fn read_signed_bits(&mut self, bits: u32) -> Option<i64> {
+        if bits == 0 {
+            return Some(0);
+        }
+
+        let value = self.read_bits(bits)?;
+        let shift = 64 - bits;
+        Some(((value << shift) as i64) >> shift)
+    }
+
+    /// 1が出るまでの0の数を読み込む。
+    pub fn read_unary(&mut self) -> Option<u32> {
+        let mut count = 0;
+        while !self.read_bit()? {
+            count += 1;
+        }
+        Some(count)
+    }
+
+    /// Rice符号化された符号ありの整数を読み込む。
+    pub fn read_rice_signed(&mut self, parameter: u32) -> Option<i64> {
+        let msb = self.read_unary()? as u64;
+        let lsb = self.read_bits(parameter)?;
+        let folded = (msb << parameter) | lsb;
+
+        // zigzagを元に戻す。
+        Some(((folded >> 1) as i64) ^ -((folded & 1) as i64))
+    }
+
+    /// フレーム番号などに使われるUTF-8のような可変長の整数を読み込む。
+    pub fn read_utf8_coded(&mut self) -> Option<u64> {
+        let first = self.read_bits(8)?;
+        let leading_ones = (first as u8).leading_ones();
+        let (mut value, following) = match leading_ones {
+            0 => (first, 0),
+            2..=7 => (first & ((1 << (7 - leading_ones)) - 1), leading_ones - 1),
+            _ => return None,
+        };
+
+        for _ in 0..following {
+            let byte = self.read_bits(8)?;
+            if (byte & 0xC0) != 0x80 {
+                return None;
+            }
+            value = (value << 6) | (byte & 0x3F);
+        }
+
+        Some(value)
+    }
+}
+
+/// FLACのビットストリームをMSBから順番に書き込むための構造体。
+#[derive(Default)]
+pub(crate) struct BitWriter {
+    buffer: Vec<u8>,
+    /// まだ`buffer`に入ってないビット
+    accumulated: u64,
+    accumulated_bits: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 値の下位`bits`分（最大32ビット）を書き込む。
+    pub fn write_bits(&mut self, value: u64, bits: u32) {
+        assert!(bits <= 32);
+        if bits == 0 {
+            return;
+        }
+
+        let mask = (1u64 << bits) - 1;
+        self.accumulated = (self.accumulated << bits) | (value & mask);
+        self.accumulated_bits += bits;
+        while self.accumulated_bits >= 8 {
+            self.accumulated_bits -= 8;
+            self.buffer.push((self.accumulated >> self.accumulated_bits) as u8);
+        }
+        self.accumulated &= (1u64 << self.accumulated_bits) - 1;
+    }
+
+    /// 2の補数で`bits`分を書き込む。
+    pub fn write_signed_bits(&mut self, value: i64, bits: u32) {
+        self.write_bits(value as u64, bits);
+    }
+
+    /// `count`分の0と最後に1を書き込む。
+    pub fn write_unary(&mut self, count: u32) {
+        let mut remained = count;
+        while remained >= 32 {
+            self.write_bits(0, 32);
+            remained -= 32;
+        }
+        self.write_bits(1, remained + 1);
+    }
+
+    /// 符号ありの整数をRice符号化して書き込む。
+    pub fn write_rice_signed(&mut self, value: i64, parameter: u32) {
+        let folded = zigzag(value);
+        self.write_unary((folded >> parameter) as u32);
+        self.write_bits(folded, parameter);
+    }
+
+    /// フレーム番号などに使われるUTF-8のような可変長の整数を書き込む。
+    pub fn write_utf8_coded(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write_bits(value, 8);
+            return;
+        }
+
+        // 後続バイトの数を求める。
+        let following = match value {
+            0..=0x7FF => 1,
+            0x800..=0xFFFF => 2,
+            0x1_0000..=0x1F_FFFF => 3,
+            0x20_0000..=0x3FF_FFFF => 4,
+            0x400_0000..=0x7FFF_FFFF => 5,
+            _ => 6,
+        };
+        let leading = (0xFF00u32 >> (following + 1)) as u64 & 0xFF;
+        self.write_bits(leading | (value >> (6 * following)), 8);
+        for i in (0..following).rev() {
+            self.write_bits(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+        }
+    }
+
+    /// 次のバイトの境界まで0で埋める。
+    pub fn align_to_byte(&mut self) {
+        if self.accumulated_bits > 0 {
+            self.write_bits(0, 8 - self.accumulated_bits);
+        }
+    }
+
+    /// 今まで書き込んだバイト列を返す。バイトの境界に揃っていること。
+    pub fn bytes(&self) -> &[u8] {
+        assert_eq!(self.accumulated_bits, 0);
+        &self.buffer
+    }
+
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        self.align_to_byte();
+        self.buffer
+    }
+}
+
+/// Rice符号化で使うため、符号ありの整数を符号なしに変換する。
+pub(crate) fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
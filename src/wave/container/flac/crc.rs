@@ -0,0 +1,29 @@
+/// フレームヘッダーのチェックに使うCRC-8を計算する。
+/// 多項式は`x^8 + x^2 + x^1 + x^0`、初期値は0。
+pub(crate) fn crc8(buffer: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in buffer {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if (crc & 0x80) != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// フレーム全体のチェックに使うCRC-16を計算する。
+/// 多項式は`x^16 + x^15 + x^2 + x^0`、初期値は0。
+pub(crate) fn crc16(buffer: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in buffer {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if (crc & 0x8000) != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,336 @@
+use crate::wave::container::flac::bitio::BitReader;
+use crate::wave::container::flac::crc::{crc16, crc8};
+use crate::wave::container::flac::streaminfo::FlacStreamInfo;
+use crate::wave::container::flac::EChannelAssignment;
+
+/// フレームのデコードに失敗した理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EFlacDecodeError {
+    /// バッファが足りなくてフレームの最後まで読めなかった。
+    NeedMoreData,
+    /// フレームが壊れているか、対応していない形式。
+    Invalid(&'static str),
+}
+
+/// デコードしたフレーム
+#[derive(Debug, Clone)]
+pub(crate) struct FlacFrame {
+    /// 各チャンネルのサンプル。チャンネルごとに`block_size`個ある。
+    pub channels: Vec<Vec<i32>>,
+    /// フレームが使ったバイト数
+    pub byte_size: usize,
+}
+
+/// `buffer`の先頭からフレームを1個デコードする。
+pub(crate) fn decode_frame(buffer: &[u8], info: &FlacStreamInfo) -> Result<FlacFrame, EFlacDecodeError> {
+    use EFlacDecodeError::{Invalid, NeedMoreData};
+
+    let mut reader = BitReader::new(buffer);
+    let header = read_frame_header(&mut reader, info)?;
+
+    // 各サブフレームを読み込む。
+    let mut channels = Vec::with_capacity(header.channel_assignment.channel_count());
+    for channel_i in 0..header.channel_assignment.channel_count() {
+        // サイドチャンネルは1ビット多く使う。
+        let bits_per_sample = header.bits_per_sample + header.channel_assignment.extra_bits_of(channel_i);
+        let samples = read_subframe(&mut reader, header.block_size, bits_per_sample)?;
+        channels.push(samples);
+    }
+
+    // 最後にCRC-16でフレーム全体をチェックする。
+    reader.align_to_byte();
+    let crc_position = reader.byte_position();
+    let frame_crc = reader.read_bits(16).ok_or(NeedMoreData)? as u16;
+    if crc16(&buffer[..crc_position]) != frame_crc {
+        return Err(Invalid("Frame CRC-16 mismatched."));
+    }
+
+    // チャンネルの相関を元に戻す。
+    restore_decorrelation(header.channel_assignment, &mut channels);
+
+    Ok(FlacFrame {
+        channels,
+        byte_size: reader.byte_position(),
+    })
+}
+
+struct FrameHeader {
+    block_size: usize,
+    channel_assignment: EChannelAssignment,
+    bits_per_sample: u32,
+}
+
+fn read_frame_header(reader: &mut BitReader, info: &FlacStreamInfo) -> Result<FrameHeader, EFlacDecodeError> {
+    use EFlacDecodeError::{Invalid, NeedMoreData};
+
+    // 同期コードは`0b11111111111110`で、次の1ビットは予約なので一緒に読み込む。
+    let sync = reader.read_bits(15).ok_or(NeedMoreData)?;
+    if sync != super::FRAME_SYNC_CODE as u64 {
+        return Err(Invalid("Frame sync code is not found."));
+    }
+    let _blocking_strategy = reader.read_bit().ok_or(NeedMoreData)?;
+
+    let block_size_code = reader.read_bits(4).ok_or(NeedMoreData)? as u32;
+    let sample_rate_code = reader.read_bits(4).ok_or(NeedMoreData)? as u32;
+    let channel_code = reader.read_bits(4).ok_or(NeedMoreData)? as u32;
+    let sample_size_code = reader.read_bits(3).ok_or(NeedMoreData)? as u32;
+    if reader.read_bit().ok_or(NeedMoreData)? {
+        return Err(Invalid("Reserved bit in frame header must be zero."));
+    }
+
+    // フレーム番号かサンプル番号。ここでは使わない。
+    reader
+        .read_utf8_coded()
+        .ok_or(Invalid("Frame number is not coded properly."))?;
+
+    let block_size = match block_size_code {
+        0 => return Err(Invalid("Reserved block size.")),
+        1 => 192,
+        2..=5 => 576 << (block_size_code - 2),
+        6 => (reader.read_bits(8).ok_or(NeedMoreData)? as usize) + 1,
+        7 => (reader.read_bits(16).ok_or(NeedMoreData)? as usize) + 1,
+        _ => 256 << (block_size_code - 8),
+    };
+
+    // サンプルレートはSTREAMINFOのものを使うので、ヘッダーの分は読み飛ばすだけ。
+    match sample_rate_code {
+        12 => {
+            reader.read_bits(8).ok_or(NeedMoreData)?;
+        }
+        13 | 14 => {
+            reader.read_bits(16).ok_or(NeedMoreData)?;
+        }
+        15 => return Err(Invalid("Invalid sample rate.")),
+        _ => (),
+    }
+
+    let channel_assignment = EChannelAssignment::from_code(channel_code).ok_or(Invalid("Reserved channel assignment."))?;
+    let bits_per_sample = match sample_size_code {
+        0 => info.bits_per_sample,
+        1 => 8,
+        2 => 12,
+        4 => 16,
+        5 => 20,
+        6 => 24,
+        7 => 32,
+        _ => return Err(Invalid("Reserved sample size.")),
+    };
+
+    // ヘッダーのCRC-8をチェックする。
+    let crc_position = reader.byte_position();
+    let header_crc = reader.read_bits(8).ok_or(NeedMoreData)? as u8;
+    if crc8(reader.buffer_until(crc_position)) != header_crc {
+        return Err(Invalid("Frame header CRC-8 mismatched."));
+    }
+
+    Ok(FrameHeader {
+        block_size,
+        channel_assignment,
+        bits_per_sample,
+    })
+}
+
+/// サブフレームを1個読み込んで、そのチャンネルのサンプルを返す。
+fn read_subframe(reader: &mut BitReader, block_size: usize, bits_per_sample: u32) -> Result<Vec<i32>, EFlacDecodeError> {
+    use EFlacDecodeError::{Invalid, NeedMoreData};
+
+    if reader.read_bit().ok_or(NeedMoreData)? {
+        return Err(Invalid("Subframe padding bit must be zero."));
+    }
+    let subframe_type = reader.read_bits(6).ok_or(NeedMoreData)? as u32;
+
+    // Wasted bitsがあれば、その分だけ少ないビットで書かれている。
+    let wasted_bits = if reader.read_bit().ok_or(NeedMoreData)? {
+        reader.read_unary().ok_or(NeedMoreData)? + 1
+    } else {
+        0
+    };
+    if wasted_bits >= bits_per_sample {
+        return Err(Invalid("Too many wasted bits."));
+    }
+    let bits_per_sample = bits_per_sample - wasted_bits;
+
+    let mut samples = match subframe_type {
+        // CONSTANT
+        0 => {
+            let value = reader.read_signed_bits(bits_per_sample).ok_or(NeedMoreData)?;
+            vec![value; block_size]
+        }
+        // VERBATIM
+        1 => {
+            let mut samples = Vec::with_capacity(block_size);
+            for _ in 0..block_size {
+                samples.push(reader.read_signed_bits(bits_per_sample).ok_or(NeedMoreData)?);
+            }
+            samples
+        }
+        // FIXED
+        8..=12 => {
+            let order = (subframe_type - 8) as usize;
+            read_fixed_subframe(reader, block_size, bits_per_sample, order)?
+        }
+        // LPC
+        32..=63 => {
+            let order = (subframe_type - 31) as usize;
+            read_lpc_subframe(reader, block_size, bits_per_sample, order)?
+        }
+        _ => return Err(Invalid("Reserved subframe type.")),
+    };
+
+    if wasted_bits > 0 {
+        samples.iter_mut().for_each(|v| *v <<= wasted_bits);
+    }
+    Ok(samples.into_iter().map(|v| v as i32).collect())
+}
+
+fn read_warm_up(reader: &mut BitReader, bits_per_sample: u32, order: usize, block_size: usize) -> Result<Vec<i64>, EFlacDecodeError> {
+    if order > block_size {
+        return Err(EFlacDecodeError::Invalid("Predictor order is larger than block size."));
+    }
+
+    let mut samples = Vec::with_capacity(block_size);
+    for _ in 0..order {
+        samples.push(
+            reader
+                .read_signed_bits(bits_per_sample)
+                .ok_or(EFlacDecodeError::NeedMoreData)?,
+        );
+    }
+    Ok(samples)
+}
+
+fn read_fixed_subframe(
+    reader: &mut BitReader,
+    block_size: usize,
+    bits_per_sample: u32,
+    order: usize,
+) -> Result<Vec<i64>, EFlacDecodeError> {
+    let mut samples = read_warm_up(reader, bits_per_sample, order, block_size)?;
+    let residuals = read_residual(reader, block_size, order)?;
+
+    // 固定の多項式予測に残差を足して復元する。
+    let coefficients = super::FIXED_COEFFICIENTS[order];
+    for residual in residuals {
+        let i = samples.len();
+        let prediction: i64 = coefficients
+            .iter()
+            .enumerate()
+            .map(|(j, c)| c * samples[i - 1 - j])
+            .sum();
+        samples.push(prediction + residual);
+    }
+
+    Ok(samples)
+}
+
+fn read_lpc_subframe(
+    reader: &mut BitReader,
+    block_size: usize,
+    bits_per_sample: u32,
+    order: usize,
+) -> Result<Vec<i64>, EFlacDecodeError> {
+    use EFlacDecodeError::{Invalid, NeedMoreData};
+
+    let mut samples = read_warm_up(reader, bits_per_sample, order, block_size)?;
+
+    let precision = reader.read_bits(4).ok_or(NeedMoreData)? as u32;
+    if precision == 0b1111 {
+        return Err(Invalid("Invalid LPC coefficient precision."));
+    }
+    let precision = precision + 1;
+    let shift = reader.read_signed_bits(5).ok_or(NeedMoreData)?;
+    if shift < 0 {
+        return Err(Invalid("Negative LPC shift is not supported."));
+    }
+
+    let mut coefficients = Vec::with_capacity(order);
+    for _ in 0..order {
+        coefficients.push(reader.read_signed_bits(precision).ok_or(NeedMoreData)?);
+    }
+
+    let residuals = read_residual(reader, block_size, order)?;
+    for residual in residuals {
+        let i = samples.len();
+        let prediction: i64 = coefficients
+            .iter()
+            .enumerate()
+            .map(|(j, c)| c * samples[i - 1 - j])
+            .sum();
+        samples.push((prediction >> shift) + residual);
+    }
+
+    Ok(samples)
+}
+
+/// Rice符号化された残差を読み込む。
+fn read_residual(reader: &mut BitReader, block_size: usize, order: usize) -> Result<Vec<i64>, EFlacDecodeError> {
+    use EFlacDecodeError::{Invalid, NeedMoreData};
+
+    let (parameter_bits, escape_code) = match reader.read_bits(2).ok_or(NeedMoreData)? {
+        0 => (4, 0b1111),
+        1 => (5, 0b11111),
+        _ => return Err(Invalid("Reserved residual coding method.")),
+    };
+
+    let partition_order = reader.read_bits(4).ok_or(NeedMoreData)? as u32;
+    let partition_count = 1usize << partition_order;
+    if !block_size.is_multiple_of(partition_count) || (block_size >> partition_order) < order {
+        return Err(Invalid("Invalid partition order."));
+    }
+
+    let mut residuals = Vec::with_capacity(block_size - order);
+    for partition_i in 0..partition_count {
+        let count = if partition_i == 0 {
+            (block_size >> partition_order) - order
+        } else {
+            block_size >> partition_order
+        };
+
+        let parameter = reader.read_bits(parameter_bits).ok_or(NeedMoreData)? as u32;
+        if parameter == escape_code {
+            // エスケープされた場合には、固定ビットで書かれている。
+            let bits = reader.read_bits(5).ok_or(NeedMoreData)? as u32;
+            for _ in 0..count {
+                residuals.push(reader.read_signed_bits(bits).ok_or(NeedMoreData)?);
+            }
+        } else {
+            for _ in 0..count {
+                residuals.push(reader.read_rice_signed(parameter).ok_or(NeedMoreData)?);
+            }
+        }
+    }
+
+    Ok(residuals)
+}
+
+/// ステレオの相関を取り除いたチャンネルから、左右のチャンネルに戻す。
+fn restore_decorrelation(assignment: EChannelAssignment, channels: &mut [Vec<i32>]) {
+    match assignment {
+        EChannelAssignment::Independent(_) => (),
+        EChannelAssignment::LeftSide => {
+            let (left, side) = channels.split_at_mut(1);
+            for (l, s) in left[0].iter().zip(side[0].iter_mut()) {
+                *s = l.wrapping_sub(*s);
+            }
+        }
+        EChannelAssignment::SideRight => {
+            let (side, right) = channels.split_at_mut(1);
+            for (s, r) in side[0].iter_mut().zip(right[0].iter()) {
+                *s = s.wrapping_add(*r);
+            }
+        }
+        EChannelAssignment::MidSide => {
+            let (mid, side) = channels.split_at_mut(1);
+            for (m, s) in mid[0].iter_mut().zip(side[0].iter_mut()) {
+                let mid = ((*m as i64) << 1) | ((*s as i64) & 1);
+                let side = *s as i64;
+                *m = ((mid + side) >> 1) as i32;
+                *s = ((mid - side) >> 1) as i32;
+            }
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
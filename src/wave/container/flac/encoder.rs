@@ -0,0 +1,338 @@
+use crate::wave::container::flac::bitio::{zigzag, BitWriter};
+use crate::wave::container::flac::crc::{crc16, crc8};
+use crate::wave::container::flac::EChannelAssignment;
+use itertools::Itertools;
+
+/// 残差のパーティションの最大オーダー
+const MAX_PARTITION_ORDER: u32 = 8;
+/// 4ビットのRiceパラメータで使える最大値。15はエスケープ用。
+const MAX_RICE_PARAMETER_4BITS: u32 = 14;
+/// 5ビットのRiceパラメータで使える最大値。31はエスケープ用。
+const MAX_RICE_PARAMETER_5BITS: u32 = 30;
+
+/// 各チャンネルのサンプル`channels`を1個のフレームにエンコードする。
+///
+/// 各チャンネルのサンプル数は同じで、`bits_per_sample`ビットで表現できる値であること。
+pub(crate) fn encode_frame(channels: &[&[i32]], bits_per_sample: u32, sample_rate: u32, frame_number: u64) -> Vec<u8> {
+    assert!(!channels.is_empty() && channels.len() <= 8);
+    let block_size = channels[0].len();
+    assert!(block_size > 0 && block_size <= (u16::MAX as usize) + 1);
+    assert!(channels.iter().all(|v| v.len() == block_size));
+
+    // サブフレームを決める。ステレオなら相関を取り除いたほうが小さくなるかを調べる。
+    let (assignment, subframes) = if channels.len() == 2 {
+        plan_stereo_subframes(channels[0], channels[1], bits_per_sample)
+    } else {
+        let subframes = channels.iter().map(|v| plan_subframe(v, bits_per_sample)).collect_vec();
+        (EChannelAssignment::Independent(channels.len()), subframes)
+    };
+
+    let mut writer = BitWriter::new();
+    write_frame_header(&mut writer, block_size, sample_rate, assignment, bits_per_sample, frame_number);
+    for subframe in &subframes {
+        subframe.write(&mut writer);
+    }
+
+    // 最後にCRC-16を付ける。
+    writer.align_to_byte();
+    let crc = crc16(writer.bytes());
+    writer.write_bits(crc as u64, 16);
+    writer.into_bytes()
+}
+
+fn write_frame_header(
+    writer: &mut BitWriter,
+    block_size: usize,
+    sample_rate: u32,
+    assignment: EChannelAssignment,
+    bits_per_sample: u32,
+    frame_number: u64,
+) {
+    // 固定ブロックサイズのストラテジーを使う。
+    writer.write_bits(super::FRAME_SYNC_CODE as u64, 15);
+    writer.write_bits(0, 1);
+
+    let (block_size_code, block_size_extra) = match block_size {
+        192 => (1, None),
+        576 | 1152 | 2304 | 4608 => (2 + (block_size / 576).trailing_zeros(), None),
+        256 | 512 | 1024 | 2048 | 4096 | 8192 | 16384 | 32768 => (8 + (block_size / 256).trailing_zeros(), None),
+        1..=256 => (6, Some(((block_size - 1) as u64, 8))),
+        _ => (7, Some(((block_size - 1) as u64, 16))),
+    };
+    let (sample_rate_code, sample_rate_extra) = match sample_rate {
+        88200 => (1, None),
+        176400 => (2, None),
+        192000 => (3, None),
+        8000 => (4, None),
+        16000 => (5, None),
+        22050 => (6, None),
+        24000 => (7, None),
+        32000 => (8, None),
+        44100 => (9, None),
+        48000 => (10, None),
+        96000 => (11, None),
+        v if (v % 1000) == 0 && (v / 1000) <= 0xFF => (12, Some(((v / 1000) as u64, 8))),
+        v if v <= 0xFFFF => (13, Some((v as u64, 16))),
+        v if (v % 10) == 0 && (v / 10) <= 0xFFFF => (14, Some(((v / 10) as u64, 16))),
+        // STREAMINFOのものを使ってもらう。
+        _ => (0, None),
+    };
+    let sample_size_code = match bits_per_sample {
+        8 => 1,
+        12 => 2,
+        16 => 4,
+        20 => 5,
+        24 => 6,
+        32 => 7,
+        _ => 0,
+    };
+
+    writer.write_bits(block_size_code as u64, 4);
+    writer.write_bits(sample_rate_code as u64, 4);
+    writer.write_bits(assignment.as_code() as u64, 4);
+    writer.write_bits(sample_size_code as u64, 3);
+    writer.write_bits(0, 1);
+    writer.write_utf8_coded(frame_number);
+    if let Some((value, bits)) = block_size_extra {
+        writer.write_bits(value, bits);
+    }
+    if let Some((value, bits)) = sample_rate_extra {
+        writer.write_bits(value, bits);
+    }
+
+    // ここまではバイトの境界に揃っている。
+    let crc = crc8(writer.bytes());
+    writer.write_bits(crc as u64, 8);
+}
+
+/// ステレオの場合、4種類のチャンネル割り当ての中から一番小さくなるものを選ぶ。
+fn plan_stereo_subframes(left: &[i32], right: &[i32], bits_per_sample: u32) -> (EChannelAssignment, Vec<SubframePlan>) {
+    let side = left.iter().zip(right).map(|(l, r)| l.wrapping_sub(*r)).collect_vec();
+    let mid = left
+        .iter()
+        .zip(right)
+        .map(|(l, r)| (((*l as i64) + (*r as i64)) >> 1) as i32)
+        .collect_vec();
+
+    let left_plan = plan_subframe(left, bits_per_sample);
+    let right_plan = plan_subframe(right, bits_per_sample);
+    let side_plan = plan_subframe(&side, bits_per_sample + 1);
+    let mid_plan = plan_subframe(&mid, bits_per_sample);
+
+    let independent = left_plan.bits + right_plan.bits;
+    let left_side = left_plan.bits + side_plan.bits;
+    let side_right = side_plan.bits + right_plan.bits;
+    let mid_side = mid_plan.bits + side_plan.bits;
+    let minimum = independent.min(left_side).min(side_right).min(mid_side);
+
+    if minimum == independent {
+        (EChannelAssignment::Independent(2), vec![left_plan, right_plan])
+    } else if minimum == left_side {
+        (EChannelAssignment::LeftSide, vec![left_plan, side_plan])
+    } else if minimum == side_right {
+        (EChannelAssignment::SideRight, vec![side_plan, right_plan])
+    } else {
+        (EChannelAssignment::MidSide, vec![mid_plan, side_plan])
+    }
+}
+
+/// 書き込む前のサブフレームの情報
+struct SubframePlan {
+    kind: ESubframeKind,
+    bits_per_sample: u32,
+    /// サブフレーム全体のビット数
+    bits: usize,
+}
+
+enum ESubframeKind {
+    Constant(i32),
+    Verbatim(Vec<i32>),
+    Fixed {
+        warm_up: Vec<i32>,
+        residual: ResidualPlan,
+    },
+}
+
+/// 残差のRice符号化の情報
+struct ResidualPlan {
+    residuals: Vec<i64>,
+    /// 予測次数。最初のパーティションはこの分だけサンプルが少ない。
+    order: usize,
+    partition_order: u32,
+    parameters: Vec<u32>,
+    /// 5ビットのRiceパラメータを使うか
+    use_5bits_parameter: bool,
+}
+
+impl SubframePlan {
+    fn write(&self, writer: &mut BitWriter) {
+        let bits_per_sample = self.bits_per_sample;
+        match &self.kind {
+            ESubframeKind::Constant(value) => {
+                writer.write_bits(0b0000_0000, 8);
+                writer.write_signed_bits(*value as i64, bits_per_sample);
+            }
+            ESubframeKind::Verbatim(samples) => {
+                writer.write_bits(0b0000_0010, 8);
+                for sample in samples {
+                    writer.write_signed_bits(*sample as i64, bits_per_sample);
+                }
+            }
+            ESubframeKind::Fixed { warm_up, residual } => {
+                // パディング + `001xxx` + Wasted bitsなし
+                writer.write_bits(0b0001_0000 | ((warm_up.len() as u64) << 1), 8);
+                for sample in warm_up {
+                    writer.write_signed_bits(*sample as i64, bits_per_sample);
+                }
+                residual.write(writer);
+            }
+        }
+    }
+}
+
+impl ResidualPlan {
+    fn write(&self, writer: &mut BitWriter) {
+        let parameter_bits = if self.use_5bits_parameter { 5 } else { 4 };
+        writer.write_bits(self.use_5bits_parameter as u64, 2);
+        writer.write_bits(self.partition_order as u64, 4);
+
+        let partition_size = (self.residuals.len() + self.order) >> self.partition_order;
+        let mut start = 0;
+        for (partition_i, parameter) in self.parameters.iter().enumerate() {
+            let count = if partition_i == 0 { partition_size - self.order } else { partition_size };
+            writer.write_bits(*parameter as u64, parameter_bits);
+            for residual in &self.residuals[start..(start + count)] {
+                writer.write_rice_signed(*residual, *parameter);
+            }
+            start += count;
+        }
+    }
+}
+
+/// 1チャンネルのサンプルから一番小さくなりそうなサブフレームを決める。
+fn plan_subframe(samples: &[i32], bits_per_sample: u32) -> SubframePlan {
+    // サブフレームのヘッダーは8ビット
+    const HEADER_BITS: usize = 8;
+
+    // 全部同じ値ならCONSTANTで済む。
+    if samples.iter().all_equal() {
+        return SubframePlan {
+            kind: ESubframeKind::Constant(samples[0]),
+            bits_per_sample,
+            bits: HEADER_BITS + (bits_per_sample as usize),
+        };
+    }
+
+    let mut best = SubframePlan {
+        kind: ESubframeKind::Verbatim(samples.to_vec()),
+        bits_per_sample,
+        bits: HEADER_BITS + (bits_per_sample as usize) * samples.len(),
+    };
+
+    // 固定の多項式予測を次数ごとに試して、一番小さいものを選ぶ。
+    let max_order = (super::FIXED_COEFFICIENTS.len() - 1).min(samples.len() - 1);
+    for order in 0..=max_order {
+        let coefficients = super::FIXED_COEFFICIENTS[order];
+        let residuals = (order..samples.len())
+            .map(|i| {
+                let prediction: i64 = coefficients
+                    .iter()
+                    .enumerate()
+                    .map(|(j, c)| c * (samples[i - 1 - j] as i64))
+                    .sum();
+                (samples[i] as i64) - prediction
+            })
+            .collect_vec();
+
+        let residual = plan_residual(residuals, order, samples.len(), bits_per_sample);
+        let bits = HEADER_BITS + (bits_per_sample as usize) * order + residual.1;
+        if bits < best.bits {
+            best = SubframePlan {
+                kind: ESubframeKind::Fixed {
+                    warm_up: samples[..order].to_vec(),
+                    residual: residual.0,
+                },
+                bits_per_sample,
+                bits,
+            };
+        }
+    }
+
+    best
+}
+
+/// 残差のパーティションとRiceパラメータを決めて、ビット数と一緒に返す。
+fn plan_residual(residuals: Vec<i64>, order: usize, block_size: usize, bits_per_sample: u32) -> (ResidualPlan, usize) {
+    let use_5bits_parameter = bits_per_sample > 16;
+    let parameter_bits = if use_5bits_parameter { 5 } else { 4 };
+    let max_parameter = if use_5bits_parameter {
+        MAX_RICE_PARAMETER_5BITS
+    } else {
+        MAX_RICE_PARAMETER_4BITS
+    };
+    let folded = residuals.iter().map(|v| zigzag(*v)).collect_vec();
+
+    // ブロックサイズを割り切れて、最初のパーティションが予測次数より大きいオーダーだけを試す。
+    let mut best: Option<(u32, Vec<u32>, usize)> = None;
+    for partition_order in 0..=MAX_PARTITION_ORDER {
+        let partition_count = 1usize << partition_order;
+        if !block_size.is_multiple_of(partition_count) || (block_size >> partition_order) <= order {
+            break;
+        }
+
+        let partition_size = block_size >> partition_order;
+        let mut parameters = Vec::with_capacity(partition_count);
+        let mut bits = 6;
+        let mut start = 0;
+        for partition_i in 0..partition_count {
+            let count = if partition_i == 0 { partition_size - order } else { partition_size };
+            let (parameter, partition_bits) = find_rice_parameter(&folded[start..(start + count)], max_parameter);
+            parameters.push(parameter);
+            bits += parameter_bits + partition_bits;
+            start += count;
+        }
+
+        if best.as_ref().is_none_or(|v| bits < v.2) {
+            best = Some((partition_order, parameters, bits));
+        }
+    }
+
+    let (partition_order, parameters, bits) = best.unwrap();
+    let plan = ResidualPlan {
+        residuals,
+        order,
+        partition_order,
+        parameters,
+        use_5bits_parameter,
+    };
+    (plan, bits)
+}
+
+/// パーティションの中で一番ビット数が小さくなるRiceパラメータとそのビット数を返す。
+fn find_rice_parameter(folded: &[u64], max_parameter: u32) -> (u32, usize) {
+    if folded.is_empty() {
+        return (0, 0);
+    }
+
+    // 平均値から大体のパラメータを求めて、その周りだけを正確に計算する。
+    let sum: u64 = folded.iter().sum();
+    let mean = sum / (folded.len() as u64);
+    let estimated = (64 - mean.leading_zeros()).min(max_parameter);
+
+    let mut best = (0, usize::MAX);
+    for parameter in estimated.saturating_sub(1)..=(estimated + 1).min(max_parameter) {
+        let bits: usize = folded
+            .iter()
+            .map(|v| ((v >> parameter) as usize) + 1 + (parameter as usize))
+            .sum();
+        if bits < best.1 {
+            best = (parameter, bits);
+        }
+    }
+
+    best
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
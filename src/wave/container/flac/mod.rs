@@ -0,0 +1,256 @@
+use crate::wave::container::flac::decoder::{decode_frame, EFlacDecodeError};
+use crate::wave::container::flac::encoder::encode_frame;
+use crate::wave::container::flac::streaminfo::FlacStreamInfo;
+use crate::wave::container::{convert_i32_to_uniformed_sample, convert_uniformed_sample_to_i32};
+use crate::wave::sample::UniformedSample;
+use itertools::Itertools;
+use std::io;
+
+// FLAC (Free Lossless Audio Codec)
+// https://www.rfc-editor.org/rfc/rfc9639.html
+mod bitio;
+mod crc;
+mod decoder;
+mod encoder;
+pub mod stream;
+pub mod streaminfo;
+
+/// FLACファイルの先頭の`fLaC`マーカー
+pub(crate) const FLAC_MARKER: [u8; 4] = [b'f', b'L', b'a', b'C'];
+/// `fLaC`マーカーと`STREAMINFO`メタデータブロックを含めたヘッダーのサイズ
+pub(crate) const FLAC_HEADER_SIZE: usize = 4 + 4 + FlacStreamInfo::STRUCTURE_SIZE;
+/// フレームの先頭の同期コード（14ビット）と予約ビット
+const FRAME_SYNC_CODE: u32 = 0b111_1111_1111_1100;
+/// エンコードする時のブロックサイズ（1チャンネルあたりのサンプル数）
+pub const FLAC_DEFAULT_BLOCK_SIZE: usize = 4096;
+/// 固定の多項式予測の係数。次数ごとに並んでいる。
+const FIXED_COEFFICIENTS: [&[i64]; 5] = [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+
+/// フレームのチャンネルの割り当て
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EChannelAssignment {
+    /// 各チャンネルを独立して持つ。中身はチャンネル数。
+    Independent(usize),
+    /// 左とサイド（左 - 右）
+    LeftSide,
+    /// サイド（左 - 右）と右
+    SideRight,
+    /// ミッド（(左 + 右) / 2）とサイド（左 - 右）
+    MidSide,
+}
+
+impl EChannelAssignment {
+    fn from_code(code: u32) -> Option<Self> {
+        match code {
+            0..=7 => Some(Self::Independent((code + 1) as usize)),
+            8 => Some(Self::LeftSide),
+            9 => Some(Self::SideRight),
+            10 => Some(Self::MidSide),
+            _ => None,
+        }
+    }
+
+    fn as_code(&self) -> u32 {
+        match self {
+            Self::Independent(channels) => (*channels as u32) - 1,
+            Self::LeftSide => 8,
+            Self::SideRight => 9,
+            Self::MidSide => 10,
+        }
+    }
+
+    fn channel_count(&self) -> usize {
+        match self {
+            Self::Independent(channels) => *channels,
+            _ => 2,
+        }
+    }
+
+    /// サイドチャンネルは1ビット多く使うので、`channel_i`のチャンネルの追加ビット数を返す。
+    fn extra_bits_of(&self, channel_i: usize) -> u32 {
+        match (self, channel_i) {
+            (Self::LeftSide, 1) | (Self::SideRight, 0) | (Self::MidSide, 1) => 1,
+            _ => 0,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+// CONTAINER
+//
+// ----------------------------------------------------------------------------
+
+/// FLACの音源の情報を保持するコンテナ。
+/// [`WaveContainer`](super::WaveContainer)と同じく、サンプルは平準化して全部メモリに持つ。
+#[derive(Debug)]
+pub struct FlacContainer {
+    stream_info: FlacStreamInfo,
+    /// 音源のバッファを平準化して保持する。チャンネルはインターリーブされている。
+    uniformed_buffer: Vec<UniformedSample>,
+}
+
+impl FlacContainer {
+    /// `reader`からFLACファイルを全部読み込んでデコードする。
+    pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
+    where
+        T: io::Read + io::Seek,
+    {
+        let stream_info = FlacStreamInfo::from_bufread(reader)?;
+
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer).ok()?;
+
+        let mut uniformed_buffer = vec![];
+        let mut position = 0;
+        while position < buffer.len() {
+            let frame = match decode_frame(&buffer[position..], &stream_info) {
+                Ok(frame) => frame,
+                Err(EFlacDecodeError::NeedMoreData) => break,
+                Err(EFlacDecodeError::Invalid(_)) => return None,
+            };
+
+            append_interleaved_samples(&mut uniformed_buffer, &frame.channels, stream_info.bits_per_sample);
+            position += frame.byte_size;
+        }
+
+        Some(Self {
+            stream_info,
+            uniformed_buffer,
+        })
+    }
+
+    /// [`FlacContainer`]の情報を[`io::Write`]ストリームにFLACとしてエンコードして書き込む。
+    ///
+    /// `writer`のflush動作などは行わない。
+    pub fn write<T>(&self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let mut stream_writer = stream::FlacStreamWriter::new(
+            self.stream_info.sample_rate,
+            self.stream_info.bits_per_sample,
+            self.stream_info.channels as usize,
+        )
+        .unwrap();
+
+        stream_writer.write_header(writer);
+        stream_writer.write_frames(writer, &self.uniformed_buffer);
+        stream_writer.finish(writer);
+    }
+
+    /// 各サンプルに適用する量子化ビットを返す。
+    pub fn bits_per_sample(&self) -> u32 {
+        self.stream_info.bits_per_sample
+    }
+
+    /// サウンドの秒ごとのサンプル数を返す。
+    pub fn samples_per_second(&self) -> u32 {
+        self.stream_info.sample_rate
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.stream_info.channels
+    }
+
+    /// サウンドの全体長さを秒数で返す。
+    pub fn sound_length(&self) -> f64 {
+        let items_per_sec = (self.samples_per_second() as usize) * (self.channel() as usize);
+        (self.uniformed_buffer.len() as f64) / (items_per_sec as f64)
+    }
+
+    /// 平準化したサンプルのバッファを返す。チャンネルはインターリーブされている。
+    pub fn uniformed_sample_buffer(&self) -> &'_ [UniformedSample] {
+        &self.uniformed_buffer
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+// BUILDER
+//
+// ----------------------------------------------------------------------------
+
+pub struct FlacBuilder {
+    pub samples_per_sec: u32,
+    pub bits_per_sample: u16,
+}
+
+impl FlacBuilder {
+    pub fn build_mono(&self, uniformed_samples: Vec<UniformedSample>) -> Option<FlacContainer> {
+        self.build(uniformed_samples, 1)
+    }
+
+    pub fn build_stereo(&self, left: Vec<UniformedSample>, right: Vec<UniformedSample>) -> Option<FlacContainer> {
+        assert_eq!(left.len(), right.len());
+        let uniformed_buffer = left.into_iter().interleave(right).collect_vec();
+        self.build(uniformed_buffer, 2)
+    }
+
+    fn build(&self, uniformed_buffer: Vec<UniformedSample>, channels: u32) -> Option<FlacContainer> {
+        if !is_supported_bits_per_sample(self.bits_per_sample as u32) {
+            return None;
+        }
+        if self.samples_per_sec == 0 {
+            return None;
+        }
+
+        let stream_info = FlacStreamInfo {
+            min_block_size: FLAC_DEFAULT_BLOCK_SIZE as u16,
+            max_block_size: FLAC_DEFAULT_BLOCK_SIZE as u16,
+            sample_rate: self.samples_per_sec,
+            channels,
+            bits_per_sample: self.bits_per_sample as u32,
+            total_samples: (uniformed_buffer.len() / (channels as usize)) as u64,
+            ..Default::default()
+        };
+        Some(FlacContainer {
+            stream_info,
+            uniformed_buffer,
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+//
+// HELPERS
+//
+// ----------------------------------------------------------------------------
+
+/// エンコードできる量子化ビットか？
+pub(crate) fn is_supported_bits_per_sample(bits_per_sample: u32) -> bool {
+    (4..=24).contains(&bits_per_sample)
+}
+
+/// デコードした各チャンネルのサンプルをインターリーブして`buffer`の後ろに追加する。
+fn append_interleaved_samples(buffer: &mut Vec<UniformedSample>, channels: &[Vec<i32>], bits_per_sample: u32) {
+    let block_size = channels.first().map_or(0, |v| v.len());
+    buffer.reserve(block_size * channels.len());
+    for sample_i in 0..block_size {
+        for channel in channels {
+            buffer.push(convert_i32_to_uniformed_sample(channel[sample_i], bits_per_sample));
+        }
+    }
+}
+
+/// インターリーブされた`samples`をチャンネルごとの整数のバッファに分ける。
+fn split_into_channels(samples: &[UniformedSample], channels: usize, bits_per_sample: u32) -> Vec<Vec<i32>> {
+    let mut result = vec![Vec::with_capacity(samples.len() / channels); channels];
+    for frame in samples.chunks_exact(channels) {
+        for (dst, sample) in result.iter_mut().zip(frame) {
+            dst.push(convert_uniformed_sample_to_i32(*sample, bits_per_sample));
+        }
+    }
+    result
+}
+
+/// 各チャンネルのバッファを1個のフレームにエンコードする。
+fn encode_channels(channels: &[Vec<i32>], bits_per_sample: u32, sample_rate: u32, frame_number: u64) -> Vec<u8> {
+    let channels = channels.iter().map(|v| v.as_slice()).collect_vec();
+    encode_frame(&channels, bits_per_sample, sample_rate, frame_number)
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,280 @@
+use crate::wave::container::flac::decoder::{decode_frame, EFlacDecodeError};
+use crate::wave::container::flac::streaminfo::FlacStreamInfo;
+use crate::wave::container::flac::{
+    append_interleaved_samples, encode_channels, is_supported_bits_per_sample, split_into_channels,
+    FLAC_DEFAULT_BLOCK_SIZE,
+};
+use crate::wave::sample::UniformedSample;
+use std::io;
+
+// ----------------------------------------------------------------------------
+// FlacStreamReader
+// ----------------------------------------------------------------------------
+
+/// [`FlacContainer`](super::FlacContainer)と違って全部デコードせずに、
+/// 必要な時に必要な分だけフレームをデコードしていくための構造体。
+///
+/// FLACはフレームの長さが可変なので、[`WaveStreamHeader`](crate::wave::container::stream::WaveStreamHeader)と違って
+/// 前から順番にしか読み込めない。
+#[derive(Debug, Clone)]
+pub struct FlacStreamReader {
+    stream_info: FlacStreamInfo,
+    /// 次にデコードするフレームのファイル上のバイト位置
+    next_frame_position: u64,
+    /// デコードしたけどまだ返してないサンプル。チャンネルはインターリーブされている。
+    pending: Vec<UniformedSample>,
+    /// ファイルの最後まで読み込んだか
+    is_end: bool,
+}
+
+impl FlacStreamReader {
+    /// フレームを読み込む時の最初のバッファサイズ
+    const INITIAL_READ_SIZE: usize = 16384;
+
+    /// `reader`からメタデータだけを読み込む。
+    pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
+    where
+        T: io::Read + io::Seek,
+    {
+        let stream_info = FlacStreamInfo::from_bufread(reader)?;
+        let next_frame_position = reader.stream_position().ok()?;
+
+        Some(Self {
+            stream_info,
+            next_frame_position,
+            pending: vec![],
+            is_end: false,
+        })
+    }
+
+    /// サウンドの秒ごとのサンプル数を返す。
+    pub fn samples_per_second(&self) -> u32 {
+        self.stream_info.sample_rate
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.stream_info.channels
+    }
+
+    /// 各サンプルに適用する量子化ビットを返す。
+    pub fn bits_per_sample(&self) -> u32 {
+        self.stream_info.bits_per_sample
+    }
+
+    /// 全チャンネルを含むフレームの総数を返す。不明なら0を返す。
+    pub fn frame_count(&self) -> usize {
+        self.stream_info.total_samples as usize
+    }
+
+    /// 前回の続きから`frame_count`分のフレームを読み込む。
+    ///
+    /// 返すバッファはチャンネルがインターリーブされている。
+    /// ファイルの最後まで読み込んだら、返すバッファは要求分より短くなる。
+    pub fn read_frames<T>(&mut self, reader: &mut T, frame_count: usize) -> Vec<UniformedSample>
+    where
+        T: io::Read + io::Seek,
+    {
+        let required_count = frame_count * (self.channel() as usize);
+        while self.pending.len() < required_count && !self.is_end {
+            self.decode_next_frame(reader);
+        }
+
+        let drain_count = required_count.min(self.pending.len());
+        self.pending.drain(..drain_count).collect()
+    }
+
+    /// 前回の続きから`frame_count`分のフレームを読み飛ばす。
+    pub fn skip_frames<T>(&mut self, reader: &mut T, frame_count: usize)
+    where
+        T: io::Read + io::Seek,
+    {
+        let _ = self.read_frames(reader, frame_count);
+    }
+
+    /// 次のフレームを1個デコードして`pending`に追加する。
+    fn decode_next_frame<T>(&mut self, reader: &mut T)
+    where
+        T: io::Read + io::Seek,
+    {
+        reader
+            .seek(io::SeekFrom::Start(self.next_frame_position))
+            .expect("Failed to seek reader.");
+
+        // フレームの最大サイズがわかればその分だけ、わからなければ足りるまで読み込む。
+        let mut read_size = match self.stream_info.max_frame_size {
+            0 => Self::INITIAL_READ_SIZE,
+            v => v as usize,
+        };
+        let mut buffer = vec![];
+        loop {
+            let read_start = buffer.len();
+            buffer.resize(read_size, 0);
+            let read_count = read_fully(reader, &mut buffer[read_start..]);
+            buffer.truncate(read_start + read_count);
+
+            match decode_frame(&buffer, &self.stream_info) {
+                Ok(frame) => {
+                    let bits_per_sample = self.bits_per_sample();
+                    append_interleaved_samples(&mut self.pending, &frame.channels, bits_per_sample);
+                    self.next_frame_position += frame.byte_size as u64;
+                    return;
+                }
+                Err(EFlacDecodeError::NeedMoreData) if buffer.len() == read_size => {
+                    read_size <<= 1;
+                }
+                // ファイルの最後か、壊れたフレームならそこで終わりにする。
+                Err(_) => {
+                    self.is_end = true;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// `buffer`がいっぱいになるか、ファイルの最後に到達するまで読み込む。
+fn read_fully<T>(reader: &mut T, buffer: &mut [u8]) -> usize
+where
+    T: io::Read,
+{
+    let mut read_count = 0;
+    while read_count < buffer.len() {
+        match reader.read(&mut buffer[read_count..]) {
+            Ok(0) => break,
+            Ok(count) => read_count += count,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => panic!("Failed to read buffer. {e}"),
+        }
+    }
+    read_count
+}
+
+// ----------------------------------------------------------------------------
+// FlacStreamWriter
+// ----------------------------------------------------------------------------
+
+/// 先にヘッダーだけを書き込んでおいて、サンプルがブロックサイズ分溜まるたびに
+/// フレームにエンコードしてファイルの後ろに追記していくための構造体。
+///
+/// `STREAMINFO`の総サンプル数とフレームサイズは[`FlacStreamWriter::write_header`]で更新する。
+#[derive(Debug, Clone)]
+pub struct FlacStreamWriter {
+    stream_info: FlacStreamInfo,
+    /// まだフレームにしてない各チャンネルのサンプル
+    pending: Vec<Vec<i32>>,
+    /// 次のフレーム番号
+    next_frame_number: u64,
+}
+
+impl FlacStreamWriter {
+    /// 設定から作る。量子化ビットは4から24ビットまで対応。
+    pub fn new(samples_per_sec: u32, bits_per_sample: u32, channels: usize) -> Option<Self> {
+        if !is_supported_bits_per_sample(bits_per_sample) {
+            return None;
+        }
+        if samples_per_sec == 0 || channels == 0 || channels > 8 {
+            return None;
+        }
+
+        let stream_info = FlacStreamInfo {
+            min_block_size: FLAC_DEFAULT_BLOCK_SIZE as u16,
+            max_block_size: FLAC_DEFAULT_BLOCK_SIZE as u16,
+            sample_rate: samples_per_sec,
+            channels: channels as u32,
+            bits_per_sample,
+            ..Default::default()
+        };
+        Some(Self {
+            stream_info,
+            pending: vec![vec![]; channels],
+            next_frame_number: 0,
+        })
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.stream_info.channels
+    }
+
+    /// フレームにエンコードしてファイルに書き込んだサンプル数（1チャンネルあたり）を返す。
+    pub fn written_frame_count(&self) -> usize {
+        self.stream_info.total_samples as usize
+    }
+
+    /// 今までの情報で`fLaC`マーカーと`STREAMINFO`をファイルの先頭に書き込む。
+    /// 書き込んだ後はカーソルをファイルの最後に移動する。
+    pub fn write_header<T>(&self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        writer.seek(io::SeekFrom::Start(0)).expect("Failed to seek writer.");
+        self.stream_info.write(writer);
+        writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+    }
+
+    /// インターリーブされた`samples`を追加して、ブロックサイズ分溜まったらファイルの最後に追記する。
+    /// `samples`の長さはチャンネル数の倍数であること。
+    pub fn write_frames<T>(&mut self, writer: &mut T, samples: &[UniformedSample])
+    where
+        T: io::Write + io::Seek,
+    {
+        let channels = self.channel() as usize;
+        assert_eq!(samples.len() % channels, 0);
+
+        let splitted = split_into_channels(samples, channels, self.stream_info.bits_per_sample);
+        for (dst, mut src) in self.pending.iter_mut().zip(splitted) {
+            dst.append(&mut src);
+        }
+
+        while self.pending[0].len() >= FLAC_DEFAULT_BLOCK_SIZE {
+            self.flush_block(writer, FLAC_DEFAULT_BLOCK_SIZE);
+        }
+    }
+
+    /// 残っているサンプルを最後のフレームとして書き込んで、ヘッダーを更新する。
+    pub fn finish<T>(&mut self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let remained = self.pending[0].len();
+        if remained > 0 {
+            self.flush_block(writer, remained);
+        }
+
+        self.write_header(writer);
+    }
+
+    /// `pending`の先頭から`block_size`分を1個のフレームにして書き込む。
+    fn flush_block<T>(&mut self, writer: &mut T, block_size: usize)
+    where
+        T: io::Write + io::Seek,
+    {
+        let block = self.pending.iter_mut().map(|v| v.drain(..block_size).collect()).collect::<Vec<Vec<i32>>>();
+        let frame = encode_channels(
+            &block,
+            self.stream_info.bits_per_sample,
+            self.stream_info.sample_rate,
+            self.next_frame_number,
+        );
+
+        writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+        writer.write_all(&frame).expect("Failed to write FLAC frame to writer.");
+
+        // STREAMINFOの情報を更新する。
+        let frame_size = frame.len() as u32;
+        let info = &mut self.stream_info;
+        info.min_frame_size = if info.min_frame_size == 0 {
+            frame_size
+        } else {
+            info.min_frame_size.min(frame_size)
+        };
+        info.max_frame_size = info.max_frame_size.max(frame_size);
+        info.total_samples += block_size as u64;
+        self.next_frame_number += 1;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,149 @@
+use crate::wave::container::flac::bitio::{BitReader, BitWriter};
+use std::io;
+
+/// FLACファイルの先頭に必ず入っている`STREAMINFO`メタデータブロックの情報。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlacStreamInfo {
+    /// 最後のブロックを除いた、最小のブロックサイズ（サンプル数）
+    pub min_block_size: u16,
+    /// 最大のブロックサイズ（サンプル数）
+    pub max_block_size: u16,
+    /// 最小のフレームサイズ（バイト数）。0なら不明。
+    pub min_frame_size: u32,
+    /// 最大のフレームサイズ（バイト数）。0なら不明。
+    pub max_frame_size: u32,
+    pub sample_rate: u32,
+    pub channels: u32,
+    pub bits_per_sample: u32,
+    /// 1チャンネルあたりの総サンプル数。0なら不明。
+    pub total_samples: u64,
+    /// デコードしたサンプルのMD5。全部0なら不明。
+    pub md5: [u8; 16],
+}
+
+impl FlacStreamInfo {
+    /// `STREAMINFO`ブロックの中身のサイズ
+    pub const STRUCTURE_SIZE: usize = 34;
+    /// メタデータブロックのタイプ
+    const BLOCK_TYPE: u8 = 0;
+
+    /// ブロックの中身から`Self`を作る。
+    pub fn from_bytes(buffer: &[u8]) -> Option<Self> {
+        if buffer.len() < Self::STRUCTURE_SIZE {
+            return None;
+        }
+
+        let mut reader = BitReader::new(buffer);
+        let mut info = Self {
+            min_block_size: reader.read_bits(16)? as u16,
+            max_block_size: reader.read_bits(16)? as u16,
+            min_frame_size: reader.read_bits(24)? as u32,
+            max_frame_size: reader.read_bits(24)? as u32,
+            sample_rate: reader.read_bits(20)? as u32,
+            channels: (reader.read_bits(3)? as u32) + 1,
+            bits_per_sample: (reader.read_bits(5)? as u32) + 1,
+            total_samples: reader.read_bits(36)?,
+            md5: [0u8; 16],
+        };
+        info.md5.copy_from_slice(&buffer[18..Self::STRUCTURE_SIZE]);
+
+        Some(info)
+    }
+
+    /// ブロックの中身をバイト列に変換する。
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        writer.write_bits(self.min_block_size as u64, 16);
+        writer.write_bits(self.max_block_size as u64, 16);
+        writer.write_bits(self.min_frame_size as u64, 24);
+        writer.write_bits(self.max_frame_size as u64, 24);
+        writer.write_bits(self.sample_rate as u64, 20);
+        writer.write_bits((self.channels - 1) as u64, 3);
+        writer.write_bits((self.bits_per_sample - 1) as u64, 5);
+        writer.write_bits(self.total_samples >> 32, 4);
+        writer.write_bits(self.total_samples & 0xFFFF_FFFF, 32);
+
+        let mut buffer = writer.into_bytes();
+        buffer.extend_from_slice(&self.md5);
+        assert_eq!(buffer.len(), Self::STRUCTURE_SIZE);
+        buffer
+    }
+
+    /// `fLaC`マーカーと最後のメタデータブロックとして`STREAMINFO`を書き込む。
+    /// 全部で[`FLAC_HEADER_SIZE`](super::FLAC_HEADER_SIZE)バイトになる。
+    pub fn write<T>(&self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let mut buffer = Vec::with_capacity(super::FLAC_HEADER_SIZE);
+        buffer.extend_from_slice(&super::FLAC_MARKER);
+        // 最後のブロックのフラグ + タイプ、そして24ビットのサイズ。
+        buffer.push(0x80 | Self::BLOCK_TYPE);
+        buffer.extend_from_slice(&(Self::STRUCTURE_SIZE as u32).to_be_bytes()[1..]);
+        buffer.extend_from_slice(&self.to_bytes());
+
+        writer.write_all(&buffer).expect("Failed to write FlacStreamInfo to writer.");
+    }
+
+    /// `reader`から`fLaC`マーカーとメタデータブロックを読み込んで、`STREAMINFO`を返す。
+    /// 他のメタデータブロックは読み飛ばすので、読み込んだ後のカーソルは最初のフレームの位置になる。
+    pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
+    where
+        T: io::Read + io::Seek,
+    {
+        skip_id3v2_tag(reader)?;
+
+        let mut marker = [0u8; 4];
+        reader.read_exact(&mut marker).ok()?;
+        if marker != super::FLAC_MARKER {
+            return None;
+        }
+
+        let mut stream_info = None;
+        loop {
+            let mut block_header = [0u8; 4];
+            reader.read_exact(&mut block_header).ok()?;
+            let is_last = (block_header[0] & 0x80) != 0;
+            let block_type = block_header[0] & 0x7F;
+            let block_size = u32::from_be_bytes([0, block_header[1], block_header[2], block_header[3]]) as usize;
+
+            if block_type == Self::BLOCK_TYPE {
+                let mut buffer = vec![0u8; block_size];
+                reader.read_exact(&mut buffer).ok()?;
+                stream_info = Self::from_bytes(&buffer);
+            } else {
+                // VORBIS_COMMENTやSEEKTABLEなどは今は使わない。
+                reader.seek(io::SeekFrom::Current(block_size as i64)).ok()?;
+            }
+
+            if is_last {
+                break;
+            }
+        }
+
+        stream_info
+    }
+}
+
+/// ファイルの先頭にID3v2タグがあれば読み飛ばす。
+fn skip_id3v2_tag<T>(reader: &mut T) -> Option<()>
+where
+    T: io::Read + io::Seek,
+{
+    let mut header = [0u8; 10];
+    let start = reader.stream_position().ok()?;
+    let read_size = reader.read(&mut header).ok()?;
+    if read_size < header.len() || &header[..3] != b"ID3" {
+        reader.seek(io::SeekFrom::Start(start)).ok()?;
+        return Some(());
+    }
+
+    // サイズは各バイトの下位7ビットだけを使う。
+    let tag_size = header[6..10].iter().fold(0u64, |acc, v| (acc << 7) | ((*v & 0x7F) as u64));
+    reader.seek(io::SeekFrom::Start(start + 10 + tag_size)).ok()?;
+    Some(())
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
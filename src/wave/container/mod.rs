@@ -10,6 +10,7 @@ use super::{
     sample::UniformedSample,
     stretch::time::{TimeStretcherBufferSetting, TimeStretcherBuilder},
 };
+use crate::wave::container::wav::adpcm::EAdpcmFormat;
 use crate::wave::container::wav::bext::LowWaveBextHeader;
 use crate::wave::container::wav::junk::LowWaveJunkHeader;
 use crate::wave::container::wav::qlty::LowWaveQualityHeader;
@@ -18,6 +19,9 @@ use num_traits::Zero;
 use std::io;
 use std::ops::BitAnd;
 
+pub mod aiff;
+pub mod flac;
+pub mod stream;
 pub mod wav;
 
 // ----------------------------------------------------------------------------
@@ -49,131 +53,49 @@ impl WaveContainer {
     where
         T: io::Read + io::Seek,
     {
-        const MINIMUM_SIZE: usize = size_of::<LowWaveRiffHeader>()
-            + size_of::<LowWaveFormatHeader>()
-            + size_of::<LowWaveFactChunk>()
-            + size_of::<LowWaveDataChunk>();
-
-        // readerの大きさを計算して判定を行う。
-        {
-            let reader_length = reader.seek(io::SeekFrom::End(0)).expect("Failed to seek reader.") as usize;
-            reader.rewind().expect("Failed to rewind reader.");
-            if MINIMUM_SIZE > reader_length {
-                // Chunkのサイズが足りなければ、そもそも読み込む必要はない。
-                return None;
-            }
-        }
-
-        // 情報を取得する。
-        let mut wave_riff_header = None;
-        let mut wave_fmt_header = None;
-        let mut wave_fact_chunk = None;
-        let mut wave_bext_header = None;
-        let mut wave_qlty_header = None;
-        loop {
-            let id = try_read_wave_header_id_str(reader);
-            match id.as_str() {
-                "RIFF" => {
-                    wave_riff_header =
-                        Some(LowWaveRiffHeader::from_bufread(reader).expect("Failed to get riff header."));
-                }
-                "fmt " => {
-                    wave_fmt_header =
-                        Some(LowWaveFormatHeader::from_bufread(reader).expect("Failed to get fmt header."));
-                }
-                "fact" => {
-                    wave_fact_chunk = Some(LowWaveFactChunk::from_bufread(reader).expect("Failed to get fact chunk."))
-                }
-                "bext" => {
-                    // 25-01-08 放送業界(EBC)で決めたWav拡張ヘッダーらしい。
-                    // このプログラムではまだ活用しない。
-                    // bext, 4bytesで次に来るチャンクの大きさ、そしてチャンクのデータがくる。
-                    wave_bext_header =
-                        Some(LowWaveBextHeader::from_bufread(reader).expect("Failed to get bext chunk."));
-                }
-                "junk" => {
-                    // 25-01-08
-                    let _junk_header =
-                        Some(LowWaveJunkHeader::from_bufread(reader).expect("Failed to get junk chunk."));
-                }
-                "qlty" => {
-                    // 25-01-09
-                    wave_qlty_header =
-                        Some(LowWaveQualityHeader::from_bufread(reader).expect("Failed to get qlty chunk."));
-                }
-                "data" => {
-                    break; // data以降はデータしか含まないはず。
-                }
-                _ => unreachable!("Unexpected header ID."),
-            }
-        }
-
-        let wave_data_chunk = LowWaveDataChunk::from_bufread(reader).expect("Failed to get data chunk");
-        debug_assert!(wave_fmt_header.is_some());
-
-        let wave_fmt_header = wave_fmt_header.unwrap();
+        let headers = read_wave_headers(reader)?;
 
         // 最後に実際データが入っているバッファーを読み取る。
         let mut buffer = vec![];
         reader.read_to_end(&mut buffer).expect("Failed to read buffer.");
 
         // bufferの各ブロックから`UniformedSample`に変換する。
-        let unit_block_size = wave_fmt_header.unit_block_size();
-        let bits_per_sample = wave_fmt_header.bits_per_sample as usize;
-        let uniformed_buffer = match bits_per_sample {
-            16 => {
-                // LPCM 16bits
-                //
-                // 16Bitsは [-32768, 32768)の範囲を持つ。
-                // 読み取ったバッファーからブロックサイズと量子化ビットサイズに合わせてsliceに変換する。
-                let p_buffer = buffer.as_ptr() as *const i16;
-                let data_count = (wave_data_chunk.data_chunk_size as usize) / unit_block_size;
-                let buffer_slice = unsafe { std::slice::from_raw_parts(p_buffer, data_count) };
-
-                buffer_slice.iter().map(|&v| UniformedSample::from_16bits(v)).collect_vec()
+        let data_size = (headers.data.data_chunk_size as usize).min(buffer.len());
+        if let Some(adpcm) = headers.adpcm.as_ref() {
+            // ADPCMはデコードして16ビットのLPCMとして持つ。
+            let channels = headers.fmt.channel as usize;
+            let mut decoded = adpcm.decode_blocks(&buffer[..data_size], headers.fmt.block_size(), channels);
+            if let Some(fact) = headers.fact.as_ref() {
+                decoded.truncate((fact.sample_length() as usize) * channels);
             }
-            24 => {
-                // LPCM 24bits
-                //
-                // −8,388,608 to +8,388,607を持つ。
-                // 一つのサンプルが3Bytesパッキングされているので、慎重に読み取る。
-                let data_count = (wave_data_chunk.data_chunk_size as usize) / unit_block_size;
-
-                // ここ最適化できそうだけど、今は愚直な方法で。
-                // 実は元バッファーのSliceを渡して変換することもできるという。
-                let mut raw_buffer = vec![];
-                raw_buffer.reserve(data_count);
-                for i in 0..data_count {
-                    let start_i = 3 * i;
-                    let raw_sample = &buffer[start_i..(start_i + 3)];
-                    // データの入り方がBig Endianになっているので、sample[2]の一番前のビットが1なら負の数扱いにする。
-                    // この辺ちょっとめんどくさい。
-                    let offset = if raw_sample[2].bitand(0b10000000).is_zero() { 0x00 } else { 0xFF };
-                    let raw_sample = [offset, raw_sample[2], raw_sample[1], raw_sample[0]];
-                    let raw_sample = i32::from_be_bytes(raw_sample);
 
-                    // チェック
-                    debug_assert!(raw_sample >= -8_388_608);
-                    debug_assert!(raw_sample < 8_388_608);
-                    // 3Bytesすすむ。
-                    raw_buffer.push(raw_sample);
-                }
+            let fmt = LowWaveFormatHeader::from_builder(fmt::EBuilder::Normal {
+                samples_per_sec: headers.fmt.samples_per_sec,
+                bits_per_sample: 16,
+                channels,
+            });
+            let data = LowWaveDataChunk::from_chunk_size((decoded.len() * 2) as u32);
+            return Some(WaveContainer {
+                riff: LowWaveRiffHeader::from_data_chunk(&data),
+                fmt,
+                bext: headers.bext,
+                qlty: headers.qlty,
+                fact: None,
+                data,
+                uniformed_buffer: decoded.into_iter().map(UniformedSample::from_16bits).collect_vec(),
+            });
+        }
 
-                raw_buffer
-                    .into_iter()
-                    .map(|v| UniformedSample::from_i32_as_24bit(v))
-                    .collect_vec()
-            }
-            _ => unreachable!("Unexpected branch"),
-        };
+        let bits_per_sample = headers.fmt.bits_per_sample as usize;
+        let uniformed_buffer = convert_lpcm_to_uniformed_samples(&buffer[..data_size], bits_per_sample);
 
         Some(WaveContainer {
-            riff: wave_riff_header.unwrap(),
-            fmt: wave_fmt_header,
-            bext: wave_bext_header,
-            qlty: wave_qlty_header,
-            fact: wave_fact_chunk,
-            data: wave_data_chunk,
+            riff: headers.riff,
+            fmt: headers.fmt,
+            bext: headers.bext,
+            qlty: headers.qlty,
+            fact: headers.fact,
+            data: headers.data,
             uniformed_buffer,
         })
     }
@@ -222,7 +144,10 @@ impl WaveContainer {
         // そしてバッファーから量子化ビットとブロックサイズに合わせて別リストに変換し書き込ませる。
         let bits_per_sample = self.bits_per_sample();
         match self.fmt.format_type() {
-            fmt::EWavFormatType::Unknown => unreachable!(),
+            fmt::EWavFormatType::Unknown | fmt::EWavFormatType::MsAdpcm | fmt::EWavFormatType::ImaAdpcm => {
+                // ADPCMは読み込む時にLPCMに変換しているので、ここには来ない。
+                unreachable!()
+            }
             fmt::EWavFormatType::LPCM => {
                 if bits_per_sample == 16 {
                     assert_eq!(unit_block_size, 2);
@@ -324,6 +249,220 @@ impl WaveContainer {
     }
 }
 
+// ----------------------------------------------------------------------------
+//
+// HEADERS
+//
+// ----------------------------------------------------------------------------
+
+/// Wavファイルの`data`チャンクまでのヘッダー情報をまとめたもの。
+pub(crate) struct WaveHeaders {
+    pub riff: LowWaveRiffHeader,
+    pub fmt: LowWaveFormatHeader,
+    pub bext: Option<LowWaveBextHeader>,
+    pub qlty: Option<LowWaveQualityHeader>,
+    pub fact: Option<LowWaveFactChunk>,
+    pub data: LowWaveDataChunk,
+    /// ADPCMの場合、`fmt`チャンクの拡張部分から読み込んだ情報。
+    pub adpcm: Option<EAdpcmFormat>,
+}
+
+/// `reader`の最初から`data`チャンクのヘッダーまでを読み込む。
+/// 成功したら`reader`のカーソルは`data`チャンクのサンプルの最初の位置にある。
+pub(crate) fn read_wave_headers<T>(reader: &mut T) -> Option<WaveHeaders>
+where
+    T: io::Read + io::Seek,
+{
+    // `fact`チャンクはなくてもいいので、最小サイズには含めない。
+    const MINIMUM_SIZE: usize =
+        size_of::<LowWaveRiffHeader>() + size_of::<LowWaveFormatHeader>() + size_of::<LowWaveDataChunk>();
+
+    // readerの大きさを計算して判定を行う。
+    let reader_length = reader.seek(io::SeekFrom::End(0)).ok()?;
+    reader.rewind().ok()?;
+    if (MINIMUM_SIZE as u64) > reader_length {
+        // Chunkのサイズが足りなければ、そもそも読み込む必要はない。
+        return None;
+    }
+
+    // 情報を取得する。
+    let mut wave_riff_header = None;
+    let mut wave_fmt_header = None;
+    let mut wave_fact_chunk = None;
+    let mut wave_bext_header = None;
+    let mut wave_qlty_header = None;
+    let mut wave_adpcm_format = None;
+    let mut wave_fmt_extension = vec![];
+    loop {
+        // チャンクのIDとサイズも読めなければ、`data`チャンクがないとみなす。
+        if reader.stream_position().ok()? + 8 > reader_length {
+            return None;
+        }
+
+        let id = try_read_wave_header_id_str(reader);
+        if wave_riff_header.is_none() && id != "RIFF" {
+            // WAVではないファイルは読み込めない。
+            return None;
+        }
+        match id.as_str() {
+            "RIFF" => {
+                wave_riff_header = Some(LowWaveRiffHeader::from_bufread(reader).expect("Failed to get riff header."));
+            }
+            "fmt " => {
+                let fmt_header = LowWaveFormatHeader::from_bufread(reader).expect("Failed to get fmt header.");
+
+                // 拡張部分があれば読み込む。ADPCMの場合はデコードに必要な情報が入っている。
+                let mut extension = vec![0u8; fmt_header.extension_size()];
+                reader.read_exact(&mut extension).expect("Failed to read fmt extension.");
+                wave_adpcm_format = EAdpcmFormat::from_extension(&fmt_header, &extension);
+                wave_fmt_header = Some(fmt_header);
+                wave_fmt_extension = extension;
+            }
+            "fact" => {
+                wave_fact_chunk = Some(LowWaveFactChunk::from_bufread(reader).expect("Failed to get fact chunk."))
+            }
+            "bext" => {
+                // 25-01-08 放送業界(EBC)で決めたWav拡張ヘッダーらしい。
+                // このプログラムではまだ活用しない。
+                // bext, 4bytesで次に来るチャンクの大きさ、そしてチャンクのデータがくる。
+                wave_bext_header = Some(LowWaveBextHeader::from_bufread(reader).expect("Failed to get bext chunk."));
+            }
+            "junk" => {
+                // 25-01-08
+                let _junk_header = Some(LowWaveJunkHeader::from_bufread(reader).expect("Failed to get junk chunk."));
+            }
+            "qlty" => {
+                // 25-01-09
+                wave_qlty_header =
+                    Some(LowWaveQualityHeader::from_bufread(reader).expect("Failed to get qlty chunk."));
+            }
+            "data" => {
+                break; // data以降はデータしか含まないはず。
+            }
+            _ => {
+                // `LIST`などの知らないチャンクは、書いてあるサイズ分だけ読み飛ばす。
+                // チャンクのサイズが奇数なら、後ろに1Byteのパディングがある。
+                let mut header = [0u8; 8];
+                reader.read_exact(&mut header).ok()?;
+                let chunk_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as i64;
+                reader.seek(io::SeekFrom::Current(chunk_size + (chunk_size & 1))).ok()?;
+            }
+        }
+    }
+
+    let wave_data_chunk = LowWaveDataChunk::from_bufread(reader).expect("Failed to get data chunk");
+    let wave_fmt_header = wave_fmt_header?;
+
+    // ADPCMでなければ、変換できるLPCMだけを読み込む。
+    let is_readable = wave_adpcm_format.is_some()
+        || (wave_fmt_header.is_lpcm(&wave_fmt_extension)
+            && is_readable_lpcm_bits_per_sample(wave_fmt_header.bits_per_sample));
+    if !is_readable {
+        return None;
+    }
+
+    Some(WaveHeaders {
+        riff: wave_riff_header?,
+        fmt: wave_fmt_header,
+        bext: wave_bext_header,
+        qlty: wave_qlty_header,
+        fact: wave_fact_chunk,
+        data: wave_data_chunk,
+        adpcm: wave_adpcm_format,
+    })
+}
+
+/// [`convert_lpcm_to_uniformed_samples`]で変換できる量子化ビットかを返す。
+pub(crate) fn is_readable_lpcm_bits_per_sample(bits_per_sample: u16) -> bool {
+    matches!(bits_per_sample, 8 | 16 | 24 | 32)
+}
+
+/// LPCMの`buffer`を`bits_per_sample`の量子化ビットとみなして[`UniformedSample`]のリストに変換する。
+/// チャンネルがインターリーブされている場合には、そのままの順番で変換される。
+///
+/// 量子化ビットは[`is_readable_lpcm_bits_per_sample`]で確認しておくこと。
+pub(crate) fn convert_lpcm_to_uniformed_samples(buffer: &[u8], bits_per_sample: usize) -> Vec<UniformedSample> {
+    match bits_per_sample {
+        8 => {
+            // LPCM 8bits
+            //
+            // 8Bitsだけは符号なしで、128が無音になる。
+            buffer
+                .iter()
+                .map(|v| UniformedSample::from_f64(((*v as f64) - 128.0) / 128.0))
+                .collect_vec()
+        }
+        16 => {
+            // LPCM 16bits
+            //
+            // 16Bitsは [-32768, 32768)の範囲を持つ。
+            // 読み取ったバッファーから2Bytesずつ取り出して変換する。
+            buffer
+                .chunks_exact(2)
+                .map(|v| UniformedSample::from_16bits(i16::from_le_bytes([v[0], v[1]])))
+                .collect_vec()
+        }
+        24 => {
+            // LPCM 24bits
+            //
+            // −8,388,608 to +8,388,607を持つ。
+            // 一つのサンプルが3Bytesパッキングされているので、慎重に読み取る。
+            // ここ最適化できそうだけど、今は愚直な方法で。
+            buffer
+                .chunks_exact(3)
+                .map(|raw_sample| {
+                    // データの入り方がBig Endianになっているので、sample[2]の一番前のビットが1なら負の数扱いにする。
+                    // この辺ちょっとめんどくさい。
+                    let offset = if raw_sample[2].bitand(0b10000000).is_zero() { 0x00 } else { 0xFF };
+                    let raw_sample = [offset, raw_sample[2], raw_sample[1], raw_sample[0]];
+                    let raw_sample = i32::from_be_bytes(raw_sample);
+
+                    // チェック
+                    debug_assert!(raw_sample >= -8_388_608);
+                    debug_assert!(raw_sample < 8_388_608);
+                    UniformedSample::from_i32_as_24bit(raw_sample)
+                })
+                .collect_vec()
+        }
+        32 => {
+            // LPCM 32bits
+            buffer
+                .chunks_exact(4)
+                .map(|v| convert_i32_to_uniformed_sample(i32::from_le_bytes([v[0], v[1], v[2], v[3]]), 32))
+                .collect_vec()
+        }
+        _ => unreachable!("Unexpected branch"),
+    }
+}
+
+/// `bits_per_sample`ビットの整数を[`UniformedSample`]に変換する。
+pub(crate) fn convert_i32_to_uniformed_sample(value: i32, bits_per_sample: u32) -> UniformedSample {
+    match bits_per_sample {
+        0..=16 => UniformedSample::from_16bits((value << (16 - bits_per_sample)) as i16),
+        17..=24 => UniformedSample::from_i32_as_24bit(value << (24 - bits_per_sample)),
+        _ => UniformedSample::from_i32_as_24bit(value >> (bits_per_sample - 24)),
+    }
+}
+
+/// [`UniformedSample`]を`bits_per_sample`ビットの整数に変換する。
+///
+/// [`UniformedSample::to_16bits`]と違って一番近い値に丸めるので、
+/// [`convert_i32_to_uniformed_sample`]で変換したサンプルは元の値に戻る。
+pub(crate) fn convert_uniformed_sample_to_i32(sample: UniformedSample, bits_per_sample: u32) -> i32 {
+    match bits_per_sample {
+        0..=16 => {
+            let value = (sample.to_f64() * (i16::MAX as f64)).round();
+            let value = value.clamp(i16::MIN as f64, i16::MAX as f64) as i32;
+            value >> (16 - bits_per_sample)
+        }
+        _ => {
+            const MAX: f64 = 8_388_608.0;
+            let value = (sample.to_f64() * MAX).round().clamp(-MAX, MAX - 1.0) as i32;
+            value >> (24 - bits_per_sample)
+        }
+    }
+}
+
 // ----------------------------------------------------------------------------
 //
 // BUILDER
@@ -0,0 +1,444 @@
+use crate::wave::container::aiff::stream::{AiffStreamHeader, AiffStreamWriter};
+use crate::wave::container::aiff::FORM_ID;
+use crate::wave::container::flac::stream::{FlacStreamReader, FlacStreamWriter};
+use crate::wave::container::flac::FLAC_MARKER;
+use crate::wave::container::wav::adpcm::{EAdpcmFormat, ImaAdpcmStreamWriter};
+use crate::wave::container::wav::data::LowWaveDataChunk;
+use crate::wave::container::wav::fmt::{self, LowWaveFormatHeader};
+use crate::wave::container::wav::riff::LowWaveRiffHeader;
+use crate::wave::container::{convert_lpcm_to_uniformed_samples, read_wave_headers};
+use crate::wave::sample::UniformedSample;
+use std::io;
+
+/// [`WaveContainer`](super::WaveContainer)と違ってヘッダーだけを保持し、
+/// サンプルは必要な時に必要な分だけファイルから読み込んで変換するための構造体。
+///
+/// 長い音源を全部メモリに展開せずにストリーミングするために使う。
+#[derive(Debug, Clone)]
+pub struct WaveStreamHeader {
+    fmt: LowWaveFormatHeader,
+    /// `data`チャンクのサンプルが始まるファイル上のバイト位置
+    data_start_position: u64,
+    /// 実際に読み込める`data`チャンクのバイト数。
+    /// 録音の途中で切れたファイルは、ヘッダーのサイズよりデータが短いことがある。
+    data_size: usize,
+    /// ADPCMの場合はブロック単位でデコードする。
+    adpcm: Option<EAdpcmFormat>,
+    /// `fact`チャンクに入っている1チャンネルあたりのサンプル数
+    fact_sample_length: Option<usize>,
+}
+
+impl WaveStreamHeader {
+    /// `reader`からヘッダーだけを読み込む。
+    pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
+    where
+        T: io::Read + io::Seek,
+    {
+        let headers = read_wave_headers(reader)?;
+        let data_start_position = reader.stream_position().ok()?;
+        let file_length = reader.seek(io::SeekFrom::End(0)).ok()?;
+        reader.seek(io::SeekFrom::Start(data_start_position)).ok()?;
+        let data_size = (headers.data.data_chunk_size as u64).min(file_length.saturating_sub(data_start_position));
+
+        Some(Self {
+            fmt: headers.fmt,
+            data_start_position,
+            data_size: data_size as usize,
+            adpcm: headers.adpcm,
+            fact_sample_length: headers.fact.map(|v| v.sample_length() as usize),
+        })
+    }
+
+    /// サウンドの秒ごとのサンプル数を返す。
+    pub fn samples_per_second(&self) -> u32 {
+        self.fmt.samples_per_sec
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.fmt.channel as u32
+    }
+
+    /// 各サンプルに適用する量子化ビットを返す。
+    pub fn bits_per_sample(&self) -> u32 {
+        self.fmt.bits_per_sample as u32
+    }
+
+    /// 全チャンネルを含むフレームの総数を返す。
+    pub fn frame_count(&self) -> usize {
+        let block_size = self.fmt.block_size();
+        if block_size == 0 {
+            return 0;
+        }
+
+        let data_size = self.data_size;
+        match self.adpcm.as_ref() {
+            Some(adpcm) => {
+                let frame_count = adpcm.frame_count(data_size, block_size, self.fmt.channel as usize);
+                frame_count.min(self.fact_sample_length.unwrap_or(usize::MAX))
+            }
+            None => data_size / block_size,
+        }
+    }
+
+    /// サウンドの全体長さを秒数で返す。
+    pub fn sound_length(&self) -> f64 {
+        (self.frame_count() as f64) / (self.samples_per_second() as f64)
+    }
+
+    /// 秒数`time`から一番近いフレームのインデックスを返す。
+    pub fn frame_index_of_time(&self, time: f64) -> usize {
+        ((self.samples_per_second() as f64) * time.max(0.0)).floor() as usize
+    }
+
+    /// `start_frame`から`frame_count`分のフレームを`reader`から読み込んで変換する。
+    ///
+    /// 複数チャンネルの場合、返すバッファはチャンネルがインターリーブされている。
+    /// ファイルの最後を超える分は読み込まないので、返すバッファは要求分より短くなることがある。
+    pub fn read_frames<T>(&self, reader: &mut T, start_frame: usize, frame_count: usize) -> Vec<UniformedSample>
+    where
+        T: io::Read + io::Seek,
+    {
+        let frame_count = frame_count.min(self.frame_count().saturating_sub(start_frame));
+        if frame_count == 0 {
+            return vec![];
+        }
+
+        if let Some(adpcm) = self.adpcm.as_ref() {
+            return self.read_adpcm_frames(reader, adpcm, start_frame, frame_count);
+        }
+
+        // 読み込む区間にカーソルを移動する。
+        let block_size = self.fmt.block_size();
+        let start_position = self.data_start_position + ((start_frame * block_size) as u64);
+        if reader.seek(io::SeekFrom::Start(start_position)).is_err() {
+            return vec![];
+        }
+
+        // 読み込めなかったら、ファイルの最後に到達したとみなす。
+        let mut buffer = vec![0u8; frame_count * block_size];
+        if reader.read_exact(&mut buffer).is_err() {
+            return vec![];
+        }
+
+        convert_lpcm_to_uniformed_samples(&buffer, self.bits_per_sample() as usize)
+    }
+
+    /// ADPCMの場合、`start_frame`から`frame_count`分を含むブロックだけを読み込んでデコードする。
+    fn read_adpcm_frames<T>(
+        &self,
+        reader: &mut T,
+        adpcm: &EAdpcmFormat,
+        start_frame: usize,
+        frame_count: usize,
+    ) -> Vec<UniformedSample>
+    where
+        T: io::Read + io::Seek,
+    {
+        let channels = self.fmt.channel as usize;
+        let block_size = self.fmt.block_size();
+        let samples_per_block = adpcm.samples_per_block();
+        let first_block_i = start_frame / samples_per_block;
+        let last_block_i = (start_frame + frame_count - 1) / samples_per_block;
+
+        let start_position = first_block_i * block_size;
+        let read_size =
+            ((last_block_i - first_block_i + 1) * block_size).min(self.data_size.saturating_sub(start_position));
+        if reader
+            .seek(io::SeekFrom::Start(self.data_start_position + (start_position as u64)))
+            .is_err()
+        {
+            return vec![];
+        }
+
+        let mut buffer = vec![0u8; read_size];
+        if reader.read_exact(&mut buffer).is_err() {
+            return vec![];
+        }
+
+        // 最初のブロックの途中から必要な分だけを取り出す。
+        let decoded = adpcm.decode_blocks(&buffer, block_size, channels);
+        let offset = (start_frame - (first_block_i * samples_per_block)) * channels;
+        let end = (offset + (frame_count * channels)).min(decoded.len());
+        decoded[offset.min(end)..end]
+            .iter()
+            .map(|v| UniformedSample::from_16bits(*v))
+            .collect()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// WaveStreamWriter
+// ----------------------------------------------------------------------------
+
+/// 先にヘッダーだけを書き込んでおいて、サンプルが来るたびにファイルの後ろに追記していくための構造体。
+///
+/// RIFFと`data`チャンクのサイズは書き込んだ分だけ[`WaveStreamWriter::write_header`]で更新する。
+/// 途中で処理が中断されても、最後にヘッダーを更新したところまでは読み込めるファイルになる。
+#[derive(Debug, Clone)]
+pub struct WaveStreamWriter {
+    fmt: LowWaveFormatHeader,
+    /// 今まで書き込んだ全チャンネルを含むフレームの数
+    written_frame_count: usize,
+}
+
+impl WaveStreamWriter {
+    /// LPCMの設定から作る。今は8ビットと16ビットだけ対応。
+    pub fn new(samples_per_sec: u32, bits_per_sample: u16, channels: usize) -> Option<Self> {
+        if bits_per_sample != 8 && bits_per_sample != 16 {
+            return None;
+        }
+        if samples_per_sec == 0 || channels == 0 {
+            return None;
+        }
+
+        let fmt = LowWaveFormatHeader::from_builder(fmt::EBuilder::Normal {
+            samples_per_sec,
+            bits_per_sample,
+            channels,
+        });
+        Some(Self {
+            fmt,
+            written_frame_count: 0,
+        })
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.fmt.channel as u32
+    }
+
+    /// 今まで書き込んだフレームの数を返す。
+    pub fn written_frame_count(&self) -> usize {
+        self.written_frame_count
+    }
+
+    /// 今まで書き込んだサンプルのサイズでRIFF・fmt・dataヘッダーをファイルの先頭に書き込む。
+    /// 書き込んだ後はカーソルをファイルの最後に移動する。
+    pub fn write_header<T>(&self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let data_chunk_size = (self.written_frame_count * self.fmt.block_size()) as u32;
+        let data = LowWaveDataChunk::from_chunk_size(data_chunk_size);
+        let riff = LowWaveRiffHeader::from_data_chunk(&data);
+
+        writer.seek(io::SeekFrom::Start(0)).expect("Failed to seek writer.");
+        riff.write(writer);
+        self.fmt.write(writer);
+        data.write(writer);
+        writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+    }
+
+    /// インターリーブされた`samples`をファイルの最後に追記する。
+    /// `samples`の長さはチャンネル数の倍数であること。
+    pub fn write_frames<T>(&mut self, writer: &mut T, samples: &[UniformedSample])
+    where
+        T: io::Write + io::Seek,
+    {
+        let channels = self.fmt.channel as usize;
+        assert_eq!(samples.len() % channels, 0);
+        if samples.is_empty() {
+            return;
+        }
+
+        let converted_buffer: Vec<u8> = match self.fmt.bits_per_sample {
+            16 => samples.iter().flat_map(|v| v.to_16bits().to_le_bytes()).collect(),
+            8 => samples.iter().map(|v| v.to_unsigned_8bits()).collect(),
+            _ => unreachable!("Unexpected branch"),
+        };
+
+        writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+        writer.write_all(&converted_buffer).expect("Failed to write Buffer to writer.");
+        self.written_frame_count += samples.len() / channels;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EStreamReader
+// ----------------------------------------------------------------------------
+
+/// ファイルの先頭からフォーマットを判別して、前から順番にサンプルを読み込んでいくためのリーダー。
+#[derive(Debug, Clone)]
+pub enum EStreamReader {
+    Wav {
+        header: WaveStreamHeader,
+        /// 次に読み込むフレームのインデックス
+        next_frame_i: usize,
+    },
+    Aiff {
+        header: AiffStreamHeader,
+        /// 次に読み込むフレームのインデックス
+        next_frame_i: usize,
+    },
+    Flac(FlacStreamReader),
+}
+
+impl EStreamReader {
+    /// `reader`の先頭を見てWAV・AIFF・FLACのどれかを判別し、ヘッダーだけを読み込む。
+    pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
+    where
+        T: io::Read + io::Seek,
+    {
+        let start = reader.stream_position().ok()?;
+        let mut marker = [0u8; 4];
+        reader.read_exact(&mut marker).ok()?;
+        reader.seek(io::SeekFrom::Start(start)).ok()?;
+
+        // FLACはID3v2タグが先についていることもある。
+        if marker == FLAC_MARKER || &marker[..3] == b"ID3" {
+            return Some(Self::Flac(FlacStreamReader::from_bufread(reader)?));
+        }
+        if marker == FORM_ID {
+            return Some(Self::Aiff {
+                header: AiffStreamHeader::from_bufread(reader)?,
+                next_frame_i: 0,
+            });
+        }
+
+        Some(Self::Wav {
+            header: WaveStreamHeader::from_bufread(reader)?,
+            next_frame_i: 0,
+        })
+    }
+
+    /// サウンドの秒ごとのサンプル数を返す。
+    pub fn samples_per_second(&self) -> u32 {
+        match self {
+            Self::Wav { header, .. } => header.samples_per_second(),
+            Self::Aiff { header, .. } => header.samples_per_second(),
+            Self::Flac(v) => v.samples_per_second(),
+        }
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        match self {
+            Self::Wav { header, .. } => header.channel(),
+            Self::Aiff { header, .. } => header.channel(),
+            Self::Flac(v) => v.channel(),
+        }
+    }
+
+    /// 秒数`time`から一番近いフレームのインデックスを返す。
+    pub fn frame_index_of_time(&self, time: f64) -> usize {
+        ((self.samples_per_second() as f64) * time.max(0.0)).floor() as usize
+    }
+
+    /// 前回の続きから`frame_count`分のフレームを読み込む。
+    ///
+    /// 返すバッファはチャンネルがインターリーブされている。
+    /// ファイルの最後まで読み込んだら、返すバッファは要求分より短くなる。
+    pub fn read_frames<T>(&mut self, reader: &mut T, frame_count: usize) -> Vec<UniformedSample>
+    where
+        T: io::Read + io::Seek,
+    {
+        match self {
+            Self::Wav { header, next_frame_i } => {
+                let result = header.read_frames(reader, *next_frame_i, frame_count);
+                *next_frame_i += result.len() / (header.channel().max(1) as usize);
+                result
+            }
+            Self::Aiff { header, next_frame_i } => {
+                let result = header.read_frames(reader, *next_frame_i, frame_count);
+                *next_frame_i += result.len() / (header.channel().max(1) as usize);
+                result
+            }
+            Self::Flac(v) => v.read_frames(reader, frame_count),
+        }
+    }
+
+    /// 前回の続きから`frame_count`分のフレームを読み飛ばす。
+    pub fn skip_frames<T>(&mut self, reader: &mut T, frame_count: usize)
+    where
+        T: io::Read + io::Seek,
+    {
+        match self {
+            Self::Wav { header, next_frame_i } => {
+                *next_frame_i = (*next_frame_i + frame_count).min(header.frame_count());
+            }
+            Self::Aiff { header, next_frame_i } => {
+                *next_frame_i = (*next_frame_i + frame_count).min(header.frame_count());
+            }
+            Self::Flac(v) => v.skip_frames(reader, frame_count),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EStreamWriter
+// ----------------------------------------------------------------------------
+
+/// 先にヘッダーを書き込んでおいて、サンプルが来るたびにファイルの後ろに追記していくためのライター。
+#[derive(Debug, Clone)]
+pub enum EStreamWriter {
+    Wav(WaveStreamWriter),
+    WavImaAdpcm(ImaAdpcmStreamWriter),
+    Aiff(AiffStreamWriter),
+    Flac(FlacStreamWriter),
+}
+
+impl EStreamWriter {
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        match self {
+            Self::Wav(v) => v.channel(),
+            Self::WavImaAdpcm(v) => v.channel(),
+            Self::Aiff(v) => v.channel(),
+            Self::Flac(v) => v.channel(),
+        }
+    }
+
+    /// ファイルに書き込んだフレームの数を返す。
+    pub fn written_frame_count(&self) -> usize {
+        match self {
+            Self::Wav(v) => v.written_frame_count(),
+            Self::WavImaAdpcm(v) => v.written_frame_count(),
+            Self::Aiff(v) => v.written_frame_count(),
+            Self::Flac(v) => v.written_frame_count(),
+        }
+    }
+
+    /// 今まで書き込んだ分でファイルの先頭のヘッダーを更新する。
+    pub fn write_header<T>(&self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        match self {
+            Self::Wav(v) => v.write_header(writer),
+            Self::WavImaAdpcm(v) => v.write_header(writer),
+            Self::Aiff(v) => v.write_header(writer),
+            Self::Flac(v) => v.write_header(writer),
+        }
+    }
+
+    /// インターリーブされた`samples`をファイルに追記する。
+    pub fn write_frames<T>(&mut self, writer: &mut T, samples: &[UniformedSample])
+    where
+        T: io::Write + io::Seek,
+    {
+        match self {
+            Self::Wav(v) => v.write_frames(writer, samples),
+            Self::WavImaAdpcm(v) => v.write_frames(writer, samples),
+            Self::Aiff(v) => v.write_frames(writer, samples),
+            Self::Flac(v) => v.write_frames(writer, samples),
+        }
+    }
+
+    /// 残っているサンプルを全部書き込んで、最後にヘッダーを更新する。
+    pub fn finish<T>(&mut self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        match self {
+            Self::Wav(v) => v.write_header(writer),
+            Self::WavImaAdpcm(v) => v.finish(writer),
+            Self::Aiff(v) => v.finish(writer),
+            Self::Flac(v) => v.finish(writer),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
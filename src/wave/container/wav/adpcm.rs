@@ -1,11 +1,12 @@
 use std::{io, ops::BitAnd};
 
 use crate::wave::container::WaveContainer;
+use crate::wave::sample::UniformedSample;
 
 use super::{
     data::LowWaveDataChunk,
     fact::LowWaveFactChunk,
-    fmt::{self, LowWaveFormatHeader, WAV_IMA_ADPCM_BLOCK_SIZE, WAV_IMA_ADPCM_SAMPLES_PER_BLOCK},
+    fmt::{self, EWavFormatType, LowWaveFormatHeader, WAV_IMA_ADPCM_BLOCK_SIZE, WAV_IMA_ADPCM_SAMPLES_PER_BLOCK},
     riff::LowWaveRiffHeader,
 };
 
@@ -21,6 +22,13 @@ const STEP_SIZE_TABLE: [i32; 89] = [
     32767,
 ];
 
+/// MS-ADPCMの差分からデルタを更新するためのテーブル
+const MS_ADAPTATION_TABLE: [i32; 16] = [230, 230, 230, 230, 307, 409, 512, 614, 768, 614, 512, 409, 307, 230, 230, 230];
+
+/// MS-ADPCMの`fmt`チャンクに係数がない場合に使う標準の予測係数
+const MS_DEFAULT_COEFFICIENTS: [(i32, i32); 7] =
+    [(256, 0), (512, -256), (0, 0), (192, 64), (240, 0), (460, -208), (392, -232)];
+
 /// @brief IMA-ADPCMの各データブロックのヘッダー情報
 #[repr(C)]
 #[derive(Debug, Clone)]
@@ -109,6 +117,84 @@ impl DataBlockBuffer {
     }
 }
 
+/// @brief IMA-ADPCMの1チャンネル分の予測の状態。
+/// エンコードとデコードで同じ伸張処理を行うので、両方で使う。
+#[derive(Debug, Clone, Copy, Default)]
+struct ImaAdpcmState {
+    /// 前のサンプルの予測値。 (sp)
+    basis_sample: i16,
+    /// [`STEP_SIZE_TABLE`]のインデックス。 (index)
+    step_size_table_i: usize,
+}
+
+impl ImaAdpcmState {
+    /// `sample`を4ビットの差分データに圧縮し、状態を更新する。
+    fn encode(&mut self, sample: i16) -> u8 {
+        // ここからはbasis_sampleといろいろと使ってAdaptiveな差分を求めて記録する。
+        // abs_d := d.
+        let step_size = STEP_SIZE_TABLE[self.step_size_table_i];
+        let (mut c, mut abs_d) = {
+            let d = (sample as i32) - (self.basis_sample as i32);
+            if d < 0 {
+                (0b1000u8, d.abs())
+            } else {
+                (0b0000u8, d)
+            }
+        };
+
+        // 圧縮フェーズ
+        if abs_d >= step_size {
+            c |= 0x04u8;
+            abs_d -= step_size;
+        }
+        if abs_d >= (step_size >> 1) {
+            c |= 0x02u8;
+            abs_d -= step_size >> 1;
+        }
+        if abs_d >= (step_size >> 2) {
+            c |= 0x01u8;
+        }
+
+        self.decode(c);
+        c
+    }
+
+    /// 4ビットの差分データ`c`を伸張してサンプルを返し、状態を更新する。
+    fn decode(&mut self, c: u8) -> i16 {
+        // 伸張フェーズ。
+        let step_size = STEP_SIZE_TABLE[self.step_size_table_i];
+        let dp = {
+            let mut v = step_size >> 3;
+            if c.bitand(0x1) > 0 {
+                v += step_size >> 2;
+            }
+            if c.bitand(0x2) > 0 {
+                v += step_size >> 1;
+            }
+            if c.bitand(0x4) > 0 {
+                v += step_size;
+            }
+            v
+        };
+
+        // basis_sampleを再指定。
+        self.basis_sample = {
+            let new_sample = if c.bitand(0x8) > 0 {
+                self.basis_sample as i32 - dp
+            } else {
+                self.basis_sample as i32 + dp
+            };
+            new_sample.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+        };
+
+        // step_size_table_iの再指定。
+        self.step_size_table_i =
+            ((self.step_size_table_i as i32) + INDEX_TABLE[(c & 0xF) as usize]).clamp(0, 88) as usize;
+
+        self.basis_sample
+    }
+}
+
 /// @brief IMA-ADPCM形式に既存WaveContainerを変換して出力するためのもの。
 pub struct IMAADPCMWriter<'a> {
     pub source_container: &'a WaveContainer,
@@ -127,6 +213,7 @@ impl<'a> IMAADPCMWriter<'a> {
 
         let format_header = LowWaveFormatHeader::from_builder(fmt::EBuilder::ImaAdpcm {
             samples_per_sec: container.samples_per_second(),
+            channels: 1,
         });
 
         // IMA-ADPCMで使うサンプルブロックの数を求める。
@@ -145,26 +232,9 @@ impl<'a> IMAADPCMWriter<'a> {
         }
 
         // Write FMT
+        // 拡張チャンク（extra_sizeとsamples_per_block）も一緒に書き込まれる。
         {
             format_header.write(writer);
-
-            // 拡張チャンクのサイズ指定。
-            // extra_size (2bytes)
-            let buffer = unsafe {
-                let mut buffer = [0u8; 2];
-                let extra_size = 2u16;
-                std::ptr::write(buffer.as_mut_ptr() as *mut _, extra_size);
-                buffer
-            };
-            writer.write(&buffer).expect("Failed to write extra_size to writer.");
-
-            // IMA_ADPCMの使用準拠でsamples_per_blockの記入。(2bytes)
-            let buffer = unsafe {
-                let mut buffer = [0u8; 2];
-                std::ptr::write(buffer.as_mut_ptr() as *mut _, WAV_IMA_ADPCM_SAMPLES_PER_BLOCK);
-                buffer
-            };
-            writer.write(&buffer).expect("Failed to write samples_per_block to writer.");
         }
 
         // Write FACT
@@ -180,9 +250,8 @@ impl<'a> IMAADPCMWriter<'a> {
         }
 
         // 既存BufferをADPCMバッファに変換して記録する。
-        let mut step_size_table_i = 0usize; // step_size_table_i := index.
-        let mut basis_sample = 0i16; // basis_sample := sp
-        let mut data_block = DataBlock::from_info(basis_sample, step_size_table_i as u8);
+        let mut state = ImaAdpcmState::default();
+        let mut data_block = DataBlock::from_info(state.basis_sample, state.step_size_table_i as u8);
 
         for block_i in 0..blocks_count {
             let sample_i_offset = block_i * samples_per_block;
@@ -195,71 +264,385 @@ impl<'a> IMAADPCMWriter<'a> {
 
                 // もしBlockのローカルインデックスが最初なら、Blockのヘッダーを初期化する。
                 if local_si == 0 {
-                    basis_sample = sample;
-                    data_block = DataBlock::from_info(basis_sample, step_size_table_i as u8);
+                    state.basis_sample = sample;
+                    data_block = DataBlock::from_info(state.basis_sample, state.step_size_table_i as u8);
                     continue;
                 }
 
-                // ここからはbasis_sampleといろいろと使ってAdaptiveな差分を求めて記録する。
-                // abs_d := d.
-                let step_size = STEP_SIZE_TABLE[step_size_table_i];
-                let (mut c, mut abs_d) = {
-                    let d = (sample as i32) - (basis_sample as i32);
-                    if d < 0 {
-                        (0b1000u8, d.abs())
-                    } else {
-                        (0b0000u8, d)
-                    }
+                // バッファーに書き込み。
+                data_buffer.add_data(state.encode(sample));
+            }
+
+            // 書き込む。
+            data_block.write(writer);
+            data_buffer.write(writer);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// ImaAdpcmStreamWriter
+// ----------------------------------------------------------------------------
+
+/// 先にヘッダーだけを書き込んでおいて、サンプルがブロック分溜まるたびに
+/// IMA-ADPCMに変換してファイルの後ろに追記していくための構造体。
+///
+/// [`IMAADPCMWriter`]と違ってステレオにも対応する。
+/// RIFF・`fact`・`data`チャンクのサイズは[`ImaAdpcmStreamWriter::write_header`]で更新する。
+#[derive(Debug, Clone)]
+pub struct ImaAdpcmStreamWriter {
+    fmt: LowWaveFormatHeader,
+    /// 各チャンネルの予測の状態
+    states: Vec<ImaAdpcmState>,
+    /// まだブロックにしてないサンプル。チャンネルはインターリーブされている。
+    pending: Vec<i16>,
+    /// 今まで書き込んだブロックの数
+    written_block_count: usize,
+    /// 今まで書き込んだフレームの数。最後のブロックの埋め合わせ分は含まない。
+    written_frame_count: usize,
+}
+
+impl ImaAdpcmStreamWriter {
+    pub fn new(samples_per_sec: u32, channels: usize) -> Option<Self> {
+        if samples_per_sec == 0 || channels == 0 || channels > 2 {
+            return None;
+        }
+
+        let fmt = LowWaveFormatHeader::from_builder(fmt::EBuilder::ImaAdpcm {
+            samples_per_sec,
+            channels,
+        });
+        Some(Self {
+            fmt,
+            states: vec![ImaAdpcmState::default(); channels],
+            pending: vec![],
+            written_block_count: 0,
+            written_frame_count: 0,
+        })
+    }
+
+    /// サウンドのチャンネル数を返す。
+    pub fn channel(&self) -> u32 {
+        self.fmt.channel as u32
+    }
+
+    /// 今まで書き込んだフレームの数を返す。
+    pub fn written_frame_count(&self) -> usize {
+        self.written_frame_count
+    }
+
+    /// 今まで書き込んだブロックでRIFF・fmt・fact・dataヘッダーをファイルの先頭に書き込む。
+    /// 書き込んだ後はカーソルをファイルの最後に移動する。
+    pub fn write_header<T>(&self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let data_chunk_size = (self.written_block_count * self.fmt.block_size()) as u32;
+        let data = LowWaveDataChunk::from_chunk_size(data_chunk_size);
+        let riff = LowWaveRiffHeader::from_data_chunk_with_ima_adpcm(&data);
+        let fact = LowWaveFactChunk::from_sample_length(self.written_frame_count as u32);
+
+        writer.seek(io::SeekFrom::Start(0)).expect("Failed to seek writer.");
+        riff.write(writer);
+        self.fmt.write(writer);
+        fact.write(writer);
+        data.write(writer);
+        writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+    }
+
+    /// インターリーブされた`samples`を追加して、ブロック分溜まったらファイルの最後に追記する。
+    /// `samples`の長さはチャンネル数の倍数であること。
+    pub fn write_frames<T>(&mut self, writer: &mut T, samples: &[UniformedSample])
+    where
+        T: io::Write + io::Seek,
+    {
+        let channels = self.fmt.channel as usize;
+        assert_eq!(samples.len() % channels, 0);
+
+        self.pending.extend(samples.iter().map(|v| v.to_16bits()));
+        let block_item_count = (WAV_IMA_ADPCM_SAMPLES_PER_BLOCK as usize) * channels;
+        while self.pending.len() >= block_item_count {
+            self.flush_block(writer);
+            self.written_frame_count += WAV_IMA_ADPCM_SAMPLES_PER_BLOCK as usize;
+        }
+    }
+
+    /// 残っているサンプルを無音で埋めて最後のブロックとして書き込んで、ヘッダーを更新する。
+    pub fn finish<T>(&mut self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let channels = self.fmt.channel as usize;
+        if !self.pending.is_empty() {
+            let remained_frame_count = self.pending.len() / channels;
+            self.pending.resize((WAV_IMA_ADPCM_SAMPLES_PER_BLOCK as usize) * channels, 0);
+            self.flush_block(writer);
+            self.written_frame_count += remained_frame_count;
+        }
+
+        self.write_header(writer);
+    }
+
+    /// `pending`の先頭から1ブロック分をIMA-ADPCMに変換して書き込む。
+    fn flush_block<T>(&mut self, writer: &mut T)
+    where
+        T: io::Write + io::Seek,
+    {
+        let channels = self.fmt.channel as usize;
+        let samples_per_block = WAV_IMA_ADPCM_SAMPLES_PER_BLOCK as usize;
+        let frames = self.pending.drain(..(samples_per_block * channels)).collect::<Vec<i16>>();
+
+        let mut buffer = Vec::with_capacity(self.fmt.block_size());
+        // 各チャンネルのブロックヘッダー。最初のサンプルはそのまま入れる。
+        for (channel_i, state) in self.states.iter_mut().enumerate() {
+            state.basis_sample = frames[channel_i];
+            buffer.extend_from_slice(&state.basis_sample.to_le_bytes());
+            buffer.push(state.step_size_table_i as u8);
+            buffer.push(0);
+        }
+
+        // 各チャンネル4Bytes（8サンプル）ずつインターリーブして入れる。
+        for group_start in (1..samples_per_block).step_by(8) {
+            for (channel_i, state) in self.states.iter_mut().enumerate() {
+                for byte_i in 0..4 {
+                    let frame_i = group_start + (byte_i * 2);
+                    let low = state.encode(frames[(frame_i * channels) + channel_i]);
+                    let high = state.encode(frames[((frame_i + 1) * channels) + channel_i]);
+                    buffer.push(low | (high << 4));
+                }
+            }
+        }
+        debug_assert_eq!(buffer.len(), self.fmt.block_size());
+
+        writer.seek(io::SeekFrom::End(0)).expect("Failed to seek writer.");
+        writer.write_all(&buffer).expect("Failed to write Buffer to writer.");
+        self.written_block_count += 1;
+    }
+}
+
+// ----------------------------------------------------------------------------
+// DECODER
+// ----------------------------------------------------------------------------
+
+/// `fmt`チャンクの拡張部分から読み込んだADPCMの情報。
+#[derive(Debug, Clone)]
+pub(crate) enum EAdpcmFormat {
+    Ima {
+        samples_per_block: usize,
+    },
+    Ms {
+        samples_per_block: usize,
+        /// 予測係数のリスト。ブロックヘッダーのインデックスで選ぶ。
+        coefficients: Vec<(i32, i32)>,
+    },
+}
+
+impl EAdpcmFormat {
+    /// `fmt`チャンクとその拡張部分`extension`から作る。ADPCMじゃなければ`None`を返す。
+    pub fn from_extension(fmt: &LowWaveFormatHeader, extension: &[u8]) -> Option<Self> {
+        let channels = (fmt.channel as usize).max(1);
+        let block_size = fmt.block_size();
+        let read_u16 = |offset: usize| extension.get(offset..(offset + 2)).map(|v| u16::from_le_bytes([v[0], v[1]]));
+
+        // extension[0..2]は拡張部分のサイズで、その後ろにsamples_per_blockが続く。
+        match fmt.format_type() {
+            EWavFormatType::ImaAdpcm => {
+                let samples_per_block = match read_u16(2) {
+                    Some(v) if v > 0 => v as usize,
+                    _ => ((block_size.saturating_sub(4 * channels) * 2) / channels) + 1,
+                };
+                Some(Self::Ima { samples_per_block })
+            }
+            EWavFormatType::MsAdpcm => {
+                let samples_per_block = match read_u16(2) {
+                    Some(v) if v > 0 => v as usize,
+                    _ => ((block_size.saturating_sub(7 * channels) * 2) / channels) + 2,
                 };
+                let coefficient_count = read_u16(4).unwrap_or(0) as usize;
+                let mut coefficients = (0..coefficient_count)
+                    .map_while(|i| {
+                        let offset = 6 + (i * 4);
+                        let c1 = read_u16(offset)? as i16;
+                        let c2 = read_u16(offset + 2)? as i16;
+                        Some((c1 as i32, c2 as i32))
+                    })
+                    .collect::<Vec<_>>();
+                if coefficients.is_empty() {
+                    coefficients = MS_DEFAULT_COEFFICIENTS.to_vec();
+                }
+                Some(Self::Ms {
+                    samples_per_block,
+                    coefficients,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 1ブロックに入っている1チャンネルあたりのサンプル数を返す。
+    pub fn samples_per_block(&self) -> usize {
+        match self {
+            Self::Ima { samples_per_block } => *samples_per_block,
+            Self::Ms { samples_per_block, .. } => *samples_per_block,
+        }
+    }
+
+    /// `data_size`バイトのデータに入っている全チャンネルを含むフレームの数を返す。
+    /// 最後のブロックが途中で切れている場合も考慮する。
+    pub fn frame_count(&self, data_size: usize, block_size: usize, channels: usize) -> usize {
+        let full_block_count = data_size / block_size;
+        let remained_size = data_size % block_size;
+        (full_block_count * self.samples_per_block()) + self.frame_count_of_block(remained_size, channels)
+    }
 
-                // 圧縮フェーズ
-                if abs_d >= step_size {
-                    c |= 0x04u8;
-                    abs_d -= step_size;
+    /// `block_size`バイトのブロックに入っているフレームの数を返す。
+    fn frame_count_of_block(&self, block_size: usize, channels: usize) -> usize {
+        let count = match self {
+            Self::Ima { .. } => {
+                let header_size = 4 * channels;
+                if block_size < header_size {
+                    return 0;
                 }
-                if abs_d >= (step_size >> 1) {
-                    c |= 0x02u8;
-                    abs_d -= step_size >> 1;
+                // モノラルは1Byteずつ、それ以外は4Bytesずつチャンネルがインターリーブされている。
+                let group_size = if channels == 1 { 1 } else { 4 };
+                let group_count = (block_size - header_size) / (group_size * channels);
+                1 + (group_count * group_size * 2)
+            }
+            Self::Ms { .. } => {
+                let header_size = 7 * channels;
+                if block_size < header_size {
+                    return 0;
                 }
-                if abs_d >= (step_size >> 2) {
-                    c |= 0x01u8;
+                2 + (((block_size - header_size) * 2) / channels)
+            }
+        };
+        count.min(self.samples_per_block())
+    }
+
+    /// ブロックが並んでいる`buffer`を全部デコードする。
+    /// 返すバッファはチャンネルがインターリーブされている。
+    pub fn decode_blocks(&self, buffer: &[u8], block_size: usize, channels: usize) -> Vec<i16> {
+        let mut result = Vec::with_capacity(self.frame_count(buffer.len(), block_size, channels) * channels);
+        for block in buffer.chunks(block_size) {
+            match self {
+                Self::Ima { .. } => self.decode_ima_block(&mut result, block, channels),
+                Self::Ms { coefficients, .. } => self.decode_ms_block(&mut result, block, channels, coefficients),
+            }
+        }
+        result
+    }
+
+    /// IMA-ADPCMの1ブロックをデコードして`result`の後ろに追加する。
+    fn decode_ima_block(&self, result: &mut Vec<i16>, block: &[u8], channels: usize) {
+        let frame_count = self.frame_count_of_block(block.len(), channels);
+        if frame_count == 0 {
+            return;
+        }
+
+        // ブロックヘッダーから各チャンネルの状態を読み込む。最初のサンプルはそのまま入っている。
+        let offset = result.len();
+        result.resize(offset + (frame_count * channels), 0);
+        let mut states = (0..channels)
+            .map(|channel_i| {
+                let header = &block[(channel_i * 4)..];
+                ImaAdpcmState {
+                    basis_sample: i16::from_le_bytes([header[0], header[1]]),
+                    step_size_table_i: (header[2] as usize).min(88),
                 }
+            })
+            .collect::<Vec<_>>();
+        for (channel_i, state) in states.iter().enumerate() {
+            result[offset + channel_i] = state.basis_sample;
+        }
 
-                // 伸張フェーズ。
-                let dp = {
-                    let mut v = step_size >> 3;
-                    if c.bitand(0x1) > 0 {
-                        v += step_size >> 2;
+        // 差分データは下位4ビットが先に入っている。
+        let group_size = if channels == 1 { 1 } else { 4 };
+        let data = &block[(4 * channels)..];
+        for (group_i, group) in data.chunks_exact(group_size * channels).enumerate() {
+            for (channel_i, state) in states.iter_mut().enumerate() {
+                let channel_bytes = &group[(channel_i * group_size)..((channel_i + 1) * group_size)];
+                for (byte_i, byte) in channel_bytes.iter().enumerate() {
+                    for (nibble_i, nibble) in [byte & 0xF, byte >> 4].into_iter().enumerate() {
+                        let frame_i = 1 + (group_i * group_size * 2) + (byte_i * 2) + nibble_i;
+                        if frame_i < frame_count {
+                            result[offset + (frame_i * channels) + channel_i] = state.decode(nibble);
+                        }
                     }
-                    if c.bitand(0x2) > 0 {
-                        v += step_size >> 1;
-                    }
-                    if c.bitand(0x4) > 0 {
-                        v += step_size;
-                    }
-                    v
-                };
-
-                // basis_sampleを再指定。
-                basis_sample = {
-                    let new_sample = if c.bitand(0x8) > 0 {
-                        basis_sample as i32 - dp
-                    } else {
-                        basis_sample as i32 + dp
-                    };
-                    new_sample.clamp(i16::MIN as i32, i16::MAX as i32) as i16
-                };
+                }
+            }
+        }
+    }
 
-                // step_size_table_iの再指定。
-                step_size_table_i = ((step_size_table_i as i32) + INDEX_TABLE[c as usize]).clamp(0, 88) as usize;
+    /// MS-ADPCMの1ブロックをデコードして`result`の後ろに追加する。
+    fn decode_ms_block(&self, result: &mut Vec<i16>, block: &[u8], channels: usize, coefficients: &[(i32, i32)]) {
+        let frame_count = self.frame_count_of_block(block.len(), channels);
+        if frame_count == 0 {
+            return;
+        }
 
-                // バッファーに書き込み。
-                data_buffer.add_data(c);
+        // ブロックヘッダーは項目ごとに全チャンネル分が並んでいる。
+        let read_i16 = |i: usize| i16::from_le_bytes([block[i], block[i + 1]]) as i32;
+        let mut states = (0..channels)
+            .map(|channel_i| {
+                let predictor_i = (block[channel_i] as usize).min(coefficients.len() - 1);
+                MsAdpcmState {
+                    coefficient: coefficients[predictor_i],
+                    delta: read_i16(channels + (channel_i * 2)),
+                    sample1: read_i16((channels * 3) + (channel_i * 2)),
+                    sample2: read_i16((channels * 5) + (channel_i * 2)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // 最初の2フレームはsample2、sample1の順番で入っている。
+        let offset = result.len();
+        result.resize(offset + (frame_count * channels), 0);
+        for (channel_i, state) in states.iter().enumerate() {
+            result[offset + channel_i] = state.sample2 as i16;
+            if frame_count > 1 {
+                result[offset + channels + channel_i] = state.sample1 as i16;
             }
+        }
 
-            // 書き込む。
-            data_block.write(writer);
-            data_buffer.write(writer);
+        // 差分データは上位4ビットが先で、チャンネルが交互に入っている。
+        let data = &block[(7 * channels)..];
+        let nibbles = data.iter().flat_map(|v| [v >> 4, v & 0xF]);
+        for (nibble_i, nibble) in nibbles.enumerate() {
+            let frame_i = 2 + (nibble_i / channels);
+            if frame_i >= frame_count {
+                break;
+            }
+            let channel_i = nibble_i % channels;
+            result[offset + (frame_i * channels) + channel_i] = states[channel_i].decode(nibble);
         }
     }
 }
+
+/// @brief MS-ADPCMの1チャンネル分の予測の状態。
+struct MsAdpcmState {
+    coefficient: (i32, i32),
+    delta: i32,
+    /// 1個前のサンプル
+    sample1: i32,
+    /// 2個前のサンプル
+    sample2: i32,
+}
+
+impl MsAdpcmState {
+    /// 4ビットの差分データを伸張してサンプルを返し、状態を更新する。
+    fn decode(&mut self, nibble: u8) -> i16 {
+        // 差分データは4ビットの符号ありの整数。
+        let signed_nibble = ((nibble << 4) as i8 >> 4) as i32;
+        let predicted = ((self.sample1 * self.coefficient.0) + (self.sample2 * self.coefficient.1)) >> 8;
+        let sample = (predicted + (signed_nibble * self.delta)).clamp(i16::MIN as i32, i16::MAX as i32);
+
+        self.sample2 = self.sample1;
+        self.sample1 = sample;
+        self.delta = ((MS_ADAPTATION_TABLE[nibble as usize] * self.delta) >> 8).max(16);
+        sample as i16
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
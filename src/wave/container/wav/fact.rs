@@ -42,6 +42,11 @@ impl LowWaveFactChunk {
         }
     }
 
+    /// 1チャンネルあたりのサンプル数を返す。
+    pub fn sample_length(&self) -> u32 {
+        self.sample_length
+    }
+
     /// `io::Read + io::Seek`から`Self`の情報を取得して作る。
     pub fn from_bufread<T>(reader: &mut T) -> Option<Self>
     where
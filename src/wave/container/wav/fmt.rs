@@ -1,8 +1,10 @@
 use std::io;
 
 pub const WAV_DATATYPE_LPCM: u16 = 1;
+pub const WAV_DATATYPE_MS_ADPCM: u16 = 2;
 pub const WAV_DATATYPE_PCMU: u16 = 7;
 pub const WAV_DATATYPE_IMA_ADPCM: u16 = 17;
+pub const WAV_DATATYPE_EXTENSIBLE: u16 = 0xFFFE;
 pub const WAV_IMA_ADPCM_BLOCK_SIZE: u16 = 256;
 pub const WAV_IMA_ADPCM_SAMPLES_PER_BLOCK: u16 = (WAV_IMA_ADPCM_BLOCK_SIZE - 4) * 2 + 1;
 
@@ -11,6 +13,9 @@ pub(crate) enum EWavFormatType {
     Unknown,
     LPCM,
     PCMU,
+    /// Microsoft ADPCM。読み込みだけ対応。
+    MsAdpcm,
+    ImaAdpcm,
 }
 
 #[repr(C)]
@@ -45,6 +50,7 @@ pub enum EBuilder {
     Pcmu, // u-lawの8kHz、8Bitsの特殊ビットスケールのPCM
     ImaAdpcm {
         samples_per_sec: u32,
+        channels: usize,
     },
 }
 
@@ -89,18 +95,23 @@ impl LowWaveFormatHeader {
                 block_size: 1,
                 bits_per_sample: 8,
             },
-            EBuilder::ImaAdpcm { samples_per_sec } => Self {
-                fmt_chunk_id: Self::ID_SPECIFIER,
-                fmt_chunk_size: Self::IMA_ADPCM_CHUNK_SIZE,
-                wave_format_type: WAV_DATATYPE_IMA_ADPCM,
-                channel: 1,
+            EBuilder::ImaAdpcm {
                 samples_per_sec,
-                bytes_per_sec: {
-                    (WAV_IMA_ADPCM_BLOCK_SIZE as u32) * samples_per_sec / (WAV_IMA_ADPCM_SAMPLES_PER_BLOCK as u32)
-                },
-                block_size: WAV_IMA_ADPCM_BLOCK_SIZE, // 252Bytes (504Samples) + 4Bytes (Headers)
-                bits_per_sample: 4,
-            },
+                channels,
+            } => {
+                // 各チャンネルごとに252Bytes (504Samples) + 4Bytes (Headers)を持つ。
+                let block_size = WAV_IMA_ADPCM_BLOCK_SIZE * (channels as u16);
+                Self {
+                    fmt_chunk_id: Self::ID_SPECIFIER,
+                    fmt_chunk_size: Self::IMA_ADPCM_CHUNK_SIZE,
+                    wave_format_type: WAV_DATATYPE_IMA_ADPCM,
+                    channel: channels as u16,
+                    samples_per_sec,
+                    bytes_per_sec: (block_size as u32) * samples_per_sec / (WAV_IMA_ADPCM_SAMPLES_PER_BLOCK as u32),
+                    block_size,
+                    bits_per_sample: 4,
+                }
+            }
         }
     }
 
@@ -121,9 +132,10 @@ impl LowWaveFormatHeader {
             assert!(id == "fmt ");
         }
         // fmt_chunk_sizeの確認。
+        // 拡張部分（ADPCMなど）は[`LowWaveFormatHeader::extension_size`]分だけ後ろに続く。
         {
             let maybe_size = maybe_header.fmt_chunk_size;
-            assert!(maybe_size == Self::NORMAL_CHUNK_SIZE || maybe_size == Self::PCMU_CHUNK_SIZE);
+            assert!(maybe_size >= Self::NORMAL_CHUNK_SIZE);
         }
 
         Some(maybe_header)
@@ -142,26 +154,56 @@ impl LowWaveFormatHeader {
         writer.write(&buffer).expect("Failed to write LowWaveFormatHeader to writer.");
 
         match self.format_type() {
-            EWavFormatType::Unknown | EWavFormatType::LPCM => {}
+            EWavFormatType::Unknown | EWavFormatType::LPCM | EWavFormatType::MsAdpcm => {}
             EWavFormatType::PCMU => {
                 // 拡張チャンクのサイズ指定。0Bytes
                 let buffer = [0u8; 2];
                 writer.write(&buffer).expect("Failed to write LowWaveFormatHeader to writer.");
             }
+            EWavFormatType::ImaAdpcm => {
+                // 拡張チャンクのサイズ指定（2Bytes）と、IMA_ADPCMの仕様準拠でsamples_per_blockの記入（2Bytes）。
+                let mut buffer = [0u8; 4];
+                buffer[..2].copy_from_slice(&2u16.to_le_bytes());
+                buffer[2..].copy_from_slice(&WAV_IMA_ADPCM_SAMPLES_PER_BLOCK.to_le_bytes());
+                writer.write_all(&buffer).expect("Failed to write LowWaveFormatHeader to writer.");
+            }
         }
     }
 
+    /// `fmt`チャンクの基本の16Bytesの後ろに続く拡張部分のサイズを返す。
+    pub fn extension_size(&self) -> usize {
+        (self.fmt_chunk_size as usize).saturating_sub(Self::NORMAL_CHUNK_SIZE as usize)
+    }
+
+    /// 全チャンネルを含む1フレームのブロックサイズを返す。
+    pub fn block_size(&self) -> usize {
+        self.block_size as usize
+    }
+
     /// １個のチャンネルのブロックサイズを返す。
     pub fn unit_block_size(&self) -> usize {
         let block_size = self.block_size as usize;
         block_size / (self.channel as usize)
     }
 
+    /// サンプルがLPCMかを返す。
+    /// `WAVE_FORMAT_EXTENSIBLE`なら、`fmt`チャンクの拡張部分`extension`にあるサブフォーマットのGUIDで判別する。
+    pub fn is_lpcm(&self, extension: &[u8]) -> bool {
+        match self.wave_format_type {
+            WAV_DATATYPE_LPCM => true,
+            // 拡張部分のサイズ(2)、有効ビット(2)、チャンネルマスク(4)の後ろにGUIDが続く。
+            WAV_DATATYPE_EXTENSIBLE => extension.get(8..10) == Some(&WAV_DATATYPE_LPCM.to_le_bytes()[..]),
+            _ => false,
+        }
+    }
+
     /// フォーマットタイプを返す。
     pub fn format_type(&self) -> EWavFormatType {
         match self.wave_format_type {
             WAV_DATATYPE_LPCM => EWavFormatType::LPCM,
             WAV_DATATYPE_PCMU => EWavFormatType::PCMU,
+            WAV_DATATYPE_MS_ADPCM => EWavFormatType::MsAdpcm,
+            WAV_DATATYPE_IMA_ADPCM => EWavFormatType::ImaAdpcm,
             _ => EWavFormatType::Unknown,
         }
     }
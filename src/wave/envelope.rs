@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+/// ADSR(Attack-Decay-Sustain-Release)エンベロープの形状。
+///
+/// Sustainの長さは持たないので、ノートオフ（リリースの開始）のタイミングは使う側で決める。
+/// 各`curve`は`1.0`より小さいとLog式、大きいと指数関数式のカーブになる。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdsrEnvelope {
+    pub attack_time: f64,
+    pub decay_time: f64,
+    pub release_time: f64,
+    pub attack_curve: f64,
+    pub decay_curve: f64,
+    pub release_curve: f64,
+    /// sustainで維持する振幅`[0, 1]`の値。
+    pub sustain_value: f64,
+}
+
+impl AdsrEnvelope {
+    /// ノートオンから`time`秒経った時点の振幅を返す。
+    /// AttackとDecayが終わったら、ずっと`sustain_value`を返す。
+    pub fn note_on_gain(&self, time: f64) -> f64 {
+        let decay_start_time = self.attack_time;
+        let sustain_start_time = decay_start_time + self.decay_time;
+
+        if time >= sustain_start_time {
+            // Sustain中。
+            self.sustain_value
+        } else if time >= decay_start_time {
+            // Decay中。
+            // y = input_rate^(curve)。
+            let rate = (time - decay_start_time) / self.decay_time;
+            let input_rate = 1.0 - rate;
+            (1.0 - self.sustain_value) * input_rate.powf(self.decay_curve) + self.sustain_value
+        } else {
+            // Attack中。
+            // y = input_rate^(curve)。
+            let rate = time / self.attack_time;
+            rate.powf(self.attack_curve)
+        }
+    }
+
+    /// 振幅が`from_gain`の時点でリリースを始めて、`time`秒経った時点の振幅を返す。
+    /// リリースが終わったら`0`を返す。
+    pub fn release_gain(&self, from_gain: f64, time: f64) -> f64 {
+        if self.is_release_finished(time) {
+            return 0.0;
+        }
+
+        // y = input_rate^(curve)。
+        let rate = time / self.release_time;
+        let input_rate = 1.0 - rate;
+        from_gain * input_rate.powf(self.release_curve)
+    }
+
+    /// リリースを始めて`time`秒経った時点で、エンベロープが完全に止まったか？
+    pub fn is_release_finished(&self, time: f64) -> bool {
+        time >= self.release_time
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,252 @@
+use std::f64::consts::{FRAC_PI_4, PI};
+
+use rand::{rngs, Rng, SeedableRng};
+
+use crate::{
+    math::{sinc, window::EWindowFunction},
+    wave::sample::UniformedSample,
+};
+
+/// 補間に使うsincカーネルの片側のタップ数
+const SINC_HALF_WIDTH: usize = 8;
+/// ピッチを上げる時に広げるsincカーネルの片側のタップ数の上限
+const MAX_SINC_HALF_WIDTH: usize = 64;
+
+/// [`GranularSynthesizer`]の設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GranularSetting {
+    /// 1つのグレインの長さ（秒）
+    pub grain_length: f64,
+    /// 1秒に発生させるグレインの数
+    pub density: f64,
+    /// グレインを切り出すソース上の位置`[0, 1]`
+    pub position: f64,
+    /// 切り出す位置をランダムにずらす最大の幅（秒）
+    pub position_jitter: f64,
+    /// グレインの再生速度の比。`2`なら1オクターブ上がる。
+    pub pitch_rate: f64,
+    /// グレインにかける窓関数
+    pub window_function: EWindowFunction,
+    /// グレインごとにランダムに振るパンの幅`[0, 1]`。`0`なら全部中央、`1`なら左右いっぱいまで振る。
+    pub pan_spread: f64,
+    /// 全体の振幅。グレインの重なりでは正規化しない。
+    pub intensity: f64,
+    /// 位置とパンの揺らぎの乱数のシード。指定すると毎回同じ出力になる。
+    pub seed: Option<u64>,
+}
+
+/// [`GranularSynthesizer`]の出力
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GranularOutput {
+    /// パンを反映しないモノラルのバッファ
+    pub mono: Vec<UniformedSample>,
+    pub left: Vec<UniformedSample>,
+    pub right: Vec<UniformedSample>,
+}
+
+#[derive(Debug, Clone)]
+struct Grain {
+    /// 次に読むソース上の位置（サンプル）
+    source_position: f64,
+    /// 今まで出力したサンプル数
+    elapsed: usize,
+    /// 出力するサンプル数
+    length: usize,
+    left_gain: f64,
+    right_gain: f64,
+}
+
+/// ソースのバッファから短いグレインを切り出して重ねるグラニュラーシンセシス。
+///
+/// グレインは`density`に合わせて一定の間隔で発生させて、切り出す位置とパンはグレインごとにランダムにずらす。
+/// ピッチを変える時はソースを窓付きsincで補間して読む。
+#[derive(Debug)]
+pub struct GranularSynthesizer {
+    setting: GranularSetting,
+    source: Vec<f64>,
+    source_sample_rate: usize,
+    /// [`Self::push_source`]で保持するソースの最大サンプル数。`None`なら制限しない。
+    max_source_length: Option<usize>,
+    sample_rate: usize,
+    grains: Vec<Grain>,
+    /// 次のグレインを発生させるまでのサンプル数
+    next_grain_countdown: f64,
+    rng: rngs::StdRng,
+}
+
+impl GranularSynthesizer {
+    /// `source`を最初から全部持って合成する。
+    pub fn new(
+        setting: GranularSetting,
+        source: &[UniformedSample],
+        source_sample_rate: usize,
+        sample_rate: usize,
+    ) -> Self {
+        assert!(source_sample_rate > 0 && sample_rate > 0);
+        assert!(setting.grain_length > 0.0, "`grain_length` must be bigger than 0.");
+        assert!(setting.density > 0.0, "`density` must be bigger than 0.");
+        assert!(setting.pitch_rate > 0.0, "`pitch_rate` must be bigger than 0.");
+
+        Self {
+            setting,
+            source: source.iter().map(|v| v.to_f64()).collect(),
+            source_sample_rate,
+            max_source_length: None,
+            sample_rate,
+            grains: vec![],
+            next_grain_countdown: 0.0,
+            rng: match setting.seed {
+                Some(seed) => rngs::StdRng::seed_from_u64(seed),
+                None => rngs::StdRng::from_entropy(),
+            },
+        }
+    }
+
+    /// 空のソースから始めて、[`Self::push_source`]で入ってくるサンプルを最大`max_source_length`個まで保持して合成する。
+    pub fn with_source_capacity(
+        setting: GranularSetting,
+        max_source_length: usize,
+        source_sample_rate: usize,
+        sample_rate: usize,
+    ) -> Self {
+        assert!(max_source_length > 0);
+        let mut synthesizer = Self::new(setting, &[], source_sample_rate, sample_rate);
+        synthesizer.max_source_length = Some(max_source_length);
+        synthesizer
+    }
+
+    /// 設定を変える。発生済みのグレインには`position`と`pitch_rate`以外は反映しない。
+    pub fn set_setting(&mut self, setting: GranularSetting) -> &mut Self {
+        self.setting = setting;
+        self
+    }
+
+    /// 保持しているソースのサンプル数
+    pub fn source_length(&self) -> usize {
+        self.source.len()
+    }
+
+    /// 鳴っているグレインの数
+    pub fn active_grain_count(&self) -> usize {
+        self.grains.len()
+    }
+
+    /// ソースの後ろにサンプルを追加する。上限を超えた分は前から捨てる。
+    pub fn push_source(&mut self, samples: &[UniformedSample], source_sample_rate: usize) {
+        assert!(source_sample_rate > 0);
+        self.source_sample_rate = source_sample_rate;
+        self.source.extend(samples.iter().map(|v| v.to_f64()));
+
+        let Some(max_source_length) = self.max_source_length else {
+            return;
+        };
+        if self.source.len() > max_source_length {
+            // 捨てた分だけグレインの読む位置も前にずらす。
+            let drain_count = self.source.len() - max_source_length;
+            self.source.drain(..drain_count);
+            self.grains.iter_mut().for_each(|v| v.source_position -= drain_count as f64);
+        }
+    }
+
+    /// `frame_count`フレーム分のサンプルを生成する。
+    pub fn next_frames(&mut self, frame_count: usize) -> GranularOutput {
+        let mut output = GranularOutput {
+            mono: Vec::with_capacity(frame_count),
+            left: Vec::with_capacity(frame_count),
+            right: Vec::with_capacity(frame_count),
+        };
+
+        let grain_interval = self.sample_rate as f64 / self.setting.density;
+        let step = self.setting.pitch_rate * (self.source_sample_rate as f64 / self.sample_rate as f64);
+        for _ in 0..frame_count {
+            // 間隔が来たらグレインを発生させる。
+            while self.next_grain_countdown <= 0.0 {
+                self.next_grain_countdown += grain_interval;
+                self.spawn_grain();
+            }
+            self.next_grain_countdown -= 1.0;
+
+            let mut mono = 0.0;
+            let mut left = 0.0;
+            let mut right = 0.0;
+            for grain in self.grains.iter_mut() {
+                let window = self
+                    .setting
+                    .window_function
+                    .get_factor_samples(grain.elapsed, grain.length.max(2) - 1);
+                let value = window * interpolate(&self.source, grain.source_position, step);
+                mono += value;
+                left += value * grain.left_gain;
+                right += value * grain.right_gain;
+
+                grain.source_position += step;
+                grain.elapsed += 1;
+            }
+            self.grains.retain(|v| v.elapsed < v.length);
+
+            let intensity = self.setting.intensity;
+            output.mono.push(UniformedSample::from_f64(mono * intensity));
+            output.left.push(UniformedSample::from_f64(left * intensity));
+            output.right.push(UniformedSample::from_f64(right * intensity));
+        }
+
+        output
+    }
+
+    /// 設定の位置からランダムにずらしてグレインを1つ発生させる。
+    fn spawn_grain(&mut self) {
+        let length = (self.setting.grain_length * self.sample_rate as f64).round().max(1.0) as usize;
+        if self.source.is_empty() {
+            return;
+        }
+
+        let jitter = match self.setting.position_jitter > 0.0 {
+            true => self.rng.gen_range(-1.0..=1.0) * self.setting.position_jitter,
+            false => 0.0,
+        };
+        let last_position = (self.source.len() - 1) as f64;
+        let source_position = ((self.setting.position.clamp(0.0, 1.0) * last_position)
+            + (jitter * self.source_sample_rate as f64))
+            .clamp(0.0, last_position);
+
+        // 等パワーでパンを振る。
+        let pan = match self.setting.pan_spread > 0.0 {
+            true => self.rng.gen_range(-1.0..=1.0) * self.setting.pan_spread.min(1.0),
+            false => 0.0,
+        };
+        let angle = (pan + 1.0) * FRAC_PI_4;
+
+        self.grains.push(Grain {
+            source_position,
+            elapsed: 0,
+            length,
+            left_gain: angle.cos(),
+            right_gain: angle.sin(),
+        });
+    }
+}
+
+/// `source`の`position`の値を窓付きsincで補間する。
+/// `step`が`1`より大きい時はカットオフを下げてエイリアスを防ぐ。範囲外のサンプルは`0`とみなす。
+fn interpolate(source: &[f64], position: f64, step: f64) -> f64 {
+    let cutoff = (1.0 / step).min(1.0);
+    let half_width = ((SINC_HALF_WIDTH as f64 / cutoff).ceil() as usize).min(MAX_SINC_HALF_WIDTH);
+    let kernel_length = (half_width * 2) as f64;
+
+    let base = position.floor() as isize;
+    let mut value = 0.0;
+    for m in (base - half_width as isize + 1)..=(base + half_width as isize) {
+        if m < 0 || m as usize >= source.len() {
+            continue;
+        }
+
+        let distance = position - (m as f64);
+        let window = EWindowFunction::Hann.get_factor_time(kernel_length, distance + half_width as f64);
+        value += cutoff * sinc(PI * cutoff * distance) * window * source[m as usize];
+    }
+    value
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
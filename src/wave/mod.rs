@@ -7,7 +7,9 @@ use serde::{Deserialize, Serialize};
 pub mod analyze;
 pub mod complex;
 pub mod container;
+pub mod envelope;
 pub mod filter;
+pub mod granular;
 pub mod sample;
 pub mod sine;
 pub mod stretch;
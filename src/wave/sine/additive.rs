@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::wave::{sample::UniformedSample, PI2};
+
+/// 加算合成の1つの部分音の設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdditivePartial {
+    /// 基本周波数に対する周波数の比
+    pub ratio: f64,
+    /// 振幅`[0, 1]`
+    pub amplitude: f64,
+    /// 最初の位相（ラジアン）
+    #[serde(default)]
+    pub phase: f64,
+    /// 振幅が`1/e`まで減衰する時間（秒）。指定しなければ減衰しない。
+    #[serde(default)]
+    pub decay_time: Option<f64>,
+}
+
+/// 部分音を重ねて、加算合成のサンプルを生成するエミッタ。
+///
+/// `inharmonicity`を`B`とすると、各部分音の周波数は`ratio * frequency * sqrt(1 + B * ratio^2)`になる。
+/// （弦の硬さによって高い倍音ほど周波数がずれるピアノのような音）
+/// ナイキスト周波数を超える部分音は鳴らさない。
+#[derive(Debug, Clone)]
+pub struct AdditiveUnitSampleEmitter {
+    intensity: f64,
+    partials: Vec<AdditivePartial>,
+    /// 各部分音の1サンプルで進める位相`[0, 1)`。鳴らさない部分音は`None`。
+    phase_deltas: Vec<Option<f64>>,
+    /// 各部分音の積算した位相`[0, 1)`
+    phases: Vec<f64>,
+    /// 各部分音の今の減衰の割合
+    gains: Vec<f64>,
+    /// 各部分音の1サンプルでかける減衰の割合
+    decay_factors: Vec<f64>,
+}
+
+impl AdditiveUnitSampleEmitter {
+    pub fn new(
+        frequency: f64,
+        intensity: f64,
+        partials: Vec<AdditivePartial>,
+        inharmonicity: f64,
+        sample_rate: usize,
+    ) -> Self {
+        assert!(sample_rate > 0);
+        assert!(inharmonicity >= 0.0);
+
+        let sample_rate = sample_rate as f64;
+        let nyquist_frequency = sample_rate * 0.5;
+        let phase_deltas = partials
+            .iter()
+            .map(|partial| {
+                let stretch = (1.0 + (inharmonicity * partial.ratio * partial.ratio)).sqrt();
+                let partial_frequency = partial.ratio * frequency * stretch;
+                match partial_frequency.abs() < nyquist_frequency {
+                    true => Some(partial_frequency / sample_rate),
+                    false => None,
+                }
+            })
+            .collect();
+        let decay_factors = partials
+            .iter()
+            .map(|partial| match partial.decay_time {
+                Some(decay_time) if decay_time > 0.0 => (-1.0 / (decay_time * sample_rate)).exp(),
+                Some(_) => 0.0,
+                None => 1.0,
+            })
+            .collect();
+
+        let partial_count = partials.len();
+        Self {
+            intensity,
+            phase_deltas,
+            phases: partials.iter().map(|v| (v.phase / PI2).rem_euclid(1.0)).collect(),
+            gains: vec![1.0; partial_count],
+            decay_factors,
+            partials,
+        }
+    }
+
+    /// 次のサンプルを取得する。
+    pub fn next_sample(&mut self) -> UniformedSample {
+        let mut sum = 0.0;
+        for (partial_i, partial) in self.partials.iter().enumerate() {
+            let Some(phase_delta) = self.phase_deltas[partial_i] else {
+                continue;
+            };
+
+            sum += partial.amplitude * self.gains[partial_i] * (PI2 * self.phases[partial_i]).sin();
+
+            // 位相と減衰を進める。
+            self.phases[partial_i] = (self.phases[partial_i] + phase_delta).rem_euclid(1.0);
+            self.gains[partial_i] *= self.decay_factors[partial_i];
+        }
+
+        UniformedSample::from_f64(self.intensity * sum)
+    }
+
+    /// `length`分のサンプルを取得する。
+    pub fn next_samples(&mut self, length: usize) -> Vec<UniformedSample> {
+        (0..length).map(|_| self.next_sample()).collect()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -1,7 +1,17 @@
 use itertools::Itertools;
-use rand::{rngs, Rng};
+use rand::{rngs, Rng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-use crate::wave::{sample::UniformedSample, PI2};
+use crate::wave::{
+    sample::UniformedSample,
+    sine::sweep::{ESineSweepMode, SineSweepSetting},
+    PI2,
+};
+
+/// ベルベットノイズの密度を指定しなかった時の、1秒あたりのインパルスの数
+pub const DEFAULT_VELVET_DENSITY: f64 = 2000.0;
+/// ブルーノイズの振幅をピンクノイズと同じくらいにするための係数
+const BLUE_NOISE_GAIN: f64 = 2.0;
 
 /// ユニット単位で音波のサンプルを生成するための、時間に影響しない音型のカテゴリ。
 #[derive(Debug, Clone)]
@@ -25,104 +35,236 @@ pub enum ESineEmitterType {
         /// 内部処理専用
         running_sum: f64,
     },
-    SineSweep {
-        /// 開始周波数
-        from_frequency: f64,
-        /// 終了周波数
-        to_frequency: f64,
-        /// 開始から終了までの長さ（秒）
-        length: f64,
+    /// ブラウンノイズを出力する
+    BrownNoise {
+        /// 内部処理専用
+        last_value: f64,
     },
+    /// ブルーノイズを出力する
+    BlueNoise {
+        /// 内部処理専用
+        rows: Vec<f64>,
+        /// 内部処理専用
+        pink_i: i32,
+        /// 内部処理専用
+        running_sum: f64,
+        /// 内部処理専用
+        last_pink_value: f64,
+    },
+    /// バイオレットノイズを出力する
+    VioletNoise {
+        /// 内部処理専用
+        last_white_value: f64,
+    },
+    /// ベルベットノイズを出力する
+    VelvetNoise {
+        /// インパルスを1つ置く区間の長さ（サンプル）
+        period: f64,
+        /// 内部処理専用
+        frame_i: usize,
+        /// 内部処理専用。次のインパルスのサンプルインデックスと符号。
+        next_impulse: Option<(usize, f64)>,
+    },
+    /// サインスイープを出力する
+    SineSweep { setting: SineSweepSetting },
+}
+
+/// ノコギリ波・矩形波・三角波のエイリアシングの抑え方。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EOscillatorAntialias {
+    /// 何もしない。高い周波数ではエイリアシングが目立つ。
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// [PolyBLEP](https://www.kvraudio.com/forum/viewtopic.php?t=375517)で不連続点の前後の2サンプルを補正する。
+    /// 三角波は傾きの不連続点をPolyBLAMPで補正する。
+    #[serde(rename = "polyblep")]
+    PolyBlep,
+}
+
+/// ノイズの色（周波数ごとのパワーの傾き）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ENoiseColor {
+    /// 全部の周波数で同じパワーになる。
+    #[default]
+    #[serde(rename = "white")]
+    White,
+    /// 1オクターブごとに-3dB下がる。
+    #[serde(rename = "pink")]
+    Pink,
+    /// 1オクターブごとに-6dB下がる。レッドノイズとも言う。
+    #[serde(rename = "brown", alias = "red")]
+    Brown,
+    /// 1オクターブごとに+3dB上がる。
+    #[serde(rename = "blue")]
+    Blue,
+    /// 1オクターブごとに+6dB上がる。
+    #[serde(rename = "violet")]
+    Violet,
+    /// 一定の区間ごとにランダムな位置に`±1`のインパルスを1つだけ置く。
+    /// [Velvet Noise](https://www.dafx.de/paper-archive/2017/papers/DAFx17_paper_96.pdf)を参考。
+    #[serde(rename = "velvet")]
+    Velvet,
+}
+
+/// ノイズの元にする乱数の分布
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ENoiseDistribution {
+    /// `[-1, 1]`の一様分布
+    #[default]
+    #[serde(rename = "uniform")]
+    Uniform,
+    /// 平均`0`、標準偏差`1/3`の正規分布。ほとんどの値が`[-1, 1]`に入る。
+    #[serde(rename = "gaussian")]
+    Gaussian,
+}
+
+/// ノイズに使う乱数の生成器。シードを指定した時だけ毎回同じ列を出す生成器を使う。
+#[derive(Debug, Clone)]
+enum NoiseRng {
+    Thread(rngs::ThreadRng),
+    Seeded(Box<rngs::StdRng>),
+}
+
+impl Default for NoiseRng {
+    fn default() -> Self {
+        Self::Thread(rand::thread_rng())
+    }
+}
+
+impl RngCore for NoiseRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            Self::Thread(v) => v.next_u32(),
+            Self::Seeded(v) => v.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            Self::Thread(v) => v.next_u64(),
+            Self::Seeded(v) => v.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            Self::Thread(v) => v.fill_bytes(dest),
+            Self::Seeded(v) => v.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            Self::Thread(v) => v.try_fill_bytes(dest),
+            Self::Seeded(v) => v.try_fill_bytes(dest),
+        }
+    }
+}
+
+/// [`SineUnitSampleEmitter::set_frequency`]で周波数を滑らかに変えている途中の情報
+#[derive(Debug, Clone, Copy)]
+struct FrequencyGlide {
+    target_frequency: f64,
+    /// 1サンプルごとに周波数にかける比
+    ratio: f64,
+    remained_sample_count: usize,
 }
 
 /// ユニット単位で音波のサンプルを生成するための、時間に影響しない音型のエミッタ。
 #[derive(Debug, Clone)]
 pub struct SineUnitSampleEmitter {
     emitter_type: ESineEmitterType,
+    antialias: EOscillatorAntialias,
     phase: f64,
     intensity: f64,
     next_sample_index: usize,
+    /// 積算した位相`[0, 1)`。周波数を変えても位相が飛ばないように、サンプルごとに周波数分だけ進める。
+    phase_cycle: f64,
+    /// 周波数を変える時にかける時間（秒）
+    glide_time: f64,
+    /// グライド中の情報
+    glide: Option<FrequencyGlide>,
     sample_rate: usize,
-    rng: rngs::ThreadRng,
+    /// ノイズの元にする乱数の分布
+    noise_distribution: ENoiseDistribution,
+    rng: NoiseRng,
 }
 
 impl SineUnitSampleEmitter {
     /// サイン波形を出力するEmitterを生成する
     pub fn new_sine(frequency: f64, phase: f64, intensity: f64, sample_rate: usize) -> Self {
-        Self {
-            emitter_type: ESineEmitterType::Sine { frequency },
-            phase,
-            intensity,
-            next_sample_index: 0usize,
-            sample_rate,
-            rng: rand::thread_rng(),
-        }
+        Self::from_type(ESineEmitterType::Sine { frequency }, phase, intensity, sample_rate)
     }
 
     /// ノコギリ波形を出力するEmitterを生成する
+    ///
+    /// サイン波と同じく、`phase`（ラジアン、`2π`で1周期）だけ周期の始まりをずらす。
     pub fn new_sawtooth(frequency: f64, phase: f64, intensity: f64, sample_rate: usize) -> Self {
-        Self {
-            emitter_type: ESineEmitterType::Sawtooth { frequency },
-            phase,
-            intensity,
-            next_sample_index: 0usize,
-            sample_rate,
-            rng: rand::thread_rng(),
-        }
+        Self::from_type(ESineEmitterType::Sawtooth { frequency }, phase, intensity, sample_rate)
     }
 
     /// 三角波形を出力するEmitterを生成する
+    ///
+    /// サイン波と同じく、`phase`（ラジアン、`2π`で1周期）だけ周期の始まりをずらす。
     pub fn new_triangle(frequency: f64, phase: f64, intensity: f64, sample_rate: usize) -> Self {
-        Self {
-            emitter_type: ESineEmitterType::Triangle { frequency },
-            phase,
-            intensity,
-            next_sample_index: 0usize,
-            sample_rate,
-            rng: rand::thread_rng(),
-        }
+        Self::from_type(ESineEmitterType::Triangle { frequency }, phase, intensity, sample_rate)
     }
 
     /// 矩形波を出力するEmitterを生成する
     pub fn new_square(frequency: f64, duty_rate: f64, phase: f64, intensity: f64, sample_rate: usize) -> Self {
-        Self {
-            emitter_type: ESineEmitterType::Square { frequency, duty_rate },
-            phase,
-            intensity,
-            next_sample_index: 0usize,
-            sample_rate,
-            rng: rand::thread_rng(),
-        }
+        Self::from_type(ESineEmitterType::Square { frequency, duty_rate }, phase, intensity, sample_rate)
     }
 
     /// ホワイトノイズを出力するEmitterを出力する
     pub fn new_whitenoise(intensity: f64) -> Self {
-        Self {
-            emitter_type: ESineEmitterType::WhiteNoise,
-            phase: 0.0,
-            intensity,
-            next_sample_index: 0usize,
-            sample_rate: 48000, // sample_rateは使わないので、0じゃなきゃ何でもいい。
-            rng: rand::thread_rng(),
-        }
+        // sample_rateは使わないので、0じゃなきゃ何でもいい。
+        Self::new_noise(ENoiseColor::White, intensity, 48000)
     }
 
     /// ピンクノイズを出力するEmitterを出力する
     pub fn new_pinknoise(intensity: f64) -> Self {
-        Self {
-            emitter_type: ESineEmitterType::PinkNoise {
+        // sample_rateは使わないので、0じゃなきゃ何でもいい。
+        Self::new_noise(ENoiseColor::Pink, intensity, 48000)
+    }
+
+    /// `color`のノイズを出力するEmitterを生成する。
+    /// ベルベットノイズは1秒あたり[`DEFAULT_VELVET_DENSITY`]個のインパルスを置く。
+    pub fn new_noise(color: ENoiseColor, intensity: f64, sample_rate: usize) -> Self {
+        let emitter_type = match color {
+            ENoiseColor::White => ESineEmitterType::WhiteNoise,
+            ENoiseColor::Pink => ESineEmitterType::PinkNoise {
                 rows: vec![],
                 pink_i: 0,
                 running_sum: 0.0,
             },
-            phase: 0.0,
-            intensity,
-            next_sample_index: 0usize,
-            sample_rate: 48000, // sample_rateは使わないので、0じゃなきゃ何でもいい。
-            rng: rand::thread_rng(),
-        }
+            ENoiseColor::Brown => ESineEmitterType::BrownNoise { last_value: 0.0 },
+            ENoiseColor::Blue => ESineEmitterType::BlueNoise {
+                rows: vec![],
+                pink_i: 0,
+                running_sum: 0.0,
+                last_pink_value: 0.0,
+            },
+            ENoiseColor::Violet => ESineEmitterType::VioletNoise { last_white_value: 0.0 },
+            ENoiseColor::Velvet => return Self::new_velvetnoise(DEFAULT_VELVET_DENSITY, intensity, sample_rate),
+        };
+
+        Self::from_type(emitter_type, 0.0, intensity, sample_rate)
     }
 
+    /// 1秒あたり`density`個のインパルスを置くベルベットノイズを出力するEmitterを生成する。
+    pub fn new_velvetnoise(density: f64, intensity: f64, sample_rate: usize) -> Self {
+        assert!(density > 0.0 && density <= sample_rate as f64);
+
+        let emitter_type = ESineEmitterType::VelvetNoise {
+            period: sample_rate as f64 / density,
+            frame_i: 0,
+            next_impulse: None,
+        };
+        Self::from_type(emitter_type, 0.0, intensity, sample_rate)
+    }
+
+    /// `from_frequency`から`to_frequency`まで線形にスイープするEmitterを生成する
     pub fn new_sinesweep(
         from_frequency: f64,
         to_frequency: f64,
@@ -130,99 +272,217 @@ impl SineUnitSampleEmitter {
         intensity: f64,
         sample_rate: usize,
     ) -> Self {
+        let setting = SineSweepSetting::new_linear(from_frequency, to_frequency, length);
+        Self::new_sinesweep_with(setting, intensity, sample_rate)
+    }
+
+    /// `setting`のサインスイープを出力するEmitterを生成する
+    pub fn new_sinesweep_with(setting: SineSweepSetting, intensity: f64, sample_rate: usize) -> Self {
+        assert!(setting.length > 0.0);
+        if setting.mode == ESineSweepMode::Exponential {
+            assert!(setting.from_frequency > 0.0 && setting.to_frequency > 0.0);
+        }
+
+        Self::from_type(ESineEmitterType::SineSweep { setting }, 0.0, intensity, sample_rate)
+    }
+
+    /// `emitter_type`の音型で、位相もグライドもまだ進めていないEmitterを生成する。
+    fn from_type(emitter_type: ESineEmitterType, phase: f64, intensity: f64, sample_rate: usize) -> Self {
         Self {
-            emitter_type: ESineEmitterType::SineSweep {
-                from_frequency,
-                to_frequency,
-                length,
-            },
-            phase: 0.0,
+            antialias: EOscillatorAntialias::None,
+            emitter_type,
+            phase,
             intensity,
-            next_sample_index: 0,
+            next_sample_index: 0usize,
             sample_rate,
-            rng: Default::default(),
+            noise_distribution: ENoiseDistribution::Uniform,
+            rng: NoiseRng::default(),
+            phase_cycle: 0.0,
+            glide_time: 0.0,
+            glide: None,
         }
     }
 }
 
 impl SineUnitSampleEmitter {
+    /// ノコギリ波・矩形波・三角波のエイリアシングの抑え方を設定する。他の音型では使わない。
+    pub fn set_antialias(&mut self, antialias: EOscillatorAntialias) -> &mut Self {
+        self.antialias = antialias;
+        self
+    }
+
+    /// ノイズの乱数のシードを設定する。同じシードなら毎回同じノイズを出力する。
+    pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+        self.rng = NoiseRng::Seeded(Box::new(rngs::StdRng::seed_from_u64(seed)));
+        self
+    }
+
+    /// ノイズの元にする乱数の分布を設定する。ノイズ以外の音型とベルベットノイズでは使わない。
+    pub fn set_noise_distribution(&mut self, distribution: ENoiseDistribution) -> &mut Self {
+        self.noise_distribution = distribution;
+        self
+    }
+
+    /// 周波数を変える音型なら、今の周波数を返す。
+    pub fn frequency(&self) -> Option<f64> {
+        match &self.emitter_type {
+            ESineEmitterType::Sine { frequency }
+            | ESineEmitterType::Sawtooth { frequency }
+            | ESineEmitterType::Triangle { frequency }
+            | ESineEmitterType::Square { frequency, .. } => Some(*frequency),
+            _ => None,
+        }
+    }
+
+    /// 周波数を`frequency`に変える。位相は今までの位相から続くので、途中で変えてもクリックノイズは出ない。
+    /// [`SineUnitSampleEmitter::set_glide_time`]が設定されていれば、その時間をかけて`frequency`まで滑らかに変える。
+    ///
+    /// サイン波・ノコギリ波・三角波・矩形波以外では何もしない。
+    pub fn set_frequency(&mut self, frequency: f64) -> &mut Self {
+        let Some(current_frequency) = self.frequency() else {
+            return self;
+        };
+
+        // ピッチが時間に比例して変わるように、周波数の比で変えていく。
+        let glide_sample_count = (self.glide_time * self.sample_rate as f64).round() as usize;
+        if glide_sample_count == 0 || current_frequency <= 0.0 || frequency <= 0.0 {
+            self.glide = None;
+            self.set_current_frequency(frequency);
+        } else {
+            self.glide = Some(FrequencyGlide {
+                target_frequency: frequency,
+                ratio: (frequency / current_frequency).powf((glide_sample_count as f64).recip()),
+                remained_sample_count: glide_sample_count,
+            });
+        }
+        self
+    }
+
+    /// [`SineUnitSampleEmitter::set_frequency`]で周波数を変える時にかける時間（秒）を設定する。
+    /// `0`なら（ポルタメントなしで）すぐに変わる。
+    pub fn set_glide_time(&mut self, glide_time: f64) -> &mut Self {
+        self.glide_time = glide_time.max(0.0);
+        self
+    }
+
+    /// 振幅`[0, 1]`を変える。
+    pub fn set_intensity(&mut self, intensity: f64) -> &mut Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// 周波数と振幅をサンプルごとに変えながら（ビブラートやピッチエンベロープなど）、次のサンプルを取得する。
+    /// 周波数はグライドせずにすぐに変わる。
+    pub fn next_sample_with(&mut self, frequency: f64, intensity: f64) -> UniformedSample {
+        self.glide = None;
+        self.set_current_frequency(frequency);
+        self.intensity = intensity;
+        self.next_sample()
+    }
+
+    fn set_current_frequency(&mut self, new_frequency: f64) {
+        match &mut self.emitter_type {
+            ESineEmitterType::Sine { frequency }
+            | ESineEmitterType::Sawtooth { frequency }
+            | ESineEmitterType::Triangle { frequency }
+            | ESineEmitterType::Square { frequency, .. } => *frequency = new_frequency,
+            _ => (),
+        }
+    }
+
+    /// 今の周波数で位相を1サンプル分進めて、グライド中なら周波数も更新する。
+    fn advance_phase(&mut self) {
+        let Some(frequency) = self.frequency() else {
+            return;
+        };
+        self.phase_cycle = (self.phase_cycle + (frequency / self.sample_rate as f64)).rem_euclid(1.0);
+
+        if let Some(glide) = self.glide.as_mut() {
+            glide.remained_sample_count -= 1;
+            let is_glide_finished = glide.remained_sample_count == 0;
+            let new_frequency = if is_glide_finished {
+                glide.target_frequency
+            } else {
+                frequency * glide.ratio
+            };
+            if is_glide_finished {
+                self.glide = None;
+            }
+            self.set_current_frequency(new_frequency);
+        }
+    }
+
     /// 次のサンプルを取得する。
     pub fn next_sample(&mut self) -> UniformedSample {
+        let sample = self.compute_sample();
+        self.advance_phase();
+        sample
+    }
+
+    fn compute_sample(&mut self) -> UniformedSample {
         // Sin波形に入れる値はf64として計算する。
         // そしてu32に変換する。最大値は`[2^32 - 1]`である。
         let sample_rate = self.sample_rate as f64;
-        let coefficient = PI2 / sample_rate;
         let unit_time = self.next_sample_index as f64;
         self.next_sample_index += 1;
+        let is_polyblep = self.antialias == EOscillatorAntialias::PolyBlep;
+
+        // 周期の中の位置`[0, 1)`。周波数を変えても続くように、サンプルのインデックスではなく積算した位相から求める。
+        let cycle = (self.phase_cycle + (self.phase / PI2)).rem_euclid(1.0);
 
         match &mut self.emitter_type {
-            ESineEmitterType::Sine { frequency } => {
-                // 振幅と周波数のエンベロープのため相対時間を計算
-                let sin_input = (coefficient * *frequency * unit_time) + self.phase;
+            ESineEmitterType::Sine { .. } => {
+                let sin_input = PI2 * cycle;
                 let sample = self.intensity * sin_input.sin();
-                assert!(sample >= -1.0 && sample <= 1.0);
 
-                UniformedSample::from_f64(sample)
+                UniformedSample::from_f64(sample.clamp(-1.0, 1.0))
             }
             ESineEmitterType::Sawtooth { frequency } => {
-                // 振幅と周波数のエンベロープのため相対時間を計算
-                let rel_time = unit_time / sample_rate;
-                let orig_intensity = 1.0 - (2.0 * (rel_time * *frequency).fract());
+                let mut orig_intensity = 1.0 - (2.0 * cycle);
+                // PolyBLEPの補正は周波数がサンプルレートに近づくと±1を超えるので、最後にクランプする。
+                if is_polyblep {
+                    // 周期の始まりで-1から1に上がる。
+                    orig_intensity += poly_blep(cycle, *frequency / sample_rate);
+                }
 
                 let sample = self.intensity * orig_intensity;
-                assert!(sample >= -1.0 && sample <= 1.0);
 
-                UniformedSample::from_f64(sample)
+                UniformedSample::from_f64(sample.clamp(-1.0, 1.0))
             }
             ESineEmitterType::Triangle { frequency } => {
-                let compute_intensity = |time_i: f64, sample_rate: f64, frequency: f64| {
-                    // 振幅と周波数のエンベロープのため相対時間を計算
-                    let rel_time = time_i / sample_rate;
-                    let orig_time = rel_time * frequency;
-
-                    let coeff = orig_time.fract();
-                    if coeff < 0.5 {
-                        // [0, 0.5)の範囲
-                        (-1.0 + (4.0 * coeff), orig_time)
-                    } else {
-                        // [0.5, 1)の範囲
-                        (3.0 - (4.0 * coeff), orig_time)
-                    }
+                let mut orig_intensity = if cycle < 0.5 {
+                    // [0, 0.5)の範囲
+                    -1.0 + (4.0 * cycle)
+                } else {
+                    // [0.5, 1)の範囲
+                    3.0 - (4.0 * cycle)
                 };
-
-                let (orig_intensity, _) = compute_intensity(unit_time, sample_rate, *frequency);
+                if is_polyblep {
+                    // 周期の始まりで傾きが-4から4に、半分で4から-4に変わる。
+                    let dt = *frequency / sample_rate;
+                    orig_intensity += 4.0 * dt * (poly_blamp(cycle, dt) - poly_blamp((cycle + 0.5).fract(), dt));
+                }
                 let sample = self.intensity * orig_intensity;
-                assert!(sample >= -1.0 && sample <= 1.0);
 
-                UniformedSample::from_f64(sample)
+                UniformedSample::from_f64(sample.clamp(-1.0, 1.0))
             }
             ESineEmitterType::Square { duty_rate, frequency } => {
-                let herz = coefficient * *frequency;
-                let duty_threshold = PI2 * duty_rate.clamp(0.0, 1.0);
-
-                // 振幅と周波数のエンベロープのため相対時間を計算
-                // 正弦波形の周期を計算する。そこでduty_rateを反映する。
-                // phaseは後に入れてSignを計算する。
-                let unittime = unit_time as f64;
-                let input = (herz * unittime) + self.phase;
-                let sample = self.intensity * {
-                    if (input % PI2) < duty_threshold {
-                        1.0
-                    } else {
-                        -1.0
-                    }
-                };
+                // 周期の中の位置とduty_rateを比べてSignを計算する。
+                let duty_rate = duty_rate.clamp(0.0, 1.0);
+                let mut orig_intensity = if cycle < duty_rate { 1.0 } else { -1.0 };
+                if is_polyblep {
+                    // 周期の始まりで-1から1に上がって、duty_rateのところで1から-1に下がる。
+                    let dt = *frequency / sample_rate;
+                    orig_intensity += poly_blep(cycle, dt) - poly_blep((cycle - duty_rate + 1.0).fract(), dt);
+                }
+                let sample = self.intensity * orig_intensity;
 
-                UniformedSample::from_f64(sample)
+
+                UniformedSample::from_f64(sample.clamp(-1.0, 1.0))
             }
             ESineEmitterType::WhiteNoise => {
-                // 正規分布からの乱数を使ってWhiteNoiseを生成する。
-                // 中ではどんな方法を使っているかわからないが、一番速いのはZiggurat法。
-                // https://andantesoft.hatenablog.com/entry/2023/04/30/183032
-
                 // [-1, 1]にする。
-                let value: f64 = self.rng.sample(rand::distributions::Standard);
-                let value = (value * 2.0) - 1.0;
+                let value = white_value(&mut self.rng, self.noise_distribution);
 
                 UniformedSample::from_f64(value * self.intensity)
             }
@@ -231,70 +491,68 @@ impl SineUnitSampleEmitter {
                 pink_i,
                 running_sum,
             } => {
-                // ピンクノイズを出力する
-                // https://www.firstpr.com.au/dsp/pink-noise/#Voss-McCartney を参考
-
-                // 実装アルゴリズムを見た感じでは、
-                // 多段階のRowをSumしたのがサンプルの値とみなす形式で進めているので
-                // 例えば時間軸で進むとしたらLSBからビットが1になるまでの0の数を見て
-                // 1 * * * * * * * * * * * * * * * *
-                // 2  *   *   *   *   *   *   *   *
-                // 3    *       *       *       *
-                // 4        *               *
-                // 5                *
-                // のように扱って各Rowに乱数の値を保持して計算することができる。（これがコスト的に安い）
-                let row_nums = 12;
-                let pmax = 1.0 * ((row_nums + 1) as f64);
-                let pink_scalar = pmax.recip();
-
-                if rows.is_empty() {
-                    rows.resize(row_nums, 0.0);
-                }
-
-                // 更新するpink_iから0の数を数えることで更新するrowsの番地を探す。
-                // もしかして0なら、何もしないのがお決まり。
-                *pink_i = (*pink_i + 1) & ((1 << row_nums) - 1);
-                if *pink_i != 0 {
-                    let row_i = pink_i.trailing_zeros() as usize;
-
-                    // running_sumから前の値を抜いて、新しい正規乱数を入れる。
-                    *running_sum -= rows[row_i];
+                let sample_value = pink_value(rows, pink_i, running_sum, &mut self.rng, self.noise_distribution);
 
-                    // [-1, 1]にする。
-                    let rng_v: f64 = self.rng.sample(rand::distributions::Standard);
-                    let value = (rng_v * 2.0) - 1.0;
+                UniformedSample::from_f64((sample_value * self.intensity).clamp(-1.0, 1.0))
+            }
+            ESineEmitterType::BrownNoise { last_value } => {
+                // ホワイトノイズを少しずつ漏らしながら積分する。
+                // 漏らさないとランダムウォークになって、直流成分がどんどんずれていく。
+                let value = white_value(&mut self.rng, self.noise_distribution);
+                *last_value = (*last_value + (0.02 * value)) / 1.02;
 
-                    // 新しい正規乱数を足して再指定する。
-                    *running_sum += value;
-                    rows[row_i] = value;
-                }
+                UniformedSample::from_f64(*last_value * 3.5 * self.intensity)
+            }
+            ESineEmitterType::BlueNoise {
+                rows,
+                pink_i,
+                running_sum,
+                last_pink_value,
+            } => {
+                // ピンクノイズを微分して、傾きを-3dBから+3dBにする。
+                let value = pink_value(rows, pink_i, running_sum, &mut self.rng, self.noise_distribution);
+                let sample_value = (value - *last_pink_value) * BLUE_NOISE_GAIN;
+                *last_pink_value = value;
 
-                // 段階が低くてもPinkNoise感を出すために（またランダム性をもたせるために）
-                // 正規乱数を入れてサンプル値にする。[-1, 1]にする。
-                let rng_v: f64 = self.rng.sample(rand::distributions::Standard);
-                let value = (rng_v * 2.0) - 1.0;
-                let sum = *running_sum + value;
-                let sample_value = pink_scalar * sum;
+                UniformedSample::from_f64(sample_value * self.intensity)
+            }
+            ESineEmitterType::VioletNoise { last_white_value } => {
+                // ホワイトノイズを微分して、傾きを+6dBにする。
+                let value = white_value(&mut self.rng, self.noise_distribution);
+                let sample_value = (value - *last_white_value) * 0.5;
+                *last_white_value = value;
 
-                UniformedSample::from_f64((sample_value * self.intensity).clamp(-1.0, 1.0))
+                UniformedSample::from_f64(sample_value * self.intensity)
             }
-            ESineEmitterType::SineSweep {
-                from_frequency,
-                to_frequency,
-                length,
+            ESineEmitterType::VelvetNoise {
+                period,
+                frame_i,
+                next_impulse,
             } => {
-                // https://www.recordingblogs.com/wiki/sine-sweep
-                // から t = unit_time / Fsにして数式通りに計算してみる。
-                let time_sec = (unit_time / sample_rate).min(*length);
-
-                let length_recip = (*length).recip();
-                let freq_range = *to_frequency - *from_frequency;
-                let sin_input =
-                    PI2 * ((*from_frequency * time_sec) + (freq_range * 0.5 * length_recip * (time_sec * time_sec))) + self.phase;
-                let sample = self.intensity * sin_input.sin();
-                assert!(sample >= -1.0 && sample <= 1.0);
+                // `period`サンプルの区間ごとに、区間の中のランダムな位置に`±1`のインパルスを1つ置く。
+                let sample_i = unit_time as usize;
+                let (impulse_i, sign) = *next_impulse.get_or_insert_with(|| {
+                    let frame_start = *frame_i as f64 * *period;
+                    let offset: f64 = self.rng.sample(rand::distributions::Standard);
+                    let impulse_i = (frame_start + (offset * (*period - 1.0))).round() as usize;
+                    let sign = if self.rng.gen::<bool>() { 1.0 } else { -1.0 };
+                    (impulse_i, sign)
+                });
+
+                if sample_i == impulse_i {
+                    *frame_i += 1;
+                    *next_impulse = None;
+                    UniformedSample::from_f64(sign * self.intensity)
+                } else {
+                    UniformedSample::MIN
+                }
+            }
+            ESineEmitterType::SineSweep { setting } => {
+                let time_sec = (unit_time / sample_rate).min(setting.length);
+                let sin_input = setting.phase_of(time_sec) + self.phase;
+                let sample = self.intensity * setting.fade_of(time_sec) * sin_input.sin();
 
-                UniformedSample::from_f64(sample)
+                UniformedSample::from_f64(sample.clamp(-1.0, 1.0))
             }
         }
     }
@@ -309,6 +567,101 @@ impl SineUnitSampleEmitter {
     }
 }
 
+/// `distribution`の乱数からホワイトノイズの値を1つ返す。
+fn white_value(rng: &mut NoiseRng, distribution: ENoiseDistribution) -> f64 {
+    match distribution {
+        ENoiseDistribution::Uniform => {
+            // [-1, 1]にする。
+            let value: f64 = rng.sample(rand::distributions::Standard);
+            (value * 2.0) - 1.0
+        }
+        ENoiseDistribution::Gaussian => {
+            // Box-Muller法で標準正規分布の値を作ってから、標準偏差を1/3にする。
+            // https://en.wikipedia.org/wiki/Box%E2%80%93Muller_transform
+            let u1: f64 = rng.sample(rand::distributions::OpenClosed01);
+            let u2: f64 = rng.sample(rand::distributions::Standard);
+            let value = (-2.0 * u1.ln()).sqrt() * (PI2 * u2).cos();
+            value / 3.0
+        }
+    }
+}
+
+/// ピンクノイズの次の値を返す。
+fn pink_value(
+    rows: &mut Vec<f64>,
+    pink_i: &mut i32,
+    running_sum: &mut f64,
+    rng: &mut NoiseRng,
+    distribution: ENoiseDistribution,
+) -> f64 {
+    // ピンクノイズを出力する
+    // https://www.firstpr.com.au/dsp/pink-noise/#Voss-McCartney を参考
+
+    // 実装アルゴリズムを見た感じでは、
+    // 多段階のRowをSumしたのがサンプルの値とみなす形式で進めているので
+    // 例えば時間軸で進むとしたらLSBからビットが1になるまでの0の数を見て
+    // 1 * * * * * * * * * * * * * * * *
+    // 2  *   *   *   *   *   *   *   *
+    // 3    *       *       *       *
+    // 4        *               *
+    // 5                *
+    // のように扱って各Rowに乱数の値を保持して計算することができる。（これがコスト的に安い）
+    let row_nums = 12;
+    let pmax = 1.0 * ((row_nums + 1) as f64);
+    let pink_scalar = pmax.recip();
+
+    if rows.is_empty() {
+        rows.resize(row_nums, 0.0);
+    }
+
+    // 更新するpink_iから0の数を数えることで更新するrowsの番地を探す。
+    // もしかして0なら、何もしないのがお決まり。
+    *pink_i = (*pink_i + 1) & ((1 << row_nums) - 1);
+    if *pink_i != 0 {
+        let row_i = pink_i.trailing_zeros() as usize;
+
+        // running_sumから前の値を抜いて、新しい乱数を入れる。
+        *running_sum -= rows[row_i];
+        let value = white_value(rng, distribution);
+        *running_sum += value;
+        rows[row_i] = value;
+    }
+
+    // 段階が低くてもPinkNoise感を出すために（またランダム性をもたせるために）
+    // 乱数を入れてサンプル値にする。
+    let value = white_value(rng, distribution);
+    let sum = *running_sum + value;
+    pink_scalar * sum
+}
+
+/// `phase`が`[0, 1)`の周期で、`phase = 0`に高さ`2`の段差がある時のPolyBLEPの補正値を返す。
+/// `dt`は1サンプルで進む`phase`の量。
+fn poly_blep(phase: f64, dt: f64) -> f64 {
+    if phase < dt {
+        let x = phase / dt;
+        x + x - (x * x) - 1.0
+    } else if phase > 1.0 - dt {
+        let x = (phase - 1.0) / dt;
+        (x * x) + x + x + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// `phase`が`[0, 1)`の周期で、`phase = 0`に傾きの不連続点がある時のPolyBLAMPの補正値を返す。
+/// 傾きの変化量と`dt`をかけて使う。
+fn poly_blamp(phase: f64, dt: f64) -> f64 {
+    if phase < dt {
+        let x = (phase / dt) - 1.0;
+        -(x * x * x) / 3.0
+    } else if phase > 1.0 - dt {
+        let x = ((phase - 1.0) / dt) + 1.0;
+        (x * x * x) / 3.0
+    } else {
+        0.0
+    }
+}
+
 // ----------------------------------------------------------------------------
 // EOF
 // ----------------------------------------------------------------------------
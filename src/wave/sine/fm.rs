@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+use crate::wave::{envelope::AdsrEnvelope, sample::UniformedSample, PI2};
+
+/// FM合成で使えるオペレーターの最小数
+pub const MIN_OPERATOR_COUNT: usize = 2;
+/// FM合成で使えるオペレーターの最大数
+pub const MAX_OPERATOR_COUNT: usize = 6;
+
+/// FM合成の1つのオペレーターの設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FmOperatorSetting {
+    /// 基本周波数に対する周波数の比
+    pub ratio: f64,
+    /// モジュレーターなら変調指数（位相をずらす最大値、ラジアン）、キャリアなら出力の振幅`[0, 1]`。
+    pub index: f64,
+    /// 自分の直前の出力で自分を変調する量。`0`なら何もしない。
+    #[serde(default)]
+    pub feedback: f64,
+    /// オペレーターの出力にかけるエンベロープ
+    pub envelope: AdsrEnvelope,
+}
+
+/// オペレーターの繋ぎ方（アルゴリズム）。
+///
+/// オペレーターは`0`から数えて、番号が大きいオペレーターが小さいオペレーターを変調する。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum EFmAlgorithm {
+    /// `0 ← 1 ← 2 ← ...`のように直列に繋いで、`0`だけを出力する。
+    #[serde(rename = "stack")]
+    Stack,
+    /// `0 ← 1`、`2 ← 3`、`4 ← 5`のように2つずつ組んで、偶数番のオペレーターを出力する。
+    #[serde(rename = "pairs")]
+    Pairs,
+    /// 変調せずに全部のオペレーターを足して出力する。
+    #[serde(rename = "parallel")]
+    Parallel,
+    /// 繋ぎ方を直接指定する。
+    #[serde(rename = "custom")]
+    Custom {
+        /// `modulators[i]`はオペレーター`i`を変調するオペレーターのリスト。`i`より大きい番号だけ指定できる。
+        modulators: Vec<Vec<usize>>,
+        /// 出力に使うオペレーターのリスト
+        carriers: Vec<usize>,
+    },
+}
+
+impl EFmAlgorithm {
+    /// `operator_count`個のオペレーターの繋ぎ方を求める。繋ぎ方が正しくない場合はエラーを返す。
+    pub fn resolve(&self, operator_count: usize) -> anyhow::Result<FmRouting> {
+        if !(MIN_OPERATOR_COUNT..=MAX_OPERATOR_COUNT).contains(&operator_count) {
+            return Err(anyhow::anyhow!(
+                "FM operator count must be in [{}, {}], but {}.",
+                MIN_OPERATOR_COUNT,
+                MAX_OPERATOR_COUNT,
+                operator_count
+            ));
+        }
+
+        let routing = match self {
+            Self::Stack => FmRouting {
+                modulators: (0..operator_count)
+                    .map(|i| if i + 1 < operator_count { vec![i + 1] } else { vec![] })
+                    .collect(),
+                carriers: vec![0],
+            },
+            Self::Pairs => FmRouting {
+                modulators: (0..operator_count)
+                    .map(|i| {
+                        if i % 2 == 0 && i + 1 < operator_count {
+                            vec![i + 1]
+                        } else {
+                            vec![]
+                        }
+                    })
+                    .collect(),
+                carriers: (0..operator_count).step_by(2).collect(),
+            },
+            Self::Parallel => FmRouting {
+                modulators: vec![vec![]; operator_count],
+                carriers: (0..operator_count).collect(),
+            },
+            Self::Custom { modulators, carriers } => {
+                if modulators.len() != operator_count {
+                    return Err(anyhow::anyhow!(
+                        "`modulators` must have {} items, but {}.",
+                        operator_count,
+                        modulators.len()
+                    ));
+                }
+                for (operator_i, list) in modulators.iter().enumerate() {
+                    if let Some(invalid_i) = list.iter().find(|&&v| v <= operator_i || v >= operator_count) {
+                        return Err(anyhow::anyhow!(
+                            "Operator {} can not be modulated by operator {}.",
+                            operator_i,
+                            invalid_i
+                        ));
+                    }
+                }
+                if carriers.is_empty() || carriers.iter().any(|&v| v >= operator_count) {
+                    return Err(anyhow::anyhow!("`carriers` must have valid operator indices."));
+                }
+
+                FmRouting {
+                    modulators: modulators.clone(),
+                    carriers: carriers.clone(),
+                }
+            }
+        };
+        Ok(routing)
+    }
+}
+
+/// [`EFmAlgorithm::resolve`]で求めたオペレーターの繋ぎ方。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FmRouting {
+    /// `modulators[i]`はオペレーター`i`を変調するオペレーターのリスト
+    pub modulators: Vec<Vec<usize>>,
+    /// 出力に使うオペレーターのリスト
+    pub carriers: Vec<usize>,
+}
+
+/// オペレーターをアルゴリズム通りに繋いで、FM（位相変調）合成のサンプルを生成するエミッタ。
+#[derive(Debug, Clone)]
+pub struct FmUnitSampleEmitter {
+    frequency: f64,
+    intensity: f64,
+    sample_rate: usize,
+    operators: Vec<FmOperatorSetting>,
+    routing: FmRouting,
+    /// エンベロープのリリースを始める時間（秒）。`None`ならずっとSustainする。
+    gate_time: Option<f64>,
+    /// 各オペレーターの積算した位相`[0, 1)`
+    phases: Vec<f64>,
+    /// 各オペレーターの直前2つの出力。フィードバックに使う。
+    prev_outputs: Vec<[f64; 2]>,
+    /// 今のサンプルの各オペレーターの出力
+    outputs: Vec<f64>,
+    next_sample_index: usize,
+}
+
+impl FmUnitSampleEmitter {
+    /// `routing`は`operators`と同じ数のオペレーターで求めたものを使うこと。
+    pub fn new(
+        frequency: f64,
+        intensity: f64,
+        operators: Vec<FmOperatorSetting>,
+        routing: FmRouting,
+        gate_time: Option<f64>,
+        sample_rate: usize,
+    ) -> Self {
+        assert_eq!(operators.len(), routing.modulators.len());
+        assert!(sample_rate > 0);
+
+        let operator_count = operators.len();
+        Self {
+            frequency,
+            intensity,
+            sample_rate,
+            operators,
+            routing,
+            gate_time,
+            phases: vec![0.0; operator_count],
+            prev_outputs: vec![[0.0; 2]; operator_count],
+            outputs: vec![0.0; operator_count],
+            next_sample_index: 0,
+        }
+    }
+
+    /// オペレーター`operator_i`の`time`秒の時点のエンベロープの振幅を返す。
+    fn envelope_gain(&self, operator_i: usize, time: f64) -> f64 {
+        let envelope = &self.operators[operator_i].envelope;
+        match self.gate_time {
+            Some(gate_time) if time >= gate_time => {
+                envelope.release_gain(envelope.note_on_gain(gate_time), time - gate_time)
+            }
+            _ => envelope.note_on_gain(time),
+        }
+    }
+
+    /// 次のサンプルを取得する。
+    pub fn next_sample(&mut self) -> UniformedSample {
+        let sample_rate = self.sample_rate as f64;
+        let time = self.next_sample_index as f64 / sample_rate;
+        self.next_sample_index += 1;
+
+        // 番号が大きいオペレーターから計算すれば、変調に使う出力は全部そろっている。
+        for operator_i in (0..self.operators.len()).rev() {
+            let operator = self.operators[operator_i];
+
+            let mut modulation: f64 = self.routing.modulators[operator_i].iter().map(|&v| self.outputs[v]).sum();
+            if operator.feedback != 0.0 {
+                // 直前2つの出力の平均を使って、フィードバックの発振を抑える。
+                let [prev, prev_prev] = self.prev_outputs[operator_i];
+                modulation += operator.feedback * (prev + prev_prev) * 0.5;
+            }
+
+            let gain = self.envelope_gain(operator_i, time);
+            let output = operator.index * gain * ((PI2 * self.phases[operator_i]) + modulation).sin();
+            self.outputs[operator_i] = output;
+            self.prev_outputs[operator_i] = [output, self.prev_outputs[operator_i][0]];
+
+            // 位相を進める。
+            let phase_delta = self.frequency * operator.ratio / sample_rate;
+            self.phases[operator_i] = (self.phases[operator_i] + phase_delta).rem_euclid(1.0);
+        }
+
+        let carrier_sum: f64 = self.routing.carriers.iter().map(|&v| self.outputs[v]).sum();
+        let sample = self.intensity * carrier_sum / self.routing.carriers.len() as f64;
+        UniformedSample::from_f64(sample.clamp(-1.0, 1.0))
+    }
+
+    /// `length`分のサンプルを取得する。
+    pub fn next_samples(&mut self, length: usize) -> Vec<UniformedSample> {
+        (0..length).map(|_| self.next_sample()).collect()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
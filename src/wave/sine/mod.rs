@@ -1,2 +1,8 @@
 pub mod emitter;
 pub mod setting;
+pub mod fm;
+pub mod wavetable;
+pub mod additive;
+pub mod pluck;
+pub mod sweep;
+pub mod test_signal;
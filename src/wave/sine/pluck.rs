@@ -0,0 +1,300 @@
+use itertools::Itertools;
+use rand::{rngs, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::wave::{sample::UniformedSample, sine::emitter::SineUnitSampleEmitter};
+
+/// 弦が止まったとみなす振幅
+const SILENCE_THRESHOLD: f64 = 1e-4;
+/// ノートオフの後に-60dBまで減衰させる時間（秒）
+const RELEASE_DECAY_TIME: f64 = 0.08;
+/// チューニングのオールパスフィルターで遅らせる最小のサンプル数。
+/// 遅延が`0`に近いと係数が`1`に近くなって不安定になるので避ける。
+const MIN_ALLPASS_DELAY: f64 = 0.1;
+
+/// 弦を弾いた時に最初に入れるノイズの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EPluckExcitation {
+    #[default]
+    #[serde(rename = "white")]
+    White,
+    #[serde(rename = "pink")]
+    Pink,
+}
+
+/// [`PluckString`]の音色の設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PluckSetting {
+    pub excitation: EPluckExcitation,
+    /// ループのダンピングフィルター`y[n] = (1 - S)x[n] + Sx[n-1]`の`S`（`(0, 1)`）。
+    /// `0.5`で高い倍音が一番早く減衰して、`0`か`1`に近いほど明るく長く響く。
+    pub damping: f64,
+    /// 基本周波数の振幅が-60dBまで減衰する時間（秒）。`None`ならダンピングフィルターだけで減衰する。
+    pub decay_time: Option<f64>,
+    /// 最初に入れるノイズの乱数のシード。指定すると毎回同じノイズで弾く。
+    pub seed: Option<u64>,
+}
+
+/// 拡張Karplus-Strong法で1本の弦を鳴らすエミッタ。
+///
+/// ノイズで埋めたディレイラインを、ダンピングフィルターとチューニングのオールパスフィルターを通してループさせる。
+#[derive(Debug, Clone)]
+pub struct PluckString {
+    frequency: f64,
+    intensity: f64,
+    damping: f64,
+    sample_rate: usize,
+    delay_line: Vec<f64>,
+    cursor: usize,
+    /// ループを1周するごとにかける減衰の割合
+    loop_gain: f64,
+    allpass_coefficient: f64,
+    /// ダンピングフィルターの直前の入力
+    damping_prev_input: f64,
+    /// オールパスフィルターの直前の入力と出力
+    allpass_prev_input: f64,
+    allpass_prev_output: f64,
+    /// 振幅が[`SILENCE_THRESHOLD`]を下回り続けているサンプル数
+    silent_sample_count: usize,
+}
+
+impl PluckString {
+    /// `sample_rate`で`frequency`の弦を弾けるか？サンプルレートの4分の1より低くないと弾けない。
+    pub fn can_pluck(frequency: f64, sample_rate: usize) -> bool {
+        frequency > 0.0 && frequency < (sample_rate as f64 * 0.25)
+    }
+
+    /// `frequency`の弦を弾く。`frequency`は[`PluckString::can_pluck`]で弾けるものにすること。
+    pub fn new(frequency: f64, intensity: f64, setting: &PluckSetting, sample_rate: usize) -> Self {
+        assert!(sample_rate > 0);
+        assert!(Self::can_pluck(frequency, sample_rate));
+        assert!(setting.damping > 0.0 && setting.damping < 1.0);
+
+        // ループ全体の遅延（周期）をディレイライン、ダンピングフィルター、オールパスフィルターで分ける。
+        // ダンピングフィルターは低い周波数で`S`サンプル遅らせる。
+        let period = sample_rate as f64 / frequency;
+        let delay_length = (period - setting.damping - MIN_ALLPASS_DELAY).floor().max(1.0);
+        let allpass_delay = period - setting.damping - delay_length;
+        let allpass_coefficient = (1.0 - allpass_delay) / (1.0 + allpass_delay);
+
+        let mut string = Self {
+            frequency,
+            intensity,
+            damping: setting.damping,
+            sample_rate,
+            delay_line: excitation(setting.excitation, delay_length as usize, setting.seed),
+            cursor: 0,
+            loop_gain: 1.0,
+            allpass_coefficient,
+            damping_prev_input: 0.0,
+            allpass_prev_input: 0.0,
+            allpass_prev_output: 0.0,
+            silent_sample_count: 0,
+        };
+        if let Some(decay_time) = setting.decay_time {
+            string.set_decay_time(decay_time);
+        }
+        string
+    }
+
+    /// 基本周波数の振幅が`decay_time`秒で-60dBまで減衰するようにループの減衰を変える。
+    /// ダンピングフィルターだけの減衰より長くはできない。
+    pub fn set_decay_time(&mut self, decay_time: f64) -> &mut Self {
+        // ダンピングフィルターの基本周波数での振幅の応答
+        let omega = std::f64::consts::TAU * self.frequency / self.sample_rate as f64;
+        let s = self.damping;
+        let filter_gain = ((1.0 - s).powi(2) + s.powi(2) + (2.0 * s * (1.0 - s) * omega.cos())).sqrt();
+
+        // ループを1周するごとに`loop_gain * filter_gain`倍になるので、`decay_time * frequency`周で-60dBにする。
+        let cycle_gain = 10f64.powf(-3.0 / (decay_time.max(f64::EPSILON) * self.frequency));
+        self.loop_gain = (cycle_gain / filter_gain).min(1.0);
+        self
+    }
+
+    /// ノートオフとして、すぐに減衰させる。
+    pub fn release(&mut self) {
+        let loop_gain = self.loop_gain;
+        self.set_decay_time(RELEASE_DECAY_TIME);
+        self.loop_gain = self.loop_gain.min(loop_gain);
+    }
+
+    /// 1周期の間ずっと振幅が十分に小さくなったか？
+    pub fn is_finished(&self) -> bool {
+        self.silent_sample_count >= self.delay_line.len()
+    }
+
+    /// 次のサンプルを取得する。
+    pub fn next_sample(&mut self) -> UniformedSample {
+        let output = self.delay_line[self.cursor];
+
+        let damped = self.loop_gain * (((1.0 - self.damping) * output) + (self.damping * self.damping_prev_input));
+        self.damping_prev_input = output;
+
+        let c = self.allpass_coefficient;
+        let tuned = (c * damped) + self.allpass_prev_input - (c * self.allpass_prev_output);
+        self.allpass_prev_input = damped;
+        self.allpass_prev_output = tuned;
+
+        self.delay_line[self.cursor] = tuned;
+        self.cursor = (self.cursor + 1) % self.delay_line.len();
+
+        match output.abs() < SILENCE_THRESHOLD {
+            true => self.silent_sample_count += 1,
+            false => self.silent_sample_count = 0,
+        }
+        UniformedSample::from_f64(output * self.intensity)
+    }
+
+    /// `length`分のサンプルを取得する。
+    pub fn next_samples(&mut self, length: usize) -> Vec<UniformedSample> {
+        (0..length).map(|_| self.next_sample()).collect()
+    }
+}
+
+/// 直流成分を除いて、最大の振幅が`1`になるノイズを`length`個作る。
+fn excitation(excitation: EPluckExcitation, length: usize, seed: Option<u64>) -> Vec<f64> {
+    let mut emitter = match excitation {
+        EPluckExcitation::White => SineUnitSampleEmitter::new_whitenoise(1.0),
+        EPluckExcitation::Pink => SineUnitSampleEmitter::new_pinknoise(1.0),
+    };
+    if let Some(seed) = seed {
+        emitter.set_seed(seed);
+    }
+    let mut samples = emitter.next_samples(length).into_iter().map(|v| v.to_f64()).collect_vec();
+
+    let mean = samples.iter().sum::<f64>() / length as f64;
+    samples.iter_mut().for_each(|v| *v -= mean);
+    let peak = samples.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+    if peak > 0.0 {
+        samples.iter_mut().for_each(|v| *v /= peak);
+    }
+    samples
+}
+
+// ----------------------------------------------------------------------------
+// Player
+// ----------------------------------------------------------------------------
+
+/// [`PluckSequencePlayer`]で弾くノート
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PluckNote {
+    /// 弾く時間（秒）
+    pub time: f64,
+    pub frequency: f64,
+    /// 振幅`[0, 1]`
+    pub intensity: f64,
+    /// ノートオフまでの時間（秒）。`None`なら自然に減衰するまで鳴らす。
+    pub length: Option<f64>,
+}
+
+#[derive(Debug)]
+struct PluckVoice {
+    string: PluckString,
+    /// ノートオフするフレーム
+    release_frame: Option<usize>,
+}
+
+/// 時間順に並んだノートで弦を弾いて、重ねて出力する。
+#[derive(Debug)]
+pub struct PluckSequencePlayer {
+    setting: PluckSetting,
+    intensity: f64,
+    polyphony: usize,
+    sample_rate: usize,
+    notes: Vec<PluckNote>,
+    /// 次に弾くノートのインデックス
+    next_note_i: usize,
+    /// 古い順に並んだボイス
+    voices: Vec<PluckVoice>,
+    /// 今まで生成したフレーム数
+    elapsed_frame_count: usize,
+    /// シードを指定した時に、ノートごとのノイズのシードを作る乱数
+    seed_rng: Option<rngs::StdRng>,
+}
+
+impl PluckSequencePlayer {
+    /// `notes`は時間順に並べ替えて使う。
+    pub fn new(
+        setting: PluckSetting,
+        intensity: f64,
+        polyphony: usize,
+        mut notes: Vec<PluckNote>,
+        sample_rate: usize,
+    ) -> Self {
+        assert!(polyphony > 0, "Polyphony must be bigger than 0.");
+        assert!(sample_rate > 0);
+        notes.sort_by(|lhs, rhs| lhs.time.total_cmp(&rhs.time));
+
+        Self {
+            setting,
+            intensity,
+            polyphony,
+            sample_rate,
+            notes,
+            next_note_i: 0,
+            voices: vec![],
+            elapsed_frame_count: 0,
+            seed_rng: setting.seed.map(rngs::StdRng::seed_from_u64),
+        }
+    }
+
+    /// サンプルレートを返す。
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    /// 全部のノートを弾いて、全部の弦が止まったか？
+    pub fn is_finished(&self) -> bool {
+        self.next_note_i >= self.notes.len() && self.voices.is_empty()
+    }
+
+    /// `frame_count`フレーム分のサンプルを生成する。
+    pub fn next_frames(&mut self, frame_count: usize) -> Vec<UniformedSample> {
+        let sample_rate = self.sample_rate as f64;
+
+        let mut samples = Vec::with_capacity(frame_count);
+        for _ in 0..frame_count {
+            let frame_i = self.elapsed_frame_count;
+            self.elapsed_frame_count += 1;
+
+            // このフレームで弾くノートを全部弾く。ボイスが足りなければ一番古いボイスを止める。
+            while let Some(note) = self.notes.get(self.next_note_i) {
+                if (note.time * sample_rate).round() as usize > frame_i {
+                    break;
+                }
+                self.next_note_i += 1;
+
+                if self.voices.len() >= self.polyphony {
+                    self.voices.remove(0);
+                }
+                // 同じシードでもノートごとに違うノイズで弾く。
+                let setting = PluckSetting {
+                    seed: self.seed_rng.as_mut().map(|v| v.next_u64()),
+                    ..self.setting
+                };
+                let release_frame = note.length.map(|v| ((note.time + v.max(0.0)) * sample_rate).round() as usize);
+                self.voices.push(PluckVoice {
+                    string: PluckString::new(note.frequency, note.intensity, &setting, self.sample_rate),
+                    release_frame,
+                });
+            }
+
+            let mut sum = 0.0;
+            for voice in self.voices.iter_mut() {
+                if voice.release_frame == Some(frame_i) {
+                    voice.string.release();
+                }
+                sum += voice.string.next_sample().to_f64();
+            }
+            self.voices.retain(|v| !v.string.is_finished());
+
+            samples.push(UniformedSample::from_f64(sum * self.intensity));
+        }
+
+        samples
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,151 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::wave::{analyze::fft::convolve, PI2};
+
+/// サインスイープで周波数を変えていく方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ESineSweepMode {
+    /// 周波数を時間に比例して変える。全部の周波数に同じエネルギーが入る。
+    #[default]
+    #[serde(rename = "linear")]
+    Linear,
+    /// 周波数を時間に対して指数的に変える（Farinaのスイープ）。1オクターブごとに同じ時間をかける。
+    /// 高調波歪みがインパルス応答の前に分かれて出てくるので、測定に向いている。
+    #[serde(rename = "exponential", alias = "logarithmic")]
+    Exponential,
+}
+
+/// サインスイープの設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SineSweepSetting {
+    /// 開始周波数
+    pub from_frequency: f64,
+    /// 終了周波数
+    pub to_frequency: f64,
+    /// 開始から終了までの長さ（秒）
+    pub length: f64,
+    pub mode: ESineSweepMode,
+    /// 最初にかけるフェードインの長さ（秒）
+    pub fade_in: f64,
+    /// 最後にかけるフェードアウトの長さ（秒）
+    pub fade_out: f64,
+}
+
+impl SineSweepSetting {
+    /// フェードなしで`from_frequency`から`to_frequency`まで線形にスイープする設定を返す。
+    pub fn new_linear(from_frequency: f64, to_frequency: f64, length: f64) -> Self {
+        Self {
+            from_frequency,
+            to_frequency,
+            length,
+            mode: ESineSweepMode::Linear,
+            fade_in: 0.0,
+            fade_out: 0.0,
+        }
+    }
+
+    /// 指数スイープで周波数が`e`倍になるまでの時間（秒）。周波数が変わらなければ`None`を返す。
+    fn exponential_rate(&self) -> Option<f64> {
+        let ratio = self.to_frequency / self.from_frequency;
+        match (ratio - 1.0).abs() > f64::EPSILON {
+            true => Some(self.length / ratio.ln()),
+            false => None,
+        }
+    }
+
+    /// `time`秒の時点の位相（ラジアン）を返す。
+    pub fn phase_of(&self, time: f64) -> f64 {
+        let time = time.clamp(0.0, self.length);
+        match (self.mode, self.exponential_rate()) {
+            (ESineSweepMode::Exponential, Some(rate)) => PI2 * self.from_frequency * rate * ((time / rate).exp() - 1.0),
+            _ => {
+                // https://www.recordingblogs.com/wiki/sine-sweep
+                let freq_range = self.to_frequency - self.from_frequency;
+                PI2 * ((self.from_frequency * time) + (freq_range * 0.5 * (time * time) / self.length))
+            }
+        }
+    }
+
+    /// `time`秒の時点の瞬時周波数を返す。
+    pub fn frequency_of(&self, time: f64) -> f64 {
+        let time = time.clamp(0.0, self.length);
+        match (self.mode, self.exponential_rate()) {
+            (ESineSweepMode::Exponential, Some(rate)) => self.from_frequency * (time / rate).exp(),
+            _ => self.from_frequency + ((self.to_frequency - self.from_frequency) * time / self.length),
+        }
+    }
+
+    /// `time`秒の時点のフェードの倍率`[0, 1]`を返す。フェードにはハン窓の半分を使う。
+    pub fn fade_of(&self, time: f64) -> f64 {
+        if time < 0.0 || time > self.length {
+            return 0.0;
+        }
+
+        let mut factor = 1.0;
+        if self.fade_in > 0.0 && time < self.fade_in {
+            factor *= 0.5 * (1.0 - (PI * time / self.fade_in).cos());
+        }
+        let remained_time = self.length - time;
+        if self.fade_out > 0.0 && remained_time < self.fade_out {
+            factor *= 0.5 * (1.0 - (PI * remained_time / self.fade_out).cos());
+        }
+        factor
+    }
+
+    /// スイープの長さ分のサンプルを振幅`1`で作る。
+    pub fn samples(&self, sample_rate: usize) -> Vec<f64> {
+        let sample_count = (self.length * sample_rate as f64).round() as usize;
+        (0..sample_count)
+            .map(|sample_i| {
+                let time = sample_i as f64 / sample_rate as f64;
+                self.phase_of(time).sin() * self.fade_of(time)
+            })
+            .collect()
+    }
+
+    /// [`Self::samples`]と畳み込むと、スイープの長さ`- 1`サンプルの位置に振幅`1`のインパルスになる逆フィルターを作る。
+    ///
+    /// スイープを時間反転して、指数スイープなら低い周波数ほど長く鳴っている分を打ち消すために
+    /// 1オクターブごとに-6dBの振幅の傾きをかける。
+    pub fn inverse_filter(&self, sample_rate: usize) -> Vec<f64> {
+        let sweep = self.samples(sample_rate);
+        let sample_count = sweep.len();
+
+        // 時間反転した後の`sample_i`の位置の振幅の倍率。反転したスイープは高い周波数から始まる。
+        let envelope = |sample_i: usize| match (self.mode, self.exponential_rate()) {
+            (ESineSweepMode::Exponential, Some(rate)) => (-(sample_i as f64 / sample_rate as f64) / rate).exp(),
+            _ => 1.0,
+        };
+        let mut inverse: Vec<f64> = (0..sample_count)
+            .map(|sample_i| sweep[sample_count - 1 - sample_i] * envelope(sample_i))
+            .collect();
+
+        // 畳み込んだ時の`sample_count - 1`の位置の値が1になるように正規化する。
+        let peak = sweep
+            .iter()
+            .enumerate()
+            .map(|(sample_i, v)| v * v * envelope(sample_count - 1 - sample_i))
+            .sum::<f64>();
+        if peak > 0.0 {
+            inverse.iter_mut().for_each(|v| *v /= peak);
+        }
+        inverse
+    }
+}
+
+/// 録音したスイープ`recorded`に[`SineSweepSetting::inverse_filter`]の`inverse`を畳み込んで、
+/// `inverse`の長さ`- 1`サンプル目から始まるインパルス応答を`length`サンプル分返す。
+pub fn deconvolve(recorded: &[f64], inverse: &[f64], length: usize) -> Vec<f64> {
+    let start_i = inverse.len().saturating_sub(1);
+    let mut response = convolve(recorded, inverse);
+    response.resize(response.len().max(start_i + length), 0.0);
+    response.drain(..start_i);
+    response.truncate(length);
+    response
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
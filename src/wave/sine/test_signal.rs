@@ -0,0 +1,101 @@
+use crate::wave::{sample::UniformedSample, PI2};
+
+/// マルチトーンの1つのサイン波
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestTone {
+    pub frequency: f64,
+    /// 振幅`[0, 1]`
+    pub amplitude: f64,
+}
+
+/// [`TestSignalEmitter`]で出力するテスト信号の種類
+#[derive(Debug, Clone, PartialEq)]
+pub enum ETestSignal {
+    /// `delay`秒の位置に1サンプルだけのインパルスを1回出す。
+    Impulse { delay: f64 },
+    /// 1秒に`rate`回、1サンプルだけのインパルスを最初のサンプルから繰り返して出す。
+    PulseTrain { rate: f64 },
+    /// ずっと同じ値を出す。
+    Dc,
+    /// ずっと0を出す。
+    Silence,
+    /// 複数のサイン波を足して出す。振幅の合計が`1`を超えた分はクリップする。
+    MultiTone { tones: Vec<TestTone> },
+}
+
+/// フィルターなどの応答を測るためのテスト信号を出力するエミッタ。
+#[derive(Debug, Clone)]
+pub struct TestSignalEmitter {
+    signal: ETestSignal,
+    /// 全体の振幅。[`ETestSignal::Dc`]ではこの値をそのまま出力するので、負の値も使える。
+    intensity: f64,
+    sample_rate: usize,
+    next_sample_index: usize,
+    /// [`ETestSignal::PulseTrain`]の次のインパルスの位置（サンプル）
+    next_pulse_position: f64,
+}
+
+impl TestSignalEmitter {
+    pub fn new(signal: ETestSignal, intensity: f64, sample_rate: usize) -> Self {
+        assert!(sample_rate > 0);
+        match &signal {
+            ETestSignal::Impulse { delay } => assert!(*delay >= 0.0, "`delay` must not be negative."),
+            ETestSignal::PulseTrain { rate } => assert!(
+                *rate > 0.0 && *rate <= sample_rate as f64,
+                "`rate` must be in (0, sample_rate]."
+            ),
+            _ => (),
+        }
+
+        Self {
+            signal,
+            intensity,
+            sample_rate,
+            next_sample_index: 0,
+            next_pulse_position: 0.0,
+        }
+    }
+
+    /// 次のサンプルを取得する。
+    pub fn next_sample(&mut self) -> UniformedSample {
+        let sample_i = self.next_sample_index;
+        self.next_sample_index += 1;
+
+        let sample_rate = self.sample_rate as f64;
+        let value = match &self.signal {
+            ETestSignal::Impulse { delay } => match (delay * sample_rate).round() as usize == sample_i {
+                true => 1.0,
+                false => 0.0,
+            },
+            ETestSignal::PulseTrain { rate } => {
+                // 周期がサンプルの整数倍でなくても、長い間で平均して`rate`になるように丸めて置く。
+                if self.next_pulse_position.round() as usize <= sample_i {
+                    self.next_pulse_position += sample_rate / rate;
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            ETestSignal::Dc => 1.0,
+            ETestSignal::Silence => 0.0,
+            ETestSignal::MultiTone { tones } => {
+                let time = sample_i as f64 / sample_rate;
+                tones
+                    .iter()
+                    .map(|tone| tone.amplitude * (PI2 * tone.frequency * time).sin())
+                    .sum::<f64>()
+            }
+        };
+
+        UniformedSample::from_f64(value * self.intensity)
+    }
+
+    /// `length`分のサンプルを取得する。
+    pub fn next_samples(&mut self, length: usize) -> Vec<UniformedSample> {
+        (0..length).map(|_| self.next_sample()).collect()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
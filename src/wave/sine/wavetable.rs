@@ -0,0 +1,241 @@
+use itertools::Itertools;
+
+use crate::math::window::EWindowFunction;
+use crate::wave::analyze::analyzer::{FrequencyAnalyzerV2, WaveContainerSetting};
+use crate::wave::analyze::method::{EAnalyzeMethod, ETransformMethod};
+use crate::wave::analyze::sine_freq::SineFrequency;
+use crate::wave::analyze::transformer::{EExportSampleCountMode, FrequencyTransformer};
+use crate::wave::complex::Complex;
+use crate::wave::sample::UniformedSample;
+
+/// 1周期のフレームのデフォルトのサンプル数
+pub const DEFAULT_FRAME_SIZE: usize = 2048;
+
+/// IFFTの結果は`[-1, 1]`に収められるので、ギブス現象ではみ出す分が削られないように一旦かける値。
+const TRANSFORM_HEADROOM: f64 = 0.5;
+
+/// 1周期の波形（フレーム）を並べたウェーブテーブル。
+///
+/// 各フレームは倍音の数を半分ずつ減らした帯域制限のテーブル（mipmap）を持っていて、
+/// 再生する周波数でエイリアシングしないテーブルを選んで使う。
+#[derive(Debug, Clone)]
+pub struct Wavetable {
+    frame_size: usize,
+    /// `frames[frame_i][level]`はフレーム`frame_i`の`level`番目のmipmap
+    frames: Vec<Vec<Vec<f64>>>,
+}
+
+impl Wavetable {
+    /// `samples`を`frame_size`ずつ区切ってフレームにする。最後の半端なサンプルは捨てる。
+    pub fn from_samples(samples: &[UniformedSample], frame_size: usize) -> anyhow::Result<Self> {
+        if frame_size < 4 || !frame_size.is_power_of_two() {
+            return Err(anyhow::anyhow!(
+                "Wavetable frame size must be power of two and at least 4, but {}.",
+                frame_size
+            ));
+        }
+        if samples.len() < frame_size {
+            return Err(anyhow::anyhow!(
+                "Wavetable needs at least {} samples, but {}.",
+                frame_size,
+                samples.len()
+            ));
+        }
+
+        let frames = samples.chunks_exact(frame_size).map(build_mipmaps).collect_vec();
+        Ok(Self { frame_size, frames })
+    }
+
+    /// 1フレームのサンプル数
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    /// フレームの数
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// 各フレームのmipmapの数
+    pub fn mipmap_count(&self) -> usize {
+        self.frames[0].len()
+    }
+
+    /// `level`番目のmipmapが持つ最大の倍音の次数
+    pub fn max_harmonic(&self, level: usize) -> usize {
+        max_harmonic(self.frame_size, level)
+    }
+
+    /// `frequency`で再生してもナイキスト周波数を超えないmipmapの番号を返す。
+    /// どのmipmapでも超える場合は、一番倍音が少ないものを返す。
+    pub fn mipmap_level(&self, frequency: f64, sample_rate: usize) -> usize {
+        let last_level = self.mipmap_count() - 1;
+        if frequency <= 0.0 {
+            return 0;
+        }
+
+        let allowed_harmonic = (sample_rate as f64 * 0.5) / frequency;
+        (0..=last_level)
+            .find(|&level| self.max_harmonic(level) as f64 <= allowed_harmonic)
+            .unwrap_or(last_level)
+    }
+
+    /// `level`番目のmipmapから、`morph``[0, 1]`の位置のフレームの`cycle`（1周期を`[0, 1)`とした位置）の値を返す。
+    ///
+    /// フレームの間と、サンプルの間はそれぞれ線形補間する。
+    pub fn sample(&self, morph: f64, cycle: f64, level: usize) -> f64 {
+        let level = level.min(self.mipmap_count() - 1);
+        let frame_position = morph.clamp(0.0, 1.0) * (self.frame_count() - 1) as f64;
+        let frame_i = (frame_position.floor() as usize).min(self.frame_count() - 1);
+        let frame_rate = frame_position - frame_i as f64;
+
+        let lhs = self.sample_frame(frame_i, cycle, level);
+        if frame_rate <= 0.0 {
+            return lhs;
+        }
+        let rhs = self.sample_frame(frame_i + 1, cycle, level);
+        lhs + ((rhs - lhs) * frame_rate)
+    }
+
+    fn sample_frame(&self, frame_i: usize, cycle: f64, level: usize) -> f64 {
+        let table = &self.frames[frame_i][level];
+        let position = cycle.rem_euclid(1.0) * self.frame_size as f64;
+        let index = (position.floor() as usize).min(self.frame_size - 1);
+        let rate = position - index as f64;
+
+        let lhs = table[index];
+        let rhs = table[(index + 1) % self.frame_size];
+        lhs + ((rhs - lhs) * rate)
+    }
+}
+
+/// `level`番目のmipmapが持つ最大の倍音の次数。ナイキストのビンは使わない。
+fn max_harmonic(frame_size: usize, level: usize) -> usize {
+    ((frame_size >> 1) - 1) >> level
+}
+
+/// 1フレームの波形から、倍音を半分ずつ減らしたテーブルを作る。直流成分は取り除く。
+fn build_mipmaps(frame: &[UniformedSample]) -> Vec<Vec<f64>> {
+    let frame_size = frame.len();
+
+    // 2周分をFFTすると、`k`次の倍音はちょうどビン`2k`に乗る。
+    // また振幅は1周分の2倍になるので半分にする。
+    let analyze_count = frame_size << 1;
+    let analyzer = FrequencyAnalyzerV2 {
+        analyze_method: EAnalyzeMethod::FFT,
+        frequency_start: 0.0,
+        frequency_width: analyze_count as f64,
+        frequency_bin_count: analyze_count as u32,
+        window_function: EWindowFunction::None,
+    };
+    let spectrum = analyzer
+        .analyze_container(&WaveContainerSetting {
+            container: frame,
+            start_sample_index: 0,
+            samples_count: analyze_count,
+        })
+        .unwrap();
+    let harmonics = (0..(frame_size >> 1))
+        .map(|k| (0.5 * TRANSFORM_HEADROOM) * spectrum[k << 1].to_complex_f64())
+        .collect_vec();
+
+    let transformer = FrequencyTransformer {
+        transform_method: ETransformMethod::IFFT,
+        sample_count_mode: EExportSampleCountMode::Automatic,
+    };
+    let mut mipmaps = vec![];
+    let mut level = 0;
+    loop {
+        let max_harmonic = max_harmonic(frame_size, level);
+        if max_harmonic == 0 {
+            break;
+        }
+
+        // 実数の波形に戻すため、負の周波数側には共役を入れる。
+        let mut bins = vec![Complex::<f64>::default(); frame_size];
+        for k in 1..=max_harmonic {
+            bins[k] = harmonics[k];
+            bins[frame_size - k] = harmonics[k].conjugate();
+        }
+        let frequencies = bins
+            .into_iter()
+            .enumerate()
+            .map(|(bin_i, bin)| SineFrequency::from_complex_f64(bin_i as f64, bin))
+            .collect_vec();
+
+        let table = transformer
+            .transform_frequencies(&frequencies)
+            .unwrap()
+            .into_iter()
+            .map(|v| v.to_f64() / TRANSFORM_HEADROOM)
+            .collect_vec();
+        mipmaps.push(table);
+        level += 1;
+    }
+
+    mipmaps
+}
+
+/// [`Wavetable`]を周波数で再生して、サンプルを生成するエミッタ。
+#[derive(Debug, Clone)]
+pub struct WavetableUnitSampleEmitter {
+    wavetable: Wavetable,
+    frequency: f64,
+    intensity: f64,
+    /// フレームをスキャンする位置`[0, 1]`
+    morph: f64,
+    /// 積算した位相`[0, 1)`
+    phase_cycle: f64,
+    sample_rate: usize,
+}
+
+impl WavetableUnitSampleEmitter {
+    pub fn new(wavetable: Wavetable, frequency: f64, intensity: f64, morph: f64, sample_rate: usize) -> Self {
+        assert!(sample_rate > 0);
+        Self {
+            wavetable,
+            frequency,
+            intensity,
+            morph: morph.clamp(0.0, 1.0),
+            phase_cycle: 0.0,
+            sample_rate,
+        }
+    }
+
+    pub fn wavetable(&self) -> &Wavetable {
+        &self.wavetable
+    }
+
+    /// 周波数を変える。位相は連続したまま次のサンプルから反映する。
+    pub fn set_frequency(&mut self, frequency: f64) -> &mut Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// フレームをスキャンする位置`[0, 1]`を変える。
+    pub fn set_morph(&mut self, morph: f64) -> &mut Self {
+        self.morph = morph.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 次のサンプルを取得する。
+    pub fn next_sample(&mut self) -> UniformedSample {
+        let level = self.wavetable.mipmap_level(self.frequency, self.sample_rate);
+        let value = self.wavetable.sample(self.morph, self.phase_cycle, level);
+
+        // 位相を進める。
+        let phase_delta = self.frequency / self.sample_rate as f64;
+        self.phase_cycle = (self.phase_cycle + phase_delta).rem_euclid(1.0);
+
+        UniformedSample::from_f64(self.intensity * value)
+    }
+
+    /// `length`分のサンプルを取得する。
+    pub fn next_samples(&mut self, length: usize) -> Vec<UniformedSample> {
+        (0..length).map(|_| self.next_sample()).collect()
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1 @@
+pub mod round_trip;
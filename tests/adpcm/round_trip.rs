@@ -0,0 +1,129 @@
+use std::{fs, io};
+
+use soundprog::wave::{
+    container::{
+        stream::WaveStreamHeader, wav::adpcm::IMAADPCMWriter, wav::adpcm::ImaAdpcmStreamWriter, WaveContainer,
+    },
+    sample::UniformedSample,
+};
+
+fn read_wave_container(path: &str) -> WaveContainer {
+    let source_file = fs::File::open(path).expect(&format!("Could not find {}.", path));
+    let mut reader = io::BufReader::new(source_file);
+
+    WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+}
+
+/// 元のサンプルに対する誤差のパワーの比率を返す。
+fn error_power_ratio(decoded: &[UniformedSample], original: &[UniformedSample]) -> f64 {
+    let signal = original.iter().map(|v| v.to_f64().powi(2)).sum::<f64>();
+    let error = decoded
+        .iter()
+        .zip(original)
+        .map(|(a, b)| (a.to_f64() - b.to_f64()).powi(2))
+        .sum::<f64>();
+    error / signal
+}
+
+#[test]
+fn ima_adpcm_writer_round_trip() {
+    let wave_container = read_wave_container("assets/ex7/vocal.wav");
+
+    let mut writer = io::Cursor::new(vec![]);
+    IMAADPCMWriter {
+        source_container: &wave_container,
+    }
+    .write(&mut writer);
+
+    let decoded =
+        WaveContainer::from_bufread(&mut io::Cursor::new(writer.into_inner())).expect("Could not read IMA-ADPCM wav.");
+    assert_eq!(decoded.channel(), 1);
+    assert_eq!(decoded.samples_per_second(), wave_container.samples_per_second());
+
+    // 最後のブロックに満たない分は書き込まれない。
+    let original = wave_container.uniformed_sample_buffer();
+    let decoded = decoded.uniformed_sample_buffer();
+    assert_eq!(decoded.len(), original.len() - (original.len() % 505));
+    assert!(error_power_ratio(decoded, original) < 0.01);
+}
+
+#[test]
+fn ima_adpcm_stream_writer_stereo() {
+    const FRAME_COUNT: usize = 2000;
+
+    let original = (0..FRAME_COUNT)
+        .flat_map(|i| {
+            let phase = (i as f64) * 0.05;
+            [
+                UniformedSample::from_f64(phase.sin() * 0.5),
+                UniformedSample::from_f64(phase.cos() * 0.25),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut writer = io::Cursor::new(vec![]);
+    let mut stream_writer = ImaAdpcmStreamWriter::new(8000, 2).unwrap();
+    stream_writer.write_header(&mut writer);
+    for chunk in original.chunks(2 * 300) {
+        stream_writer.write_frames(&mut writer, chunk);
+    }
+    stream_writer.finish(&mut writer);
+    assert_eq!(stream_writer.written_frame_count(), FRAME_COUNT);
+
+    let written = writer.into_inner();
+    let container = WaveContainer::from_bufread(&mut io::Cursor::new(written.clone())).unwrap();
+    assert_eq!(container.channel(), 2);
+    assert_eq!(container.uniformed_sample_buffer().len(), original.len());
+    assert!(error_power_ratio(container.uniformed_sample_buffer(), &original) < 0.01);
+
+    // ブロックをまたいで途中から読み込んでも、全部デコードした時と同じになる。
+    let mut reader = io::Cursor::new(written);
+    let header = WaveStreamHeader::from_bufread(&mut reader).unwrap();
+    assert_eq!(header.frame_count(), FRAME_COUNT);
+    let frames = header.read_frames(&mut reader, 700, 600);
+    assert_eq!(frames, &container.uniformed_sample_buffer()[(700 * 2)..(1300 * 2)]);
+}
+
+#[test]
+fn ms_adpcm_decodes_block() {
+    const SAMPLES_PER_BLOCK: u16 = 10;
+    const COEFFICIENTS: [(i16, i16); 7] =
+        [(256, 0), (512, -256), (0, 0), (192, 64), (240, 0), (460, -208), (392, -232)];
+
+    let mut fmt = vec![];
+    fmt.extend_from_slice(&2u16.to_le_bytes()); // MS-ADPCM
+    fmt.extend_from_slice(&1u16.to_le_bytes());
+    fmt.extend_from_slice(&8000u32.to_le_bytes());
+    fmt.extend_from_slice(&8000u32.to_le_bytes());
+    fmt.extend_from_slice(&11u16.to_le_bytes()); // 7Bytesのヘッダー + 4Bytes（8サンプル）
+    fmt.extend_from_slice(&4u16.to_le_bytes());
+    fmt.extend_from_slice(&32u16.to_le_bytes());
+    fmt.extend_from_slice(&SAMPLES_PER_BLOCK.to_le_bytes());
+    fmt.extend_from_slice(&(COEFFICIENTS.len() as u16).to_le_bytes());
+    for (c1, c2) in COEFFICIENTS {
+        fmt.extend_from_slice(&c1.to_le_bytes());
+        fmt.extend_from_slice(&c2.to_le_bytes());
+    }
+
+    // 予測係数0番 (256, 0)なので、1個前のサンプルに差分 * deltaを足したものになる。
+    let mut data = vec![0u8];
+    data.extend_from_slice(&16i16.to_le_bytes()); // delta
+    data.extend_from_slice(&100i16.to_le_bytes()); // sample1
+    data.extend_from_slice(&50i16.to_le_bytes()); // sample2
+    data.extend_from_slice(&[0x10, 0x00, 0xF0, 0x00]);
+
+    let mut file = vec![];
+    file.extend_from_slice(b"RIFF");
+    file.extend_from_slice(&((4 + 8 + fmt.len() + 8 + data.len()) as u32).to_le_bytes());
+    file.extend_from_slice(b"WAVE");
+    for (id, chunk) in [(b"fmt ", &fmt), (b"data", &data)] {
+        file.extend_from_slice(id);
+        file.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        file.extend_from_slice(chunk);
+    }
+
+    let container = WaveContainer::from_bufread(&mut io::Cursor::new(file)).expect("Could not read MS-ADPCM wav.");
+    // 1番目の差分で+16して、deltaは230/256倍になって16のまま。5番目の差分で-1 * 16。
+    let expected = [50i16, 100, 116, 116, 116, 116, 100, 100, 100, 100].map(UniformedSample::from_16bits);
+    assert_eq!(container.uniformed_sample_buffer(), &expected);
+}
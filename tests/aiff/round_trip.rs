@@ -0,0 +1,193 @@
+use std::{fs, io};
+
+use soundprog::wave::{
+    container::{
+        aiff::{
+            comm::EAiffCompression,
+            stream::{AiffStreamHeader, AiffStreamWriter},
+            AiffBuilder, AiffContainer,
+        },
+        WaveContainer,
+    },
+    sample::UniformedSample,
+};
+
+/// テストに使うWAVファイル（モノラル16ビット）
+const ASSET_PATHS: [&'static str; 4] = [
+    "assets/ex1/a.wav",
+    "assets/ex6/drum.wav",
+    "assets/ex7/synth.wav",
+    "assets/ex7/white_noise.wav",
+];
+
+fn read_wave_container(path: &str) -> WaveContainer {
+    let source_file = fs::File::open(path).expect(&format!("Could not find {}.", path));
+    let mut reader = io::BufReader::new(source_file);
+
+    WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+}
+
+/// AIFFとして書き込んだバイト列を返す。
+fn write_to_bytes(container: &AiffContainer) -> Vec<u8> {
+    let mut writer = io::Cursor::new(vec![]);
+    container.write(&mut writer);
+    writer.into_inner()
+}
+
+#[test]
+fn aiff_round_trip_mono_assets() {
+    for path in ASSET_PATHS {
+        let wave_container = read_wave_container(path);
+        let aiff_container = AiffBuilder {
+            samples_per_sec: wave_container.samples_per_second(),
+            bits_per_sample: 16,
+        }
+        .build_mono(wave_container.uniformed_sample_buffer().to_vec())
+        .unwrap();
+
+        let written = write_to_bytes(&aiff_container);
+        assert_eq!(&written[..4], b"FORM");
+        assert_eq!(&written[8..12], b"AIFF");
+
+        let decoded = AiffContainer::from_bufread(&mut io::Cursor::new(written)).expect("Could not read AIFF.");
+        assert_eq!(decoded.channel(), 1, "{}", path);
+        assert_eq!(decoded.samples_per_second(), wave_container.samples_per_second(), "{}", path);
+        assert_eq!(
+            decoded.uniformed_sample_buffer(),
+            wave_container.uniformed_sample_buffer(),
+            "{}",
+            path
+        );
+    }
+}
+
+#[test]
+fn aiff_stream_header_reads_padded_8bits() {
+    // 奇数個のサンプルなので、SSNDチャンクの後ろにパディングが入る。
+    let samples = (0..101)
+        .map(|i| UniformedSample::from_f64(((i as f64) * 0.1).sin()))
+        .collect::<Vec<_>>();
+    let aiff_container = AiffBuilder {
+        samples_per_sec: 8000,
+        bits_per_sample: 8,
+    }
+    .build_mono(samples.clone())
+    .unwrap();
+
+    let written = write_to_bytes(&aiff_container);
+    assert_eq!(written.len() % 2, 0);
+    let form_size = u32::from_be_bytes(written[4..8].try_into().unwrap()) as usize;
+    assert_eq!(form_size + 8, written.len());
+
+    let mut reader = io::Cursor::new(written);
+    let header = AiffStreamHeader::from_bufread(&mut reader).expect("Could not read AIFF header.");
+    assert_eq!(header.frame_count(), samples.len());
+    assert_eq!(header.samples_per_second(), 8000);
+
+    let frames = header.read_frames(&mut reader, 50, 100);
+    assert_eq!(frames.len(), samples.len() - 50);
+    for (decoded, original) in frames.iter().zip(&samples[50..]) {
+        assert!((decoded.to_f64() - original.to_f64()).abs() < (2.0 / 127.0));
+    }
+}
+
+#[test]
+fn aifc_sowt_is_little_endian() {
+    const SAMPLES: [i16; 4] = [0, 1000, -1000, i16::MAX];
+
+    // AIFC・sowtのファイルを手で作る。
+    let mut comm = vec![];
+    comm.extend_from_slice(&1u16.to_be_bytes());
+    comm.extend_from_slice(&(SAMPLES.len() as u32).to_be_bytes());
+    comm.extend_from_slice(&16u16.to_be_bytes());
+    // 44100Hzの80ビット拡張倍精度浮動小数点
+    comm.extend_from_slice(&[0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+    comm.extend_from_slice(b"sowt");
+    comm.extend_from_slice(&[0, 0]);
+
+    let mut ssnd = vec![0u8; 8];
+    for sample in SAMPLES {
+        ssnd.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    let mut body = vec![];
+    body.extend_from_slice(b"AIFC");
+    for (id, chunk) in [(b"COMM", &comm), (b"SSND", &ssnd)] {
+        body.extend_from_slice(id);
+        body.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+        body.extend_from_slice(chunk);
+    }
+    let mut file = vec![];
+    file.extend_from_slice(b"FORM");
+    file.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    file.append(&mut body);
+
+    let decoded = AiffContainer::from_bufread(&mut io::Cursor::new(file)).expect("Could not read AIFC.");
+    assert_eq!(decoded.samples_per_second(), 44100);
+    let expected = SAMPLES.map(UniformedSample::from_16bits);
+    assert_eq!(decoded.uniformed_sample_buffer(), &expected);
+}
+
+#[test]
+fn aifc_round_trip_none_and_sowt() {
+    let left = (0..301)
+        .map(|i| UniformedSample::from_16bits((i * 100 - 15000) as i16))
+        .collect::<Vec<_>>();
+    let right = left.iter().map(|v| UniformedSample::from_f64(-v.to_f64())).collect::<Vec<_>>();
+    let aiff_container = AiffBuilder {
+        samples_per_sec: 48000,
+        bits_per_sample: 16,
+    }
+    .build_stereo(left, right)
+    .unwrap();
+
+    for (compression, id) in [
+        (EAiffCompression::BigEndian, b"NONE"),
+        (EAiffCompression::LittleEndian, b"sowt"),
+    ] {
+        let mut writer = io::Cursor::new(vec![]);
+        aiff_container.write_aifc(&mut writer, compression);
+        let written = writer.into_inner();
+        assert_eq!(&written[8..12], b"AIFC");
+        assert_eq!(&written[12..16], b"FVER");
+        assert!(written.windows(4).any(|v| v == id), "{:?}", compression);
+        let form_size = u32::from_be_bytes(written[4..8].try_into().unwrap()) as usize;
+        assert_eq!(form_size + 8, written.len());
+
+        let decoded = AiffContainer::from_bufread(&mut io::Cursor::new(written)).expect("Could not read AIFC.");
+        assert_eq!(decoded.channel(), 2, "{:?}", compression);
+        assert_eq!(decoded.samples_per_second(), 48000, "{:?}", compression);
+        assert_eq!(
+            decoded.uniformed_sample_buffer(),
+            aiff_container.uniformed_sample_buffer(),
+            "{:?}",
+            compression
+        );
+    }
+}
+
+#[test]
+fn aifc_stream_writer_sowt_is_little_endian() {
+    const SAMPLES: [i16; 4] = [0, 1000, -1000, i16::MAX];
+    let samples = SAMPLES.map(UniformedSample::from_16bits);
+
+    let mut writer = AiffStreamWriter::new_aifc(44100, 16, 1, EAiffCompression::LittleEndian).unwrap();
+    let mut cursor = io::Cursor::new(vec![]);
+    writer.write_header(&mut cursor);
+    writer.write_frames(&mut cursor, &samples[..2]);
+    writer.write_frames(&mut cursor, &samples[2..]);
+    writer.finish(&mut cursor);
+
+    // SSNDチャンクの最後にリトルエンディアンで入っている。
+    let written = cursor.into_inner();
+    let expected = SAMPLES.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>();
+    assert_eq!(&written[(written.len() - expected.len())..], &expected[..]);
+
+    let mut reader = io::Cursor::new(written);
+    let header = AiffStreamHeader::from_bufread(&mut reader).expect("Could not read AIFC header.");
+    assert_eq!(header.frame_count(), SAMPLES.len());
+    assert_eq!(header.read_frames(&mut reader, 0, SAMPLES.len()), samples);
+
+    // 浮動小数点の形式では書き込めない。
+    assert!(AiffStreamWriter::new_aifc(44100, 16, 1, EAiffCompression::Float32).is_none());
+}
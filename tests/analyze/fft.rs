@@ -0,0 +1,55 @@
+use soundprog::wave::analyze::fft::{convolve, fft};
+use soundprog::wave::complex::Complex;
+
+/// 定義通りに線形畳み込みする。
+fn convolve_direct(lhs: &[f64], rhs: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; lhs.len() + rhs.len() - 1];
+    for (lhs_i, lhs_value) in lhs.iter().enumerate() {
+        for (rhs_i, rhs_value) in rhs.iter().enumerate() {
+            result[lhs_i + rhs_i] += lhs_value * rhs_value;
+        }
+    }
+    result
+}
+
+/// FFTでの畳み込みは、長さが2のべき乗でなくても定義通りの線形畳み込みと同じになる。
+#[test]
+fn test_convolve_matches_direct() {
+    let lhs = (0..37).map(|v| ((v * 7 % 11) as f64 - 5.0) * 0.1).collect::<Vec<_>>();
+    let rhs = (0..13).map(|v| ((v * 5 % 7) as f64 - 3.0) * 0.25).collect::<Vec<_>>();
+
+    let expected = convolve_direct(&lhs, &rhs);
+    let actual = convolve(&lhs, &rhs);
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(&expected) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+    assert!(convolve(&lhs, &[]).is_empty());
+}
+
+/// FFTして逆変換すると、長さ倍された元の信号に戻る。
+#[test]
+fn test_fft_round_trip() {
+    let original = (0..64)
+        .map(|v| Complex::<f64> {
+            real: (v as f64 * 0.3).sin(),
+            imag: 0.0,
+        })
+        .collect::<Vec<_>>();
+
+    let mut signals = original.clone();
+    fft(&mut signals, false);
+    // 直流成分は信号の和になる。
+    let sum = original.iter().map(|v| v.real).sum::<f64>();
+    assert!((signals[0].real - sum).abs() < 1e-9);
+
+    fft(&mut signals, true);
+    for (actual, expected) in signals.iter().zip(&original) {
+        assert!((actual.real / 64.0 - expected.real).abs() < 1e-9);
+        assert!((actual.imag / 64.0).abs() < 1e-9);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,35 @@
+use soundprog::device::virtual_device::VirtualAudioDeviceSetting;
+use soundprog::device::{AudioDevice, AudioDeviceConfig};
+
+const SAMPLE_RATE: usize = 44100;
+
+/// キャプチャーした入力を読まないでいると、リングバッファに入りきらない分は捨てて測定値に数える。
+#[test]
+fn test_audio_device_capture_overflow() {
+    let _lock = crate::device::lock_audio_device();
+
+    let mut config = AudioDeviceConfig::new();
+    config
+        .set_channels(0)
+        .set_capture_channels(1)
+        .set_sample_rate(SAMPLE_RATE)
+        .set_period_size(Some(441))
+        .set_virtual_device(Some(VirtualAudioDeviceSetting::default()));
+    let proxy = AudioDevice::initialize(config).expect("Failed to initialize audio device");
+    let proxy = proxy.upgrade().expect("Proxy must be valid");
+
+    // 1秒分をキャプチャーしてから読み込む。
+    AudioDevice::pre_process(0.0);
+    AudioDevice::post_process(1.0);
+    let captured_samples = proxy.lock().unwrap().receive_captured_samples();
+    let metrics = proxy.lock().unwrap().get_metrics().expect("Device must be valid");
+    AudioDevice::cleanup();
+
+    assert!(!captured_samples.is_empty());
+    assert!(metrics.capture_dropped_samples > 0);
+    assert_eq!(captured_samples.len() + metrics.capture_dropped_samples, SAMPLE_RATE);
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
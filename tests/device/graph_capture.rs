@@ -0,0 +1,59 @@
+use super::{assert_graph_error, GraphJsonBuilder};
+use soundprog::wave::container::WaveContainer;
+use std::{fs, io};
+
+const SAMPLE_RATE: usize = 44100;
+const LENGTH: f64 = 0.1;
+
+/// `emitter-device-input → output-file`のグラフを、仮想デバイスから入力を受け取るように設定する。
+fn create_graph_json(capture_channels: usize, file_name: &str) -> serde_json::Value {
+    let audio_device = serde_json::json!({
+        "channels": 0,
+        "capture_channels": capture_channels,
+        "sample_rate": SAMPLE_RATE,
+        "period_size": 441,
+        "virtual": {}
+    });
+    let input = serde_json::json!({ "type": "emitter-device-input", "length": LENGTH });
+    GraphJsonBuilder::output_file(input, SAMPLE_RATE, file_name)
+        .system_setting("audio_device", audio_device)
+        .build()
+}
+
+/// デバイスの入力を`length`の分だけ受け取ってファイルに書き込む。
+#[test]
+fn test_graph_device_input_to_file() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_capture";
+    let json = create_graph_json(1, file_name);
+    let output = super::run_graph(&dir, file_name, &json);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let wav_path = dir.join(format!("{}.wav", file_name));
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Output file must be written");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+
+    // 仮想デバイスの入力は無音になっている。
+    let samples = container.uniformed_sample_buffer();
+    assert_eq!(samples.len(), (LENGTH * SAMPLE_RATE as f64).round() as usize);
+    assert!(samples.iter().all(|v| v.to_f64() == 0.0));
+}
+
+/// `capture_channels`を指定しないで使うと、パニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_device_input_without_capture_channels() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_capture_error";
+    let mut json = create_graph_json(0, file_name);
+    json["system_setting"]["audio_device"]["channels"] = serde_json::json!(1);
+    let output = super::run_graph(&dir, file_name, &json);
+    assert_graph_error(&output, "capture_channels");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
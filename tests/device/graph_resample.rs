@@ -0,0 +1,122 @@
+use super::{assert_graph_error, GraphJsonBuilder};
+use soundprog::wave::container::stream::{EStreamWriter, WaveStreamWriter};
+use soundprog::wave::container::WaveContainer;
+use soundprog::wave::sample::UniformedSample;
+use std::path::Path;
+use std::{fs, io};
+
+const DEVICE_SAMPLE_RATE: usize = 44100;
+const INPUT_SAMPLE_RATE: usize = 22050;
+const PERIOD_SIZE: usize = 441;
+const FREQUENCY: f64 = 441.0;
+const INTENSITY: f64 = 0.5;
+const LENGTH: f64 = 0.1;
+
+/// デバイスとサンプルレートが違う`emitter-sine → output-device`のグラフを作る。
+fn create_graph_json(wav_path: &Path, strict_sample_rate: bool) -> serde_json::Value {
+    let input = serde_json::json!({
+        "type": "emitter-sine",
+        "frequency": { "type": "constant", "value": FREQUENCY },
+        "intensity": INTENSITY,
+        "range": { "start": 0.0, "length": LENGTH },
+        "sample_rate": INPUT_SAMPLE_RATE
+    });
+    create_graph_json_with(wav_path, input, strict_sample_rate)
+}
+
+/// `input`ノードを`output-device`に繋いだグラフを作る。
+fn create_graph_json_with(wav_path: &Path, input: serde_json::Value, strict_sample_rate: bool) -> serde_json::Value {
+    let mut json = GraphJsonBuilder::output_device(input)
+        .setting("scheduler", serde_json::json!("pull"))
+        .virtual_device(1, DEVICE_SAMPLE_RATE, PERIOD_SIZE, wav_path)
+        .build();
+    json["node"]["output"]["strict_sample_rate"] = serde_json::json!(strict_sample_rate);
+    json
+}
+
+/// 入力をデバイスのサンプルレートにリサンプリングして、最後まで捨てずに書き込む。
+#[test]
+fn test_graph_output_device_resample() {
+    let dir = std::env::temp_dir();
+    let name = "soundprog_test_graph_resample";
+    let wav_path = dir.join(format!("{}.wav", name));
+    let json = create_graph_json(&wav_path, false);
+    let output = super::run_graph(&dir, name, &json);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Virtual device must write wav file");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+    assert_eq!(container.samples_per_second() as usize, DEVICE_SAMPLE_RATE);
+
+    // 正弦波の長さの分だけ、音のあるサンプルが書き込まれている。
+    let samples: Vec<f64> = container.uniformed_sample_buffer().iter().map(|v| v.to_f64()).collect();
+    let length = (LENGTH * DEVICE_SAMPLE_RATE as f64).round() as usize;
+    let first = samples.iter().position(|v| v.abs() > 1e-3).expect("Sine must be written");
+    let last = samples.iter().rposition(|v| v.abs() > 1e-3).unwrap();
+    assert!((last - first).abs_diff(length) < PERIOD_SIZE / 4, "{}..{}", first, last);
+
+    // 振幅もそのまま保たれている。
+    let peak = samples.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+    assert!((peak - INTENSITY).abs() < 0.05, "{}", peak);
+}
+
+/// `strict_sample_rate`なら、設定でサンプルレートが決まるノードはグラフを検査する時にエラーにして、
+/// デバイスには何も送らない。
+#[test]
+fn test_graph_output_device_strict_sample_rate() {
+    let dir = std::env::temp_dir();
+    let name = "soundprog_test_graph_resample_strict";
+    let wav_path = dir.join(format!("{}.wav", name));
+    let json = create_graph_json(&wav_path, true);
+    let output = super::run_graph(&dir, name, &json);
+    let written_frame_count = read_frame_count(&wav_path);
+    let _ = fs::remove_file(&wav_path);
+    assert_graph_error(
+        &output,
+        "Sample rate 22050Hz of node `input` does not match audio device sample rate 44100Hz",
+    );
+    assert_eq!(written_frame_count, 0);
+}
+
+/// ファイルでサンプルレートが決まるノードは、処理中にサンプルレートが違うというエラーで処理を止める。
+#[test]
+fn test_graph_output_device_strict_sample_rate_from_file() {
+    let dir = std::env::temp_dir();
+    let name = "soundprog_test_graph_resample_strict_file";
+    let wav_path = dir.join(format!("{}.wav", name));
+    let input_path = dir.join(format!("{}_input.wav", name));
+    {
+        let mut writer = EStreamWriter::Wav(WaveStreamWriter::new(INPUT_SAMPLE_RATE as u32, 16, 1).unwrap());
+        let mut cursor = io::Cursor::new(vec![]);
+        writer.write_header(&mut cursor);
+        writer.write_frames(&mut cursor, &vec![UniformedSample::from_f64(INTENSITY); INPUT_SAMPLE_RATE / 10]);
+        writer.finish(&mut cursor);
+        fs::write(&input_path, cursor.into_inner()).expect("Failed to write input wav file");
+    }
+
+    let input = serde_json::json!({
+        "type": "emitter-wav-mono",
+        "path": input_path.to_str().unwrap()
+    });
+    let output = super::run_graph(&dir, name, &create_graph_json_with(&wav_path, input, true));
+    let _ = fs::remove_file(&wav_path);
+    fs::remove_file(&input_path).expect("Failed to remove input wav file");
+    assert_graph_error(&output, "Input sample rate 22050Hz does not match audio device sample rate");
+}
+
+/// 仮想デバイスが書き込んだファイルのフレーム数を返す。ファイルが無ければ`0`を返す。
+fn read_frame_count(wav_path: &Path) -> usize {
+    let Ok(file) = fs::File::open(wav_path) else {
+        return 0;
+    };
+    let mut reader = io::BufReader::new(file);
+    WaveContainer::from_bufread(&mut reader).map_or(0, |v| v.uniformed_sample_buffer().len())
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
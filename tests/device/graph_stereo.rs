@@ -0,0 +1,115 @@
+use super::{assert_graph_error, GraphJsonBuilder};
+use soundprog::wave::container::WaveContainer;
+use std::path::Path;
+use std::{fs, io};
+
+const SAMPLE_RATE: usize = 44100;
+const PERIOD_SIZE: usize = 441;
+const LEFT_FREQUENCY: f64 = 441.0;
+const RIGHT_FREQUENCY: f64 = 882.0;
+const INTENSITY: f64 = 0.5;
+const LENGTH: f64 = 0.1;
+
+/// 左右で周波数の違う正弦波を`mix-stereo`でステレオにして、2チャンネルの仮想デバイスに書き込むグラフを作る。
+fn create_graph_json(wav_path: &Path, routing: Option<serde_json::Value>) -> serde_json::Value {
+    let sine = |frequency: f64| {
+        serde_json::json!({
+            "type": "emitter-sine",
+            "frequency": { "type": "constant", "value": frequency },
+            "intensity": INTENSITY,
+            "range": { "start": 0.0, "length": LENGTH },
+            "sample_rate": SAMPLE_RATE
+        })
+    };
+    let mut output = serde_json::json!({ "type": "output-device" });
+    if let Some(routing) = routing {
+        output["routing"] = routing;
+    }
+    let mixer = serde_json::json!({
+        "type": "mix-stereo",
+        "gain_0": { "type": "constant", "value": 1.0 },
+        "gain_1": { "type": "constant", "value": 1.0 }
+    });
+
+    GraphJsonBuilder::new(output)
+        .setting("scheduler", serde_json::json!("pull"))
+        .virtual_device(2, SAMPLE_RATE, PERIOD_SIZE, wav_path)
+        .emitter("left", sine(LEFT_FREQUENCY))
+        .emitter("right", sine(RIGHT_FREQUENCY))
+        .node("mixer", mixer)
+        .connect(("left", "out"), ("mixer", "in_1"))
+        .connect(("right", "out"), ("mixer", "in_2"))
+        .connect(("mixer", "out"), ("output", "in"))
+        .build()
+}
+
+/// 実行ファイルでグラフを最後まで処理して、仮想デバイスが書き込んだWAVファイルの左右のサンプルを返す。
+fn render_graph(name: &str, routing: Option<serde_json::Value>) -> (Vec<f64>, Vec<f64>) {
+    let dir = std::env::temp_dir();
+    let wav_path = dir.join(format!("soundprog_test_{}.wav", name));
+    let json = create_graph_json(&wav_path, routing);
+    let output = super::run_graph(&dir, &format!("soundprog_test_{}", name), &json);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Virtual device must write wav file");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+    assert_eq!(container.channel(), 2);
+    assert_eq!(container.samples_per_second() as usize, SAMPLE_RATE);
+
+    let samples = container.uniformed_sample_buffer();
+    let left = samples.iter().step_by(2).map(|v| v.to_f64()).collect();
+    let right = samples.iter().skip(1).step_by(2).map(|v| v.to_f64()).collect();
+    (left, right)
+}
+
+/// `frequency`の正弦波が`length`だけ書き込まれて、あとは無音になっているかを確認する。
+fn assert_sine(samples: &[f64], frequency: f64) {
+    let length = (LENGTH * SAMPLE_RATE as f64).round() as usize;
+    assert!(samples.len() >= length, "{}", samples.len());
+
+    // 16ビットで書き込んでいるので、その分の誤差は許す。
+    for (i, sample) in samples.iter().enumerate() {
+        let expected = if i < length {
+            INTENSITY * (std::f64::consts::TAU * frequency * i as f64 / SAMPLE_RATE as f64).sin()
+        } else {
+            0.0
+        };
+        assert!((sample - expected).abs() < 1e-3, "{}: {} != {}", i, sample, expected);
+    }
+}
+
+/// ステレオの入力が左右を入れ替えずにそのままデバイスの左右に書き込まれる。
+#[test]
+fn test_graph_output_device_stereo_round_trip() {
+    let (left, right) = render_graph("graph_stereo", None);
+    assert_sine(&left, LEFT_FREQUENCY);
+    assert_sine(&right, RIGHT_FREQUENCY);
+}
+
+/// `routing`で左右を入れ替えると、入れ替わって書き込まれる。
+#[test]
+fn test_graph_output_device_stereo_routing_swap() {
+    let (left, right) = render_graph("graph_stereo_swap", Some(serde_json::json!([1, 0])));
+    assert_sine(&left, RIGHT_FREQUENCY);
+    assert_sine(&right, LEFT_FREQUENCY);
+}
+
+/// 入力はステレオまでなので、3チャンネル目以降を`routing`で指定するとエラーになる。
+#[test]
+fn test_graph_output_device_routing_over_stereo() {
+    let dir = std::env::temp_dir();
+    let name = "soundprog_test_graph_stereo_error";
+    let wav_path = dir.join(format!("{}.wav", name));
+    let json = create_graph_json(&wav_path, Some(serde_json::json!([0, 2])));
+    let output = super::run_graph(&dir, name, &json);
+    let _ = fs::remove_file(&wav_path);
+    assert_graph_error(&output, "at most 2 input channels");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
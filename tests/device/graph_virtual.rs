@@ -0,0 +1,84 @@
+use super::GraphJsonBuilder;
+use soundprog::wave::container::WaveContainer;
+use std::path::Path;
+use std::{fs, io};
+
+const SAMPLE_RATE: usize = 44100;
+const PERIOD_SIZE: usize = 441;
+const FREQUENCY: f64 = 441.0;
+const INTENSITY: f64 = 0.5;
+const LENGTH: f64 = 0.1;
+
+/// `emitter-sine → output-device`のグラフを、仮想デバイスに書き込むように設定する。
+fn create_graph_json(scheduler: &str, wav_path: &Path) -> serde_json::Value {
+    let input = serde_json::json!({
+        "type": "emitter-sine",
+        "frequency": { "type": "constant", "value": FREQUENCY },
+        "intensity": INTENSITY,
+        "range": { "start": 0.0, "length": LENGTH },
+        "sample_rate": SAMPLE_RATE
+    });
+    GraphJsonBuilder::output_device(input)
+        .setting("scheduler", serde_json::json!(scheduler))
+        .virtual_device(1, SAMPLE_RATE, PERIOD_SIZE, wav_path)
+        .build()
+}
+
+/// 実行ファイルでグラフを最後まで処理して、仮想デバイスが書き込んだWAVファイルのサンプルを返す。
+fn render_graph(name: &str, scheduler: &str) -> Vec<f64> {
+    let dir = std::env::temp_dir();
+    let wav_path = dir.join(format!("soundprog_test_{}.wav", name));
+    let json = create_graph_json(scheduler, &wav_path);
+    let output = super::run_graph(&dir, &format!("soundprog_test_{}", name), &json);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Virtual device must write wav file");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    assert_eq!(container.channel(), 1);
+    assert_eq!(container.samples_per_second() as usize, SAMPLE_RATE);
+    let samples = container.uniformed_sample_buffer().iter().map(|v| v.to_f64()).collect();
+
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+    samples
+}
+
+/// プル型ならデバイスが要求した分だけ処理するので、正弦波がそのまま隙間なく書き込まれる。
+#[test]
+fn test_graph_virtual_device_pull_golden() {
+    let samples = render_graph("graph_virtual_pull", "pull");
+    let length = (LENGTH * SAMPLE_RATE as f64).round() as usize;
+    assert!(samples.len() >= length, "{}", samples.len());
+    assert!(samples.len() <= length + 2 * PERIOD_SIZE, "{}", samples.len());
+    assert_eq!(samples.len() % PERIOD_SIZE, 0);
+
+    // 16ビットで書き込んでいるので、その分の誤差は許す。
+    // 長さの分だけ書き込んで、あとは無音になる。
+    for (i, sample) in samples.iter().enumerate() {
+        let expected = if i < length {
+            INTENSITY * (std::f64::consts::TAU * FREQUENCY * i as f64 / SAMPLE_RATE as f64).sin()
+        } else {
+            0.0
+        };
+        assert!((sample - expected).abs() < 1e-3, "{}: {} != {}", i, sample, expected);
+    }
+}
+
+/// プッシュ型でも仮想デバイスの1周期ずつ時間を進めるので、何度処理しても同じ結果になる。
+#[test]
+fn test_graph_virtual_device_push_deterministic() {
+    let first = render_graph("graph_virtual_push_1", "push");
+    let second = render_graph("graph_virtual_push_2", "push");
+    assert_eq!(first, second);
+
+    // 正弦波の全部のサンプルがどこかに書き込まれている。
+    let length = (LENGTH * SAMPLE_RATE as f64).round() as usize;
+    let written = first.iter().filter(|v| v.abs() > 1e-3).count();
+    assert!(written > length / 2, "{}", written);
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
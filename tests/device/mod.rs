@@ -0,0 +1,153 @@
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::{Mutex, MutexGuard};
+
+pub mod capture;
+pub mod graph_capture;
+pub mod graph_resample;
+pub mod graph_stereo;
+pub mod graph_virtual;
+pub mod proxy_remix;
+pub mod reinitialize;
+pub mod virtual_device;
+
+/// [`soundprog::device::AudioDevice`]はプロセスに1つしか初期化できないので、
+/// 初期化するテストはこれをロックしてから順番に処理する。
+static AUDIO_DEVICE_LOCK: Mutex<()> = Mutex::new(());
+
+/// グローバルのデバイスを使うテストを順番に処理するためのロックを取る。
+/// 他のテストがパニックしてロックが壊れていても続けて使う。
+pub fn lock_audio_device() -> MutexGuard<'static, ()> {
+    AUDIO_DEVICE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// `json`のグラフを実行ファイルで最後まで処理する。
+/// グラフのファイルは`dir`に書き込んで、相対パスも`dir`から探すように実行する。
+pub fn run_graph(dir: &Path, name: &str, json: &serde_json::Value) -> Output {
+    let json_path = dir.join(format!("{}.json", name));
+    std::fs::write(&json_path, json.to_string()).expect("Failed to write graph file");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_soundprog"))
+        .current_dir(dir)
+        .arg("-i")
+        .arg(&json_path)
+        .output()
+        .expect("Failed to run soundprog");
+    std::fs::remove_file(&json_path).expect("Failed to remove graph file");
+    output
+}
+
+/// 実行ファイルで処理したグラフが、パニックせずに`message`のエラーで終わったかを確認する。
+pub fn assert_graph_error(output: &Output, message: &str) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{}", stderr);
+    assert!(stderr.contains(message), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+/// [`run_graph`]に渡すグラフのJSONを作るビルダー。
+///
+/// `_start_pin`と`_dummy`、`output`ノードは最初から入っていて、
+/// テストでは入力のノードとそのテストだけの設定を追加する。
+pub struct GraphJsonBuilder {
+    value: serde_json::Value,
+}
+
+impl GraphJsonBuilder {
+    /// `output`ノードだけのグラフを作る。
+    pub fn new(output: serde_json::Value) -> Self {
+        Self {
+            value: serde_json::json!({
+                "version": 2,
+                "setting": {
+                    "time_tick_mode": "realtime",
+                    "process_limit_time": 0.016
+                },
+                "system_setting": {
+                    "file_io": {}
+                },
+                "node": {
+                    "_start_pin": { "type": "_start_pin" },
+                    "_dummy": { "type": "_dummy" },
+                    "output": output
+                },
+                "relation": []
+            }),
+        }
+    }
+
+    /// `input`ノードを`output-file`で`file_name`のWAVファイルに書き込むグラフを作る。
+    pub fn output_file(input: serde_json::Value, sample_rate: usize, file_name: &str) -> Self {
+        let output = serde_json::json!({
+            "type": "output-file",
+            "format": { "type": "wav_lpcm16", "sample_rate": sample_rate },
+            "file_name": file_name,
+            "add_date_time": false
+        });
+        Self::new(output).input(input)
+    }
+
+    /// `input`ノードを`output-device`に流すグラフを作る。
+    pub fn output_device(input: serde_json::Value) -> Self {
+        Self::new(serde_json::json!({ "type": "output-device" })).input(input)
+    }
+
+    /// `_start_pin`から始まって`output`ノードに繋がる`input`ノードを追加する。
+    pub fn input(self, input: serde_json::Value) -> Self {
+        self.emitter("input", input).connect(("input", "out"), ("output", "in"))
+    }
+
+    /// `_start_pin`から始まる`name`のノードを追加する。
+    pub fn emitter(self, name: &str, node: serde_json::Value) -> Self {
+        self.node(name, node).connect(("_start_pin", "out"), (name, "in"))
+    }
+
+    /// `name`のノードを追加する。繋がりは[`Self::connect`]で追加する。
+    pub fn node(mut self, name: &str, node: serde_json::Value) -> Self {
+        self.value["node"][name] = node;
+        self
+    }
+
+    /// `(ノード, ピン)`の`prev`から`next`に繋ぐ。
+    pub fn connect(mut self, prev: (&str, &str), next: (&str, &str)) -> Self {
+        let relation = serde_json::json!({
+            "prev": { "node": prev.0, "pin": prev.1 },
+            "next": { "node": next.0, "pin": next.1 }
+        });
+        self.value["relation"].as_array_mut().unwrap().push(relation);
+        self
+    }
+
+    /// `setting`の`name`を設定する。
+    pub fn setting(mut self, name: &str, value: serde_json::Value) -> Self {
+        self.value["setting"][name] = value;
+        self
+    }
+
+    /// `system_setting`の`name`を設定する。
+    pub fn system_setting(mut self, name: &str, value: serde_json::Value) -> Self {
+        self.value["system_setting"][name] = value;
+        self
+    }
+
+    /// 出力を`wav_path`のWAVファイルに書き込む仮想デバイスを使うように設定する。
+    pub fn virtual_device(self, channels: usize, sample_rate: usize, period_size: usize, wav_path: &Path) -> Self {
+        let audio_device = serde_json::json!({
+            "channels": channels,
+            "sample_rate": sample_rate,
+            "period_size": period_size,
+            "virtual": {
+                "file_name": wav_path.to_str().unwrap()
+            }
+        });
+        self.system_setting("audio_device", audio_device)
+    }
+
+    pub fn build(self) -> serde_json::Value {
+        self.value
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,88 @@
+use soundprog::device::remix::{ChannelRemixMatrix, ChannelRemixSetting, EChannelLayout};
+use soundprog::device::virtual_device::VirtualAudioDeviceSetting;
+use soundprog::device::{AudioDevice, AudioDeviceConfig, EDrainedChannelBuffers};
+use soundprog::wave::sample::UniformedSample;
+
+const CHANNELS: usize = 2;
+const PERIOD_SIZE: usize = 441;
+
+/// `input`の1フレームを`setting`でデバイスのチャンネルに混ぜた結果を返す。
+fn remix_frame(setting: &ChannelRemixSetting, layout: EChannelLayout, input: &[f32]) -> Vec<f32> {
+    let mut output = vec![0.0; CHANNELS];
+    ChannelRemixMatrix::new(setting, layout, CHANNELS).apply(input, &mut output);
+    output
+}
+
+/// 入力の配置やミックスの設定を途中で変えても、送るたびにその設定で混ぜてデバイスに流す。
+#[test]
+fn test_audio_device_proxy_remix_setting_change() {
+    let _lock = crate::device::lock_audio_device();
+
+    let mut config = AudioDeviceConfig::new();
+    config
+        .set_channels(CHANNELS)
+        .set_sample_rate(44100)
+        .set_period_size(Some(PERIOD_SIZE as u32))
+        .set_virtual_device(Some(VirtualAudioDeviceSetting::default()));
+    let proxy = AudioDevice::initialize(config).expect("Failed to initialize audio device");
+    let proxy = proxy.upgrade().expect("Proxy must be valid");
+
+    let default_setting = ChannelRemixSetting::default();
+    let swap_setting = ChannelRemixSetting {
+        layout: None,
+        routing: Some(vec![Some(1), Some(0)]),
+    };
+    let mono = |frame_count| EDrainedChannelBuffers::Mono {
+        channel: vec![UniformedSample::from_f64(0.5); frame_count],
+    };
+    let stereo = |frame_count| EDrainedChannelBuffers::Stereo {
+        ch_left: vec![UniformedSample::from_f64(0.25); frame_count],
+        ch_right: vec![UniformedSample::from_f64(-0.75); frame_count],
+    };
+
+    // モノラル → ステレオ → ステレオ（左右を入れ替え）→ モノラルの順で1周期ずつ送って、デバイスに読ませる。
+    let mut samples = vec![];
+    for (setting, is_mono) in [
+        (&default_setting, true),
+        (&default_setting, false),
+        (&swap_setting, false),
+        (&default_setting, true),
+    ] {
+        {
+            // リングバッファの境目で止まることがあるので、全部送るまで繰り返す。
+            let mut proxy = proxy.lock().unwrap();
+            let mut remained_frames = PERIOD_SIZE;
+            while remained_frames > 0 {
+                let sent_count = match is_mono {
+                    true => proxy.send_sample_frames_with(setting, remained_frames, mono),
+                    false => proxy.send_sample_frames_with(setting, remained_frames, stereo),
+                };
+                assert!(sent_count > 0);
+                remained_frames -= sent_count / CHANNELS;
+            }
+        }
+
+        AudioDevice::pre_process(0.0);
+        AudioDevice::post_process(0.01);
+        samples.extend(AudioDevice::take_virtual_samples());
+    }
+    AudioDevice::cleanup();
+
+    let expected_frames = [
+        remix_frame(&default_setting, EChannelLayout::Mono, &[0.5]),
+        remix_frame(&default_setting, EChannelLayout::Stereo, &[0.25, -0.75]),
+        remix_frame(&swap_setting, EChannelLayout::Stereo, &[0.25, -0.75]),
+        remix_frame(&default_setting, EChannelLayout::Mono, &[0.5]),
+    ];
+    assert_eq!(expected_frames[2], vec![-0.75, 0.25]);
+    assert_eq!(samples.len(), expected_frames.len() * PERIOD_SIZE * CHANNELS);
+    for (period, expected) in samples.chunks_exact(PERIOD_SIZE * CHANNELS).zip(&expected_frames) {
+        for frame in period.chunks_exact(CHANNELS) {
+            assert_eq!(frame, expected.as_slice());
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,86 @@
+use soundprog::device::virtual_device::VirtualAudioDeviceSetting;
+use soundprog::device::{AudioDevice, AudioDeviceConfig};
+use soundprog::file::reader::FileReaderSetting;
+use soundprog::file::{EFileAccessSetting, FileIO, FileIOSetting};
+use std::io::Read;
+
+fn create_virtual_config() -> AudioDeviceConfig {
+    let mut config = AudioDeviceConfig::new();
+    config
+        .set_channels(1)
+        .set_sample_rate(44100)
+        .set_period_size(Some(441))
+        .set_virtual_device(Some(VirtualAudioDeviceSetting::default()));
+    config
+}
+
+/// 解放する前にもう一度初期化するとエラーになり、解放したあとならまた初期化できる。
+#[test]
+fn test_audio_device_initialize_after_cleanup() {
+    let _lock = crate::device::lock_audio_device();
+
+    let proxy = AudioDevice::initialize(create_virtual_config()).expect("Failed to initialize audio device");
+    assert!(AudioDevice::get_proxy().is_some());
+    assert_eq!(AudioDevice::get_virtual_period_time(), Some(0.01));
+    let error = AudioDevice::initialize(create_virtual_config())
+        .err()
+        .expect("Initializing twice must be an error");
+    assert!(error.to_string().contains("already initialized"), "{}", error);
+
+    // 解放したら前のプロキシからはもう接近できない。
+    AudioDevice::cleanup();
+    assert!(proxy.upgrade().is_none());
+    assert!(AudioDevice::get_proxy().is_none());
+    assert!(AudioDevice::get_virtual_period_time().is_none());
+
+    // もう一度初期化したデバイスも最初から処理できる。
+    let proxy = AudioDevice::initialize(create_virtual_config()).expect("Failed to initialize audio device again");
+    AudioDevice::pre_process(0.0);
+    AudioDevice::post_process(0.03);
+    let samples = AudioDevice::take_virtual_samples();
+    assert_eq!(samples.len(), 3 * 441);
+    assert!(samples.iter().all(|v| *v == 0.0));
+
+    let metrics = proxy.upgrade().unwrap().lock().unwrap().get_metrics().unwrap();
+    assert_eq!(metrics.xrun_count, 3);
+    AudioDevice::cleanup();
+}
+
+/// ファイルIOも解放したあとならまた初期化して、同じファイルを読み込める。
+#[test]
+fn test_file_io_initialize_after_cleanup() {
+    let path = std::env::temp_dir().join("soundprog_test_file_io_reinitialize.txt");
+    std::fs::write(&path, b"soundprog").expect("Failed to write file");
+    let read_all = |proxy: &soundprog::file::FileIOProxyWeakPtr| {
+        let setting = EFileAccessSetting::Read {
+            path: path.to_str().unwrap().to_owned(),
+        };
+        let handle = proxy.upgrade().unwrap().lock().unwrap().create_handle(setting);
+        let mut reader = handle
+            .try_read(FileReaderSetting {
+                seek_to_first_when_drop: false,
+            })
+            .expect("File must be readable");
+        let mut text = String::new();
+        reader.read_to_string(&mut text).expect("Failed to read file");
+        text
+    };
+
+    let proxy = FileIO::initialize(FileIOSetting::default());
+    assert!(FileIO::get_proxy().is_some());
+    assert_eq!(read_all(&proxy), "soundprog");
+
+    // 解放したら前のプロキシからはもう接近できない。
+    FileIO::cleanup();
+    assert!(proxy.upgrade().is_none());
+    assert!(FileIO::get_proxy().is_none());
+
+    let proxy = FileIO::initialize(FileIOSetting::default());
+    assert_eq!(read_all(&proxy), "soundprog");
+    FileIO::cleanup();
+    std::fs::remove_file(&path).expect("Failed to remove file");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,82 @@
+use soundprog::device::virtual_device::{VirtualAudioDevice, VirtualAudioDeviceSetting};
+
+const CHANNELS: usize = 2;
+const PERIOD_SIZE: u32 = 480;
+const SAMPLE_RATE: usize = 48000;
+
+/// 仮想デバイスがシミュレーションした時間でピリオド単位にコールバックを処理して、
+/// 読み込んだサンプルをそのままメモリに溜めるかを確認する。
+#[test]
+fn test_virtual_audio_device_memory() {
+    let mut device = VirtualAudioDevice::new(
+        &VirtualAudioDeviceSetting::default(),
+        CHANNELS,
+        0,
+        SAMPLE_RATE,
+        Some(PERIOD_SIZE),
+    )
+    .expect("Failed to create virtual audio device");
+    let period_time = device.period_time();
+    assert!((period_time - 0.01).abs() < 1e-12);
+
+    // 1ピリオドの半分ずつ進めると、2回に1回だけコールバックが呼ばれる。
+    let mut next_value = 0usize;
+    let mut callback_count = 0;
+    for _ in 0..10 {
+        let results = device.process(period_time * 0.5, |output, input| {
+            assert!(input.is_empty());
+            for v in output.iter_mut() {
+                *v = (next_value % 100) as f32 * 0.01;
+                next_value += 1;
+            }
+            output.len()
+        });
+        callback_count += results.len();
+        for result in results {
+            assert_eq!(result.required_samples, PERIOD_SIZE as usize * CHANNELS);
+            assert_eq!(result.read_samples, result.required_samples);
+        }
+    }
+    assert_eq!(callback_count, 5);
+
+    // コールバックで書いた順番のまま溜まっている。
+    let samples = device.take_samples();
+    assert_eq!(samples.len(), 5 * PERIOD_SIZE as usize * CHANNELS);
+    for (i, v) in samples.iter().enumerate() {
+        assert_eq!(*v, (i % 100) as f32 * 0.01);
+    }
+    assert!(device.take_samples().is_empty());
+}
+
+/// WAVファイルに書き込む場合、解放した時にヘッダーが書き込んだサイズに更新されるかを確認する。
+#[test]
+fn test_virtual_audio_device_wav_file() {
+    let path = std::env::temp_dir().join("soundprog_test_virtual_audio_device.wav");
+    let setting = VirtualAudioDeviceSetting {
+        file_name: Some(path.to_str().unwrap().to_owned()),
+    };
+
+    {
+        let mut device = VirtualAudioDevice::new(&setting, CHANNELS, 0, SAMPLE_RATE, Some(PERIOD_SIZE))
+            .expect("Failed to create virtual audio device");
+        let results = device.process(device.period_time() * 3.0, |output, _input| {
+            output.fill(0.5);
+            output.len()
+        });
+        assert_eq!(results.len(), 3);
+
+        // ファイルに書き込むのでメモリには溜まらない。
+        assert!(device.take_samples().is_empty());
+    }
+
+    let bytes = std::fs::read(&path).expect("Failed to read written file");
+    let data_size = 3 * PERIOD_SIZE as usize * CHANNELS * 2;
+    assert_eq!(bytes.len(), 44 + data_size);
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()) as usize, data_size);
+    std::fs::remove_file(&path).expect("Failed to remove written file");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,126 @@
+use std::{fs, io};
+
+use soundprog::wave::container::{
+    flac::{stream::FlacStreamReader, FlacBuilder, FlacContainer},
+    WaveContainer,
+};
+
+/// `assets/`にある全部のWAVファイル（モノラル16ビット）
+const ASSET_PATHS: [&'static str; 16] = [
+    "assets/ex1/a.wav",
+    "assets/ex11/ex11_3_output.wav",
+    "assets/ex11/sine_2s.wav",
+    "assets/ex4/sine_500hz.wav",
+    "assets/ex4/sine_500hz_idft.wav",
+    "assets/ex4/sine_500hz_idft_from_fft.wav",
+    "assets/ex4/sine_500hz_ifft.wav",
+    "assets/ex6/drum.wav",
+    "assets/ex6/response.wav",
+    "assets/ex6/sine_3500hz_bpf.wav",
+    "assets/ex6/sine_500hz_3500hz.wav",
+    "assets/ex7/pulse_train.wav",
+    "assets/ex7/pulse_train2.wav",
+    "assets/ex7/synth.wav",
+    "assets/ex7/vocal.wav",
+    "assets/ex7/white_noise.wav",
+];
+
+fn read_wave_container(path: &str) -> WaveContainer {
+    let source_file = fs::File::open(path).expect(&format!("Could not find {}.", path));
+    let mut reader = io::BufReader::new(source_file);
+
+    WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+}
+
+/// FLACにエンコードしたバイト列を返す。
+fn encode_to_bytes(container: &FlacContainer) -> Vec<u8> {
+    let mut writer = io::Cursor::new(vec![]);
+    container.write(&mut writer);
+    writer.into_inner()
+}
+
+#[test]
+fn flac_round_trip_mono_assets() {
+    for path in ASSET_PATHS {
+        let wave_container = read_wave_container(path);
+        assert_eq!(wave_container.channel(), 1);
+
+        let flac_container = FlacBuilder {
+            samples_per_sec: wave_container.samples_per_second(),
+            bits_per_sample: wave_container.bits_per_sample() as u16,
+        }
+        .build_mono(wave_container.uniformed_sample_buffer().to_vec())
+        .unwrap();
+
+        let encoded = encode_to_bytes(&flac_container);
+        let decoded = FlacContainer::from_bufread(&mut io::Cursor::new(encoded)).expect("Could not decode FLAC.");
+
+        assert_eq!(decoded.channel(), 1, "{}", path);
+        assert_eq!(decoded.samples_per_second(), wave_container.samples_per_second(), "{}", path);
+        assert_eq!(decoded.bits_per_sample(), wave_container.bits_per_sample(), "{}", path);
+        assert_eq!(
+            decoded.uniformed_sample_buffer(),
+            wave_container.uniformed_sample_buffer(),
+            "{}",
+            path
+        );
+    }
+}
+
+#[test]
+fn flac_round_trip_stereo_assets() {
+    let left = read_wave_container("assets/ex6/drum.wav");
+    let right = read_wave_container("assets/ex7/vocal.wav");
+    assert_eq!(left.samples_per_second(), right.samples_per_second());
+
+    let length = left.uniformed_sample_buffer().len().min(right.uniformed_sample_buffer().len());
+    let ch_left = left.uniformed_sample_buffer()[..length].to_vec();
+    let ch_right = right.uniformed_sample_buffer()[..length].to_vec();
+
+    let flac_container = FlacBuilder {
+        samples_per_sec: left.samples_per_second(),
+        bits_per_sample: 16,
+    }
+    .build_stereo(ch_left.clone(), ch_right.clone())
+    .unwrap();
+
+    let encoded = encode_to_bytes(&flac_container);
+    let decoded = FlacContainer::from_bufread(&mut io::Cursor::new(encoded)).expect("Could not decode FLAC.");
+
+    assert_eq!(decoded.channel(), 2);
+    let buffer = decoded.uniformed_sample_buffer();
+    assert_eq!(buffer.len(), length * 2);
+    for (i, (l, r)) in ch_left.iter().zip(ch_right.iter()).enumerate() {
+        assert_eq!(buffer[i * 2], *l);
+        assert_eq!(buffer[i * 2 + 1], *r);
+    }
+}
+
+#[test]
+fn flac_stream_reader_reads_all_frames() {
+    const READ_FRAME_COUNT: usize = 1000;
+
+    let wave_container = read_wave_container("assets/ex7/synth.wav");
+    let flac_container = FlacBuilder {
+        samples_per_sec: wave_container.samples_per_second(),
+        bits_per_sample: 16,
+    }
+    .build_mono(wave_container.uniformed_sample_buffer().to_vec())
+    .unwrap();
+
+    let mut reader = io::Cursor::new(encode_to_bytes(&flac_container));
+    let mut stream = FlacStreamReader::from_bufread(&mut reader).expect("Could not read FLAC header.");
+    assert_eq!(stream.frame_count(), wave_container.uniformed_sample_buffer().len());
+
+    let mut buffer = vec![];
+    loop {
+        let mut frames = stream.read_frames(&mut reader, READ_FRAME_COUNT);
+        let is_end = frames.len() < READ_FRAME_COUNT;
+        buffer.append(&mut frames);
+        if is_end {
+            break;
+        }
+    }
+
+    assert_eq!(buffer, wave_container.uniformed_sample_buffer());
+}
@@ -0,0 +1,48 @@
+use crate::device::{assert_graph_error, GraphJsonBuilder};
+use std::fs;
+
+/// `source`からグレインを切り出す`emitter-granular → output-file`のグラフを作る。
+fn create_graph_json(source: serde_json::Value, file_name: &str) -> serde_json::Value {
+    let input = serde_json::json!({
+        "type": "emitter-granular",
+        "source": source,
+        "grain_length": 0.05,
+        "density": 20.0,
+        "intensity": 0.5,
+        "range": { "start": 0.0, "length": 0.1 },
+        "sample_rate": 22050
+    });
+    GraphJsonBuilder::output_file(input, 22050, file_name).build()
+}
+
+/// ソースのファイルが無ければ、処理を始める前にパニックせずエラーで終わる。
+#[test]
+fn test_graph_granular_missing_source_file() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_granular_missing_source";
+    let source = serde_json::json!({ "type": "file", "path": "./soundprog_not_existing_granular_source.wav" });
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(source, file_name));
+    let _ = fs::remove_file(dir.join(format!("{}.wav", file_name)));
+    assert_graph_error(&output, "Could not open sound file");
+}
+
+/// 長さや密度が0以下の設定は、パニックせずにエラーで終わる。
+#[test]
+fn test_graph_granular_invalid_setting() {
+    let dir = std::env::temp_dir();
+    for (field, file_name) in [
+        ("grain_length", "soundprog_test_graph_granular_zero_grain_length"),
+        ("density", "soundprog_test_graph_granular_zero_density"),
+    ] {
+        let source = serde_json::json!({ "type": "input", "buffer_length": 1.0 });
+        let mut json = create_graph_json(source, file_name);
+        json["node"]["input"][field] = serde_json::json!(0.0);
+        let output = crate::device::run_graph(&dir, file_name, &json);
+        let _ = fs::remove_file(dir.join(format!("{}.wav", file_name)));
+        assert_graph_error(&output, &format!("`{}` must be bigger than 0.", field));
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,143 @@
+use soundprog::math::window::EWindowFunction;
+use soundprog::wave::granular::{GranularSetting, GranularSynthesizer};
+use soundprog::wave::sample::UniformedSample;
+use soundprog::wave::PI2;
+
+const SAMPLE_RATE: usize = 48000;
+
+/// 重ならずに隙間なく並ぶ、窓をかけないグレインの設定
+fn tiled_setting(grain_length: f64) -> GranularSetting {
+    GranularSetting {
+        grain_length,
+        density: 1.0 / grain_length,
+        position: 0.0,
+        position_jitter: 0.0,
+        pitch_rate: 1.0,
+        window_function: EWindowFunction::None,
+        pan_spread: 0.0,
+        intensity: 1.0,
+        seed: None,
+    }
+}
+
+fn sine(frequency: f64, length: usize) -> Vec<UniformedSample> {
+    (0..length)
+        .map(|v| UniformedSample::from_f64(0.5 * (PI2 * frequency * v as f64 / SAMPLE_RATE as f64).sin()))
+        .collect()
+}
+
+/// ピッチを変えなければ、グレインはソースをそのまま切り出す。
+#[test]
+fn test_unit_pitch_copies_source() {
+    let source = sine(440.0, 4800);
+    let mut synthesizer = GranularSynthesizer::new(tiled_setting(0.01), &source, SAMPLE_RATE, SAMPLE_RATE);
+
+    let output = synthesizer.next_frames(960);
+    for (sample_i, sample) in output.mono.iter().enumerate() {
+        // 各グレインは最初から切り出す。
+        let expected = source[sample_i % 480].to_f64();
+        assert!(
+            (expected - sample.to_f64()).abs() < 1e-6,
+            "{}: {} {:?}",
+            sample_i,
+            expected,
+            sample
+        );
+    }
+}
+
+/// `pitch_rate`の分だけ速く読んで、周波数を変える。
+#[test]
+fn test_pitch_rate() {
+    let source = sine(200.0, 48000);
+    let mut setting = tiled_setting(0.1);
+    setting.position = 0.5;
+    setting.pitch_rate = 1.5;
+    let mut synthesizer = GranularSynthesizer::new(setting, &source, SAMPLE_RATE, SAMPLE_RATE);
+
+    let output = synthesizer.next_frames(4800);
+    let start_phase = (0.5 * 47999.0 * 200.0 / SAMPLE_RATE as f64) * PI2;
+    for sample_i in 0..4800 {
+        let expected = 0.5 * (start_phase + (PI2 * 300.0 * sample_i as f64 / SAMPLE_RATE as f64)).sin();
+        let actual = output.mono[sample_i].to_f64();
+        assert!((expected - actual).abs() < 1e-3, "{}: {} {}", sample_i, expected, actual);
+    }
+}
+
+/// グレインは`density`の間隔で発生して、`grain_length`の間だけ鳴る。
+#[test]
+fn test_density_overlap() {
+    let source = sine(440.0, 48000);
+    let setting = GranularSetting {
+        density: 100.0,
+        position_jitter: 0.05,
+        window_function: EWindowFunction::Hann,
+        ..tiled_setting(0.04)
+    };
+    let mut synthesizer = GranularSynthesizer::new(setting, &source, SAMPLE_RATE, SAMPLE_RATE);
+
+    // 0.01秒ごとに0.04秒のグレインを発生させるので、発生した直後は4つ重なる。
+    synthesizer.next_frames(SAMPLE_RATE + 1);
+    assert_eq!(synthesizer.active_grain_count(), 4);
+}
+
+/// パンを振らなければ左右は同じで、等パワーで中央に置く。振ると左右で異なる。
+#[test]
+fn test_pan_spread() {
+    let source = sine(440.0, 4800);
+    let mut synthesizer = GranularSynthesizer::new(tiled_setting(0.01), &source, SAMPLE_RATE, SAMPLE_RATE);
+    let output = synthesizer.next_frames(960);
+    for sample_i in 0..960 {
+        let expected = output.mono[sample_i].to_f64() * std::f64::consts::FRAC_1_SQRT_2;
+        assert!((expected - output.left[sample_i].to_f64()).abs() < 1e-6);
+        assert!((output.left[sample_i].to_f64() - output.right[sample_i].to_f64()).abs() < 1e-12);
+    }
+
+    let setting = GranularSetting {
+        pan_spread: 1.0,
+        ..tiled_setting(0.01)
+    };
+    let mut synthesizer = GranularSynthesizer::new(setting, &source, SAMPLE_RATE, SAMPLE_RATE);
+    let output = synthesizer.next_frames(4800);
+    assert!(output
+        .left
+        .iter()
+        .zip(&output.right)
+        .any(|(l, r)| (l.to_f64() - r.to_f64()).abs() > 1e-3));
+}
+
+/// 同じシードなら位置とパンの揺らぎも毎回同じになる。
+#[test]
+fn test_seed() {
+    let source = sine(440.0, 48000);
+    let render = |seed: u64| {
+        let setting = GranularSetting {
+            density: 100.0,
+            position: 0.5,
+            position_jitter: 0.05,
+            pan_spread: 1.0,
+            window_function: EWindowFunction::Hann,
+            seed: Some(seed),
+            ..tiled_setting(0.04)
+        };
+        let mut synthesizer = GranularSynthesizer::new(setting, &source, SAMPLE_RATE, SAMPLE_RATE);
+        synthesizer.next_frames(4800)
+    };
+
+    assert_eq!(render(1), render(1));
+    assert_ne!(render(1).left, render(2).left);
+}
+
+/// 入力を追加していく時は、上限を超えた古いサンプルを捨てる。
+#[test]
+fn test_push_source_capacity() {
+    let mut synthesizer =
+        GranularSynthesizer::with_source_capacity(tiled_setting(0.01), 1000, SAMPLE_RATE, SAMPLE_RATE);
+    assert!(synthesizer.next_frames(100).mono.iter().all(|v| v.to_f64() == 0.0));
+
+    synthesizer.push_source(&sine(440.0, 600), SAMPLE_RATE);
+    assert_eq!(synthesizer.source_length(), 600);
+    synthesizer.push_source(&sine(440.0, 600), SAMPLE_RATE);
+    assert_eq!(synthesizer.source_length(), 1000);
+    assert!(synthesizer.next_frames(480).mono.iter().any(|v| v.to_f64() != 0.0));
+}
@@ -0,0 +1,40 @@
+use soundprog::device::metrics::AudioDeviceMetrics;
+use std::time::Duration;
+
+/// まだ何も処理してなければ全部0になる。
+#[test]
+fn test_metrics_empty() {
+    let metrics = AudioDeviceMetrics::default();
+    assert_eq!(metrics.average_process_time(), Duration::ZERO);
+    assert_eq!(metrics.ring_fill_ratio(), 0.0);
+    assert_eq!(metrics.estimated_latency(), Duration::ZERO);
+}
+
+/// 平均の処理時間、リングバッファの使用率、推定レイテンシーを計算する。
+#[test]
+fn test_metrics_values() {
+    let metrics = AudioDeviceMetrics {
+        channels: 2,
+        sample_rate: 48000,
+        frame_count: 4,
+        xrun_count: 1,
+        last_process_time: Duration::from_millis(3),
+        max_process_time: Duration::from_millis(5),
+        total_process_time: Duration::from_millis(12),
+        ring_fill_samples: 4096,
+        ring_capacity_samples: 16384,
+        callback_samples: 960,
+        capture_dropped_samples: 128,
+    };
+
+    assert_eq!(metrics.average_process_time(), Duration::from_millis(3));
+    assert_eq!(metrics.ring_fill_ratio(), 0.25);
+    // (4096 + 960) / 2 = 2528フレーム
+    let latency = metrics.estimated_latency().as_secs_f64();
+    assert!((latency - (2528.0 / 48000.0)).abs() < 1e-9, "{}", latency);
+
+    let text = metrics.to_string();
+    assert!(text.contains("xruns: 1"), "{}", text);
+    assert!(text.contains("ring buffer: 4096/16384 (25.0%)"), "{}", text);
+    assert!(text.contains("capture drops: 128"), "{}", text);
+}
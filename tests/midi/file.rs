@@ -0,0 +1,149 @@
+use super::{midi_file_bytes, track_chunk};
+use soundprog::midi::file::MidiFile;
+use soundprog::midi::{key_to_frequency, EMidiMessage};
+
+const TICKS_PER_QUARTER: u16 = 480;
+
+/// フォーマット1で、最初のトラックのテンポが他のトラックの時間にも反映されるかを確認する。
+/// ランニングステータスとベロシティ`0`のノートオンも一緒に確認する。
+#[test]
+fn test_midi_file_format1_tempo() {
+    // 120BPMで四分音符1つ分のあとに60BPMに変える。
+    let tempo_track = track_chunk(&[
+        (0, vec![0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20]),
+        (480, vec![0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40]),
+    ]);
+    let note_track = track_chunk(&[
+        (0, vec![0x91, 60, 100]),
+        // ランニングステータスのベロシティ0はノートオフ。
+        (480, vec![60, 0]),
+        (0, vec![0xB1, 10, 127]),
+        (480, vec![0x81, 62, 64]),
+    ]);
+    let bytes = midi_file_bytes(1, TICKS_PER_QUARTER, &[tempo_track, note_track]);
+
+    let midi_file = MidiFile::from_bytes(&bytes).expect("Failed to parse midi file");
+    assert_eq!(midi_file.format, 1);
+    assert_eq!(midi_file.ticks_per_quarter, TICKS_PER_QUARTER);
+    assert_eq!(midi_file.tracks.len(), 2);
+
+    let messages = midi_file.timed_messages();
+    let expected = [
+        (
+            0.0,
+            EMidiMessage::NoteOn {
+                channel: 1,
+                key: 60,
+                velocity: 100,
+            },
+        ),
+        (
+            0.5,
+            EMidiMessage::NoteOff {
+                channel: 1,
+                key: 60,
+                velocity: 0,
+            },
+        ),
+        (
+            0.5,
+            EMidiMessage::ControlChange {
+                channel: 1,
+                controller: 10,
+                value: 127,
+            },
+        ),
+        (
+            1.5,
+            EMidiMessage::NoteOff {
+                channel: 1,
+                key: 62,
+                velocity: 64,
+            },
+        ),
+    ];
+    assert_eq!(messages.len(), expected.len());
+    for (message, (time, expected_message)) in messages.iter().zip(expected) {
+        assert!((message.time - time).abs() < 1e-9, "{:?}", message);
+        assert_eq!(message.message, expected_message);
+    }
+    assert!((midi_file.duration() - 1.5).abs() < 1e-9);
+}
+
+/// メタイベントとSysExの後はランニングステータスが取り消されて、
+/// システムコモン・リアルタイムメッセージは読み飛ばされるかを確認する。
+#[test]
+fn test_midi_file_running_status_around_meta() {
+    let track = track_chunk(&[
+        (0, vec![0x90, 60, 100]),
+        (0, vec![64, 100]),
+        // テキストのメタイベント
+        (240, vec![0xFF, 0x01, 0x02, b'h', b'i']),
+        (0, vec![0x80, 60, 0]),
+        // リアルタイムメッセージはランニングステータスを変えない。
+        (0, vec![0xF8]),
+        (0, vec![64, 0]),
+        (0, vec![0xF3, 0x01]),
+    ]);
+    let bytes = midi_file_bytes(0, TICKS_PER_QUARTER, &[track]);
+    let midi_file = MidiFile::from_bytes(&bytes).expect("Failed to parse midi file");
+
+    let messages = midi_file.timed_messages().into_iter().map(|v| v.message).collect::<Vec<_>>();
+    let expected = [
+        EMidiMessage::NoteOn {
+            channel: 0,
+            key: 60,
+            velocity: 100,
+        },
+        EMidiMessage::NoteOn {
+            channel: 0,
+            key: 64,
+            velocity: 100,
+        },
+        EMidiMessage::NoteOff {
+            channel: 0,
+            key: 60,
+            velocity: 0,
+        },
+        EMidiMessage::NoteOff {
+            channel: 0,
+            key: 64,
+            velocity: 0,
+        },
+    ];
+    assert_eq!(messages, expected);
+
+    // メタイベントやSysEx、システムコモンメッセージの直後のデータバイトは使えない。
+    for event in [vec![0xFF, 0x01, 0x00], vec![0xF0, 0x01, 0xF7], vec![0xF6]] {
+        let track = track_chunk(&[(0, vec![0x90, 60, 100]), (0, event), (0, vec![60, 0])]);
+        assert!(MidiFile::from_bytes(&midi_file_bytes(0, TICKS_PER_QUARTER, &[track])).is_none());
+    }
+}
+
+/// 対応してないフォーマットや壊れたファイルは読み込まない。
+#[test]
+fn test_midi_file_unsupported() {
+    let track = track_chunk(&[(0, vec![0x90, 60, 100])]);
+
+    // フォーマット2
+    assert!(MidiFile::from_bytes(&midi_file_bytes(2, TICKS_PER_QUARTER, &[track.clone()])).is_none());
+    // SMPTEタイムコード
+    assert!(MidiFile::from_bytes(&midi_file_bytes(0, 0xE728, &[track.clone()])).is_none());
+    // トラックが途中で切れている。
+    let bytes = midi_file_bytes(0, TICKS_PER_QUARTER, &[track]);
+    assert!(MidiFile::from_bytes(&bytes[..bytes.len() - 2]).is_none());
+    // ランニングステータスなしでデータバイトから始まる。
+    let track = track_chunk(&[(0, vec![60, 100])]);
+    assert!(MidiFile::from_bytes(&midi_file_bytes(0, TICKS_PER_QUARTER, &[track])).is_none());
+}
+
+#[test]
+fn test_key_to_frequency() {
+    assert!((key_to_frequency(69) - 440.0).abs() < 1e-9);
+    assert!((key_to_frequency(81) - 880.0).abs() < 1e-9);
+    assert!((key_to_frequency(60) - 261.6256).abs() < 1e-4);
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
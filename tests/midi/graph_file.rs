@@ -0,0 +1,80 @@
+use crate::device::{assert_graph_error, GraphJsonBuilder};
+use soundprog::wave::container::WaveContainer;
+use std::path::Path;
+use std::{fs, io};
+
+const SAMPLE_RATE: usize = 44100;
+const RELEASE_TIME: f64 = 0.05;
+
+/// `emitter-midi-file → output-file`のグラフを作る。
+fn create_graph_json(midi_path: &Path, file_name: &str, polyphony: usize) -> serde_json::Value {
+    let input = serde_json::json!({
+        "type": "emitter-midi-file",
+        "path": midi_path.to_str().unwrap(),
+        "intensity": 0.5,
+        "envelope": {
+            "attack_time": 0.0,
+            "decay_time": 0.0,
+            "release_time": RELEASE_TIME,
+            "attack_curve": 1.0,
+            "decay_curve": 1.0,
+            "release_curve": 1.0,
+            "sustain_value": 1.0
+        },
+        "polyphony": polyphony,
+        "sample_rate": SAMPLE_RATE
+    });
+    GraphJsonBuilder::output_file(input, SAMPLE_RATE, file_name).build()
+}
+
+/// ノートオフがないノートがあっても、曲の終わりでリリースしてグラフの処理が終わる。
+#[test]
+fn test_graph_midi_file_unterminated_note() {
+    // 120BPMで四分音符が480ティックなので、ノートオンから0.5秒後にトラックが終わる。
+    let dir = std::env::temp_dir();
+    let midi_path = dir.join("soundprog_test_graph_midi_unterminated.mid");
+    let track = super::track_chunk(&[(0, vec![0x90, 69, 100]), (480, vec![0xB0, 7, 100])]);
+    fs::write(&midi_path, super::midi_file_bytes(0, 480, &[track])).expect("Failed to write MIDI file");
+
+    let file_name = "soundprog_test_graph_midi_unterminated";
+    let json = create_graph_json(&midi_path, file_name, 4);
+    let output = crate::device::run_graph(&dir, file_name, &json);
+    fs::remove_file(&midi_path).expect("Failed to remove MIDI file");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let wav_path = dir.join(format!("{}.wav", file_name));
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Output file must be written");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+
+    // トラックの終わりまで鳴って、リリースが終わったら止まる。
+    let samples = container.uniformed_sample_buffer();
+    let last = samples.iter().rposition(|v| v.to_f64() != 0.0).expect("Note must be played");
+    let end_i = (SAMPLE_RATE as f64 * (0.5 + RELEASE_TIME)) as usize;
+    assert!(last.abs_diff(end_i) < SAMPLE_RATE / 100, "{} != {}", last, end_i);
+}
+
+/// 読み込めないMIDIファイルや正しくない設定は、パニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_midi_file_errors() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_midi_error";
+
+    let missing_path = dir.join("soundprog_test_graph_midi_missing.mid");
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(&missing_path, file_name, 4));
+    assert_graph_error(&output, "Could not read MIDI file");
+
+    let midi_path = dir.join("soundprog_test_graph_midi_error.mid");
+    let track = super::track_chunk(&[(0, vec![0x90, 69, 100]), (480, vec![0x80, 69, 0])]);
+    fs::write(&midi_path, super::midi_file_bytes(0, 480, &[track])).expect("Failed to write MIDI file");
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(&midi_path, file_name, 0));
+    fs::remove_file(&midi_path).expect("Failed to remove MIDI file");
+    assert_graph_error(&output, "`polyphony` must be bigger than 0.");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,36 @@
+use crate::device::{assert_graph_error, GraphJsonBuilder};
+
+/// 開けないMIDI入力を指定すると、パニックせずにシステムを初期化する時のエラーになる。
+#[test]
+fn test_graph_midi_input_not_found() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_midi_input_error";
+    let input = serde_json::json!({
+        "type": "emitter-midi-input",
+        "intensity": 0.5,
+        "envelope": {
+            "attack_time": 0.0,
+            "decay_time": 0.0,
+            "release_time": 0.05,
+            "attack_curve": 1.0,
+            "decay_curve": 1.0,
+            "release_curve": 1.0,
+            "sustain_value": 1.0
+        },
+        "sample_rate": 44100,
+        "length": 0.1
+    });
+    let midi_input = serde_json::json!({
+        "source": { "type": "raw", "path": "/nonexistent/soundprog_test_midi" }
+    });
+    let json = GraphJsonBuilder::output_file(input, 44100, file_name)
+        .system_setting("midi_input", midi_input)
+        .build();
+    let output = crate::device::run_graph(&dir, file_name, &json);
+    let _ = std::fs::remove_file(dir.join(format!("{}.wav", file_name)));
+    assert_graph_error(&output, "Could not open midi input");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,242 @@
+use soundprog::midi::input::{EMidiInputSource, MidiInput, MidiInputSetting, MidiStreamParser};
+use soundprog::midi::sequencer::{parse_sequencer_events, SEQUENCER_EVENT_SIZE};
+use soundprog::midi::synth::{EMidiVoiceWaveform, MidiSynthSetting, MidiSynthesizer};
+use soundprog::midi::EMidiMessage;
+use soundprog::wave::envelope::AdsrEnvelope;
+use soundprog::wave::sine::emitter::EOscillatorAntialias;
+
+const SAMPLE_RATE: usize = 48000;
+
+fn parse_all(bytes: &[u8]) -> Vec<EMidiMessage> {
+    let mut parser = MidiStreamParser::new();
+    bytes.iter().filter_map(|v| parser.push(*v)).collect()
+}
+
+/// ランニングステータスが続いて、途中のリアルタイムメッセージはメッセージを壊さない。
+#[test]
+fn test_stream_parser_running_status() {
+    let messages = parse_all(&[0x92, 60, 0xF8, 100, 64, 90, 64, 0, 0xFE, 0xB2, 10, 0]);
+    assert_eq!(
+        messages,
+        vec![
+            EMidiMessage::NoteOn {
+                channel: 2,
+                key: 60,
+                velocity: 100,
+            },
+            EMidiMessage::NoteOn {
+                channel: 2,
+                key: 64,
+                velocity: 90,
+            },
+            EMidiMessage::NoteOff {
+                channel: 2,
+                key: 64,
+                velocity: 0,
+            },
+            EMidiMessage::ControlChange {
+                channel: 2,
+                controller: 10,
+                value: 0,
+            },
+        ]
+    );
+}
+
+/// SysExとシステムコモンメッセージのデータバイトはノートにならない。
+#[test]
+fn test_stream_parser_skip_system_messages() {
+    // SysExの後はランニングステータスが解除されているので、ステータスなしのデータは捨てる。
+    let messages = parse_all(&[0x90, 60, 100, 0xF0, 0x7E, 0x01, 0x02, 0xF7, 62, 100]);
+    assert_eq!(messages.len(), 1);
+
+    // ソングセレクトのデータバイトも捨てる。
+    let messages = parse_all(&[0xF3, 0x05, 0x80, 60, 0]);
+    assert_eq!(
+        messages,
+        vec![EMidiMessage::NoteOff {
+            channel: 0,
+            key: 60,
+            velocity: 0,
+        }]
+    );
+}
+
+/// テスト用にALSAシーケンサーのイベントを作る。`data`はイベントのデータの部分に入れる。
+fn sequencer_event(event_type: u8, flags: u8, data: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; SEQUENCER_EVENT_SIZE];
+    result[0] = event_type;
+    result[1] = flags;
+    result[16..(16 + data.len())].copy_from_slice(data);
+    result
+}
+
+/// ALSAシーケンサーのイベントをメッセージにして、使わないイベントと可変長のデータは読み飛ばす。
+#[test]
+fn test_sequencer_events() {
+    let mut bytes = vec![];
+    // ノートオン、チャンネル3
+    bytes.extend(sequencer_event(6, 0, &[3, 60, 100]));
+    // SysExは可変長で、30バイトのデータがイベント2つ分続く。
+    let mut sysex = sequencer_event(130, 1 << 2, &30u32.to_ne_bytes());
+    sysex.extend(vec![0xF0; SEQUENCER_EVENT_SIZE * 2]);
+    bytes.extend(sysex);
+    // コントロールチェンジ
+    let mut control = vec![3, 0, 0, 0];
+    control.extend(7u32.to_ne_bytes());
+    control.extend(100i32.to_ne_bytes());
+    bytes.extend(sequencer_event(10, 0, &control));
+    // クロックは使わない。
+    bytes.extend(sequencer_event(36, 0, &[]));
+    // ピッチベンド
+    let mut pitch_bend = vec![3, 0, 0, 0, 0, 0, 0, 0];
+    pitch_bend.extend((-8192i32).to_ne_bytes());
+    bytes.extend(sequencer_event(13, 0, &pitch_bend));
+    // ベロシティが0のノートオンはノートオフ
+    bytes.extend(sequencer_event(6, 0, &[3, 60, 0]));
+
+    assert_eq!(
+        parse_sequencer_events(&bytes),
+        vec![
+            EMidiMessage::NoteOn {
+                channel: 3,
+                key: 60,
+                velocity: 100,
+            },
+            EMidiMessage::ControlChange {
+                channel: 3,
+                controller: 7,
+                value: 100,
+            },
+            EMidiMessage::PitchBend {
+                channel: 3,
+                value: -8192
+            },
+            EMidiMessage::NoteOff {
+                channel: 3,
+                key: 60,
+                velocity: 0,
+            },
+        ]
+    );
+}
+
+/// 仮想の入力に送ったメッセージがレシーバーに届いて、シンセサイザーで鳴らせる。
+#[test]
+fn test_midi_input_virtual_synth() {
+    let _lock = crate::midi::lock_midi_input();
+    let setting = MidiInputSetting {
+        source: EMidiInputSource::Virtual,
+    };
+    let proxy = MidiInput::initialize(setting).expect("Virtual midi input must be initialized");
+    let proxy = proxy.upgrade().expect("Proxy must be valid");
+    let receiver = proxy.lock().unwrap().subscribe();
+
+    let mut synth = MidiSynthesizer::new(MidiSynthSetting {
+        waveform: EMidiVoiceWaveform::Sine,
+        duty_rate: 0.5,
+        antialias: EOscillatorAntialias::None,
+        intensity: 0.5,
+        envelope: AdsrEnvelope {
+            attack_time: 0.0,
+            decay_time: 0.0,
+            release_time: 0.01,
+            attack_curve: 1.0,
+            decay_curve: 1.0,
+            release_curve: 1.0,
+            sustain_value: 1.0,
+        },
+        polyphony: 4,
+        velocity_sensitivity: 0.0,
+        sample_rate: SAMPLE_RATE,
+    });
+    let mut process = |message: EMidiMessage, frame_count: usize| {
+        proxy.lock().unwrap().send_message(message);
+        receiver.try_iter().for_each(|v| synth.handle_message(&v));
+        synth.next_frames(frame_count).mono
+    };
+
+    // ノートオンで鳴って、ノートオフでリリースが終わったら無音になる。
+    let note_on = EMidiMessage::NoteOn {
+        channel: 0,
+        key: 69,
+        velocity: 100,
+    };
+    let samples = process(note_on, SAMPLE_RATE / 10);
+    let peak = samples.iter().map(|v| v.to_f64().abs()).fold(0.0, f64::max);
+    assert!((peak - 0.5).abs() < 1e-3, "{}", peak);
+
+    let note_off = EMidiMessage::NoteOff {
+        channel: 0,
+        key: 69,
+        velocity: 0,
+    };
+    let samples = process(note_off, SAMPLE_RATE / 10);
+    assert!(samples[(SAMPLE_RATE / 50)..].iter().all(|v| v.to_f64() == 0.0));
+    MidiInput::cleanup();
+}
+
+/// 開けない入力はパニックせずに初期化のエラーになる。
+#[test]
+fn test_midi_input_raw_not_found() {
+    let setting = MidiInputSetting {
+        source: EMidiInputSource::Raw {
+            path: "/nonexistent/soundprog_test_midi".to_owned(),
+        },
+    };
+    let error = MidiInput::initialize(setting)
+        .err()
+        .expect("Missing midi input must be an error");
+    assert!(error.to_string().contains("Could not open midi input"), "{}", error);
+}
+
+/// 解放する前にもう一度初期化するとエラーになり、解放したあとならまた初期化できる。
+#[test]
+fn test_midi_input_initialize_after_cleanup() {
+    let _lock = crate::midi::lock_midi_input();
+    let create_setting = || MidiInputSetting {
+        source: EMidiInputSource::Virtual,
+    };
+    let note_on = EMidiMessage::NoteOn {
+        channel: 0,
+        key: 60,
+        velocity: 100,
+    };
+
+    let proxy = MidiInput::initialize(create_setting()).expect("Virtual midi input must be initialized");
+    assert!(MidiInput::get_proxy().is_some());
+    let error = MidiInput::initialize(create_setting())
+        .err()
+        .expect("Initializing twice must be an error");
+    assert!(error.to_string().contains("already initialized"), "{}", error);
+
+    // 解放したら前のプロキシからはもう接近できない。
+    MidiInput::cleanup();
+    assert!(proxy.upgrade().is_none());
+    assert!(MidiInput::get_proxy().is_none());
+
+    // もう一度初期化した入力からもメッセージを受け取れる。
+    MidiInput::initialize(create_setting()).expect("Virtual midi input must be initialized again");
+    let proxy = MidiInput::get_proxy().and_then(|v| v.upgrade()).expect("Proxy must be valid");
+    let receiver = proxy.lock().unwrap().subscribe();
+    proxy.lock().unwrap().send_message(note_on);
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![note_on]);
+    MidiInput::cleanup();
+}
+
+/// つなぐポートのアドレスが読めなければ、シーケンサーを開く前に初期化のエラーになる。
+#[test]
+fn test_midi_input_sequencer_invalid_address() {
+    let setting = MidiInputSetting {
+        source: EMidiInputSource::AlsaSequencer {
+            port_name: "soundprog test".to_owned(),
+            connect_from: Some("keyboard".to_owned()),
+        },
+    };
+    let error = MidiInput::initialize(setting).err().expect("Invalid address must be an error");
+    assert!(error.to_string().contains("`connect_from` must be `client:port`"), "{}", error);
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,57 @@
+pub mod file;
+pub mod graph_file;
+pub mod graph_input;
+pub mod input;
+pub mod synth;
+
+use std::sync::{Mutex, MutexGuard};
+
+/// [`soundprog::midi::input::MidiInput`]はプロセスに1つしか初期化できないので、
+/// 初期化するテストはこれをロックしてから順番に処理する。
+static MIDI_INPUT_LOCK: Mutex<()> = Mutex::new(());
+
+/// グローバルのMIDI入力を使うテストを順番に処理するためのロックを取る。
+/// 他のテストがパニックしてロックが壊れていても続けて使う。
+pub fn lock_midi_input() -> MutexGuard<'static, ()> {
+    MIDI_INPUT_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// テスト用に可変長数値を作る。
+pub fn variable_length(mut value: u32) -> Vec<u8> {
+    let mut result = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        result.push(((value & 0x7F) as u8) | 0x80);
+        value >>= 7;
+    }
+    result.reverse();
+    result
+}
+
+/// `(デルタタイム, イベントのバイト)`からトラックチャンクを作る。最後にトラックの終わりを付ける。
+pub fn track_chunk(events: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut data = vec![];
+    for (delta, event) in events {
+        data.extend(variable_length(*delta));
+        data.extend(event);
+    }
+    data.extend([0x00, 0xFF, 0x2F, 0x00]);
+
+    let mut result = b"MTrk".to_vec();
+    result.extend((data.len() as u32).to_be_bytes());
+    result.extend(data);
+    result
+}
+
+/// ヘッダーチャンクとトラックチャンクからStandard MIDI Fileを作る。
+pub fn midi_file_bytes(format: u16, division: u16, tracks: &[Vec<u8>]) -> Vec<u8> {
+    let mut result = b"MThd".to_vec();
+    result.extend(6u32.to_be_bytes());
+    result.extend(format.to_be_bytes());
+    result.extend((tracks.len() as u16).to_be_bytes());
+    result.extend(division.to_be_bytes());
+    for track in tracks {
+        result.extend(track);
+    }
+    result
+}
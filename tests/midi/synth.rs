@@ -0,0 +1,158 @@
+use soundprog::midi::file::MidiTimedMessage;
+use soundprog::midi::synth::{EMidiVoiceWaveform, MidiSequencePlayer, MidiSynthSetting, MidiSynthesizer};
+use soundprog::midi::EMidiMessage;
+use soundprog::wave::envelope::AdsrEnvelope;
+use soundprog::wave::sample::UniformedSample;
+use soundprog::wave::sine::emitter::EOscillatorAntialias;
+
+const SAMPLE_RATE: usize = 48000;
+
+fn create_setting(polyphony: usize) -> MidiSynthSetting {
+    MidiSynthSetting {
+        waveform: EMidiVoiceWaveform::Square,
+        duty_rate: 0.5,
+        antialias: EOscillatorAntialias::None,
+        intensity: 0.25,
+        envelope: AdsrEnvelope {
+            attack_time: 0.0,
+            decay_time: 0.0,
+            release_time: 0.01,
+            attack_curve: 1.0,
+            decay_curve: 1.0,
+            release_curve: 1.0,
+            sustain_value: 1.0,
+        },
+        polyphony,
+        velocity_sensitivity: 1.0,
+        sample_rate: SAMPLE_RATE,
+    }
+}
+
+fn note_on(key: u8, velocity: u8) -> EMidiMessage {
+    EMidiMessage::NoteOn {
+        channel: 0,
+        key,
+        velocity,
+    }
+}
+
+fn note_off(key: u8) -> EMidiMessage {
+    EMidiMessage::NoteOff {
+        channel: 0,
+        key,
+        velocity: 0,
+    }
+}
+
+fn peak(samples: &[UniformedSample]) -> f64 {
+    samples.iter().map(|v| v.to_f64().abs()).fold(0.0, f64::max)
+}
+
+/// ボイスの最大数を超えたら古いボイスから止めて、リリースが終わったボイスは消える。
+#[test]
+fn test_synth_polyphony() {
+    let mut synth = MidiSynthesizer::new(create_setting(2));
+    for key in [60, 64, 67] {
+        synth.handle_message(&note_on(key, 127));
+    }
+    assert_eq!(synth.active_voice_count(), 2);
+
+    // 最初のノートはもう止められているので、ノートオフしても変わらない。
+    synth.handle_message(&note_off(60));
+    synth.next_frames(SAMPLE_RATE / 10);
+    assert_eq!(synth.active_voice_count(), 2);
+
+    synth.release_all();
+    synth.next_frames(SAMPLE_RATE / 10);
+    assert_eq!(synth.active_voice_count(), 0);
+    assert_eq!(peak(&synth.next_frames(64).mono), 0.0);
+}
+
+/// ベロシティの感度が`1`ならベロシティに比例した振幅になる。
+#[test]
+fn test_synth_velocity() {
+    let mut loud = MidiSynthesizer::new(create_setting(4));
+    loud.handle_message(&note_on(69, 127));
+    let loud_peak = peak(&loud.next_frames(1024).mono);
+
+    let mut quiet = MidiSynthesizer::new(create_setting(4));
+    quiet.handle_message(&note_on(69, 32));
+    let quiet_peak = peak(&quiet.next_frames(1024).mono);
+
+    assert!((loud_peak - 0.25).abs() < 1e-9, "{}", loud_peak);
+    assert!((quiet_peak / loud_peak - 32.0 / 127.0).abs() < 1e-6, "{}", quiet_peak);
+}
+
+/// パンを右いっぱいにすると左チャンネルは無音になる。
+#[test]
+fn test_synth_pan() {
+    let mut synth = MidiSynthesizer::new(create_setting(4));
+    synth.handle_message(&EMidiMessage::ControlChange {
+        channel: 0,
+        controller: 10,
+        value: 127,
+    });
+    synth.handle_message(&note_on(69, 127));
+
+    let output = synth.next_frames(1024);
+    assert_eq!(peak(&output.left), 0.0);
+    assert!((peak(&output.right) - peak(&output.mono)).abs() < 1e-9);
+}
+
+/// メッセージがサンプル単位のタイミングで反映されて、最後のリリースが終わったら終了する。
+#[test]
+fn test_sequence_player_timing() {
+    let messages = vec![
+        MidiTimedMessage {
+            time: 0.01,
+            message: note_on(69, 127),
+        },
+        MidiTimedMessage {
+            time: 0.02,
+            message: note_off(69),
+        },
+    ];
+    let mut player = MidiSequencePlayer::new(create_setting(4), messages, 0.02);
+
+    // ティックの区切りとメッセージのタイミングが合わなくても同じ結果になるように、半端な長さで処理する。
+    let mut samples = vec![];
+    while !player.is_finished() {
+        samples.extend(player.next_frames(333).mono);
+        assert!(samples.len() < SAMPLE_RATE, "Player must be finished");
+    }
+
+    let note_on_i = SAMPLE_RATE / 100;
+    // 時間を足していく誤差で、リリースの終わりは1サンプルずれることがある。
+    let release_end_i = SAMPLE_RATE * 3 / 100 + 1;
+    assert_eq!(peak(&samples[..note_on_i]), 0.0);
+    assert!(samples[note_on_i].to_f64() > 0.0);
+    assert!(samples.len() >= release_end_i);
+    assert_eq!(peak(&samples[release_end_i..]), 0.0);
+}
+
+/// ノートオフがないノートも、曲の長さが過ぎたらリリースして終了する。
+#[test]
+fn test_sequence_player_unterminated_note() {
+    let messages = vec![MidiTimedMessage {
+        time: 0.01,
+        message: note_on(69, 127),
+    }];
+    let mut player = MidiSequencePlayer::new(create_setting(4), messages, 0.05);
+
+    let mut samples = vec![];
+    while !player.is_finished() {
+        samples.extend(player.next_frames(333).mono);
+        assert!(samples.len() < SAMPLE_RATE, "Player must be finished");
+    }
+
+    // 曲の終わりまでは鳴り続けて、リリースが終わったら無音になる。
+    let end_i = SAMPLE_RATE * 5 / 100;
+    let release_end_i = SAMPLE_RATE * 6 / 100 + 1;
+    assert!(peak(&samples[end_i - 64..end_i]) > 0.2);
+    assert!(samples.len() >= release_end_i);
+    assert_eq!(peak(&samples[release_end_i..]), 0.0);
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,51 @@
+use miniaudio::{Backend, DeviceType};
+use soundprog::device::backend::{create_context, find_device_id, EAudioDeviceBackend, EAudioDeviceSelector};
+use soundprog::device::{AudioDevice, AudioDeviceConfig};
+
+/// ヌルバックエンドを指定して、番号と名前でデバイスを選べるかを確認する。
+#[test]
+fn test_miniaudio_select_null_backend_device() {
+    let context = create_context(Some(EAudioDeviceBackend::Null)).expect("Failed to create null backend context");
+    assert_eq!(context.backend(), Backend::Null);
+
+    find_device_id(&context, DeviceType::Playback, &EAudioDeviceSelector::Index(0))
+        .expect("Failed to find playback device by index");
+    find_device_id(&context, DeviceType::Capture, &EAudioDeviceSelector::Name("NULL".to_owned()))
+        .expect("Failed to find capture device by name");
+
+    assert!(find_device_id(&context, DeviceType::Playback, &EAudioDeviceSelector::Index(100)).is_err());
+    assert!(find_device_id(&context, DeviceType::Capture, &EAudioDeviceSelector::Name("unknown".to_owned())).is_err());
+}
+
+/// 見つからないデバイスを指定したら、パニックせずに初期化のエラーになる。
+/// 失敗した時は何も登録しないので、あとからまた初期化できる。
+#[test]
+fn test_audio_device_initialize_unknown_device() {
+    let _lock = crate::device::lock_audio_device();
+    let mut config = AudioDeviceConfig::new();
+    config
+        .set_channels(2)
+        .set_sample_rate(48000)
+        .set_backend(Some(EAudioDeviceBackend::Null))
+        .set_device(Some(EAudioDeviceSelector::Name("unknown".to_owned())));
+
+    let error = AudioDevice::initialize(config).err().expect("Unknown device must be an error");
+    assert!(error.to_string().contains("Could not find"), "{}", error);
+}
+
+/// 設定ファイルからは番号と名前のどちらでもデバイスを指定できる。
+#[test]
+fn test_deserialize_audio_device_selector() {
+    let index: EAudioDeviceSelector = serde_json::from_str("1").unwrap();
+    assert_eq!(index, EAudioDeviceSelector::Index(1));
+
+    let name: EAudioDeviceSelector = serde_json::from_str("\"Speakers\"").unwrap();
+    assert_eq!(name, EAudioDeviceSelector::Name("Speakers".to_owned()));
+
+    let backend: EAudioDeviceBackend = serde_json::from_str("\"null\"").unwrap();
+    assert_eq!(backend, EAudioDeviceBackend::Null);
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,50 @@
+use soundprog::device::backend::EAudioDeviceBackend;
+use soundprog::device::{AudioDevice, AudioDeviceConfig};
+use std::time::{Duration, Instant};
+
+pub const DEVICE_CHANNELS: usize = 2;
+pub const DEVICE_SAMPLE_RATE: usize = 48000;
+
+/// ヌルバックエンドの入力デバイスから、[`AudioDevice`]のキャプチャーのリングバッファを通して
+/// プロキシまでフレームが届くかを確認する。サウンドカードがない環境でも動く。
+#[test]
+fn test_audio_device_capture_null_backend() {
+    let _lock = crate::device::lock_audio_device();
+
+    let mut config = AudioDeviceConfig::new();
+    config
+        .set_channels(0)
+        .set_capture_channels(DEVICE_CHANNELS)
+        .set_sample_rate(DEVICE_SAMPLE_RATE)
+        .set_backend(Some(EAudioDeviceBackend::Null));
+    let proxy = AudioDevice::initialize(config).expect("Failed to initialize capture device");
+    let proxy = proxy.upgrade().expect("Proxy must be valid");
+
+    // 最初のpre_processでデバイスが始まる。
+    AudioDevice::pre_process(0.0);
+
+    // 0.1秒分のフレームが届くまで待つ。
+    let required_samples = DEVICE_SAMPLE_RATE / 10 * DEVICE_CHANNELS;
+    let mut captured_samples = vec![];
+    let start = Instant::now();
+    while captured_samples.len() < required_samples && start.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(Duration::from_millis(10));
+        captured_samples.extend(proxy.lock().unwrap().receive_captured_samples());
+    }
+    AudioDevice::post_process(0.0);
+
+    assert_eq!(proxy.lock().unwrap().get_capture_channels(), DEVICE_CHANNELS);
+    AudioDevice::cleanup();
+
+    assert!(
+        captured_samples.len() >= required_samples,
+        "captured only {} samples",
+        captured_samples.len()
+    );
+    assert_eq!(captured_samples.len() % DEVICE_CHANNELS, 0);
+    assert!(captured_samples.iter().all(|v| v.is_finite()));
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
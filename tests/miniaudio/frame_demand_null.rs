@@ -0,0 +1,56 @@
+use soundprog::device::backend::EAudioDeviceBackend;
+use soundprog::device::{AudioDevice, AudioDeviceConfig};
+use std::time::Duration;
+
+const PERIOD_SIZE: u32 = 480;
+const SAMPLE_RATE: usize = 48000;
+
+/// プル型で処理する時に、デバイスのコールバックが読み込んだ分のフレーム数が要求として届くかを確認する。
+/// ヌルバックエンドを使うので、サウンドカードがない環境でも動く。
+#[test]
+fn test_audio_device_frame_demand_null_backend() {
+    let _lock = crate::device::lock_audio_device();
+    let mut config = AudioDeviceConfig::new();
+    config
+        .set_channels(2)
+        .set_sample_rate(SAMPLE_RATE)
+        .set_backend(Some(EAudioDeviceBackend::Null))
+        .set_period_size(Some(PERIOD_SIZE))
+        .set_use_frame_demand(true);
+    let _proxy = AudioDevice::initialize(config).expect("Failed to initialize audio device");
+
+    // デバイスが始まる前にリングバッファを埋められるように、最初の1周期分は先に要求されている。
+    let demand = AudioDevice::wait_frame_demand(Duration::from_millis(10)).expect("First period must be requested");
+    assert_eq!(demand, PERIOD_SIZE as usize);
+
+    // 最初のpre_processでデバイスが始まる。
+    AudioDevice::pre_process(0.0);
+    let demand = AudioDevice::wait_frame_demand(Duration::from_secs(1)).expect("Device callback must request frames");
+    AudioDevice::post_process(0.0);
+
+    // コールバック1回分のフレーム数の倍数になる。
+    assert!(demand >= PERIOD_SIZE as usize, "{}", demand);
+    assert_eq!(demand % PERIOD_SIZE as usize, 0, "{}", demand);
+
+    // 解放したら要求は届かない。
+    AudioDevice::cleanup();
+    assert!(AudioDevice::wait_frame_demand(Duration::from_millis(10)).is_none());
+}
+
+/// 周期を指定しなければ、最初の要求はminiaudioのデフォルトの周期（10ミリ秒）分になる。
+#[test]
+fn test_audio_device_frame_demand_default_period() {
+    let _lock = crate::device::lock_audio_device();
+    let mut config = AudioDeviceConfig::new();
+    config
+        .set_channels(2)
+        .set_sample_rate(SAMPLE_RATE)
+        .set_backend(Some(EAudioDeviceBackend::Null))
+        .set_use_frame_demand(true);
+    let _proxy = AudioDevice::initialize(config).expect("Failed to initialize audio device");
+
+    let demand = AudioDevice::wait_frame_demand(Duration::from_millis(10)).expect("First period must be requested");
+    assert_eq!(demand, SAMPLE_RATE / 100);
+
+    AudioDevice::cleanup();
+}
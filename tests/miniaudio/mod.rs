@@ -1,6 +1,9 @@
 //mod enumeration;
 //mod playback_sine;
 mod playback_simple_thread;
+mod capture_null;
+mod backend_null;
+mod frame_demand_null;
 
 /// Shows a prompt and waits for input on stdin.
 pub fn wait_for_enter() {
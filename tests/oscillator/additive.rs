@@ -0,0 +1,142 @@
+use crate::device::assert_graph_error;
+use soundprog::wave::sine::additive::{AdditivePartial, AdditiveUnitSampleEmitter};
+use soundprog::wave::PI2;
+
+const SAMPLE_RATE: usize = 48000;
+
+fn partial(ratio: f64, amplitude: f64) -> AdditivePartial {
+    AdditivePartial {
+        ratio,
+        amplitude,
+        phase: 0.0,
+        decay_time: None,
+    }
+}
+
+/// 部分音をそれぞれのサイン波の和で鳴らす。
+#[test]
+fn test_partials_are_summed() {
+    let partials = vec![partial(1.0, 0.5), partial(2.0, 0.25), partial(3.0, 0.125)];
+    let mut emitter = AdditiveUnitSampleEmitter::new(220.0, 0.8, partials, 0.0, SAMPLE_RATE);
+
+    for (sample_i, sample) in emitter.next_samples(1024).iter().enumerate() {
+        let time = sample_i as f64 / SAMPLE_RATE as f64;
+        let expected = 0.8
+            * [(1.0, 0.5), (2.0, 0.25), (3.0, 0.125)]
+                .iter()
+                .map(|(ratio, amplitude)| amplitude * (PI2 * 220.0 * ratio * time).sin())
+                .sum::<f64>();
+        assert!(
+            (expected - sample.to_f64()).abs() < 1e-6,
+            "{}: {} {:?}",
+            sample_i,
+            expected,
+            sample
+        );
+    }
+}
+
+/// 最初の位相を反映して、ナイキスト周波数を超える部分音は鳴らさない。
+#[test]
+fn test_phase_and_nyquist() {
+    let partials = vec![
+        AdditivePartial {
+            ratio: 1.0,
+            amplitude: 0.5,
+            phase: PI2 * 0.25,
+            decay_time: None,
+        },
+        partial(100.0, 0.5),
+    ];
+    let mut emitter = AdditiveUnitSampleEmitter::new(440.0, 1.0, partials, 0.0, SAMPLE_RATE);
+
+    // 44000Hzの部分音は鳴らないので、コサイン波だけになる。
+    for (sample_i, sample) in emitter.next_samples(1024).iter().enumerate() {
+        let time = sample_i as f64 / SAMPLE_RATE as f64;
+        let expected = 0.5 * (PI2 * 440.0 * time).cos();
+        assert!(
+            (expected - sample.to_f64()).abs() < 1e-6,
+            "{}: {} {:?}",
+            sample_i,
+            expected,
+            sample
+        );
+    }
+}
+
+/// `decay_time`が経つと、振幅が`1/e`になる。
+#[test]
+fn test_partial_decay() {
+    let partials = vec![AdditivePartial {
+        ratio: 1.0,
+        amplitude: 1.0,
+        phase: PI2 * 0.25,
+        decay_time: Some(0.5),
+    }];
+    // 1Hzにしておけば、最初の周期の間はコサイン波の山の近くにいる。
+    let mut emitter = AdditiveUnitSampleEmitter::new(1.0, 1.0, partials, 0.0, SAMPLE_RATE);
+    let samples = emitter.next_samples(SAMPLE_RATE / 2 + 1);
+
+    let expected = (-1.0f64).exp() * (PI2 * 0.5).cos();
+    let actual = samples[SAMPLE_RATE / 2].to_f64();
+    assert!((expected - actual).abs() < 1e-3, "{} {}", expected, actual);
+}
+
+/// 非調和性があると、部分音の周波数が高くずれる。
+#[test]
+fn test_inharmonicity() {
+    const INHARMONICITY: f64 = 0.01;
+    let mut emitter = AdditiveUnitSampleEmitter::new(100.0, 1.0, vec![partial(4.0, 1.0)], INHARMONICITY, SAMPLE_RATE);
+    let frequency = 400.0 * (1.0 + INHARMONICITY * 16.0f64).sqrt();
+
+    for (sample_i, sample) in emitter.next_samples(1024).iter().enumerate() {
+        let time = sample_i as f64 / SAMPLE_RATE as f64;
+        let expected = (PI2 * frequency * time).sin();
+        assert!(
+            (expected - sample.to_f64()).abs() < 1e-6,
+            "{}: {} {:?}",
+            sample_i,
+            expected,
+            sample
+        );
+    }
+}
+
+/// 使えない設定の`emitter-additive`は、パニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_additive_invalid_setting() {
+    let dir = std::env::temp_dir();
+    for (field, value, message, file_name) in [
+        (
+            "sample_rate",
+            serde_json::json!(0),
+            "`sample_rate` must be bigger than 0.",
+            "soundprog_test_graph_additive_zero_sample_rate",
+        ),
+        (
+            "partials",
+            serde_json::json!([]),
+            "`partials` must not be empty.",
+            "soundprog_test_graph_additive_empty_partials",
+        ),
+        (
+            "inharmonicity",
+            serde_json::json!(-0.1),
+            "`inharmonicity` must not be negative.",
+            "soundprog_test_graph_additive_negative_inharmonicity",
+        ),
+    ] {
+        let mut json: serde_json::Value =
+            serde_json::from_str(include_str!("../../example/additive.json")).expect("Example must be valid");
+        json["node"]["input"][field] = value;
+        json["node"]["output"]["file_name"] = serde_json::json!(file_name);
+        json["node"]["output"]["add_date_time"] = serde_json::json!(false);
+        let output = crate::device::run_graph(&dir, file_name, &json);
+        let _ = std::fs::remove_file(dir.join(format!("{}.wav", file_name)));
+        assert_graph_error(&output, message);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,101 @@
+use soundprog::math::window::EWindowFunction;
+use soundprog::wave::analyze::analyzer::{FrequencyAnalyzerV2, WaveContainerSetting};
+use soundprog::wave::analyze::method::EAnalyzeMethod;
+use soundprog::wave::sine::emitter::{EOscillatorAntialias, SineUnitSampleEmitter};
+
+const SAMPLE_RATE: usize = 48000;
+/// FFTのサンプル数
+const SAMPLES_COUNT: usize = 4096;
+/// 基本周波数がちょうど乗るFFTのビン。高い周波数でエイリアシングが目立つようにする。
+const FUNDAMENTAL_BIN: usize = 107;
+/// 倍音のビンからこれ以上離れていたら、エイリアシングとみなす。（Hann窓の漏れを含めない）
+const HARMONIC_MARGIN_BIN: usize = 3;
+
+fn fundamental_frequency() -> f64 {
+    (SAMPLE_RATE * FUNDAMENTAL_BIN) as f64 / SAMPLES_COUNT as f64
+}
+
+/// ナイキスト周波数までのエネルギーのうち、倍音ではないところにあるエネルギーの割合を返す。
+fn aliased_energy_rate(mut emitter: SineUnitSampleEmitter, antialias: EOscillatorAntialias) -> f64 {
+    emitter.set_antialias(antialias);
+    let samples = emitter.next_samples(SAMPLES_COUNT);
+
+    let analyzer = FrequencyAnalyzerV2 {
+        analyze_method: EAnalyzeMethod::FFT,
+        frequency_start: 0.0,
+        frequency_width: SAMPLE_RATE as f64,
+        frequency_bin_count: SAMPLES_COUNT as u32,
+        window_function: EWindowFunction::Hann,
+    };
+    let frequencies = analyzer
+        .analyze_container(&WaveContainerSetting {
+            container: &samples,
+            start_sample_index: 0,
+            samples_count: SAMPLES_COUNT,
+        })
+        .unwrap();
+
+    let mut total_energy = 0.0;
+    let mut aliased_energy = 0.0;
+    for (bin_i, frequency) in frequencies.iter().enumerate().take(SAMPLES_COUNT >> 1).skip(1) {
+        let energy = frequency.amplitude.powi(2);
+        total_energy += energy;
+
+        let harmonic_offset = bin_i % FUNDAMENTAL_BIN;
+        if harmonic_offset.min(FUNDAMENTAL_BIN - harmonic_offset) > HARMONIC_MARGIN_BIN {
+            aliased_energy += energy;
+        }
+    }
+    aliased_energy / total_energy
+}
+
+/// PolyBLEPにすると、エイリアシングのエネルギーが少なくとも10分の1以下になる。
+fn assert_antialiased(create_emitter: impl Fn() -> SineUnitSampleEmitter) {
+    let naive_rate = aliased_energy_rate(create_emitter(), EOscillatorAntialias::None);
+    let polyblep_rate = aliased_energy_rate(create_emitter(), EOscillatorAntialias::PolyBlep);
+    assert!(
+        polyblep_rate * 10.0 < naive_rate,
+        "naive: {}, polyblep: {}",
+        naive_rate,
+        polyblep_rate
+    );
+}
+
+#[test]
+fn test_polyblep_sawtooth() {
+    assert_antialiased(|| SineUnitSampleEmitter::new_sawtooth(fundamental_frequency(), 0.0, 0.5, SAMPLE_RATE));
+}
+
+#[test]
+fn test_polyblep_square() {
+    assert_antialiased(|| SineUnitSampleEmitter::new_square(fundamental_frequency(), 0.3, 0.0, 0.5, SAMPLE_RATE));
+}
+
+#[test]
+fn test_polyblep_triangle() {
+    assert_antialiased(|| SineUnitSampleEmitter::new_triangle(fundamental_frequency(), 0.0, 0.5, SAMPLE_RATE));
+}
+
+/// ナイキスト周波数の近くや、変調でサンプルレートを超えた周波数では補正が±1を超えるけど、
+/// パニックせずに範囲内に収まる。
+#[test]
+fn test_polyblep_near_nyquist() {
+    for rate in [0.49, 0.97, 1.5] {
+        let frequency = SAMPLE_RATE as f64 * rate;
+        let emitters = [
+            SineUnitSampleEmitter::new_sawtooth(frequency, 0.0, 1.0, SAMPLE_RATE),
+            SineUnitSampleEmitter::new_triangle(frequency, 0.0, 1.0, SAMPLE_RATE),
+            SineUnitSampleEmitter::new_square(frequency, 0.5, 0.0, 1.0, SAMPLE_RATE),
+        ];
+
+        for mut emitter in emitters {
+            emitter.set_antialias(EOscillatorAntialias::PolyBlep);
+            let samples = emitter.next_samples(SAMPLE_RATE / 10);
+            assert!(samples.iter().all(|v| (-1.0..=1.0).contains(&v.to_f64())), "{}", frequency);
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,129 @@
+use crate::device::assert_graph_error;
+use soundprog::math::window::EWindowFunction;
+use soundprog::wave::analyze::analyzer::{FrequencyAnalyzerV2, WaveContainerSetting};
+use soundprog::wave::analyze::method::EAnalyzeMethod;
+use soundprog::wave::envelope::AdsrEnvelope;
+use soundprog::wave::sine::emitter::SineUnitSampleEmitter;
+use soundprog::wave::sine::fm::{EFmAlgorithm, FmOperatorSetting, FmUnitSampleEmitter};
+
+const SAMPLE_RATE: usize = 48000;
+/// FFTのサンプル数
+const SAMPLES_COUNT: usize = 4096;
+/// キャリアの周波数がちょうど乗るFFTのビン
+const CARRIER_BIN: usize = 256;
+
+/// ずっと最大の振幅を維持するエンベロープ
+fn flat_envelope() -> AdsrEnvelope {
+    AdsrEnvelope {
+        attack_time: 0.0,
+        decay_time: 0.0,
+        release_time: 0.0,
+        attack_curve: 1.0,
+        decay_curve: 1.0,
+        release_curve: 1.0,
+        sustain_value: 1.0,
+    }
+}
+
+fn operator(ratio: f64, index: f64) -> FmOperatorSetting {
+    FmOperatorSetting {
+        ratio,
+        index,
+        feedback: 0.0,
+        envelope: flat_envelope(),
+    }
+}
+
+/// 変調指数が0のモジュレーターなら、キャリアだけのサイン波と同じになる。
+#[test]
+fn test_zero_index_is_sine() {
+    let operators = vec![operator(1.0, 1.0), operator(2.0, 0.0)];
+    let routing = EFmAlgorithm::Stack.resolve(operators.len()).unwrap();
+    let mut fm = FmUnitSampleEmitter::new(440.0, 0.5, operators, routing, None, SAMPLE_RATE);
+    let mut sine = SineUnitSampleEmitter::new_sine(440.0, 0.0, 0.5, SAMPLE_RATE);
+
+    for _ in 0..4096 {
+        let expected = sine.next_sample().to_f64();
+        let actual = fm.next_sample().to_f64();
+        assert!((expected - actual).abs() < 1e-6, "expected: {}, actual: {}", expected, actual);
+    }
+}
+
+/// 変調指数が1なら、1次の側波帯とキャリアの振幅の比は`J1(1) / J0(1)`になる。
+#[test]
+fn test_first_sideband_ratio() {
+    let carrier_frequency = (SAMPLE_RATE * CARRIER_BIN) as f64 / SAMPLES_COUNT as f64;
+    let operators = vec![operator(1.0, 1.0), operator(0.125, 1.0)];
+    let routing = EFmAlgorithm::Stack.resolve(operators.len()).unwrap();
+    let mut fm = FmUnitSampleEmitter::new(carrier_frequency, 0.5, operators, routing, None, SAMPLE_RATE);
+    let samples = fm.next_samples(SAMPLES_COUNT);
+
+    let analyzer = FrequencyAnalyzerV2 {
+        analyze_method: EAnalyzeMethod::FFT,
+        frequency_start: 0.0,
+        frequency_width: SAMPLE_RATE as f64,
+        frequency_bin_count: SAMPLES_COUNT as u32,
+        window_function: EWindowFunction::Hann,
+    };
+    let frequencies = analyzer
+        .analyze_container(&WaveContainerSetting {
+            container: &samples,
+            start_sample_index: 0,
+            samples_count: SAMPLES_COUNT,
+        })
+        .unwrap();
+
+    // J1(1) / J0(1)
+    let expected_rate = 0.44005 / 0.76520;
+    let sideband_bin = CARRIER_BIN + CARRIER_BIN / 8;
+    let actual_rate = frequencies[sideband_bin].amplitude / frequencies[CARRIER_BIN].amplitude;
+    assert!(
+        (expected_rate - actual_rate).abs() < 0.01,
+        "expected: {}, actual: {}",
+        expected_rate,
+        actual_rate
+    );
+}
+
+#[test]
+fn test_resolve_routing() {
+    let routing = EFmAlgorithm::Pairs.resolve(5).unwrap();
+    assert_eq!(routing.modulators, vec![vec![1], vec![], vec![3], vec![], vec![]]);
+    assert_eq!(routing.carriers, vec![0, 2, 4]);
+
+    // オペレーターの数が範囲外
+    assert!(EFmAlgorithm::Stack.resolve(1).is_err());
+    assert!(EFmAlgorithm::Parallel.resolve(7).is_err());
+
+    // 番号が小さいオペレーターでは変調できない。
+    let invalid = EFmAlgorithm::Custom {
+        modulators: vec![vec![], vec![0]],
+        carriers: vec![0],
+    };
+    assert!(invalid.resolve(2).is_err());
+
+    let valid = EFmAlgorithm::Custom {
+        modulators: vec![vec![1, 2], vec![], vec![]],
+        carriers: vec![0, 1],
+    };
+    assert!(valid.resolve(3).is_ok());
+}
+
+/// `sample_rate`が0の`emitter-fm`は、パニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_fm_zero_sample_rate() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_fm_zero_sample_rate";
+    let mut json: serde_json::Value =
+        serde_json::from_str(include_str!("../../example/fm.json")).expect("Example must be valid");
+    json["node"]["input"]["sample_rate"] = serde_json::json!(0);
+    json["node"]["output"]["file_name"] = serde_json::json!(file_name);
+    json["node"]["output"]["add_date_time"] = serde_json::json!(false);
+    let output = crate::device::run_graph(&dir, file_name, &json);
+    let _ = std::fs::remove_file(dir.join(format!("{}.wav", file_name)));
+    assert_graph_error(&output, "`sample_rate` must be bigger than 0.");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
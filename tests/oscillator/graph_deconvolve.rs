@@ -0,0 +1,82 @@
+use crate::device::assert_graph_error;
+use soundprog::wave::container::WaveContainer;
+use std::{fs, io};
+
+/// `example/sweep_deconvolve.json`のグラフを、日付を付けずに`file_name`へ書き込むようにして返す。
+fn create_graph_json(file_name: &str) -> serde_json::Value {
+    let mut json: serde_json::Value =
+        serde_json::from_str(include_str!("../../example/sweep_deconvolve.json")).expect("Example must be valid");
+    json["node"]["output"]["file_name"] = serde_json::json!(file_name);
+    json["node"]["output"]["add_date_time"] = serde_json::json!(false);
+    json
+}
+
+/// 指数スイープをそのまま逆畳み込みすると、インパルス応答の先頭にピークが立つ。
+#[test]
+fn test_graph_sweep_deconvolve() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_sweep_deconvolve";
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(file_name));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let wav_path = dir.join(format!("{}.wav", file_name));
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Output file must be written");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+
+    let samples: Vec<f64> = container.uniformed_sample_buffer().iter().map(|v| v.to_f64().abs()).collect();
+    let peak_i = (0..samples.len()).max_by(|&l, &r| samples[l].total_cmp(&samples[r])).unwrap();
+    assert!(peak_i < 4, "{}", peak_i);
+}
+
+/// 逆フィルターが変わるので、`mode`を省略するとエラーになる。
+#[test]
+fn test_graph_deconvolve_requires_mode() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_deconvolve_no_mode";
+    let mut json = create_graph_json(file_name);
+    json["node"]["deconvolve"].as_object_mut().unwrap().remove("mode");
+    let output = crate::device::run_graph(&dir, file_name, &json);
+    let _ = fs::remove_file(dir.join(format!("{}.wav", file_name)));
+    assert_graph_error(&output, "missing field `mode`");
+}
+
+/// 長さが0以下の設定は、パニックせずにエラーで終わる。
+#[test]
+fn test_graph_deconvolve_invalid_length() {
+    let dir = std::env::temp_dir();
+    for (field, file_name) in [
+        ("sweep_length", "soundprog_test_graph_deconvolve_zero_sweep_length"),
+        ("ir_length", "soundprog_test_graph_deconvolve_zero_ir_length"),
+    ] {
+        let mut json = create_graph_json(file_name);
+        json["node"]["deconvolve"][field] = serde_json::json!(0.0);
+        let output = crate::device::run_graph(&dir, file_name, &json);
+        let _ = fs::remove_file(dir.join(format!("{}.wav", file_name)));
+        assert_graph_error(&output, &format!("`{}` must be bigger than 0.", field));
+    }
+}
+
+/// 指数スイープでは、0以下の周波数や同じ周波数の組み合わせはエラーになる。
+#[test]
+fn test_graph_deconvolve_invalid_exponential_frequency() {
+    let dir = std::env::temp_dir();
+    for (from, to, message, file_name) in [
+        (0.0, 20000.0, "must be bigger than 0", "soundprog_test_graph_deconvolve_zero_frequency"),
+        (440.0, 440.0, "must be different", "soundprog_test_graph_deconvolve_same_frequency"),
+    ] {
+        let mut json = create_graph_json(file_name);
+        json["node"]["deconvolve"]["from_frequency"]["value"] = serde_json::json!(from);
+        json["node"]["deconvolve"]["to_frequency"]["value"] = serde_json::json!(to);
+        let output = crate::device::run_graph(&dir, file_name, &json);
+        let _ = fs::remove_file(dir.join(format!("{}.wav", file_name)));
+        assert_graph_error(&output, message);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,113 @@
+use crate::device::{assert_graph_error, GraphJsonBuilder};
+use soundprog::wave::container::WaveContainer;
+use std::{fs, io};
+
+const SAMPLE_RATE: usize = 44100;
+const FREQUENCY: f64 = 441.0;
+const INTENSITY: f64 = 0.8;
+const LENGTH: f64 = 0.2;
+
+/// `emitter-test-signal`の直流で`emitter-sine`の`in_freq`と`in_amp`を変調して、`output-file`に書き込むグラフを作る。
+fn create_graph_json(file_name: &str, pitch: f64, amplitude: f64) -> serde_json::Value {
+    let dc = |intensity: f64| {
+        serde_json::json!({
+            "type": "emitter-test-signal",
+            "signal": { "type": "dc" },
+            "intensity": intensity,
+            "range": { "start": 0.0, "length": LENGTH },
+            "sample_rate": SAMPLE_RATE
+        })
+    };
+
+    let input = serde_json::json!({
+        "type": "emitter-sine",
+        "frequency": { "type": "constant", "value": FREQUENCY },
+        "intensity": INTENSITY,
+        "range": { "start": 0.0, "length": LENGTH },
+        "sample_rate": SAMPLE_RATE,
+        "pitch_range": 12.0
+    });
+
+    GraphJsonBuilder::output_file(input, SAMPLE_RATE, file_name)
+        .emitter("pitch", dc(pitch))
+        .emitter("amplitude", dc(amplitude))
+        .connect(("pitch", "out"), ("input", "in_freq"))
+        .connect(("amplitude", "out"), ("input", "in_amp"))
+        .build()
+}
+
+/// `in_freq`が`1`なら`pitch_range`の12半音（1オクターブ）上がって、`in_amp`の分だけ振幅が変わる。
+#[test]
+fn test_graph_sine_modulation_pins() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_sine_modulation";
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(file_name, 1.0, 0.5));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let wav_path = dir.join(format!("{}.wav", file_name));
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Output file must be written");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+
+    // 変調が始まった後の区間で、ゼロ交差の数と振幅を確認する。
+    let samples: Vec<f64> = container.uniformed_sample_buffer().iter().map(|v| v.to_f64()).collect();
+    let checked = &samples[(SAMPLE_RATE / 20)..(SAMPLE_RATE * 3 / 20)];
+    let crossings = checked.windows(2).filter(|v| (v[0] < 0.0) != (v[1] < 0.0)).count();
+    let expected_crossings = (2.0 * FREQUENCY * 2.0 * 0.1) as usize;
+    assert!(crossings.abs_diff(expected_crossings) <= 2, "{} != {}", crossings, expected_crossings);
+
+    let peak = checked.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+    assert!((peak - INTENSITY * 0.5).abs() < 1e-2, "{}", peak);
+}
+
+/// `in_amp`で変調した振幅が1を超えても、パニックせずに`[-1, 1]`にクランプして書き込む。
+#[test]
+fn test_graph_sine_amplitude_over_one() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_sine_amplitude_over_one";
+
+    // 変調した振幅は`2.0 * 0.8 = 1.6`になる。
+    let mut json = create_graph_json(file_name, 0.0, 0.8);
+    json["node"]["input"]["intensity"] = serde_json::json!(2.0);
+
+    let output = crate::device::run_graph(&dir, file_name, &json);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+
+    let wav_path = dir.join(format!("{}.wav", file_name));
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Output file must be written");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+
+    let samples: Vec<f64> = container.uniformed_sample_buffer().iter().map(|v| v.to_f64()).collect();
+    let checked = &samples[(SAMPLE_RATE / 20)..(SAMPLE_RATE * 3 / 20)];
+    let peak = checked.iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+    assert!((peak - 1.0).abs() < 1e-3, "{}", peak);
+
+    // 1を超えた分だけが削られるので、クランプされたサンプルが連続する区間がある。
+    let clipped_count = checked.iter().filter(|v| v.abs() > 0.999).count();
+    assert!(clipped_count > checked.len() / 10, "{}", clipped_count);
+}
+
+/// 負の`glide`はパニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_sine_negative_glide() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_sine_glide_error";
+    let mut json = create_graph_json(file_name, 0.0, 1.0);
+    json["node"]["input"]["glide"] = serde_json::json!(-0.1);
+    let output = crate::device::run_graph(&dir, file_name, &json);
+    let _ = fs::remove_file(dir.join(format!("{}.wav", file_name)));
+    assert_graph_error(&output, "`glide` must be 0 or bigger.");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
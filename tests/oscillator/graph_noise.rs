@@ -0,0 +1,29 @@
+use crate::device::{assert_graph_error, GraphJsonBuilder};
+
+/// `density`のベルベットノイズを`output-file`に書き込むグラフを作る。
+fn create_graph_json(file_name: &str, density: f64) -> serde_json::Value {
+    let input = serde_json::json!({
+        "type": "emitter-velvetnoise",
+        "intensity": 0.5,
+        "range": { "start": 0.0, "length": 0.1 },
+        "sample_rate": 8000,
+        "density": density
+    });
+    GraphJsonBuilder::output_file(input, 8000, file_name).build()
+}
+
+/// ベルベットノイズの`density`が0以下か、サンプルレートより大きいとパニックせずにエラーになる。
+#[test]
+fn test_graph_velvet_density_error() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_velvet_error";
+    for density in [0.0, 8001.0] {
+        let output = crate::device::run_graph(&dir, file_name, &create_graph_json(file_name, density));
+        let _ = std::fs::remove_file(dir.join(format!("{}.wav", file_name)));
+        assert_graph_error(&output, "`density` must be in (0, 8000]");
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
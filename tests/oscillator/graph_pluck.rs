@@ -0,0 +1,69 @@
+use crate::device::{assert_graph_error, GraphJsonBuilder};
+use soundprog::wave::container::WaveContainer;
+use std::{fs, io};
+
+const SAMPLE_RATE: usize = 22050;
+
+/// `trigger`で弾く`emitter-pluck → output-file`のグラフを作る。
+fn create_graph_json(trigger: serde_json::Value, file_name: &str) -> serde_json::Value {
+    let input = serde_json::json!({
+        "type": "emitter-pluck",
+        "trigger": trigger,
+        "decay_time": 0.2,
+        "intensity": 0.5,
+        "sample_rate": SAMPLE_RATE
+    });
+    GraphJsonBuilder::output_file(input, SAMPLE_RATE, file_name).build()
+}
+
+/// `notes`にサンプルレートの4分の1以上の周波数があれば、パニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_pluck_note_frequency_error() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_pluck_error";
+    let trigger = serde_json::json!({
+        "type": "notes",
+        "notes": [
+            { "time": 0.0, "frequency": { "type": "constant", "value": 220.0 }, "intensity": 1.0 },
+            { "time": 0.1, "frequency": { "type": "constant", "value": 6000.0 }, "intensity": 1.0 }
+        ]
+    });
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(trigger, file_name));
+    let _ = fs::remove_file(dir.join(format!("{}.wav", file_name)));
+    assert_graph_error(&output, "`frequency` 6000Hz");
+}
+
+/// MIDIファイルの弾けない高さのノートは飛ばして、残りのノートだけを弾く。
+#[test]
+fn test_graph_pluck_midi_file_skips_high_note() {
+    let dir = std::env::temp_dir();
+    let midi_path = dir.join("soundprog_test_graph_pluck.mid");
+    let track = crate::midi::track_chunk(&[
+        (0, vec![0x90, 127, 100]),
+        (0, vec![0x90, 57, 100]),
+        (240, vec![0x80, 127, 0]),
+        (0, vec![0x80, 57, 0]),
+    ]);
+    fs::write(&midi_path, crate::midi::midi_file_bytes(0, 480, &[track])).expect("Failed to write MIDI file");
+
+    let file_name = "soundprog_test_graph_pluck_midi";
+    let trigger = serde_json::json!({ "type": "midi_file", "path": midi_path.to_str().unwrap() });
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(trigger, file_name));
+    fs::remove_file(&midi_path).expect("Failed to remove MIDI file");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let wav_path = dir.join(format!("{}.wav", file_name));
+    let container = {
+        let file = fs::File::open(&wav_path).expect("Output file must be written");
+        let mut reader = io::BufReader::new(file);
+        WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+    };
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+
+    let samples = container.uniformed_sample_buffer();
+    assert!(samples.iter().any(|v| v.to_f64().abs() > 0.01));
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,54 @@
+use crate::device::{assert_graph_error, GraphJsonBuilder};
+use std::fs;
+
+/// `signal`を出す`emitter-test-signal → output-file`のグラフを作る。
+fn create_graph_json(signal: serde_json::Value, sample_rate: usize, file_name: &str) -> serde_json::Value {
+    let input = serde_json::json!({
+        "type": "emitter-test-signal",
+        "signal": signal,
+        "intensity": 0.5,
+        "range": { "start": 0.0, "length": 0.1 },
+        "sample_rate": sample_rate
+    });
+    GraphJsonBuilder::output_file(input, 22050, file_name).build()
+}
+
+/// 信号の設定が間違っていれば、パニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_test_signal_invalid_setting() {
+    let dir = std::env::temp_dir();
+    let cases = [
+        (serde_json::json!({ "type": "dc" }), 0, "`sample_rate` must be bigger than 0."),
+        (
+            serde_json::json!({ "type": "impulse", "delay": -0.1 }),
+            22050,
+            "`delay` must not be negative.",
+        ),
+        (
+            serde_json::json!({ "type": "pulse_train", "rate": 0.0 }),
+            22050,
+            "`rate` must be in (0, sample_rate].",
+        ),
+        (
+            serde_json::json!({ "type": "pulse_train", "rate": 44100.0 }),
+            22050,
+            "`rate` must be in (0, sample_rate].",
+        ),
+        (
+            serde_json::json!({ "type": "multi_tone", "tones": [] }),
+            22050,
+            "`tones` must not be empty.",
+        ),
+    ];
+
+    for (i, (signal, sample_rate, message)) in cases.into_iter().enumerate() {
+        let file_name = format!("soundprog_test_graph_test_signal_error_{}", i);
+        let output = crate::device::run_graph(&dir, &file_name, &create_graph_json(signal, sample_rate, &file_name));
+        let _ = fs::remove_file(dir.join(format!("{}.wav", file_name)));
+        assert_graph_error(&output, message);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
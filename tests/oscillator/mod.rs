@@ -0,0 +1,14 @@
+pub mod additive;
+pub mod antialias;
+pub mod fm;
+pub mod graph_deconvolve;
+pub mod graph_modulation;
+pub mod graph_noise;
+pub mod graph_pluck;
+pub mod graph_test_signal;
+pub mod noise;
+pub mod phase;
+pub mod pluck;
+pub mod sweep;
+pub mod test_signal;
+pub mod wavetable;
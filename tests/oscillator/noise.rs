@@ -0,0 +1,88 @@
+use soundprog::wave::sine::emitter::{ENoiseColor, ENoiseDistribution, SineUnitSampleEmitter};
+
+const SAMPLE_RATE: usize = 48000;
+
+const COLORS: [ENoiseColor; 6] = [
+    ENoiseColor::White,
+    ENoiseColor::Pink,
+    ENoiseColor::Brown,
+    ENoiseColor::Blue,
+    ENoiseColor::Violet,
+    ENoiseColor::Velvet,
+];
+
+fn noise(color: ENoiseColor, distribution: ENoiseDistribution, seed: u64, length: usize) -> Vec<f64> {
+    let mut emitter = SineUnitSampleEmitter::new_noise(color, 1.0, SAMPLE_RATE);
+    emitter.set_seed(seed).set_noise_distribution(distribution);
+    emitter.next_samples(length).iter().map(|v| v.to_f64()).collect()
+}
+
+/// 隣り合うサンプルの相関。高い周波数が強いほど小さくなる。
+fn lag1_correlation(samples: &[f64]) -> f64 {
+    let energy = samples.iter().map(|v| v * v).sum::<f64>();
+    samples.windows(2).map(|v| v[0] * v[1]).sum::<f64>() / energy
+}
+
+/// 同じシードなら同じノイズを出力して、シードが違えば違うノイズになる。
+#[test]
+fn test_seed_is_reproducible() {
+    for color in COLORS {
+        for distribution in [ENoiseDistribution::Uniform, ENoiseDistribution::Gaussian] {
+            let lhs = noise(color, distribution, 42, 4096);
+            let rhs = noise(color, distribution, 42, 4096);
+            assert_eq!(lhs, rhs, "{:?} {:?}", color, distribution);
+
+            let other = noise(color, distribution, 43, 4096);
+            assert_ne!(lhs, other, "{:?} {:?}", color, distribution);
+        }
+    }
+}
+
+/// 一様分布は`[-1, 1]`に広がって、正規分布は標準偏差が`1/3`になる。
+#[test]
+fn test_distribution() {
+    let uniform = noise(ENoiseColor::White, ENoiseDistribution::Uniform, 1, SAMPLE_RATE);
+    let gaussian = noise(ENoiseColor::White, ENoiseDistribution::Gaussian, 1, SAMPLE_RATE);
+
+    let rms = |samples: &[f64]| (samples.iter().map(|v| v * v).sum::<f64>() / samples.len() as f64).sqrt();
+    assert!((rms(&uniform) - (1.0 / 3f64.sqrt())).abs() < 0.01, "{}", rms(&uniform));
+    assert!((rms(&gaussian) - (1.0 / 3.0)).abs() < 0.01, "{}", rms(&gaussian));
+
+    // 正規分布は一様分布より0の近くに集まる。
+    let near_zero = |samples: &[f64]| samples.iter().filter(|v| v.abs() < 0.25).count();
+    assert!(near_zero(&gaussian) > near_zero(&uniform) * 2);
+}
+
+/// 低い周波数が強いノイズほど隣り合うサンプルの相関が大きい。
+#[test]
+fn test_color_slope() {
+    let correlations = [
+        ENoiseColor::Brown,
+        ENoiseColor::Pink,
+        ENoiseColor::White,
+        ENoiseColor::Blue,
+        ENoiseColor::Violet,
+    ]
+    .map(|color| lag1_correlation(&noise(color, ENoiseDistribution::Uniform, 7, SAMPLE_RATE)));
+
+    for pair in correlations.windows(2) {
+        assert!(pair[0] > pair[1], "{:?}", correlations);
+    }
+    assert!(correlations[2].abs() < 0.02, "{:?}", correlations);
+}
+
+/// ベルベットノイズは区間ごとに`±1`のインパルスを1つだけ置く。
+#[test]
+fn test_velvet_density() {
+    let density = 1000.0;
+    let mut emitter = SineUnitSampleEmitter::new_velvetnoise(density, 0.5, SAMPLE_RATE);
+    emitter.set_seed(3);
+    let samples = emitter.next_samples(SAMPLE_RATE);
+
+    let period = (SAMPLE_RATE as f64 / density) as usize;
+    for (frame_i, frame) in samples.chunks(period).enumerate() {
+        let impulses = frame.iter().filter(|v| v.to_f64() != 0.0).collect::<Vec<_>>();
+        assert_eq!(impulses.len(), 1, "{}", frame_i);
+        assert!((impulses[0].to_f64().abs() - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,78 @@
+use soundprog::wave::sine::emitter::SineUnitSampleEmitter;
+use soundprog::wave::PI2;
+
+const SAMPLE_RATE: usize = 48000;
+
+/// 隣り合うサンプルの差の最大値を返す。
+fn max_step(samples: &[f64]) -> f64 {
+    samples.windows(2).map(|v| (v[1] - v[0]).abs()).fold(0.0, f64::max)
+}
+
+/// 途中で周波数を変えても位相が飛ばないので、サンプルの差は高い方の周波数で進める分を超えない。
+#[test]
+fn test_frequency_change_is_phase_continuous() {
+    let mut emitter = SineUnitSampleEmitter::new_sine(440.0, 0.0, 1.0, SAMPLE_RATE);
+    let mut samples = vec![];
+    // 440Hzの周期の途中（1/3）で切り替える。
+    for _ in 0..(SAMPLE_RATE / 440 + SAMPLE_RATE / 1320) {
+        samples.push(emitter.next_sample().to_f64());
+    }
+    emitter.set_frequency(1000.0);
+    for _ in 0..1024 {
+        samples.push(emitter.next_sample().to_f64());
+    }
+
+    let max_allowed_step = PI2 * 1000.0 / SAMPLE_RATE as f64;
+    assert!(max_step(&samples) <= max_allowed_step + 1e-6, "{}", max_step(&samples));
+}
+
+/// サンプルごとに周波数を変えても、同じ周波数なら普通に生成したものと同じになる。
+#[test]
+fn test_next_sample_with_constant_frequency() {
+    let mut plain = SineUnitSampleEmitter::new_triangle(523.25, 0.0, 0.5, SAMPLE_RATE);
+    let mut modulated = SineUnitSampleEmitter::new_triangle(100.0, 0.0, 1.0, SAMPLE_RATE);
+    for _ in 0..4096 {
+        let expected = plain.next_sample().to_f64();
+        let actual = modulated.next_sample_with(523.25, 0.5).to_f64();
+        assert!((expected - actual).abs() < 1e-6);
+    }
+}
+
+/// グライドは指定した時間でピッチが比例して変わって、最後は目標の周波数になる。
+#[test]
+fn test_frequency_glide() {
+    let glide_sample_count = SAMPLE_RATE / 10;
+    let mut emitter = SineUnitSampleEmitter::new_sawtooth(220.0, 0.0, 1.0, SAMPLE_RATE);
+    emitter.set_glide_time(0.1).set_frequency(880.0);
+    assert_eq!(emitter.frequency(), Some(220.0));
+
+    emitter.next_samples(glide_sample_count / 2);
+    // 半分の時間では、ピッチも半分（1オクターブ）まで上がる。
+    assert!((emitter.frequency().unwrap() - 440.0).abs() < 1e-6);
+
+    emitter.next_samples(glide_sample_count - glide_sample_count / 2);
+    assert_eq!(emitter.frequency(), Some(880.0));
+}
+
+/// ノコギリ波と三角波も`phase`で周期の始まりがずれる。`π`なら半周期遅れたものと同じになる。
+#[test]
+fn test_sawtooth_and_triangle_phase() {
+    // 480Hzなら1周期がちょうど100サンプルになる。
+    let half_period = SAMPLE_RATE / 480 / 2;
+    let emitters: [fn(f64) -> SineUnitSampleEmitter; 2] = [
+        |phase| SineUnitSampleEmitter::new_sawtooth(480.0, phase, 1.0, SAMPLE_RATE),
+        |phase| SineUnitSampleEmitter::new_triangle(480.0, phase, 1.0, SAMPLE_RATE),
+    ];
+    for create_emitter in emitters {
+        let shifted = create_emitter(PI2 * 0.5).next_samples(1024);
+        let plain = create_emitter(0.0).next_samples(1024 + half_period);
+        for (actual, expected) in shifted.iter().zip(&plain[half_period..]) {
+            assert!((actual.to_f64() - expected.to_f64()).abs() < 1e-6);
+        }
+        assert!((shifted[0].to_f64() - plain[0].to_f64()).abs() > 0.5);
+    }
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,127 @@
+use soundprog::wave::sample::UniformedSample;
+use soundprog::wave::sine::pluck::{EPluckExcitation, PluckNote, PluckSequencePlayer, PluckSetting, PluckString};
+
+const SAMPLE_RATE: usize = 48000;
+const SEED: u64 = 7;
+
+fn setting(decay_time: Option<f64>) -> PluckSetting {
+    PluckSetting {
+        excitation: EPluckExcitation::White,
+        damping: 0.5,
+        decay_time,
+        seed: Some(SEED),
+    }
+}
+
+fn rms(samples: &[UniformedSample]) -> f64 {
+    (samples.iter().map(|v| v.to_f64().powi(2)).sum::<f64>() / samples.len() as f64).sqrt()
+}
+
+/// 自己相関の一番大きいところから周期を求めて、周波数を返す。
+fn estimate_frequency(samples: &[f64], min_lag: usize, max_lag: usize) -> f64 {
+    let correlation = |lag: usize| -> f64 { samples.iter().zip(&samples[lag..]).map(|(a, b)| a * b).sum() };
+    let best_lag = (min_lag..=max_lag)
+        .max_by(|&a, &b| correlation(a).total_cmp(&correlation(b)))
+        .unwrap();
+
+    // 前後の値から放物線で補間する。
+    let (prev, now, next) = (correlation(best_lag - 1), correlation(best_lag), correlation(best_lag + 1));
+    let offset = 0.5 * (prev - next) / (prev - (2.0 * now) + next);
+    SAMPLE_RATE as f64 / (best_lag as f64 + offset)
+}
+
+/// 周期がサンプル単位で割り切れなくても、オールパスフィルターで周波数が合う。
+#[test]
+fn test_pluck_tuning() {
+    for frequency in [220.0, 329.63, 1046.5] {
+        let mut string = PluckString::new(frequency, 1.0, &setting(Some(2.0)), SAMPLE_RATE);
+        let samples = string
+            .next_samples(SAMPLE_RATE / 2)
+            .iter()
+            .map(|v| v.to_f64())
+            .collect::<Vec<_>>();
+
+        let period = (SAMPLE_RATE as f64 / frequency) as usize;
+        let actual = estimate_frequency(&samples[(SAMPLE_RATE / 10)..], period - 10, period + 10);
+        let cent = 1200.0 * (actual / frequency).log2();
+        assert!(cent.abs() < 2.0, "{}: {} ({} cent)", frequency, actual, cent);
+    }
+}
+
+/// `decay_time`が経つと、基本周波数の振幅が-60dBになる。
+#[test]
+fn test_pluck_decay_time() {
+    let mut string = PluckString::new(220.0, 1.0, &setting(Some(0.5)), SAMPLE_RATE);
+    let samples = string.next_samples(SAMPLE_RATE);
+
+    // 高い倍音が先に消えて基本周波数だけが残るところで比べる。
+    let window = SAMPLE_RATE / 10;
+    let before = rms(&samples[(SAMPLE_RATE / 4)..(SAMPLE_RATE / 4 + window)]);
+    let after = rms(&samples[(SAMPLE_RATE * 3 / 4)..(SAMPLE_RATE * 3 / 4 + window)]);
+    let decibel = 20.0 * (after / before).log10();
+    assert!((decibel + 60.0).abs() < 3.0, "{}", decibel);
+}
+
+/// ノートオフしたら早く止まって、全部のノートが終わったらプレイヤーも終わる。
+#[test]
+fn test_pluck_player_release() {
+    let notes = vec![
+        PluckNote {
+            time: 0.1,
+            frequency: 440.0,
+            intensity: 1.0,
+            length: Some(0.1),
+        },
+        PluckNote {
+            time: 0.0,
+            frequency: 220.0,
+            intensity: 1.0,
+            length: Some(0.05),
+        },
+    ];
+    let mut player = PluckSequencePlayer::new(setting(Some(10.0)), 0.5, 4, notes, SAMPLE_RATE);
+
+    let samples = player.next_frames(SAMPLE_RATE / 10);
+    assert!(rms(&samples[..(SAMPLE_RATE / 50)]) > 0.01);
+    assert!(!player.is_finished());
+
+    // 最後のノートオフ（0.2秒）からしばらく経てば止まる。
+    let samples = player.next_frames(SAMPLE_RATE / 2);
+    assert!(player.is_finished());
+    assert!(rms(&samples[(SAMPLE_RATE * 2 / 5)..]) < 1e-4);
+}
+
+/// 同じシードなら毎回同じ音になって、同じ高さのノートでもノートごとに違うノイズで弾く。
+#[test]
+fn test_pluck_player_seed() {
+    let notes = [0.0, 0.1]
+        .into_iter()
+        .map(|time| PluckNote {
+            time,
+            frequency: 440.0,
+            intensity: 1.0,
+            length: None,
+        })
+        .collect::<Vec<_>>();
+    let render = || {
+        let mut player = PluckSequencePlayer::new(setting(Some(0.05)), 1.0, 1, notes.clone(), SAMPLE_RATE);
+        player
+            .next_frames(SAMPLE_RATE / 5)
+            .iter()
+            .map(|v| v.to_f64())
+            .collect::<Vec<_>>()
+    };
+
+    let samples = render();
+    assert_eq!(samples, render());
+
+    // ボイスは1つなので、2つ目のノートで1つ目は止まって最初から弾き直す。
+    let period = SAMPLE_RATE / 10;
+    let first = &samples[..64];
+    let second = &samples[period..(period + 64)];
+    assert!(first.iter().zip(second).any(|(lhs, rhs)| (lhs - rhs).abs() > 1e-3));
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,96 @@
+use soundprog::wave::sine::emitter::SineUnitSampleEmitter;
+use soundprog::wave::sine::sweep::{deconvolve, ESineSweepMode, SineSweepSetting};
+use soundprog::wave::PI2;
+
+const SAMPLE_RATE: usize = 8000;
+
+fn exponential_setting() -> SineSweepSetting {
+    SineSweepSetting {
+        from_frequency: 20.0,
+        to_frequency: 3000.0,
+        length: 1.0,
+        mode: ESineSweepMode::Exponential,
+        fade_in: 0.05,
+        fade_out: 0.05,
+    }
+}
+
+/// 指数スイープは最初と最後で指定した周波数になって、半分の時間で幾何平均の周波数になる。
+#[test]
+fn test_exponential_frequency() {
+    let setting = exponential_setting();
+    assert!((setting.frequency_of(0.0) - 20.0).abs() < 1e-9);
+    assert!((setting.frequency_of(1.0) - 3000.0).abs() < 1e-6);
+    assert!((setting.frequency_of(0.5) - (20.0f64 * 3000.0).sqrt()).abs() < 1e-6);
+}
+
+/// 線形スイープは今までの式と同じサンプルを出力する。
+#[test]
+fn test_linear_matches_previous_formula() {
+    let (from, to, length) = (100.0, 1000.0, 0.5);
+    let mut emitter = SineUnitSampleEmitter::new_sinesweep(from, to, length, 1.0, SAMPLE_RATE);
+    let samples = emitter.next_samples(SAMPLE_RATE / 2);
+
+    for (sample_i, sample) in samples.iter().enumerate() {
+        let time = sample_i as f64 / SAMPLE_RATE as f64;
+        let expected = (PI2 * ((from * time) + ((to - from) * 0.5 * time * time / length))).sin();
+        assert!((sample.to_f64() - expected).abs() < 1e-9, "{sample_i}");
+    }
+}
+
+/// フェードは端で0になって、フェードの外では1になる。
+#[test]
+fn test_fade() {
+    let setting = exponential_setting();
+    assert_eq!(setting.fade_of(0.0), 0.0);
+    assert_eq!(setting.fade_of(1.0), 0.0);
+    assert_eq!(setting.fade_of(0.5), 1.0);
+    assert!((setting.fade_of(0.025) - 0.5).abs() < 1e-9);
+
+    let samples = setting.samples(SAMPLE_RATE);
+    assert_eq!(samples.len(), SAMPLE_RATE);
+    assert_eq!(samples[0], 0.0);
+}
+
+/// スイープをそのまま逆畳み込みすると、先頭に振幅1のインパルスが立つ。
+#[test]
+fn test_deconvolve_sweep_itself() {
+    for mode in [ESineSweepMode::Linear, ESineSweepMode::Exponential] {
+        let setting = SineSweepSetting {
+            mode,
+            ..exponential_setting()
+        };
+        let sweep = setting.samples(SAMPLE_RATE);
+        let inverse = setting.inverse_filter(SAMPLE_RATE);
+
+        let response = deconvolve(&sweep, &inverse, 400);
+        assert_eq!(response.len(), 400);
+        assert!((response[0] - 1.0).abs() < 1e-9, "{mode:?}: {}", response[0]);
+        // インパルス以外はずっと小さい。
+        let rest_peak = response[10..].iter().fold(0.0f64, |acc, v| acc.max(v.abs()));
+        assert!(rest_peak < 0.1, "{mode:?}: {rest_peak}");
+    }
+}
+
+/// 遅れて小さくなったスイープを逆畳み込みすると、遅れた位置に同じ大きさのインパルスが立つ。
+#[test]
+fn test_deconvolve_delayed_sweep() {
+    const DELAY: usize = 123;
+    let setting = exponential_setting();
+    let sweep = setting.samples(SAMPLE_RATE);
+    let inverse = setting.inverse_filter(SAMPLE_RATE);
+
+    let recorded: Vec<f64> = std::iter::repeat(0.0)
+        .take(DELAY)
+        .chain(sweep.iter().map(|v| v * 0.5))
+        .collect();
+    let response = deconvolve(&recorded, &inverse, 400);
+
+    let (peak_i, peak) = response
+        .iter()
+        .enumerate()
+        .max_by(|lhs, rhs| lhs.1.abs().total_cmp(&rhs.1.abs()))
+        .unwrap();
+    assert_eq!(peak_i, DELAY);
+    assert!((peak - 0.5).abs() < 1e-9, "{peak}");
+}
@@ -0,0 +1,68 @@
+use soundprog::wave::sine::test_signal::{ETestSignal, TestSignalEmitter, TestTone};
+use soundprog::wave::PI2;
+
+const SAMPLE_RATE: usize = 8000;
+
+fn samples(signal: ETestSignal, intensity: f64, length: usize) -> Vec<f64> {
+    let mut emitter = TestSignalEmitter::new(signal, intensity, SAMPLE_RATE);
+    emitter.next_samples(length).iter().map(|v| v.to_f64()).collect()
+}
+
+/// インパルスは`delay`の位置の1サンプルだけ`intensity`になる。
+#[test]
+fn test_impulse() {
+    let buffer = samples(ETestSignal::Impulse { delay: 0.01 }, 0.8, 200);
+    for (sample_i, sample) in buffer.iter().enumerate() {
+        let expected = if sample_i == 80 { 0.8 } else { 0.0 };
+        assert_eq!(*sample, expected, "{sample_i}");
+    }
+}
+
+/// パルス列は最初のサンプルから周期ごとにインパルスを出して、半端な周期でも平均が`rate`になる。
+#[test]
+fn test_pulse_train() {
+    let buffer = samples(ETestSignal::PulseTrain { rate: 100.0 }, 1.0, 400);
+    let pulse_indices: Vec<usize> = (0..buffer.len()).filter(|&i| buffer[i] != 0.0).collect();
+    assert_eq!(pulse_indices, vec![0, 80, 160, 240, 320]);
+
+    // 8000 / 3000 = 2.666...サンプルの周期
+    let buffer = samples(ETestSignal::PulseTrain { rate: 3000.0 }, 1.0, SAMPLE_RATE);
+    let pulse_indices: Vec<usize> = (0..buffer.len()).filter(|&i| buffer[i] != 0.0).collect();
+    assert_eq!(pulse_indices.len(), 3000);
+    assert!(pulse_indices.windows(2).all(|v| v[1] - v[0] == 2 || v[1] - v[0] == 3));
+}
+
+/// DCは`intensity`を、無音は0をずっと出す。
+#[test]
+fn test_dc_and_silence() {
+    assert!(samples(ETestSignal::Dc, -0.25, 100).iter().all(|v| *v == -0.25));
+    assert!(samples(ETestSignal::Silence, 1.0, 100).iter().all(|v| *v == 0.0));
+}
+
+/// マルチトーンはそれぞれの周波数で指定した振幅の成分を持つ。
+#[test]
+fn test_multi_tone() {
+    let tones = vec![
+        TestTone {
+            frequency: 100.0,
+            amplitude: 0.5,
+        },
+        TestTone {
+            frequency: 1000.0,
+            amplitude: 0.25,
+        },
+    ];
+    let buffer = samples(ETestSignal::MultiTone { tones }, 1.0, SAMPLE_RATE);
+
+    // 1秒分なので、整数の周波数のDFTのビンにそのまま乗る。
+    let amplitude_of = |frequency: f64| {
+        let (real, imag) = buffer.iter().enumerate().fold((0.0, 0.0), |(real, imag), (i, v)| {
+            let phase = PI2 * frequency * (i as f64) / (SAMPLE_RATE as f64);
+            (real + v * phase.cos(), imag + v * phase.sin())
+        });
+        2.0 * (real * real + imag * imag).sqrt() / buffer.len() as f64
+    };
+    assert!((amplitude_of(100.0) - 0.5).abs() < 1e-9);
+    assert!((amplitude_of(1000.0) - 0.25).abs() < 1e-9);
+    assert!(amplitude_of(500.0) < 1e-9);
+}
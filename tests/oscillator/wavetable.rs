@@ -0,0 +1,100 @@
+use crate::device::assert_graph_error;
+use soundprog::wave::sample::UniformedSample;
+use soundprog::wave::sine::wavetable::{Wavetable, WavetableUnitSampleEmitter};
+use soundprog::wave::PI2;
+
+const SAMPLE_RATE: usize = 48000;
+const FRAME_SIZE: usize = 256;
+
+/// `harmonics`次までの倍音を`1/k`で重ねた1周期の波形を作る。
+fn harmonic_frame(harmonics: usize, amplitude: f64) -> Vec<UniformedSample> {
+    (0..FRAME_SIZE)
+        .map(|i| {
+            let cycle = i as f64 / FRAME_SIZE as f64;
+            let value: f64 = (1..=harmonics).map(|k| (PI2 * cycle * k as f64).sin() / k as f64).sum();
+            UniformedSample::from_f64(value * amplitude)
+        })
+        .collect()
+}
+
+/// 帯域制限しないmipmapは元のフレームと同じになる。
+#[test]
+fn test_first_mipmap_keeps_frame() {
+    let frame = harmonic_frame(16, 0.5);
+    let wavetable = Wavetable::from_samples(&frame, FRAME_SIZE).unwrap();
+    assert_eq!(wavetable.frame_count(), 1);
+    assert_eq!(wavetable.mipmap_count(), 7);
+
+    for (i, expected) in frame.iter().enumerate() {
+        let actual = wavetable.sample(0.0, i as f64 / FRAME_SIZE as f64, 0);
+        assert!((expected.to_f64() - actual).abs() < 1e-6, "{}: {:?} {}", i, expected, actual);
+    }
+}
+
+/// 高い周波数では倍音が少ないmipmapを選んで、ナイキスト周波数を超える倍音を取り除く。
+#[test]
+fn test_mipmap_removes_high_harmonics() {
+    let frame = harmonic_frame(64, 0.25);
+    let wavetable = Wavetable::from_samples(&frame, FRAME_SIZE).unwrap();
+
+    // 3000Hzなら8次（24000Hz）まで鳴らせるので、8次以下の倍音しか持たないmipmapになる。
+    let level = wavetable.mipmap_level(3000.0, SAMPLE_RATE);
+    assert!(wavetable.max_harmonic(level) <= 8);
+    assert!(wavetable.max_harmonic(level) * 2 > 8);
+    assert_eq!(wavetable.mipmap_level(10.0, SAMPLE_RATE), 0);
+
+    let expected = harmonic_frame(wavetable.max_harmonic(level), 0.25);
+    for (i, expected) in expected.iter().enumerate() {
+        let actual = wavetable.sample(0.0, i as f64 / FRAME_SIZE as f64, level);
+        assert!((expected.to_f64() - actual).abs() < 1e-6, "{}: {:?} {}", i, expected, actual);
+    }
+}
+
+/// `morph`でフレームの間を線形に補間する。
+#[test]
+fn test_morph_between_frames() {
+    let mut frames = harmonic_frame(1, 0.8);
+    frames.extend(harmonic_frame(1, 0.4));
+    let wavetable = Wavetable::from_samples(&frames, FRAME_SIZE).unwrap();
+    assert_eq!(wavetable.frame_count(), 2);
+
+    let quarter = 0.25;
+    assert!((wavetable.sample(0.0, quarter, 0) - 0.8).abs() < 1e-6);
+    assert!((wavetable.sample(1.0, quarter, 0) - 0.4).abs() < 1e-6);
+    assert!((wavetable.sample(0.5, quarter, 0) - 0.6).abs() < 1e-6);
+
+    // エミッタでも同じ振幅になる。
+    let mut emitter = WavetableUnitSampleEmitter::new(wavetable, 1000.0, 1.0, 0.5, SAMPLE_RATE);
+    let max = emitter
+        .next_samples(SAMPLE_RATE / 100)
+        .iter()
+        .map(|v| v.to_f64())
+        .fold(0.0, f64::max);
+    assert!((max - 0.6).abs() < 1e-3, "{}", max);
+}
+
+#[test]
+fn test_invalid_frame_size() {
+    let frame = harmonic_frame(1, 0.5);
+    assert!(Wavetable::from_samples(&frame, 100).is_err());
+    assert!(Wavetable::from_samples(&frame[..128], FRAME_SIZE).is_err());
+}
+
+/// `sample_rate`が0の`emitter-wavetable`は、パニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_wavetable_zero_sample_rate() {
+    let dir = std::env::temp_dir();
+    let file_name = "soundprog_test_graph_wavetable_zero_sample_rate";
+    let mut json: serde_json::Value =
+        serde_json::from_str(include_str!("../../example/wavetable.json")).expect("Example must be valid");
+    json["node"]["input"]["sample_rate"] = serde_json::json!(0);
+    json["node"]["output"]["file_name"] = serde_json::json!(file_name);
+    json["node"]["output"]["add_date_time"] = serde_json::json!(false);
+    let output = crate::device::run_graph(&dir, file_name, &json);
+    let _ = std::fs::remove_file(dir.join(format!("{}.wav", file_name)));
+    assert_graph_error(&output, "`sample_rate` must be bigger than 0.");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
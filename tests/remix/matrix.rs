@@ -0,0 +1,97 @@
+use soundprog::device::remix::{ChannelRemixMatrix, ChannelRemixSetting, EChannelLayout};
+
+const MINUS_3DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+fn apply(matrix: &ChannelRemixMatrix, input: &[f32]) -> Vec<f32> {
+    let mut output = vec![0.0; matrix.output_channels()];
+    matrix.apply(input, &mut output);
+    output
+}
+
+fn assert_near(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+    }
+}
+
+/// モノラルはセンターがあればセンターだけに、なければ前の左右に流す。
+#[test]
+fn test_upmix_mono() {
+    let to_stereo = ChannelRemixMatrix::from_layouts(EChannelLayout::Mono, EChannelLayout::Stereo);
+    assert_near(&apply(&to_stereo, &[0.5]), &[0.5, 0.5]);
+
+    let to_quad = ChannelRemixMatrix::from_layouts(EChannelLayout::Mono, EChannelLayout::Quad);
+    assert_near(&apply(&to_quad, &[0.5]), &[0.5, 0.5, 0.0, 0.0]);
+
+    let to_51 = ChannelRemixMatrix::from_layouts(EChannelLayout::Mono, EChannelLayout::Surround51);
+    assert_near(&apply(&to_51, &[0.5]), &[0.0, 0.0, 0.5, 0.0, 0.0, 0.0]);
+}
+
+/// ステレオのアップミックスは前の左右だけに流して、LFEとサラウンドは無音にする。
+#[test]
+fn test_upmix_stereo() {
+    let to_71 = ChannelRemixMatrix::from_layouts(EChannelLayout::Stereo, EChannelLayout::Surround71);
+    assert_near(&apply(&to_71, &[0.25, -0.5]), &[0.25, -0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+}
+
+/// ITU-R BS.775のダウンミックス。LFEは捨てる。
+#[test]
+fn test_downmix_itu() {
+    // FL, FR, FC, LFE, SL, SR
+    let input = [0.1, 0.2, 0.3, 1.0, 0.4, 0.5];
+
+    let to_stereo = ChannelRemixMatrix::from_layouts(EChannelLayout::Surround51, EChannelLayout::Stereo);
+    let left = 0.1 + (MINUS_3DB * 0.3) + (MINUS_3DB * 0.4);
+    let right = 0.2 + (MINUS_3DB * 0.3) + (MINUS_3DB * 0.5);
+    assert_near(&apply(&to_stereo, &input), &[left, right]);
+
+    let to_mono = ChannelRemixMatrix::from_layouts(EChannelLayout::Surround51, EChannelLayout::Mono);
+    assert_near(&apply(&to_mono, &input), &[(left + right) * 0.5]);
+
+    let to_quad = ChannelRemixMatrix::from_layouts(EChannelLayout::Surround51, EChannelLayout::Quad);
+    let left = 0.1 + (MINUS_3DB * 0.3);
+    let right = 0.2 + (MINUS_3DB * 0.3);
+    assert_near(&apply(&to_quad, &input), &[left, right, 0.4, 0.5]);
+}
+
+/// 7.1から5.1に後ろと横のサラウンドをまとめても、フルスケールの入力で1を超えるチャンネルはない。
+#[test]
+fn test_downmix_surround_fold_unity() {
+    // FL, FR, FC, LFE, BL, BR, SL, SR
+    let to_51 = ChannelRemixMatrix::from_layouts(EChannelLayout::Surround71, EChannelLayout::Surround51);
+    for sign in [1.0, -1.0] {
+        let result = apply(&to_51, &[sign; 8]);
+        assert!(result.iter().all(|v| v.abs() <= 1.0 + 1e-6), "{:?}", result);
+    }
+
+    let input = [0.1, 0.2, 0.3, 1.0, 0.4, 0.5, 0.6, 0.7];
+    assert_near(&apply(&to_51, &input), &[0.1, 0.2, 0.3, 1.0, 0.5, 0.6]);
+
+    let to_quad = ChannelRemixMatrix::from_layouts(EChannelLayout::Surround71, EChannelLayout::Quad);
+    let left = 0.1 + (MINUS_3DB * 0.3);
+    let right = 0.2 + (MINUS_3DB * 0.3);
+    assert_near(&apply(&to_quad, &input), &[left, right, 0.5, 0.6]);
+}
+
+/// `routing`を指定した場合はそのまま流して、指定してないチャンネルは無音にする。
+#[test]
+fn test_routing() {
+    let setting = ChannelRemixSetting {
+        layout: None,
+        routing: Some(vec![Some(1), None, Some(0), Some(5)]),
+    };
+    let matrix = ChannelRemixMatrix::new(&setting, EChannelLayout::Stereo, 6);
+    assert_near(&apply(&matrix, &[0.25, -0.5]), &[-0.5, 0.0, 0.25, 0.0, 0.0, 0.0]);
+}
+
+/// 配置が分からないチャンネル数なら最初の2チャンネルだけをステレオとして使う。
+#[test]
+fn test_unknown_channels() {
+    let matrix = ChannelRemixMatrix::new(&ChannelRemixSetting::default(), EChannelLayout::Mono, 3);
+    assert_near(&apply(&matrix, &[0.5]), &[0.5, 0.5, 0.0]);
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,71 @@
+use soundprog::resample::stream::ResampleStream;
+use soundprog::resample::ResampleHeaderSetting;
+use soundprog::wave::sample::UniformedSample;
+use std::f64::consts::PI;
+
+fn create_sine(frequency: f64, sample_rate: usize, length: usize) -> Vec<UniformedSample> {
+    (0..length)
+        .map(|i| UniformedSample::from_f64(0.5 * (2.0 * PI * frequency * (i as f64) / (sample_rate as f64)).sin()))
+        .collect()
+}
+
+fn process_chunks(stream: &mut ResampleStream, samples: &[UniformedSample], chunk_size: usize) -> Vec<UniformedSample> {
+    let mut results = vec![];
+    for chunk in samples.chunks(chunk_size) {
+        results.append(&mut stream.process(chunk));
+    }
+    results.append(&mut stream.flush());
+    results
+}
+
+fn rms(samples: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = samples.fold((0.0, 0usize), |(sum, count), v| (sum + (v * v), count + 1));
+    (sum / count as f64).sqrt()
+}
+
+/// 細かく分けて入れても、続けて入れた結果とほぼ同じになる。
+#[test]
+fn test_stream_chunked() {
+    let setting = ResampleHeaderSetting {
+        from_fs: 44100,
+        to_fs: 48000,
+        is_high_quality: true,
+    };
+    let samples = create_sine(440.0, 44100, 44100);
+
+    let whole = process_chunks(&mut ResampleStream::new(&setting), &samples, samples.len());
+    let chunked = process_chunks(&mut ResampleStream::new(&setting), &samples, 441);
+    assert_eq!(whole.len(), chunked.len());
+
+    // 区切りの位置によってPhaseの誤差が少し変わるので、全体の差だけを見る。
+    let diff = rms(whole.iter().zip(&chunked).map(|(a, b)| a.to_f64() - b.to_f64()));
+    assert!(diff < 1e-3, "{}", diff);
+}
+
+/// 1秒の入力は変換後のサンプルレートでも1秒になって、同じ周波数と大きさのサイン波になる。
+#[test]
+fn test_stream_sine() {
+    for (from_fs, to_fs) in [(44100, 48000), (48000, 44100)] {
+        let setting = ResampleHeaderSetting {
+            from_fs,
+            to_fs,
+            is_high_quality: true,
+        };
+        let samples = create_sine(1000.0, from_fs, from_fs);
+        let results = process_chunks(&mut ResampleStream::new(&setting), &samples, 480);
+        assert!(results.len().abs_diff(to_fs) <= 2, "{} != {}", results.len(), to_fs);
+
+        // フィルターの影響がある最初と最後を除いて比べる。
+        let middle = &results[1000..(to_fs - 1000)];
+        let crossings = middle
+            .windows(2)
+            .filter(|v| (v[0].to_f64() < 0.0) != (v[1].to_f64() < 0.0))
+            .count();
+        let expected_crossings = 2.0 * 1000.0 * (middle.len() as f64) / (to_fs as f64);
+        assert!((crossings as f64 - expected_crossings).abs() <= 2.0, "{}", crossings);
+
+        // ダウンサンプリングでは少し小さくなるので、大きくずれてないかだけを見る。
+        let amplitude = rms(middle.iter().map(|v| v.to_f64())) / (0.5 * std::f64::consts::FRAC_1_SQRT_2);
+        assert!((amplitude - 1.0).abs() < 0.1, "{}", amplitude);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::device::{assert_graph_error, GraphJsonBuilder};
+use soundprog::wave::container::stream::{EStreamWriter, WaveStreamWriter};
+use soundprog::wave::container::WaveContainer;
+use soundprog::wave::sample::UniformedSample;
+use std::{fs, io};
+
+const SAMPLE_RATE: usize = 44100;
+
+/// `input_path`のWAVファイルを`emitter-wav-mono`で読み込んで、`output-file`に書き込むグラフを作る。
+fn create_graph_json(input_path: &str, file_name: &str, sample_rate: usize) -> serde_json::Value {
+    let input = serde_json::json!({ "type": "emitter-wav-mono", "path": input_path });
+    GraphJsonBuilder::output_file(input, sample_rate, file_name).build()
+}
+
+/// ヘッダーだけの空のWAVファイルを`path`に書き込む。
+fn write_empty_wav(path: &std::path::Path) {
+    let writer = EStreamWriter::Wav(WaveStreamWriter::new(SAMPLE_RATE as u32, 16, 1).unwrap());
+    let mut cursor = io::Cursor::new(vec![]);
+    writer.write_header(&mut cursor);
+    fs::write(path, cursor.into_inner()).expect("Failed to write empty wav file");
+}
+
+/// 入力が一度も来ないまま終わっても、`data`チャンクが空のファイルを書き込む。
+#[test]
+fn test_graph_output_file_without_input() {
+    let dir = std::env::temp_dir();
+    let input_name = "soundprog_test_graph_output_empty_input.wav";
+    let file_name = "soundprog_test_graph_output_empty";
+    write_empty_wav(&dir.join(input_name));
+
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(input_name, file_name, SAMPLE_RATE));
+    fs::remove_file(dir.join(input_name)).expect("Failed to remove input file");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let wav_path = dir.join(format!("{}.wav", file_name));
+    let bytes = fs::read(&wav_path).expect("Output file must be written");
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+
+    assert_eq!(bytes.len(), 44);
+    assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 0);
+    let container = WaveContainer::from_bufread(&mut io::Cursor::new(bytes)).expect("Could not read container.");
+    assert_eq!(container.samples_per_second(), SAMPLE_RATE as u32);
+    assert!(container.uniformed_sample_buffer().is_empty());
+}
+
+/// `sample_rate`が0のフォーマットはパニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_output_file_zero_sample_rate() {
+    let dir = std::env::temp_dir();
+    let input_name = "soundprog_test_graph_output_zero_rate_input.wav";
+    let file_name = "soundprog_test_graph_output_zero_rate";
+    write_empty_wav(&dir.join(input_name));
+
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(input_name, file_name, 0));
+    fs::remove_file(dir.join(input_name)).expect("Failed to remove input file");
+    assert_graph_error(&output, "`sample_rate` of `format` must be bigger than 0.");
+    assert!(!dir.join(format!("{}.wav", file_name)).exists());
+}
+
+/// 見つからないファイルはパニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_wav_mono_missing_file() {
+    let dir = std::env::temp_dir();
+    let input_name = "soundprog_test_graph_missing_input.wav";
+    let file_name = "soundprog_test_graph_missing";
+
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(input_name, file_name, SAMPLE_RATE));
+    assert_graph_error(&output, "Could not open sound file");
+    assert!(!dir.join(format!("{}.wav", file_name)).exists());
+}
+
+/// 8ビットのWAVファイルも、パニックせずに最後まで読み込んで書き込む。
+#[test]
+fn test_graph_wav_mono_8bits() {
+    let dir = std::env::temp_dir();
+    let input_name = "soundprog_test_graph_8bits_input.wav";
+    let file_name = "soundprog_test_graph_8bits";
+    {
+        let mut writer = EStreamWriter::Wav(WaveStreamWriter::new(SAMPLE_RATE as u32, 8, 1).unwrap());
+        let mut cursor = io::Cursor::new(vec![]);
+        writer.write_header(&mut cursor);
+        writer.write_frames(&mut cursor, &vec![UniformedSample::from_f64(0.5); SAMPLE_RATE / 10]);
+        writer.finish(&mut cursor);
+        fs::write(dir.join(input_name), cursor.into_inner()).expect("Failed to write input file");
+    }
+
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(input_name, file_name, SAMPLE_RATE));
+    fs::remove_file(dir.join(input_name)).expect("Failed to remove input file");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let wav_path = dir.join(format!("{}.wav", file_name));
+    let bytes = fs::read(&wav_path).expect("Output file must be written");
+    fs::remove_file(&wav_path).expect("Failed to remove written file");
+    let container = WaveContainer::from_bufread(&mut io::Cursor::new(bytes)).expect("Could not read container.");
+    let samples = container.uniformed_sample_buffer();
+    assert_eq!(samples.len(), SAMPLE_RATE / 10);
+    assert!(samples.iter().all(|v| (v.to_f64() - 0.5).abs() < 0.02));
+}
+
+/// 読み込めない量子化ビットのWAVファイルは、パニックせずにノードを作る時のエラーになる。
+#[test]
+fn test_graph_wav_mono_unsupported_bits() {
+    let dir = std::env::temp_dir();
+    let input_name = "soundprog_test_graph_12bits_input.wav";
+    let file_name = "soundprog_test_graph_12bits";
+    {
+        let mut writer = EStreamWriter::Wav(WaveStreamWriter::new(SAMPLE_RATE as u32, 16, 1).unwrap());
+        let mut cursor = io::Cursor::new(vec![]);
+        writer.write_header(&mut cursor);
+        let mut bytes = cursor.into_inner();
+        // `fmt`チャンクの量子化ビットを12に書き換える。
+        bytes[34..36].copy_from_slice(&12u16.to_le_bytes());
+        fs::write(dir.join(input_name), bytes).expect("Failed to write input file");
+    }
+
+    let output = crate::device::run_graph(&dir, file_name, &create_graph_json(input_name, file_name, SAMPLE_RATE));
+    fs::remove_file(dir.join(input_name)).expect("Failed to remove input file");
+    assert_graph_error(&output, "is not a supported WAV, AIFF or FLAC file.");
+}
+
+// ----------------------------------------------------------------------------
+// EOF
+// ----------------------------------------------------------------------------
@@ -0,0 +1,209 @@
+use std::{fs, io};
+
+use soundprog::wave::{
+    container::{
+        stream::{EStreamReader, WaveStreamHeader},
+        WaveBuilder, WaveContainer,
+    },
+    sample::UniformedSample,
+};
+
+const ASSET_PATHS: [&'static str; 3] = ["assets/ex1/a.wav", "assets/ex6/drum.wav", "assets/ex7/vocal.wav"];
+
+fn read_wave_container(path: &str) -> WaveContainer {
+    let source_file = fs::File::open(path).expect(&format!("Could not find {}.", path));
+    let mut reader = io::BufReader::new(source_file);
+
+    WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.")
+}
+
+fn open_reader(path: &str) -> io::BufReader<fs::File> {
+    io::BufReader::new(fs::File::open(path).expect(&format!("Could not find {}.", path)))
+}
+
+/// 途中のフレームから読み込んでも、全部読み込んだ時と同じサンプルになる。
+#[test]
+fn test_read_frames_matches_container() {
+    for path in ASSET_PATHS {
+        let container = read_wave_container(path);
+        let expected = container.uniformed_sample_buffer();
+
+        let mut reader = open_reader(path);
+        let header = WaveStreamHeader::from_bufread(&mut reader).expect("Could not read header.");
+        assert_eq!(header.frame_count(), expected.len(), "{}", path);
+
+        for start_frame in [0, 1, 777, expected.len() / 2] {
+            let frames = header.read_frames(&mut reader, start_frame, 1024);
+            let end = (start_frame + 1024).min(expected.len());
+            assert_eq!(frames, &expected[start_frame..end], "{}: {}", path, start_frame);
+        }
+
+        // ファイルの最後を超える分は読み込まない。
+        let frames = header.read_frames(&mut reader, expected.len() - 10, 1024);
+        assert_eq!(frames.len(), 10, "{}", path);
+        assert!(header.read_frames(&mut reader, expected.len(), 1024).is_empty());
+    }
+}
+
+/// `start_time`の分を読み飛ばしてから少しずつ読み込むと、開始位置から最後までのサンプルになる。
+#[test]
+fn test_stream_reader_start_time() {
+    for path in ASSET_PATHS {
+        let container = read_wave_container(path);
+        let expected = container.uniformed_sample_buffer();
+
+        let mut reader = open_reader(path);
+        let mut stream = EStreamReader::from_bufread(&mut reader).expect("Could not read header.");
+        let start_time = 0.25;
+        let start_frame = stream.frame_index_of_time(start_time);
+        assert_eq!(start_frame, (container.samples_per_second() as f64 * start_time) as usize);
+        stream.skip_frames(&mut reader, start_frame);
+
+        let mut streamed = vec![];
+        loop {
+            let frames = stream.read_frames(&mut reader, 1000);
+            if frames.is_empty() {
+                break;
+            }
+            streamed.extend(frames);
+        }
+        assert_eq!(streamed, &expected[start_frame..], "{}", path);
+    }
+}
+
+/// ステレオはチャンネルがインターリーブされたまま、フレーム単位で読み込む。
+#[test]
+fn test_stream_reader_stereo() {
+    let left = (0..500).map(|i| UniformedSample::from_16bits(i as i16)).collect::<Vec<_>>();
+    let right = (0..500).map(|i| UniformedSample::from_16bits(-(i as i16))).collect::<Vec<_>>();
+    let container = WaveBuilder {
+        samples_per_sec: 1000,
+        bits_per_sample: 16,
+    }
+    .build_stereo(left, right)
+    .unwrap();
+
+    let mut writer = io::Cursor::new(vec![]);
+    container.write(&mut writer);
+    let mut reader = io::Cursor::new(writer.into_inner());
+
+    let mut stream = EStreamReader::from_bufread(&mut reader).expect("Could not read header.");
+    assert_eq!(stream.channel(), 2);
+    stream.skip_frames(&mut reader, stream.frame_index_of_time(0.1));
+
+    let frames = stream.read_frames(&mut reader, 3);
+    let expected = [100, -100, 101, -101, 102, -102].map(UniformedSample::from_16bits);
+    assert_eq!(frames, expected);
+}
+
+/// `data`チャンクのサイズよりファイルが短ければ、ファイルにある分だけを読み込む。
+#[test]
+fn test_stream_reader_truncated_file() {
+    let left = (0..500).map(|i| UniformedSample::from_16bits(i as i16)).collect::<Vec<_>>();
+    let right = (0..500).map(|i| UniformedSample::from_16bits(-(i as i16))).collect::<Vec<_>>();
+    let container = WaveBuilder {
+        samples_per_sec: 1000,
+        bits_per_sample: 16,
+    }
+    .build_stereo(left, right)
+    .unwrap();
+
+    // 録音の途中で切れたように、ヘッダーはそのままで100フレームの後ろを切り捨てる。
+    let mut writer = io::Cursor::new(vec![]);
+    container.write(&mut writer);
+    let mut bytes = writer.into_inner();
+    bytes.truncate(44 + (100 * 4));
+    let mut reader = io::Cursor::new(bytes);
+
+    let header = WaveStreamHeader::from_bufread(&mut reader).expect("Could not read header.");
+    assert_eq!(header.frame_count(), 100);
+
+    let mut stream = EStreamReader::from_bufread(&mut reader).expect("Could not read header.");
+    let frames = stream.read_frames(&mut reader, 1000);
+    assert_eq!(frames.len(), 200);
+    assert_eq!(frames[198..], [99, -99].map(UniformedSample::from_16bits));
+    assert!(stream.read_frames(&mut reader, 1000).is_empty());
+}
+
+/// `format_tag`と`bits_per_sample`のモノラルのWAVファイルを作る。`chunks`は`fmt`と`data`の間に入れる。
+fn wav_bytes(format_tag: u16, bits_per_sample: u16, chunks: &[u8], data: &[u8]) -> Vec<u8> {
+    let block_size = bits_per_sample / 8;
+    let mut fmt = b"fmt ".to_vec();
+    fmt.extend(16u32.to_le_bytes());
+    fmt.extend(format_tag.to_le_bytes());
+    fmt.extend(1u16.to_le_bytes());
+    fmt.extend(1000u32.to_le_bytes());
+    fmt.extend((1000 * block_size as u32).to_le_bytes());
+    fmt.extend(block_size.to_le_bytes());
+    fmt.extend(bits_per_sample.to_le_bytes());
+
+    let mut body = b"WAVE".to_vec();
+    body.extend(fmt);
+    body.extend(chunks);
+    body.extend(b"data");
+    body.extend((data.len() as u32).to_le_bytes());
+    body.extend(data);
+
+    let mut result = b"RIFF".to_vec();
+    result.extend((body.len() as u32).to_le_bytes());
+    result.extend(body);
+    result
+}
+
+/// 8ビットと32ビットのLPCMも読み込める。
+#[test]
+fn test_stream_reader_8bits_and_32bits() {
+    let mut reader = io::Cursor::new(wav_bytes(1, 8, &[], &[128, 255, 0, 192]));
+    let mut stream = EStreamReader::from_bufread(&mut reader).expect("Could not read header.");
+    let frames = stream.read_frames(&mut reader, 10).iter().map(|v| v.to_f64()).collect::<Vec<_>>();
+    assert_eq!(frames, [0.0, 127.0 / 128.0, -1.0, 0.5]);
+
+    let data = [0i32, i32::MAX, i32::MIN, 1 << 30].iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>();
+    let mut reader = io::Cursor::new(wav_bytes(1, 32, &[], &data));
+    let mut stream = EStreamReader::from_bufread(&mut reader).expect("Could not read header.");
+    let frames = stream.read_frames(&mut reader, 10).iter().map(|v| v.to_f64()).collect::<Vec<_>>();
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0], 0.0);
+    assert!((frames[1] - 1.0).abs() < 1e-6, "{}", frames[1]);
+    assert_eq!(frames[2], -1.0);
+    assert_eq!(frames[3], 0.5);
+}
+
+/// `LIST`のような知らないチャンクは、奇数サイズのパディングも含めて読み飛ばす。
+#[test]
+fn test_stream_reader_skips_unknown_chunks() {
+    let mut chunks = b"LIST".to_vec();
+    chunks.extend(3u32.to_le_bytes());
+    chunks.extend([b'a', b'b', b'c', 0]);
+    chunks.extend(b"bext");
+    chunks.extend(0u32.to_le_bytes());
+    let data = [100i16, -100].iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>();
+    let bytes = wav_bytes(1, 16, &chunks, &data);
+
+    let mut reader = io::Cursor::new(bytes.clone());
+    let mut stream = EStreamReader::from_bufread(&mut reader).expect("Could not read header.");
+    assert_eq!(stream.read_frames(&mut reader, 10), [100, -100].map(UniformedSample::from_16bits));
+
+    let mut reader = io::Cursor::new(bytes);
+    let container = WaveContainer::from_bufread(&mut reader).expect("Could not create WaveContainer.");
+    assert_eq!(container.uniformed_sample_buffer(), [100, -100].map(UniformedSample::from_16bits));
+}
+
+/// 変換できないフォーマットや量子化ビットは、パニックせずに読み込まない。
+#[test]
+fn test_stream_reader_unsupported_format() {
+    // 32ビットのIEEE float
+    let mut reader = io::Cursor::new(wav_bytes(3, 32, &[], &[0u8; 8]));
+    assert!(EStreamReader::from_bufread(&mut reader).is_none());
+    // 12ビットのLPCM
+    let mut reader = io::Cursor::new(wav_bytes(1, 12, &[], &[0u8; 8]));
+    assert!(EStreamReader::from_bufread(&mut reader).is_none());
+    // μ-law
+    let mut reader = io::Cursor::new(wav_bytes(7, 8, &[], &[0u8; 8]));
+    assert!(WaveContainer::from_bufread(&mut reader).is_none());
+    // `data`チャンクがない。
+    let mut bytes = wav_bytes(1, 16, &[], &[0u8; 8]);
+    bytes[36..40].copy_from_slice(b"abcd");
+    let mut reader = io::Cursor::new(bytes);
+    assert!(EStreamReader::from_bufread(&mut reader).is_none());
+}
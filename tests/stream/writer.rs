@@ -0,0 +1,59 @@
+use std::io;
+
+use soundprog::wave::{
+    container::{
+        stream::{EStreamWriter, WaveStreamWriter},
+        WaveBuilder, WaveContainer,
+    },
+    sample::UniformedSample,
+};
+
+/// RIFFチャンクと`data`チャンクのサイズをバイト列から読み込む。
+fn read_chunk_sizes(bytes: &[u8]) -> (u32, u32) {
+    let riff_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    let data_size = u32::from_le_bytes(bytes[40..44].try_into().unwrap());
+    (riff_size, data_size)
+}
+
+/// 最初は空のサイズを書き込んで、`finish`で書き込んだ分のRIFFと`data`のサイズに更新する。
+#[test]
+fn test_finish_patches_chunk_sizes() {
+    let samples = (0..600)
+        .map(|i| UniformedSample::from_16bits((i * 10 - 3000) as i16))
+        .collect::<Vec<_>>();
+    let mut writer = EStreamWriter::Wav(WaveStreamWriter::new(1000, 16, 2).unwrap());
+    let mut cursor = io::Cursor::new(vec![]);
+
+    writer.write_header(&mut cursor);
+    let (empty_riff_size, empty_data_size) = read_chunk_sizes(cursor.get_ref());
+    assert_eq!(empty_data_size, 0);
+
+    // 何回かに分けて追記しても、ヘッダーを更新するまではサイズは変わらない。
+    for chunk in samples.chunks(100) {
+        writer.write_frames(&mut cursor, chunk);
+    }
+    assert_eq!(writer.written_frame_count(), 300);
+    assert_eq!(read_chunk_sizes(cursor.get_ref()), (empty_riff_size, 0));
+
+    writer.finish(&mut cursor);
+    let bytes = cursor.into_inner();
+    assert_eq!(bytes.len(), 44 + 1200);
+    assert_eq!(read_chunk_sizes(&bytes), (empty_riff_size + 1200, 1200));
+
+    // 一度に全部書き込んだファイルと同じになる。
+    let (left, right): (Vec<_>, Vec<_>) = samples.chunks(2).map(|v| (v[0], v[1])).unzip();
+    let mut expected = io::Cursor::new(vec![]);
+    WaveBuilder {
+        samples_per_sec: 1000,
+        bits_per_sample: 16,
+    }
+    .build_stereo(left, right)
+    .unwrap()
+    .write(&mut expected);
+    assert_eq!(bytes, expected.into_inner());
+
+    // 更新したヘッダーで全部のサンプルが読み込める。
+    let container = WaveContainer::from_bufread(&mut io::Cursor::new(bytes)).expect("Could not read container.");
+    assert_eq!(container.channel(), 2);
+    assert_eq!(container.uniformed_sample_buffer(), &samples[..]);
+}
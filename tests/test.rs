@@ -8,4 +8,16 @@
 //pub mod ex7;
 //pub mod ex9;
 //pub mod ex11;
+pub mod adpcm;
+pub mod aiff;
+pub mod analyze;
+pub mod device;
+pub mod flac;
+pub mod granular;
+pub mod metrics;
+pub mod midi;
 pub mod miniaudio;
+pub mod oscillator;
+pub mod remix;
+pub mod resample;
+pub mod stream;